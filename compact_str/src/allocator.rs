@@ -0,0 +1,116 @@
+//! Support for heap allocating a [`CompactStr`](crate::CompactStr) from a user provided allocator.
+//!
+//! The `Allocator` trait from the standard library is unstable, so we provide a minimal stand-in
+//! modeled after it, which only contains the methods we need.
+
+use core::alloc::Layout;
+use core::fmt;
+use core::ptr::NonNull;
+
+/// An implementation of [`Allocator`] can allocate and deallocate blocks of memory that a
+/// [`CompactStr`] stores its heap allocated strings in.
+///
+/// A [`CompactStr`] stores its allocator inline, and needs to copy it whenever it creates a new
+/// heap allocation, so an [`Allocator`] should be a cheap handle, e.g. a zero-sized type, or a
+/// reference to an arena.
+///
+/// # Examples
+/// ```
+/// use core::alloc::Layout;
+/// use core::ptr::NonNull;
+/// use core::sync::atomic::{AtomicUsize, Ordering};
+///
+/// use compact_str::{AllocError, Allocator, CompactStr, Global};
+///
+/// /// Counts the number of bytes currently allocated
+/// #[derive(Default)]
+/// struct Counter(AtomicUsize);
+///
+/// #[derive(Clone, Copy)]
+/// struct CountingAllocator<'a>(&'a Counter);
+///
+/// unsafe impl<'a> Allocator for CountingAllocator<'a> {
+///     fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+///         (self.0).0.fetch_add(layout.size(), Ordering::Relaxed);
+///         Global.allocate(layout)
+///     }
+///
+///     unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+///         (self.0).0.fetch_sub(layout.size(), Ordering::Relaxed);
+///         Global.deallocate(ptr, layout)
+///     }
+/// }
+///
+/// let counter = Counter::default();
+/// let alloc = CountingAllocator(&counter);
+///
+/// // short strings are still inlined, and don't allocate
+/// let short = CompactStr::new_in("hello world", alloc);
+/// assert_eq!(counter.0.load(Ordering::Relaxed), 0);
+///
+/// let long = CompactStr::new_in("this string is too long to be stored inline", alloc);
+/// assert!(counter.0.load(Ordering::Relaxed) >= long.len());
+///
+/// drop(long);
+/// assert_eq!(counter.0.load(Ordering::Relaxed), 0);
+/// ```
+///
+/// # Safety
+/// * Memory returned by [`Allocator::allocate`] must remain valid until it's passed to
+///   [`Allocator::deallocate`], or the allocator, and all of its clones, are dropped.
+/// * Cloning an allocator must return an allocator that behaves like the original, i.e. memory
+///   allocated by one of them can be deallocated by the other.
+///
+/// [`CompactStr`]: crate::CompactStr
+pub unsafe trait Allocator: Clone {
+    /// Attempts to allocate a block of memory that fits `layout`.
+    ///
+    /// Note: A [`CompactStr`](crate::CompactStr) never requests a zero-sized allocation.
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError>;
+
+    /// Deallocates the memory pointed to by `ptr`.
+    ///
+    /// # Safety
+    /// * `ptr` must have been allocated by this allocator, or one of its clones
+    /// * `layout` must be the same layout that was used to allocate `ptr`
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+}
+
+/// The error type returned by an [`Allocator`] when it fails to allocate memory.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct AllocError;
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("memory allocation failed")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AllocError {}
+
+/// The global memory allocator, i.e. the one registered with `#[global_allocator]`.
+///
+/// This is the default [`Allocator`] used by a [`CompactStr`](crate::CompactStr).
+#[derive(Copy, Clone, Default, Debug)]
+pub struct Global;
+
+unsafe impl Allocator for Global {
+    #[inline]
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        debug_assert!(layout.size() > 0);
+
+        // SAFETY: `alloc(...)` has undefined behavior if the layout is zero-sized, which we never
+        // request
+        let raw_ptr = unsafe { alloc::alloc::alloc(layout) };
+
+        // Check to make sure our pointer is non-null, some allocators return null pointers instead
+        // of panicking
+        NonNull::new(raw_ptr).ok_or(AllocError)
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        alloc::alloc::dealloc(ptr.as_ptr(), layout)
+    }
+}
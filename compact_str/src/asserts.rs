@@ -40,3 +40,8 @@ macro_rules! assert_size {
     };
 }
 pub(crate) use assert_size;
+
+// The unused values of the last byte of a `CompactStr` are used as a niche, which makes
+// `Option<CompactStr>` the same size as a `String`, i.e. 24 bytes on 64-bit architectures and 12
+// bytes on 32-bit architectures
+assert_size_eq!(Option<crate::CompactStr>, alloc::string::String);
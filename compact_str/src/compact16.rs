@@ -0,0 +1,1564 @@
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::fmt;
+use core::hash::{
+    Hash,
+    Hasher,
+};
+use core::iter::{
+    FromIterator,
+    FusedIterator,
+};
+use core::ops::{
+    Deref,
+    DerefMut,
+    Index,
+    IndexMut,
+    RangeBounds,
+};
+use core::slice::SliceIndex;
+use core::str::{
+    Chars,
+    FromStr,
+};
+
+use crate::repr::{
+    char_range,
+    ThinRepr,
+};
+use crate::{
+    CompactStr,
+    FromUtf8Error,
+    TryReserveError,
+    Utf16Error,
+};
+
+/// A [`CompactStr16`] is a compact string type that's only two words, i.e. 16 bytes on 64-bit
+/// architectures, and 8 bytes on 32-bit architectures.
+///
+/// It has almost the same API as a [`CompactStr`], see below for the differences, but is a third
+/// smaller than a [`String`], which makes a difference when storing lots of strings, e.g. in a
+/// columnar index. A `Vec<String>` of short strings needs 24 bytes per string plus a heap
+/// allocation for each string, while a `Vec<CompactStr16>` needs only 16 bytes per string. The
+/// tradeoff is that it can only store up to 15 bytes inline (7 bytes on 32-bit architectures),
+/// instead of 24, and its heap allocations store their capacity in a header before the string.
+///
+/// # Memory Layout
+/// A [`CompactStr16`] is either inline, storing up to 15 bytes followed by its length, or heap
+/// allocated, in which case it stores a pointer to the heap followed by its length. Heap
+/// allocations store the capacity of the string directly before the string itself.
+///
+/// The length of a heap allocated string is packed into 7 bytes (3 bytes on 32-bit
+/// architectures), so it can store strings up to 2<sup>56</sup> - 1 bytes long (16 MiB on 32-bit
+/// architectures).
+///
+/// Like [`CompactStr`], the unused values of the last byte are used as a niche, so an
+/// `Option<CompactStr16>` is also 16 bytes.
+///
+/// # Differences from [`CompactStr`]
+/// * A [`CompactStr16`] always heap allocates from the global allocator
+/// * Converting from a [`String`] or [`Box<str>`] copies the string, since we need space for our
+///   header before the string
+/// * Strings created via [`CompactStr16::from_static_str`] that are too long to inline are copied
+///   onto the heap
+/// * There are no allocator APIs, e.g. [`CompactStr::new_in`], and no
+///   [`CompactStr::from_string_preserve_capacity`]
+/// * Draining returns a [`Drain16`] instead of a [`Drain`](crate::Drain)
+/// * Of the optional integrations, only `serde` is implemented, there's no `bytes`, `rkyv`, or
+///   [`ToCompactStr`](crate::ToCompactStr) support
+///
+/// # Examples
+/// ```
+/// use compact_str::CompactStr16;
+/// use std::mem::size_of;
+///
+/// assert_eq!(size_of::<CompactStr16>(), 2 * size_of::<usize>());
+/// assert_eq!(size_of::<Option<CompactStr16>>(), 2 * size_of::<usize>());
+///
+/// let mut tag = CompactStr16::new("rust");
+/// assert!(!tag.is_heap_allocated());
+///
+/// tag.push_str(" is pretty neat");
+/// assert_eq!(tag, "rust is pretty neat");
+/// assert!(tag.is_heap_allocated());
+/// ```
+#[derive(Clone)]
+pub struct CompactStr16 {
+    repr: ThinRepr,
+}
+
+crate::asserts::assert_size_eq!(CompactStr16, Option<CompactStr16>, [usize; 2]);
+
+impl CompactStr16 {
+    /// Creates a new [`CompactStr16`] from any type that implements `AsRef<str>`.
+    /// If the string is short enough, then it will be inlined on the stack!
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let compact = CompactStr16::new("hello world");
+    ///
+    /// assert_eq!(compact, "hello world");
+    /// assert!(!compact.is_heap_allocated());
+    /// ```
+    #[inline]
+    pub fn new<T: AsRef<str>>(text: T) -> Self {
+        CompactStr16 {
+            repr: ThinRepr::new(text.as_ref()),
+        }
+    }
+
+    /// Creates a new inline [`CompactStr16`] at compile time.
+    ///
+    /// Trying to create a long string that can't be inlined will fail to build.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// const DEFAULT_NAME: CompactStr16 = CompactStr16::new_inline("untitled");
+    ///
+    /// assert_eq!(DEFAULT_NAME, "untitled");
+    /// ```
+    #[inline]
+    pub const fn new_inline(text: &str) -> Self {
+        CompactStr16 {
+            repr: ThinRepr::new_inline(text),
+        }
+    }
+
+    /// Creates a new [`CompactStr16`] from a `&'static str`.
+    ///
+    /// Unlike [`CompactStr::from_static_str`], a [`CompactStr16`] doesn't have space to borrow a
+    /// `&'static str`, so strings that are too long to inline get copied onto the heap.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let compact = CompactStr16::from_static_str("hello");
+    /// assert_eq!(compact, "hello");
+    /// ```
+    #[inline]
+    pub fn from_static_str(text: &'static str) -> Self {
+        CompactStr16::new(text)
+    }
+
+    /// Creates a new empty [`CompactStr16`] with the capacity to fit at least `capacity` bytes.
+    ///
+    /// A `CompactStr16` will inline strings of up to 15 bytes (7 bytes on 32-bit architectures),
+    /// so a capacity at most that large won't heap allocate.
+    ///
+    /// # Panics
+    /// Panics if the capacity is larger than the longest string a [`CompactStr16`] can store
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let empty = CompactStr16::with_capacity(0);
+    /// assert!(!empty.is_heap_allocated());
+    ///
+    /// let large = CompactStr16::with_capacity(64);
+    /// assert!(large.is_heap_allocated());
+    /// assert_eq!(large.capacity(), 64);
+    /// ```
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        CompactStr16 {
+            repr: ThinRepr::with_capacity(capacity),
+        }
+    }
+
+    /// Fallible version of [`CompactStr16::with_capacity`].
+    ///
+    /// Instead of aborting or panicking, returns an error if the capacity overflows, or if the
+    /// allocator fails.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let compact = CompactStr16::try_with_capacity(64).unwrap();
+    /// assert_eq!(compact.capacity(), 64);
+    ///
+    /// assert!(CompactStr16::try_with_capacity(usize::MAX).is_err());
+    /// ```
+    #[inline]
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+        ThinRepr::try_with_capacity(capacity).map(|repr| CompactStr16 { repr })
+    }
+
+    /// Converts a buffer of bytes to a [`CompactStr16`], failing if they're not valid UTF-8.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let compact = CompactStr16::from_utf8(b"hello").unwrap();
+    /// assert_eq!(compact, "hello");
+    ///
+    /// assert!(CompactStr16::from_utf8(&[0, 159]).is_err());
+    /// ```
+    #[inline]
+    pub fn from_utf8<B: AsRef<[u8]>>(buf: B) -> Result<Self, FromUtf8Error> {
+        ThinRepr::from_utf8(buf.as_ref()).map(|repr| CompactStr16 { repr })
+    }
+
+    /// Converts a buffer of bytes to a [`CompactStr16`], without checking that they're valid
+    /// UTF-8.
+    ///
+    /// # Safety
+    /// * The provided bytes must be valid UTF-8
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// // SAFETY: "hello" is valid UTF-8
+    /// let compact = unsafe { CompactStr16::from_utf8_unchecked(b"hello") };
+    /// assert_eq!(compact, "hello");
+    /// ```
+    #[inline]
+    pub unsafe fn from_utf8_unchecked<B: AsRef<[u8]>>(buf: B) -> Self {
+        CompactStr16::new(core::str::from_utf8_unchecked(buf.as_ref()))
+    }
+
+    /// Converts a slice of bytes into a [`CompactStr16`], replacing any invalid UTF-8 sequences
+    /// with [`U+FFFD REPLACEMENT CHARACTER`][U+FFFD], like [`String::from_utf8_lossy`].
+    ///
+    /// [U+FFFD]: core::char::REPLACEMENT_CHARACTER
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let compact = CompactStr16::from_utf8_lossy(b"Hi \xF0\x90\x80World");
+    ///
+    /// assert_eq!(compact, "Hi �World");
+    /// assert!(!compact.is_heap_allocated());
+    /// ```
+    #[inline]
+    pub fn from_utf8_lossy(v: &[u8]) -> Self {
+        CompactStr16 {
+            repr: ThinRepr::from_utf8_lossy(v),
+        }
+    }
+
+    /// Decodes a slice of UTF-16 code units into a [`CompactStr16`], returning an [`Utf16Error`]
+    /// if it contains any unpaired surrogates.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// // 𝄞music
+    /// let v = &[0xD834, 0xDD1E, 0x006d, 0x0075, 0x0073, 0x0069, 0x0063];
+    /// assert_eq!(CompactStr16::from_utf16(v).unwrap(), "𝄞music");
+    ///
+    /// // 𝄞mu<invalid>ic
+    /// let v = &[0xD834, 0xDD1E, 0x006d, 0x0075, 0xD800, 0x0069, 0x0063];
+    /// assert!(CompactStr16::from_utf16(v).is_err());
+    /// ```
+    #[inline]
+    pub fn from_utf16<B: AsRef<[u16]>>(buf: B) -> Result<Self, Utf16Error> {
+        ThinRepr::from_utf16(buf.as_ref().iter().copied()).map(|repr| CompactStr16 { repr })
+    }
+
+    /// Decodes a slice of UTF-16 code units into a [`CompactStr16`], replacing any unpaired
+    /// surrogates with [`U+FFFD REPLACEMENT CHARACTER`][U+FFFD].
+    ///
+    /// [U+FFFD]: core::char::REPLACEMENT_CHARACTER
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// // 𝄞mus<invalid>ic<invalid>
+    /// let v = &[0xD834, 0xDD1E, 0x006d, 0x0075, 0x0073, 0xDD1E, 0x0069, 0x0063, 0xD834];
+    /// assert_eq!(CompactStr16::from_utf16_lossy(v), "𝄞mus\u{FFFD}ic\u{FFFD}");
+    /// ```
+    #[inline]
+    pub fn from_utf16_lossy<B: AsRef<[u16]>>(buf: B) -> Self {
+        CompactStr16 {
+            repr: ThinRepr::from_utf16_lossy(buf.as_ref().iter().copied()),
+        }
+    }
+
+    /// Decodes a slice of bytes containing little endian UTF-16 into a [`CompactStr16`],
+    /// returning an [`Utf16Error`] if the slice has an odd length, or contains any unpaired
+    /// surrogates.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let bytes = [0x68, 0x00, 0x69, 0x00, 0x3D, 0xD8, 0x00, 0xDE];
+    /// assert_eq!(CompactStr16::from_utf16le(bytes).unwrap(), "hi😀");
+    ///
+    /// // odd length
+    /// assert!(CompactStr16::from_utf16le([0x68, 0x00, 0x69]).is_err());
+    /// ```
+    #[inline]
+    pub fn from_utf16le<B: AsRef<[u8]>>(buf: B) -> Result<Self, Utf16Error> {
+        ThinRepr::from_utf16_bytes(buf.as_ref(), u16::from_le_bytes)
+            .map(|repr| CompactStr16 { repr })
+    }
+
+    /// Decodes a slice of bytes containing little endian UTF-16 into a [`CompactStr16`],
+    /// replacing any unpaired surrogates, or a trailing odd byte, with
+    /// [`U+FFFD REPLACEMENT CHARACTER`][U+FFFD].
+    ///
+    /// [U+FFFD]: core::char::REPLACEMENT_CHARACTER
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let bytes = [0x68, 0x00, 0x00, 0xD8, 0x69];
+    /// assert_eq!(CompactStr16::from_utf16le_lossy(bytes), "h\u{FFFD}\u{FFFD}");
+    /// ```
+    #[inline]
+    pub fn from_utf16le_lossy<B: AsRef<[u8]>>(buf: B) -> Self {
+        CompactStr16 {
+            repr: ThinRepr::from_utf16_bytes_lossy(buf.as_ref(), u16::from_le_bytes),
+        }
+    }
+
+    /// Decodes a slice of bytes containing big endian UTF-16 into a [`CompactStr16`], returning
+    /// an [`Utf16Error`] if the slice has an odd length, or contains any unpaired surrogates.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let bytes = [0x00, 0x68, 0x00, 0x69, 0xD8, 0x3D, 0xDE, 0x00];
+    /// assert_eq!(CompactStr16::from_utf16be(bytes).unwrap(), "hi😀");
+    ///
+    /// // unpaired surrogate
+    /// assert!(CompactStr16::from_utf16be([0x00, 0x68, 0xD8, 0x3D]).is_err());
+    /// ```
+    #[inline]
+    pub fn from_utf16be<B: AsRef<[u8]>>(buf: B) -> Result<Self, Utf16Error> {
+        ThinRepr::from_utf16_bytes(buf.as_ref(), u16::from_be_bytes)
+            .map(|repr| CompactStr16 { repr })
+    }
+
+    /// Decodes a slice of bytes containing big endian UTF-16 into a [`CompactStr16`], replacing
+    /// any unpaired surrogates, or a trailing odd byte, with
+    /// [`U+FFFD REPLACEMENT CHARACTER`][U+FFFD].
+    ///
+    /// [U+FFFD]: core::char::REPLACEMENT_CHARACTER
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let bytes = [0x00, 0x68, 0xD8, 0x00, 0x69];
+    /// assert_eq!(CompactStr16::from_utf16be_lossy(bytes), "h\u{FFFD}\u{FFFD}");
+    /// ```
+    #[inline]
+    pub fn from_utf16be_lossy<B: AsRef<[u8]>>(buf: B) -> Self {
+        CompactStr16 {
+            repr: ThinRepr::from_utf16_bytes_lossy(buf.as_ref(), u16::from_be_bytes),
+        }
+    }
+
+    /// Converts a [`CompactStr16`] into a [`String`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let compact = CompactStr16::new("hello");
+    /// let string: String = compact.into_string();
+    ///
+    /// assert_eq!(string, "hello");
+    /// ```
+    #[inline]
+    pub fn into_string(self) -> String {
+        self.repr.into_string()
+    }
+
+    /// Converts a [`CompactStr16`] into a [`Box<str>`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let compact = CompactStr16::new("hello world");
+    /// let b: Box<str> = compact.into_boxed_str();
+    ///
+    /// assert_eq!(&*b, "hello world");
+    /// ```
+    #[inline]
+    pub fn into_boxed_str(self) -> Box<str> {
+        self.into_string().into_boxed_str()
+    }
+
+    /// Converts a [`CompactStr16`] into a vector of bytes.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let compact = CompactStr16::new("hello");
+    /// let bytes = compact.into_bytes();
+    ///
+    /// assert_eq!(&[104, 101, 108, 108, 111][..], &bytes[..]);
+    /// ```
+    #[inline]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.into_string().into_bytes()
+    }
+
+    /// Returns the length of the [`CompactStr16`] in `bytes`, not [`char`]s or graphemes.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let ascii = CompactStr16::new("hello world");
+    /// assert_eq!(ascii.len(), 11);
+    ///
+    /// let emoji = CompactStr16::new("👱");
+    /// assert_eq!(emoji.len(), 4);
+    /// ```
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.repr.len()
+    }
+
+    /// Returns `true` if the [`CompactStr16`] has a length of 0, `false` otherwise
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let mut msg = CompactStr16::new("");
+    /// assert!(msg.is_empty());
+    ///
+    /// msg.push_str("hello reader!");
+    /// assert!(!msg.is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the capacity of the [`CompactStr16`], in bytes.
+    ///
+    /// # Note
+    /// * A `CompactStr16` will always have a capacity of at least `2 * size_of::<usize>() - 1`
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let min_size = 2 * std::mem::size_of::<usize>() - 1;
+    /// let compact = CompactStr16::new("");
+    ///
+    /// assert_eq!(compact.capacity(), min_size);
+    /// ```
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.repr.capacity()
+    }
+
+    /// Ensures that this [`CompactStr16`]'s capacity is at least `additional` bytes longer than
+    /// its length. The capacity may be increased by more than `additional` bytes, to prevent
+    /// frequent reallocations.
+    ///
+    /// # Panics
+    /// Panics if the new capacity is larger than the longest string a [`CompactStr16`] can store
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let mut compact = CompactStr16::default();
+    ///
+    /// compact.reserve(200);
+    /// assert!(compact.is_heap_allocated());
+    /// assert!(compact.capacity() >= 200);
+    /// ```
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        self.repr.reserve(additional)
+    }
+
+    /// Tries to reserve capacity for at least `additional` more bytes, like
+    /// [`CompactStr16::reserve`].
+    ///
+    /// Instead of aborting or panicking, returns an error if the capacity overflows, or if the
+    /// allocator fails. If an error is returned, the `CompactStr16` is unchanged.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let mut compact = CompactStr16::new("hello");
+    ///
+    /// compact.try_reserve(200).unwrap();
+    /// assert!(compact.capacity() >= 205);
+    ///
+    /// assert!(compact.try_reserve(usize::MAX).is_err());
+    /// assert_eq!(compact, "hello");
+    /// ```
+    #[inline]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.repr.try_reserve(additional)
+    }
+
+    /// Shrinks the capacity of this [`CompactStr16`] to match its length.
+    ///
+    /// If the string is short enough to be inlined, this moves it back onto the stack and frees
+    /// its heap allocation.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let mut compact = CompactStr16::new("a string that's heap allocated");
+    /// compact.reserve(100);
+    ///
+    /// compact.shrink_to_fit();
+    /// assert_eq!(compact.capacity(), compact.len());
+    ///
+    /// compact.truncate(8);
+    /// compact.shrink_to_fit();
+    /// assert_eq!(compact, "a string");
+    /// assert!(!compact.is_heap_allocated());
+    /// ```
+    #[inline]
+    pub fn shrink_to_fit(&mut self) {
+        self.repr.shrink_to_fit()
+    }
+
+    /// Shrinks the capacity of this [`CompactStr16`] with a lower bound.
+    ///
+    /// The capacity will remain at least as large as both the length and the supplied value. If
+    /// the current capacity is less than the lower bound, this is a no-op.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let mut compact = CompactStr16::with_capacity(100);
+    /// compact.push_str("hello world");
+    ///
+    /// compact.shrink_to(50);
+    /// assert_eq!(compact.capacity(), 50);
+    ///
+    /// compact.shrink_to(0);
+    /// assert!(!compact.is_heap_allocated());
+    /// assert_eq!(compact, "hello world");
+    /// ```
+    #[inline]
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        self.repr.shrink_to(min_capacity)
+    }
+
+    /// Returns a string slice containing the entire [`CompactStr16`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let s = CompactStr16::new("hello");
+    ///
+    /// assert_eq!(s.as_str(), "hello");
+    /// ```
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.repr.as_str()
+    }
+
+    /// Returns a byte slice of the [`CompactStr16`]'s contents.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let s = CompactStr16::new("hello");
+    ///
+    /// assert_eq!(&[104, 101, 108, 108, 111], s.as_bytes());
+    /// ```
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        self.repr.as_str().as_bytes()
+    }
+
+    /// Returns a mutable string slice containing the entire [`CompactStr16`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let mut s = CompactStr16::new("hello");
+    /// s.as_mut_str().make_ascii_uppercase();
+    ///
+    /// assert_eq!(s, "HELLO");
+    /// ```
+    #[inline]
+    pub fn as_mut_str(&mut self) -> &mut str {
+        self.repr.as_mut_str()
+    }
+
+    /// Provides a mutable reference to the underlying buffer of bytes.
+    ///
+    /// The returned slice spans the entire capacity of the [`CompactStr16`], not just its length.
+    ///
+    /// # Safety
+    /// * All Rust strings, including `CompactStr16`, must be valid UTF-8. The caller must guarantee
+    ///   that any modifications made to the underlying buffer are valid UTF-8.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let mut s = CompactStr16::new("hello");
+    ///
+    /// let slice = unsafe { s.as_mut_bytes() };
+    /// slice[5..11].copy_from_slice(" world".as_bytes());
+    /// unsafe { s.set_len(11) };
+    ///
+    /// assert_eq!(s, "hello world");
+    /// ```
+    #[inline]
+    pub unsafe fn as_mut_bytes(&mut self) -> &mut [u8] {
+        self.repr.as_mut_slice()
+    }
+
+    /// Appends the given [`char`] to the end of this [`CompactStr16`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let mut s = CompactStr16::new("foo");
+    ///
+    /// s.push('b');
+    /// s.push('a');
+    /// s.push('r');
+    ///
+    /// assert_eq!("foobar", s);
+    /// ```
+    #[inline]
+    pub fn push(&mut self, ch: char) {
+        self.repr.push(ch)
+    }
+
+    /// Removes the last character from the [`CompactStr16`] and returns it.
+    /// Returns `None` if this `CompactStr16` is empty.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let mut s = CompactStr16::new("ab");
+    ///
+    /// assert_eq!(s.pop(), Some('b'));
+    /// assert_eq!(s.pop(), Some('a'));
+    /// assert_eq!(s.pop(), None);
+    /// ```
+    #[inline]
+    pub fn pop(&mut self) -> Option<char> {
+        self.repr.pop()
+    }
+
+    /// Appends a given string slice onto the end of this [`CompactStr16`]
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let mut s = CompactStr16::new("abc");
+    ///
+    /// s.push_str("123");
+    ///
+    /// assert_eq!("abc123", s);
+    /// ```
+    #[inline]
+    pub fn push_str(&mut self, s: &str) {
+        self.repr.push_str(s)
+    }
+
+    /// Fallible version of [`CompactStr16::push_str`].
+    ///
+    /// Instead of aborting or panicking, returns an error if growing the `CompactStr16` fails. If
+    /// an error is returned, the `CompactStr16` is unchanged.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let mut s = CompactStr16::new("abc");
+    ///
+    /// s.try_push_str("123").unwrap();
+    ///
+    /// assert_eq!("abc123", s);
+    /// ```
+    #[inline]
+    pub fn try_push_str(&mut self, s: &str) -> Result<(), TryReserveError> {
+        self.repr.try_push_str(s)
+    }
+
+    /// Shortens this [`CompactStr16`] to the specified length.
+    ///
+    /// If `new_len` is greater than the string's current length, this has no effect. This has no
+    /// effect on the allocated capacity of the string.
+    ///
+    /// # Panics
+    /// Panics if `new_len` does not lie on a [`char`] boundary.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let mut s = CompactStr16::new("hello");
+    /// s.truncate(2);
+    ///
+    /// assert_eq!(s, "he");
+    /// ```
+    #[inline]
+    pub fn truncate(&mut self, new_len: usize) {
+        self.repr.truncate(new_len)
+    }
+
+    /// Truncates this [`CompactStr16`], removing all contents.
+    ///
+    /// While this means the string will have a length of zero, it does not touch its capacity.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let mut s = CompactStr16::new("a string that's heap allocated");
+    /// let capacity = s.capacity();
+    ///
+    /// s.clear();
+    ///
+    /// assert!(s.is_empty());
+    /// assert_eq!(s.capacity(), capacity);
+    /// ```
+    #[inline]
+    pub fn clear(&mut self) {
+        self.repr.truncate(0)
+    }
+
+    /// Inserts a character into this [`CompactStr16`] at a byte position.
+    ///
+    /// # Panics
+    /// Panics if `idx` is larger than the string's length, or if it does not lie on a [`char`]
+    /// boundary.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let mut s = CompactStr16::new("bar");
+    ///
+    /// s.insert(0, 'f');
+    /// s.insert(1, 'o');
+    /// s.insert(2, 'o');
+    ///
+    /// assert_eq!(s, "foobar");
+    /// ```
+    #[inline]
+    pub fn insert(&mut self, idx: usize, ch: char) {
+        let mut buf = [0u8; 4];
+        self.repr.insert_str(idx, ch.encode_utf8(&mut buf))
+    }
+
+    /// Inserts a string slice into this [`CompactStr16`] at a byte position.
+    ///
+    /// # Panics
+    /// Panics if `idx` is larger than the string's length, or if it does not lie on a [`char`]
+    /// boundary.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let mut s = CompactStr16::new("bar");
+    ///
+    /// s.insert_str(0, "foo");
+    ///
+    /// assert_eq!(s, "foobar");
+    /// ```
+    #[inline]
+    pub fn insert_str(&mut self, idx: usize, s: &str) {
+        self.repr.insert_str(idx, s)
+    }
+
+    /// Removes a [`char`] from this [`CompactStr16`] at a byte position and returns it.
+    ///
+    /// # Panics
+    /// Panics if `idx` is larger than or equal to the string's length, or if it does not lie on a
+    /// [`char`] boundary.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let mut s = CompactStr16::new("foo");
+    ///
+    /// assert_eq!(s.remove(0), 'f');
+    /// assert_eq!(s.remove(1), 'o');
+    /// assert_eq!(s.remove(0), 'o');
+    ///
+    /// assert!(s.is_empty());
+    /// ```
+    #[inline]
+    pub fn remove(&mut self, idx: usize) -> char {
+        self.repr.remove(idx)
+    }
+
+    /// Retains only the characters specified by the predicate.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let mut s = CompactStr16::new("f_o_ob_ar");
+    ///
+    /// s.retain(|c| c != '_');
+    ///
+    /// assert_eq!(s, "foobar");
+    /// ```
+    #[inline]
+    pub fn retain<F: FnMut(char) -> bool>(&mut self, predicate: F) {
+        self.repr.retain(predicate)
+    }
+
+    /// Removes the specified range from this [`CompactStr16`] in bulk, returning all removed
+    /// characters as an iterator.
+    ///
+    /// The range is removed when the iterator is dropped, even if it was not fully consumed. If the
+    /// iterator is leaked, e.g. with [`core::mem::forget`], the string is left unmodified.
+    ///
+    /// # Panics
+    /// Panics if the starting point or end point do not lie on a [`char`] boundary, or if they're
+    /// out of bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let mut s = CompactStr16::new("α is alpha, β is beta");
+    /// let beta_offset = s.find('β').unwrap_or(s.len());
+    ///
+    /// let t: CompactStr16 = s.drain(..beta_offset).collect();
+    /// assert_eq!(t, "α is alpha, ");
+    /// assert_eq!(s, "β is beta");
+    /// ```
+    #[inline]
+    pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain16<'_> {
+        let (start, end) = char_range(self.as_str(), range);
+        let repr: *mut ThinRepr = &mut self.repr;
+
+        // SAFETY: `char_range(...)` asserted that `start` and `end` are in bounds, and fall on char
+        // boundaries. `Drain16` holds a mutable borrow of `self`, so the underlying buffer can't be
+        // modified until the `Drain16` gets dropped
+        let chars = unsafe { (*repr).as_str().get_unchecked(start..end) }.chars();
+
+        Drain16 {
+            repr,
+            start,
+            end,
+            chars,
+        }
+    }
+
+    /// Removes the specified range in this [`CompactStr16`], and replaces it with the given
+    /// string. The given string doesn't need to be the same length as the range.
+    ///
+    /// # Panics
+    /// Panics if the starting point or end point do not lie on a [`char`] boundary, or if they're
+    /// out of bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let mut s = CompactStr16::new("α is alpha");
+    ///
+    /// s.replace_range(..2, "Α");
+    /// assert_eq!(s, "Α is alpha");
+    /// ```
+    #[inline]
+    pub fn replace_range<R: RangeBounds<usize>>(&mut self, range: R, replace_with: &str) {
+        self.repr.replace_range(range, replace_with)
+    }
+
+    /// Splits this [`CompactStr16`] into two at the given byte index.
+    ///
+    /// Returns a new [`CompactStr16`]. `self` contains bytes `[0, at)`, and the returned
+    /// [`CompactStr16`] contains bytes `[at, len)`.
+    ///
+    /// # Panics
+    /// Panics if `at` is not on a [`char`] boundary, or if it is beyond the last code point of the
+    /// string.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let mut hello = CompactStr16::new("Hello, World!");
+    /// let world = hello.split_off(7);
+    ///
+    /// assert_eq!(hello, "Hello, ");
+    /// assert_eq!(world, "World!");
+    /// ```
+    #[inline]
+    #[must_use = "use `.truncate()` if you don't need the other half"]
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(self.as_str().is_char_boundary(at));
+
+        let other = CompactStr16::new(&self.as_str()[at..]);
+        self.truncate(at);
+        other
+    }
+
+    /// Returns a new [`CompactStr16`] containing the bytes in `range`.
+    ///
+    /// Unlike [`CompactStr::substr`], a [`CompactStr16`] is never reference counted, so a
+    /// substring that's too long to inline gets copied.
+    ///
+    /// # Panics
+    /// Panics if the starting point or end point do not lie on a [`char`] boundary, or if they're
+    /// out of bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let text = CompactStr16::new("the quick brown fox");
+    ///
+    /// let fox = text.substr(16..);
+    /// assert_eq!(fox, "fox");
+    /// assert!(!fox.is_heap_allocated());
+    /// ```
+    #[inline]
+    pub fn substr<R: RangeBounds<usize>>(&self, range: R) -> Self {
+        let (start, end) = char_range(self.as_str(), range);
+        CompactStr16::new(&self.as_str()[start..end])
+    }
+
+    /// Returns a new [`CompactStr16`] equivalent to `subset`, which must be a slice of `self`.
+    ///
+    /// Like [`CompactStr16::substr`], this copies `subset`.
+    ///
+    /// # Panics
+    /// Panics if `subset` is not contained within `self`. An empty `subset` is always accepted.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let text = CompactStr16::new("the quick brown fox");
+    /// let words: Vec<CompactStr16> = text
+    ///     .split_whitespace()
+    ///     .map(|word| text.slice_ref(word))
+    ///     .collect();
+    ///
+    /// assert_eq!(words, ["the", "quick", "brown", "fox"]);
+    /// ```
+    #[inline]
+    pub fn slice_ref(&self, subset: &str) -> Self {
+        // An empty slice could point anywhere, e.g. it could be dangling, so we don't check it
+        if subset.is_empty() {
+            return CompactStr16::default();
+        }
+
+        let self_ptr = self.as_str().as_ptr() as usize;
+        let subset_ptr = subset.as_ptr() as usize;
+
+        assert!(
+            subset_ptr >= self_ptr && subset_ptr + subset.len() <= self_ptr + self.len(),
+            "subset is not contained within this CompactStr16"
+        );
+
+        let start = subset_ptr - self_ptr;
+        self.substr(start..start + subset.len())
+    }
+
+    /// Forces the length of the [`CompactStr16`] to `new_len`.
+    ///
+    /// # Safety
+    /// * `new_len` must be less than or equal to `capacity()`
+    /// * The elements at `old_len..new_len` must be initialized
+    #[inline]
+    pub unsafe fn set_len(&mut self, new_len: usize) {
+        self.repr.set_len(new_len)
+    }
+
+    /// Returns whether or not the [`CompactStr16`] is heap allocated.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// assert!(!CompactStr16::new("hello world").is_heap_allocated());
+    /// assert!(CompactStr16::new("hello world, it's me!").is_heap_allocated());
+    /// ```
+    #[inline]
+    pub fn is_heap_allocated(&self) -> bool {
+        self.repr.is_heap_allocated()
+    }
+}
+
+impl Default for CompactStr16 {
+    #[inline]
+    fn default() -> Self {
+        CompactStr16::new_inline("")
+    }
+}
+
+impl Deref for CompactStr16 {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl DerefMut for CompactStr16 {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut str {
+        self.as_mut_str()
+    }
+}
+
+impl<I: SliceIndex<str>> Index<I> for CompactStr16 {
+    type Output = I::Output;
+
+    #[inline]
+    fn index(&self, index: I) -> &Self::Output {
+        &self.as_str()[index]
+    }
+}
+
+impl<I: SliceIndex<str>> IndexMut<I> for CompactStr16 {
+    #[inline]
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        &mut self.as_mut_str()[index]
+    }
+}
+
+impl AsRef<str> for CompactStr16 {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for CompactStr16 {
+    #[inline]
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Eq for CompactStr16 {}
+
+impl<T: AsRef<str>> PartialEq<T> for CompactStr16 {
+    fn eq(&self, other: &T) -> bool {
+        self.as_str() == other.as_ref()
+    }
+}
+
+impl PartialEq<CompactStr16> for String {
+    fn eq(&self, other: &CompactStr16) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl PartialEq<CompactStr16> for &str {
+    fn eq(&self, other: &CompactStr16) -> bool {
+        *self == other.as_str()
+    }
+}
+
+impl<'a> PartialEq<CompactStr16> for Cow<'a, str> {
+    fn eq(&self, other: &CompactStr16) -> bool {
+        *self == other.as_str()
+    }
+}
+
+impl Ord for CompactStr16 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl PartialOrd for CompactStr16 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Hash for CompactStr16 {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl<'a> From<&'a str> for CompactStr16 {
+    fn from(s: &'a str) -> Self {
+        CompactStr16::new(s)
+    }
+}
+
+impl From<String> for CompactStr16 {
+    fn from(s: String) -> Self {
+        CompactStr16::new(s)
+    }
+}
+
+impl<'a> From<&'a String> for CompactStr16 {
+    fn from(s: &'a String) -> Self {
+        CompactStr16::new(s)
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for CompactStr16 {
+    fn from(s: Cow<'a, str>) -> Self {
+        CompactStr16::new(s)
+    }
+}
+
+impl From<Box<str>> for CompactStr16 {
+    fn from(b: Box<str>) -> Self {
+        CompactStr16::new(b)
+    }
+}
+
+/// Converts a vector of bytes into a [`CompactStr16`], returning the vector in the
+/// [`FromUtf8Error`] if the bytes aren't valid UTF-8.
+///
+/// # Examples
+/// ```
+/// # use compact_str::CompactStr16;
+/// use std::convert::TryFrom;
+///
+/// let compact = CompactStr16::try_from(b"hello".to_vec()).unwrap();
+/// assert_eq!(compact, "hello");
+///
+/// let err = CompactStr16::try_from(vec![b'a', 255, b'b']).unwrap_err();
+/// assert_eq!(err.into_bytes(), vec![b'a', 255, b'b']);
+/// ```
+impl TryFrom<Vec<u8>> for CompactStr16 {
+    type Error = FromUtf8Error;
+
+    fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
+        match core::str::from_utf8(&bytes) {
+            Ok(s) => Ok(CompactStr16::new(s)),
+            Err(error) => Err(FromUtf8Error::new(bytes, error)),
+        }
+    }
+}
+
+impl From<CompactStr> for CompactStr16 {
+    fn from(s: CompactStr) -> Self {
+        CompactStr16::new(s)
+    }
+}
+
+impl From<CompactStr16> for String {
+    fn from(s: CompactStr16) -> Self {
+        s.into_string()
+    }
+}
+
+impl From<CompactStr16> for CompactStr {
+    fn from(s: CompactStr16) -> Self {
+        CompactStr::new(s)
+    }
+}
+
+impl FromStr for CompactStr16 {
+    type Err = core::convert::Infallible;
+    fn from_str(s: &str) -> Result<CompactStr16, Self::Err> {
+        Ok(CompactStr16::from(s))
+    }
+}
+
+impl fmt::Debug for CompactStr16 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for CompactStr16 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Write for CompactStr16 {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+impl FromIterator<char> for CompactStr16 {
+    fn from_iter<T: IntoIterator<Item = char>>(iter: T) -> Self {
+        let mut s = CompactStr16::default();
+        s.extend(iter);
+        s
+    }
+}
+
+impl<'a> FromIterator<&'a char> for CompactStr16 {
+    fn from_iter<T: IntoIterator<Item = &'a char>>(iter: T) -> Self {
+        iter.into_iter().copied().collect()
+    }
+}
+
+impl<'a> FromIterator<&'a str> for CompactStr16 {
+    fn from_iter<T: IntoIterator<Item = &'a str>>(iter: T) -> Self {
+        let mut s = CompactStr16::default();
+        s.extend(iter);
+        s
+    }
+}
+
+impl FromIterator<Box<str>> for CompactStr16 {
+    fn from_iter<T: IntoIterator<Item = Box<str>>>(iter: T) -> Self {
+        let mut s = CompactStr16::default();
+        s.extend(iter);
+        s
+    }
+}
+
+impl FromIterator<String> for CompactStr16 {
+    fn from_iter<T: IntoIterator<Item = String>>(iter: T) -> Self {
+        let mut s = CompactStr16::default();
+        s.extend(iter);
+        s
+    }
+}
+
+impl Extend<char> for CompactStr16 {
+    fn extend<T: IntoIterator<Item = char>>(&mut self, iter: T) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        iter.for_each(|ch| self.push(ch));
+    }
+}
+
+impl<'a> Extend<&'a char> for CompactStr16 {
+    fn extend<T: IntoIterator<Item = &'a char>>(&mut self, iter: T) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<'a> Extend<&'a str> for CompactStr16 {
+    fn extend<T: IntoIterator<Item = &'a str>>(&mut self, iter: T) {
+        iter.into_iter().for_each(|s| self.push_str(s));
+    }
+}
+
+impl Extend<Box<str>> for CompactStr16 {
+    fn extend<T: IntoIterator<Item = Box<str>>>(&mut self, iter: T) {
+        iter.into_iter().for_each(|s| self.push_str(&s));
+    }
+}
+
+impl<'a> Extend<Cow<'a, str>> for CompactStr16 {
+    fn extend<T: IntoIterator<Item = Cow<'a, str>>>(&mut self, iter: T) {
+        iter.into_iter().for_each(|s| self.push_str(&s));
+    }
+}
+
+impl Extend<String> for CompactStr16 {
+    fn extend<T: IntoIterator<Item = String>>(&mut self, iter: T) {
+        iter.into_iter().for_each(|s| self.push_str(&s));
+    }
+}
+
+/// A draining iterator for [`CompactStr16`].
+///
+/// This struct is created by the [`drain`](CompactStr16::drain) method on [`CompactStr16`]. See
+/// its documentation for more.
+pub struct Drain16<'a> {
+    repr: *mut ThinRepr,
+    start: usize,
+    end: usize,
+    chars: Chars<'a>,
+}
+
+// SAFETY: `Drain16` only exposes the `ThinRepr` it mutably borrows via `chars`, which is
+// `Send + Sync`
+unsafe impl Send for Drain16<'_> {}
+unsafe impl Sync for Drain16<'_> {}
+
+impl<'a> Drain16<'a> {
+    /// Returns the remaining (sub)string of this iterator as a slice.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr16;
+    /// let mut s = CompactStr16::new("abc");
+    /// let mut drain = s.drain(..);
+    /// assert_eq!(drain.as_str(), "abc");
+    ///
+    /// let _ = drain.next().unwrap();
+    /// assert_eq!(drain.as_str(), "bc");
+    /// ```
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.chars.as_str()
+    }
+}
+
+impl fmt::Debug for Drain16<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Drain16").field(&self.as_str()).finish()
+    }
+}
+
+impl Drop for Drain16<'_> {
+    fn drop(&mut self) {
+        // SAFETY: `Drain16` holds the only (mutable) borrow of the `ThinRepr`, and
+        // `CompactStr16::drain` asserted that `start` and `end` are in bounds, and fall on char
+        // boundaries
+        unsafe { (*self.repr).replace_range(self.start..self.end, "") };
+    }
+}
+
+impl Iterator for Drain16<'_> {
+    type Item = char;
+
+    #[inline]
+    fn next(&mut self) -> Option<char> {
+        self.chars.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chars.size_hint()
+    }
+
+    #[inline]
+    fn last(mut self) -> Option<char> {
+        self.next_back()
+    }
+}
+
+impl DoubleEndedIterator for Drain16<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<char> {
+        self.chars.next_back()
+    }
+}
+
+impl FusedIterator for Drain16<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::{
+        String,
+        ToString,
+    };
+    use alloc::vec::Vec;
+    use core::mem::size_of;
+
+    use proptest::prelude::*;
+    use test_strategy::proptest;
+
+    use super::CompactStr16;
+    use crate::tests::{
+        rand_unicode,
+        rand_unicode_collection,
+    };
+    use crate::CompactStr;
+
+    const MAX_INLINE: usize = 2 * size_of::<usize>() - 1;
+
+    #[test]
+    fn test_size() {
+        assert_eq!(size_of::<CompactStr16>(), 2 * size_of::<usize>());
+        assert_eq!(size_of::<Option<CompactStr16>>(), 2 * size_of::<usize>());
+        // two `CompactStr16`s fit in the space of three `String`s
+        assert_eq!(3 * size_of::<CompactStr16>(), 2 * size_of::<String>());
+    }
+
+    #[test]
+    fn test_max_inline() {
+        let word = "a".repeat(MAX_INLINE);
+        let compact = CompactStr16::new(&word);
+        assert_eq!(compact, word);
+        assert!(!compact.is_heap_allocated());
+
+        let word = "a".repeat(MAX_INLINE + 1);
+        let compact = CompactStr16::new(&word);
+        assert_eq!(compact, word);
+        assert!(compact.is_heap_allocated());
+    }
+
+    #[test]
+    fn test_push_grows_onto_heap() {
+        let mut compact = CompactStr16::default();
+        let mut control = String::new();
+
+        for ch in "the quick brown 🦊 jumps over the lazy 🐶".chars() {
+            compact.push(ch);
+            control.push(ch);
+            assert_eq!(compact, control);
+            assert_eq!(compact.is_heap_allocated(), control.len() > MAX_INLINE);
+        }
+
+        while let Some(ch) = control.pop() {
+            assert_eq!(compact.pop(), Some(ch));
+            assert_eq!(compact, control);
+        }
+        assert_eq!(compact.pop(), None);
+    }
+
+    #[test]
+    fn test_clone_heap() {
+        let original = CompactStr16::new("this string is long enough to be heap allocated");
+        let mut clone = original.clone();
+        assert_eq!(original, clone);
+        assert_ne!(original.as_ptr(), clone.as_ptr());
+
+        clone.push('!');
+        assert_eq!(original, "this string is long enough to be heap allocated");
+    }
+
+    #[test]
+    fn test_edits() {
+        let mut s = CompactStr16::new("hello world");
+
+        s.insert_str(5, ", wonderful");
+        assert_eq!(s, "hello, wonderful world");
+        assert!(s.is_heap_allocated());
+
+        assert_eq!(s.remove(0), 'h');
+        s.retain(|c| c != 'o');
+        assert_eq!(s, "ell, wnderful wrld");
+
+        // note: short enough to be inlined on 32-bit architectures too
+        s.replace_range(3..14, "");
+        assert_eq!(s, "ellwrld");
+        s.shrink_to_fit();
+        assert!(!s.is_heap_allocated());
+
+        let other = s.split_off(3);
+        assert_eq!(s, "ell");
+        assert_eq!(other, "wrld");
+    }
+
+    #[test]
+    fn test_conversions() {
+        let compact = CompactStr16::from("hello world, this is a long string".to_string());
+        let big: CompactStr = compact.clone().into();
+        assert_eq!(big, compact);
+        assert_eq!(compact, big);
+
+        let string: String = compact.into();
+        assert_eq!(string, "hello world, this is a long string");
+    }
+
+    #[test]
+    fn test_write() {
+        use core::fmt::Write;
+
+        let mut s = CompactStr16::default();
+        write!(s, "{} + {} = {}", 40, 2, 42).unwrap();
+        assert_eq!(s, "40 + 2 = 42");
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut s = CompactStr16::new("the quick brown 🦊 jumps");
+        let drained: String = s.drain(4..16).collect();
+        assert_eq!(drained, "quick brown ");
+        assert_eq!(s, "the 🦊 jumps");
+
+        // a partially consumed, or leaked, `Drain16`
+        let mut drain = s.drain(..4);
+        assert_eq!(drain.next_back(), Some(' '));
+        drop(drain);
+        assert_eq!(s, "🦊 jumps");
+        core::mem::forget(s.drain(..));
+        assert_eq!(s, "🦊 jumps");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_drain_not_char_boundary() {
+        let mut s = CompactStr16::new("🦊");
+        let _ = s.drain(1..);
+    }
+
+    #[test]
+    fn test_substr() {
+        let text = CompactStr16::new("a string that's long enough to be heap allocated");
+
+        let word = text.substr(2..8);
+        assert_eq!(word, "string");
+        assert!(!word.is_heap_allocated());
+
+        let tail = text.slice_ref(&text[2..]);
+        assert_eq!(tail, &text[2..]);
+        assert_ne!(tail.as_ptr(), text[2..].as_ptr());
+
+        assert_eq!(text.slice_ref(""), "");
+    }
+
+    #[test]
+    #[should_panic(expected = "subset is not contained within this CompactStr16")]
+    fn test_slice_ref_outside() {
+        let text = CompactStr16::new("hello");
+        let _ = text.slice_ref("world");
+    }
+
+    #[test]
+    fn test_index() {
+        let mut s = CompactStr16::new("hello world");
+        assert_eq!(&s[6..], "world");
+
+        s[..5].make_ascii_uppercase();
+        assert_eq!(s, "HELLO world");
+    }
+
+    #[test]
+    fn test_try_from_vec() {
+        use core::convert::TryFrom;
+
+        let compact = CompactStr16::try_from(b"hello world, it's me!".to_vec()).unwrap();
+        assert_eq!(compact, "hello world, it's me!");
+
+        let err = CompactStr16::try_from(vec![b'a', 255]).unwrap_err();
+        assert_eq!(err.utf8_error().valid_up_to(), 1);
+        assert_eq!(err.into_bytes(), vec![b'a', 255]);
+    }
+
+    #[test]
+    fn test_decode() {
+        let compact = CompactStr16::from_utf8_lossy(b"abc\xF0\x9F\xA6");
+        assert_eq!(compact, "abc\u{FFFD}");
+
+        let units: Vec<u16> = "hello 🦀".encode_utf16().collect();
+        assert_eq!(CompactStr16::from_utf16(&units).unwrap(), "hello 🦀");
+        assert_eq!(CompactStr16::from_utf16_lossy([0x61, 0xD800]), "a\u{FFFD}");
+
+        let le: Vec<u8> = units
+            .iter()
+            .flat_map(|u| u.to_le_bytes().to_vec())
+            .collect();
+        let be: Vec<u8> = units
+            .iter()
+            .flat_map(|u| u.to_be_bytes().to_vec())
+            .collect();
+        assert_eq!(CompactStr16::from_utf16le(&le).unwrap(), "hello 🦀");
+        assert_eq!(CompactStr16::from_utf16be(&be).unwrap(), "hello 🦀");
+        assert!(CompactStr16::from_utf16le(&le[1..]).is_err());
+        assert_eq!(CompactStr16::from_utf16be_lossy(&be[..3]), "h\u{FFFD}");
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde() {
+        use serde::de::value::{
+            Error,
+            StrDeserializer,
+        };
+        use serde::de::IntoDeserializer;
+        use serde::Deserialize;
+
+        let word = "this word is long enough to be heap allocated";
+        let de: StrDeserializer<Error> = word.into_deserializer();
+        let compact = CompactStr16::deserialize(de).unwrap();
+        assert_eq!(compact, word);
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn test_strings_roundtrip(#[strategy(rand_unicode())] word: String) {
+        let compact = CompactStr16::new(&word);
+
+        prop_assert_eq!(&compact, &word);
+        prop_assert_eq!(compact.len(), word.len());
+        prop_assert_eq!(compact.is_heap_allocated(), word.len() > MAX_INLINE);
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn test_extend_matches_string(#[strategy(rand_unicode_collection())] words: Vec<String>) {
+        let compact: CompactStr16 = words.iter().map(String::as_str).collect();
+        let control: String = words.iter().map(String::as_str).collect();
+
+        prop_assert_eq!(&compact, &control);
+        prop_assert!(compact.capacity() >= compact.len());
+    }
+}
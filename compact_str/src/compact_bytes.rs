@@ -0,0 +1,843 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::borrow::{
+    Borrow,
+    BorrowMut,
+};
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{
+    Hash,
+    Hasher,
+};
+use core::iter::FromIterator;
+use core::ops::{
+    Deref,
+    DerefMut,
+};
+
+use crate::repr::Repr;
+use crate::{
+    CompactStr,
+    FromUtf8Error,
+    Global,
+    TryReserveError,
+};
+
+/// A [`CompactBytes`] is a compact vector of bytes, that can be used almost anywhere a
+/// [`Vec<u8>`] or `[u8]` can be used.
+///
+/// It shares the same storage as a [`CompactStr`], so short byte strings, e.g. hashes or small
+/// protobuf messages, are stored inline without a heap allocation, but unlike a [`CompactStr`],
+/// the bytes don't need to be valid UTF-8.
+///
+/// # Memory Layout
+/// A [`CompactBytes`] is the same size as a [`Vec<u8>`], and can store up to 23 bytes inline (11
+/// bytes on 32-bit architectures), one less than a [`CompactStr`]. The last byte of an inline
+/// [`CompactStr`] either stores its length, or the last byte of a 24 byte long string, which we
+/// can tell apart because the last byte of valid UTF-8 never looks like a length. Arbitrary bytes
+/// could, so a [`CompactBytes`] moves onto the heap once it needs that last byte.
+///
+/// # Converting to and from [`CompactStr`]
+/// [`CompactBytes::into_compact_str`] checks that the bytes are valid UTF-8 and then re-uses the
+/// same storage, without copying, and converting a [`CompactStr`] into a [`CompactBytes`] is free
+/// too, unless the string is exactly 24 bytes long and inline.
+///
+/// # Examples
+/// ```
+/// use compact_str::CompactBytes;
+///
+/// let mut key = CompactBytes::from(&[0xDE, 0xAD][..]);
+/// key.extend_from_slice(&[0xBE, 0xEF]);
+///
+/// assert_eq!(key, [0xDE, 0xAD, 0xBE, 0xEF]);
+/// assert!(!key.is_heap_allocated());
+///
+/// key.extend_from_slice(&[0; 32]);
+/// assert_eq!(key.len(), 36);
+/// assert!(key.is_heap_allocated());
+/// ```
+#[derive(Clone)]
+pub struct CompactBytes {
+    // Note: Unlike the `Repr` of a `CompactStr`, this might not be valid UTF-8, so we only ever
+    // use the `*_bytes()` methods of `Repr` to resize it, see `Repr::bytes_capacity()`
+    pub(crate) repr: Repr,
+}
+
+crate::asserts::assert_size_eq!(CompactBytes, Option<CompactBytes>, Vec<u8>);
+
+impl CompactBytes {
+    /// Creates a new, empty [`CompactBytes`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactBytes;
+    /// let empty = CompactBytes::new();
+    /// assert!(empty.is_empty());
+    /// assert_eq!(empty.capacity(), std::mem::size_of::<Vec<u8>>() - 1);
+    /// ```
+    #[inline]
+    pub fn new() -> Self {
+        CompactBytes {
+            repr: Repr::new(""),
+        }
+    }
+
+    /// Creates a new, empty [`CompactBytes`] that can store at least `capacity` bytes without
+    /// reallocating. If `capacity` is small enough, no heap allocation occurs.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactBytes;
+    /// let small = CompactBytes::with_capacity(8);
+    /// assert!(!small.is_heap_allocated());
+    ///
+    /// let large = CompactBytes::with_capacity(128);
+    /// assert!(large.is_heap_allocated());
+    /// assert!(large.capacity() >= 128);
+    /// ```
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut bytes = CompactBytes::new();
+        bytes.reserve(capacity);
+        bytes
+    }
+
+    /// Fallible version of [`CompactBytes::with_capacity()`]
+    ///
+    /// This method won't panic if the system is out of memory, but returns a
+    /// [`TryReserveError`]. Otherwise it behaves the same as [`CompactBytes::with_capacity()`].
+    #[inline]
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+        let mut bytes = CompactBytes::new();
+        bytes.try_reserve(capacity)?;
+        Ok(bytes)
+    }
+
+    /// Returns the length of the [`CompactBytes`] in bytes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.repr.len()
+    }
+
+    /// Returns `true` if the [`CompactBytes`] has a length of 0, `false` otherwise.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the capacity of the [`CompactBytes`]. If the bytes are inline, this is one less
+    /// than the size of a [`Vec<u8>`], see the [memory layout](CompactBytes#memory-layout).
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.repr.bytes_capacity()
+    }
+
+    /// Ensures that this [`CompactBytes`]'s capacity is at least `additional` bytes longer than
+    /// its length. The capacity may be increased by more than `additional` bytes in order to
+    /// prevent frequent reallocations.
+    ///
+    /// # Panics
+    /// This method panics if the new capacity overflows `usize`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactBytes;
+    /// let mut bytes = CompactBytes::new();
+    /// bytes.reserve(100);
+    ///
+    /// assert!(bytes.is_heap_allocated());
+    /// assert!(bytes.capacity() >= 100);
+    /// ```
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        crate::error::handle_reserve(self.try_reserve(additional))
+    }
+
+    /// Fallible version of [`CompactBytes::reserve()`]
+    ///
+    /// This method won't panic if the system is out of memory, but returns a
+    /// [`TryReserveError`]. Otherwise it behaves the same as [`CompactBytes::reserve()`].
+    #[inline]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.repr.try_reserve_bytes(additional)
+    }
+
+    /// Shrinks the capacity of this [`CompactBytes`] as much as possible, moving it inline if it's
+    /// short enough.
+    #[inline]
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink_to(0);
+    }
+
+    /// Shrinks the capacity of this [`CompactBytes`] with a lower bound, moving it inline if
+    /// both the length and `min_capacity` are short enough.
+    #[inline]
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        self.repr.shrink_to_bytes(min_capacity);
+    }
+
+    /// Returns a slice containing the entire [`CompactBytes`].
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        self.repr.as_bytes()
+    }
+
+    /// Returns a mutable slice containing the entire [`CompactBytes`].
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        let len = self.len();
+        // SAFETY: We're inline only if we're shorter than `MAX_SIZE`, so the first `len` bytes
+        // never include the last byte of an inline `Repr`, and any bytes are valid here
+        unsafe { &mut self.repr.as_mut_slice()[..len] }
+    }
+
+    /// Appends a byte to the end of this [`CompactBytes`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactBytes;
+    /// let mut bytes = CompactBytes::new();
+    /// bytes.push(0xFF);
+    /// bytes.push(0x00);
+    ///
+    /// assert_eq!(bytes, [0xFF, 0x00]);
+    /// ```
+    #[inline]
+    pub fn push(&mut self, byte: u8) {
+        self.extend_from_slice(&[byte]);
+    }
+
+    /// Removes the last byte from this [`CompactBytes`] and returns it, or [`None`] if it's empty.
+    #[inline]
+    pub fn pop(&mut self) -> Option<u8> {
+        let len = self.len();
+        let byte = *self.as_slice().last()?;
+        // SAFETY: We're shrinking our length by one, and any bytes are valid here
+        unsafe { self.repr.set_len(len - 1) };
+        Some(byte)
+    }
+
+    /// Appends all of the bytes in `bytes` to this [`CompactBytes`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactBytes;
+    /// let mut bytes = CompactBytes::from(&b"GET "[..]);
+    /// bytes.extend_from_slice(b"/index.html");
+    ///
+    /// assert_eq!(bytes, b"GET /index.html");
+    /// ```
+    #[inline]
+    pub fn extend_from_slice(&mut self, bytes: &[u8]) {
+        let len = self.len();
+        self.reserve(bytes.len());
+
+        // SAFETY: We reserved enough space, which `Repr::try_reserve_bytes()` made sure is never
+        // the last byte of an inline `Repr`, and any bytes are valid here
+        unsafe {
+            let buffer = self.repr.as_mut_slice();
+            buffer[len..len + bytes.len()].copy_from_slice(bytes);
+            self.repr.set_len(len + bytes.len());
+        }
+    }
+
+    /// Inserts a byte into this [`CompactBytes`] at index `idx`, shifting all of the bytes after
+    /// it to the right.
+    ///
+    /// # Panics
+    /// Panics if `idx` is larger than the [`CompactBytes`]'s length.
+    #[inline]
+    pub fn insert(&mut self, idx: usize, byte: u8) {
+        let len = self.len();
+        assert!(
+            idx <= len,
+            "insertion index (is {}) should be <= len (is {})",
+            idx,
+            len
+        );
+
+        self.push(byte);
+        self.as_mut_slice()[idx..].rotate_right(1);
+    }
+
+    /// Removes the byte at index `idx` from this [`CompactBytes`] and returns it, shifting all of
+    /// the bytes after it to the left.
+    ///
+    /// # Panics
+    /// Panics if `idx` is out of bounds.
+    #[inline]
+    pub fn remove(&mut self, idx: usize) -> u8 {
+        let len = self.len();
+        assert!(
+            idx < len,
+            "removal index (is {}) should be < len (is {})",
+            idx,
+            len
+        );
+
+        let slice = self.as_mut_slice();
+        let byte = slice[idx];
+        slice.copy_within(idx + 1.., idx);
+        // SAFETY: We're shrinking our length by one, and any bytes are valid here
+        unsafe { self.repr.set_len(len - 1) };
+
+        byte
+    }
+
+    /// Retains only the bytes specified by the predicate, i.e. removes all bytes `b` for which
+    /// `predicate(b)` returns `false`.
+    #[inline]
+    pub fn retain<F: FnMut(u8) -> bool>(&mut self, mut predicate: F) {
+        let slice = self.as_mut_slice();
+
+        let mut new_len = 0;
+        for idx in 0..slice.len() {
+            let byte = slice[idx];
+            if predicate(byte) {
+                slice[new_len] = byte;
+                new_len += 1;
+            }
+        }
+
+        // SAFETY: We only shrink our length, and any bytes are valid here
+        unsafe { self.repr.set_len(new_len) };
+    }
+
+    /// Shortens this [`CompactBytes`] to the specified length. If `new_len` is greater than the
+    /// current length, this has no effect.
+    ///
+    /// Note: like [`Vec::truncate`], this doesn't change the capacity, so it won't move back
+    /// inline, see [`CompactBytes::shrink_to_fit()`].
+    #[inline]
+    pub fn truncate(&mut self, new_len: usize) {
+        if new_len < self.len() {
+            // SAFETY: We only shrink our length, and any bytes are valid here
+            unsafe { self.repr.set_len(new_len) };
+        }
+    }
+
+    /// Resizes this [`CompactBytes`] in-place so that its length is `new_len`, either truncating
+    /// it, or filling the new bytes with `value`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactBytes;
+    /// let mut bytes = CompactBytes::from(&[1, 2, 3][..]);
+    ///
+    /// bytes.resize(5, 0);
+    /// assert_eq!(bytes, [1, 2, 3, 0, 0]);
+    ///
+    /// bytes.resize(2, 0);
+    /// assert_eq!(bytes, [1, 2]);
+    /// ```
+    #[inline]
+    pub fn resize(&mut self, new_len: usize, value: u8) {
+        let len = self.len();
+        if new_len <= len {
+            self.truncate(new_len);
+            return;
+        }
+
+        self.reserve(new_len - len);
+        // SAFETY: We reserved enough space, which `Repr::try_reserve_bytes()` made sure is never
+        // the last byte of an inline `Repr`, and any bytes are valid here
+        unsafe {
+            let buffer = self.repr.as_mut_slice();
+            for byte in &mut buffer[len..new_len] {
+                *byte = value;
+            }
+            self.repr.set_len(new_len);
+        }
+    }
+
+    /// Truncates this [`CompactBytes`], removing all of its bytes.
+    ///
+    /// Note: this doesn't change the capacity, see [`CompactBytes::shrink_to_fit()`].
+    #[inline]
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Splits this [`CompactBytes`] into two at the given index, returning the bytes from `at` to
+    /// the end as a new [`CompactBytes`].
+    ///
+    /// # Panics
+    /// Panics if `at` is larger than the [`CompactBytes`]'s length.
+    #[inline]
+    pub fn split_off(&mut self, at: usize) -> Self {
+        let other = CompactBytes::from(&self.as_slice()[at..]);
+        self.truncate(at);
+        other
+    }
+
+    /// Converts this [`CompactBytes`] into a [`CompactStr`], without copying, if the bytes are
+    /// valid UTF-8.
+    ///
+    /// # Errors
+    /// Returns a [`FromUtf8Error`], which contains a copy of the bytes, if they aren't valid UTF-8.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactBytes;
+    /// let bytes = CompactBytes::from(&b"this is long enough to be heap allocated"[..]);
+    /// let ptr = bytes.as_ptr();
+    ///
+    /// let compact = bytes.into_compact_str().unwrap();
+    /// assert_eq!(compact, "this is long enough to be heap allocated");
+    /// assert_eq!(compact.as_ptr(), ptr);
+    ///
+    /// let invalid = CompactBytes::from(&[b'o', b'k', 0xFF][..]);
+    /// let err = invalid.into_compact_str().unwrap_err();
+    /// assert_eq!(err.utf8_error().valid_up_to(), 2);
+    /// ```
+    #[inline]
+    pub fn into_compact_str(self) -> Result<CompactStr, FromUtf8Error> {
+        match core::str::from_utf8(self.as_slice()) {
+            Ok(_) => Ok(CompactStr { repr: self.repr }),
+            Err(e) => Err(FromUtf8Error::new(self.as_slice().to_vec(), e)),
+        }
+    }
+
+    /// Converts this [`CompactBytes`] into a [`Vec<u8>`].
+    ///
+    /// Like [`CompactStr::into_bytes`], this re-uses the heap allocation if possible.
+    #[inline]
+    pub fn into_vec(self) -> Vec<u8> {
+        self.repr.into_vec()
+    }
+
+    /// Returns `true` if the [`CompactBytes`] is heap allocated, `false` otherwise.
+    #[inline]
+    pub fn is_heap_allocated(&self) -> bool {
+        self.repr.is_heap_allocated()
+    }
+}
+
+impl Default for CompactBytes {
+    #[inline]
+    fn default() -> Self {
+        CompactBytes::new()
+    }
+}
+
+impl Deref for CompactBytes {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl DerefMut for CompactBytes {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [u8] {
+        self.as_mut_slice()
+    }
+}
+
+impl AsRef<[u8]> for CompactBytes {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl AsMut<[u8]> for CompactBytes {
+    #[inline]
+    fn as_mut(&mut self) -> &mut [u8] {
+        self.as_mut_slice()
+    }
+}
+
+impl Borrow<[u8]> for CompactBytes {
+    #[inline]
+    fn borrow(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl BorrowMut<[u8]> for CompactBytes {
+    #[inline]
+    fn borrow_mut(&mut self) -> &mut [u8] {
+        self.as_mut_slice()
+    }
+}
+
+impl Eq for CompactBytes {}
+
+impl<T: AsRef<[u8]>> PartialEq<T> for CompactBytes {
+    fn eq(&self, other: &T) -> bool {
+        self.as_slice() == other.as_ref()
+    }
+}
+
+impl PartialEq<CompactBytes> for Vec<u8> {
+    fn eq(&self, other: &CompactBytes) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl PartialEq<CompactBytes> for [u8] {
+    fn eq(&self, other: &CompactBytes) -> bool {
+        self == other.as_slice()
+    }
+}
+
+impl PartialEq<CompactBytes> for &[u8] {
+    fn eq(&self, other: &CompactBytes) -> bool {
+        *self == other.as_slice()
+    }
+}
+
+impl Ord for CompactBytes {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_slice().cmp(other.as_slice())
+    }
+}
+
+impl PartialOrd for CompactBytes {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Hash for CompactBytes {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state)
+    }
+}
+
+impl<'a> From<&'a [u8]> for CompactBytes {
+    fn from(bytes: &'a [u8]) -> Self {
+        CompactBytes {
+            repr: Repr::from_bytes_in(bytes, Global),
+        }
+    }
+}
+
+impl<'a> From<&'a Vec<u8>> for CompactBytes {
+    fn from(bytes: &'a Vec<u8>) -> Self {
+        CompactBytes::from(bytes.as_slice())
+    }
+}
+
+/// Converts a [`Vec<u8>`] into a [`CompactBytes`], inlining the bytes if they're short enough,
+/// otherwise re-using the heap allocated buffer.
+impl From<Vec<u8>> for CompactBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        CompactBytes {
+            repr: Repr::from_vec(bytes),
+        }
+    }
+}
+
+/// Converts a [`Box<[u8]>`] into a [`CompactBytes`], see `From<Vec<u8>>`
+impl From<Box<[u8]>> for CompactBytes {
+    fn from(bytes: Box<[u8]>) -> Self {
+        CompactBytes::from(bytes.into_vec())
+    }
+}
+
+/// Converts a [`CompactStr`] into a [`CompactBytes`], without copying, unless the string is
+/// inline and exactly 24 bytes long, see the [memory layout](CompactBytes#memory-layout).
+impl From<CompactStr> for CompactBytes {
+    fn from(s: CompactStr) -> Self {
+        CompactBytes {
+            repr: s.repr.into_bytes_repr(),
+        }
+    }
+}
+
+impl From<CompactBytes> for Vec<u8> {
+    fn from(bytes: CompactBytes) -> Self {
+        bytes.into_vec()
+    }
+}
+
+impl fmt::Debug for CompactBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_slice(), f)
+    }
+}
+
+impl Extend<u8> for CompactBytes {
+    fn extend<T: IntoIterator<Item = u8>>(&mut self, iter: T) {
+        let iter = iter.into_iter();
+        let (lower_bound, _) = iter.size_hint();
+        self.reserve(lower_bound);
+        iter.for_each(move |byte| self.push(byte));
+    }
+}
+
+impl<'a> Extend<&'a u8> for CompactBytes {
+    fn extend<T: IntoIterator<Item = &'a u8>>(&mut self, iter: T) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<'a> Extend<&'a [u8]> for CompactBytes {
+    fn extend<T: IntoIterator<Item = &'a [u8]>>(&mut self, iter: T) {
+        iter.into_iter()
+            .for_each(move |bytes| self.extend_from_slice(bytes));
+    }
+}
+
+impl FromIterator<u8> for CompactBytes {
+    fn from_iter<T: IntoIterator<Item = u8>>(iter: T) -> Self {
+        let mut bytes = CompactBytes::new();
+        bytes.extend(iter);
+        bytes
+    }
+}
+
+impl<'a> FromIterator<&'a u8> for CompactBytes {
+    fn from_iter<T: IntoIterator<Item = &'a u8>>(iter: T) -> Self {
+        let mut bytes = CompactBytes::new();
+        bytes.extend(iter);
+        bytes
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Write for CompactBytes {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use core::mem::size_of;
+
+    use proptest::prelude::*;
+    use test_strategy::proptest;
+
+    use super::CompactBytes;
+    use crate::tests::{
+        rand_bytes,
+        rand_unicode,
+    };
+    use crate::CompactStr;
+
+    const MAX_SIZE: usize = size_of::<String>();
+    const MAX_INLINE: usize = MAX_SIZE - 1;
+
+    #[test]
+    fn test_size() {
+        assert_eq!(size_of::<CompactBytes>(), size_of::<Vec<u8>>());
+        assert_eq!(size_of::<Option<CompactBytes>>(), size_of::<Vec<u8>>());
+    }
+
+    #[test]
+    fn test_max_inline() {
+        // bytes that look like our length and discriminant, if we stored them in the last byte
+        for last in [0xC0, 0xFE, 0xFF].iter().copied() {
+            let mut bytes = vec![0xFF; MAX_INLINE];
+            let compact = CompactBytes::from(&bytes);
+            assert_eq!(compact, bytes);
+            assert!(!compact.is_heap_allocated());
+
+            bytes.push(last);
+            let compact = CompactBytes::from(&bytes);
+            assert_eq!(compact, bytes);
+            assert!(compact.is_heap_allocated());
+        }
+    }
+
+    #[test]
+    fn test_push_never_writes_last_byte_inline() {
+        let mut compact = CompactBytes::new();
+        let mut control = Vec::new();
+
+        for _ in 0..MAX_SIZE + 8 {
+            compact.push(0xFF);
+            control.push(0xFF);
+            assert_eq!(compact, control);
+            assert_eq!(compact.is_heap_allocated(), control.len() > MAX_INLINE);
+        }
+
+        while let Some(byte) = control.pop() {
+            assert_eq!(compact.pop(), Some(byte));
+            assert_eq!(compact, control);
+        }
+        assert_eq!(compact.pop(), None);
+    }
+
+    #[test]
+    fn test_extend_small_capacity_heap() {
+        // a heap allocated `CompactStr` can have less capacity than we can store inline
+        let word = "abcdefghijklmnopqrstuvw";
+        let compact = CompactStr::from_string_preserve_capacity(word.into());
+        assert!(compact.is_heap_allocated());
+
+        let mut bytes = CompactBytes::from(compact);
+        bytes.extend_from_slice(&[0xFF]);
+
+        let mut control = word.as_bytes().to_vec();
+        control.push(0xFF);
+        assert_eq!(bytes.as_slice(), control.as_slice());
+        assert_eq!(bytes.len(), word.len() + 1);
+        assert!(bytes.is_heap_allocated());
+    }
+
+    #[test]
+    fn test_vec_roundtrip_reuses_allocation() {
+        let control = vec![0xFF; 64];
+        let ptr = control.as_ptr();
+
+        let compact = CompactBytes::from(control);
+        assert_eq!(compact, vec![0xFF; 64]);
+        assert_eq!(compact.as_ptr(), ptr);
+
+        let control = compact.into_vec();
+        assert_eq!(control, vec![0xFF; 64]);
+        assert_eq!(control.as_ptr(), ptr);
+
+        let compact = CompactBytes::from(control.into_boxed_slice());
+        assert_eq!(compact.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn test_shrink_to_fit() {
+        let mut compact = CompactBytes::from(vec![0xFE; 64]);
+        compact.truncate(MAX_SIZE);
+        compact.shrink_to_fit();
+        assert!(compact.is_heap_allocated());
+        assert_eq!(compact, vec![0xFE; MAX_SIZE]);
+
+        compact.truncate(MAX_INLINE);
+        compact.shrink_to_fit();
+        assert!(!compact.is_heap_allocated());
+        assert_eq!(compact, vec![0xFE; MAX_INLINE]);
+
+        // the last byte of an inline `Repr` is still our length
+        compact.as_mut_slice().iter_mut().for_each(|b| *b = 0xFF);
+        assert_eq!(compact.len(), MAX_INLINE);
+    }
+
+    #[test]
+    fn test_edits() {
+        let mut compact = CompactBytes::from(&b"hello"[..]);
+
+        compact.insert(0, b'>');
+        compact.insert(6, b'!');
+        assert_eq!(compact, b">hello!");
+
+        assert_eq!(compact.remove(0), b'>');
+        assert_eq!(compact, b"hello!");
+
+        compact.retain(|b| b != b'l');
+        assert_eq!(compact, b"heo!");
+
+        let other = compact.split_off(2);
+        assert_eq!(compact, b"he");
+        assert_eq!(other, b"o!");
+
+        compact.resize(4, 0);
+        assert_eq!(compact, b"he\0\0");
+
+        compact.clear();
+        assert!(compact.is_empty());
+    }
+
+    #[test]
+    fn test_compact_str_roundtrip() {
+        let long = "this string is long enough to be heap allocated";
+        let compact = CompactStr::new(long);
+        let ptr = compact.as_ptr();
+
+        let bytes = CompactBytes::from(compact);
+        assert_eq!(bytes.as_ptr(), ptr);
+
+        let compact = bytes.into_compact_str().unwrap();
+        assert_eq!(compact, long);
+        assert_eq!(compact.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn test_from_max_inline_compact_str() {
+        let word = "a".repeat(MAX_SIZE);
+        let compact = CompactStr::new(&word);
+        assert!(!compact.is_heap_allocated());
+
+        let mut bytes = CompactBytes::from(compact);
+        assert!(bytes.is_heap_allocated());
+        assert_eq!(bytes, word);
+
+        bytes.as_mut_slice()[MAX_SIZE - 1] = 0xFF;
+        assert_eq!(bytes.len(), MAX_SIZE);
+        assert_eq!(bytes.last(), Some(&0xFF));
+    }
+
+    #[test]
+    fn test_from_static_compact_str() {
+        let mut compact = CompactStr::from_static_str("this is a long &'static str");
+        compact.truncate(MAX_SIZE);
+
+        let mut bytes = CompactBytes::from(compact);
+        bytes.as_mut_slice()[MAX_SIZE - 1] = 0xFF;
+        assert_eq!(bytes.len(), MAX_SIZE);
+        assert_eq!(bytes.last(), Some(&0xFF));
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_roundtrip(#[strategy(rand_bytes())] control: Vec<u8>) {
+        let compact = CompactBytes::from(&control);
+        prop_assert_eq!(&compact, &control);
+        prop_assert_eq!(compact.is_heap_allocated(), control.len() > MAX_INLINE);
+        prop_assert_eq!(compact.into_vec(), control);
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_extend_matches_vec(
+        #[strategy(rand_bytes())] a: Vec<u8>,
+        #[strategy(rand_bytes())] b: Vec<u8>,
+    ) {
+        let mut compact: CompactBytes = a.iter().collect();
+        compact.extend_from_slice(&b);
+
+        let mut control = a.clone();
+        control.extend_from_slice(&b);
+
+        prop_assert_eq!(&compact, &control);
+        prop_assert!(compact.capacity() >= compact.len());
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_into_compact_str_only_valid_utf8(#[strategy(rand_bytes())] control: Vec<u8>) {
+        let compact = CompactBytes::from(&control);
+
+        match (compact.into_compact_str(), core::str::from_utf8(&control)) {
+            (Ok(c), Ok(s)) => prop_assert_eq!(c, s),
+            (Err(c_err), Err(s_err)) => {
+                prop_assert_eq!(c_err.utf8_error(), s_err);
+                prop_assert_eq!(c_err.into_bytes(), control);
+            }
+            _ => panic!("CompactBytes and core::str read UTF-8 differently?"),
+        }
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_compact_str_roundtrip(#[strategy(rand_unicode())] word: String) {
+        let bytes = CompactBytes::from(CompactStr::new(&word));
+        prop_assert_eq!(bytes.as_slice(), word.as_bytes());
+
+        let compact = bytes.into_compact_str().unwrap();
+        prop_assert_eq!(compact, word);
+    }
+}
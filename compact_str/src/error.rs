@@ -0,0 +1,191 @@
+//! Error types returned by fallible [`CompactStr`](crate::CompactStr) APIs
+
+use alloc::vec::Vec;
+use core::alloc::Layout;
+use core::fmt;
+use core::str::Utf8Error;
+
+/// The error type for `try_reserve` methods, e.g. [`CompactStr::try_reserve`].
+///
+/// Modeled after the `TryReserveError` from the standard library, which is only available in
+/// Rust 1.57 and newer.
+///
+/// [`CompactStr::try_reserve`]: crate::CompactStr::try_reserve
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TryReserveError {
+    kind: TryReserveErrorKind,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) enum TryReserveErrorKind {
+    /// The computed capacity exceeded the maximum we can store, e.g. `usize::MAX`
+    CapacityOverflow,
+    /// The memory allocator returned an error
+    AllocError { layout: Layout },
+}
+
+impl TryReserveError {
+    #[inline]
+    pub(crate) const fn capacity_overflow() -> Self {
+        TryReserveError {
+            kind: TryReserveErrorKind::CapacityOverflow,
+        }
+    }
+
+    #[inline]
+    pub(crate) const fn alloc_error(layout: Layout) -> Self {
+        TryReserveError {
+            kind: TryReserveErrorKind::AllocError { layout },
+        }
+    }
+
+    /// Returns `true` if the error was caused by the computed capacity overflowing, as opposed
+    /// to the allocator failing.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let mut s = CompactStr::new("hello");
+    /// let err = s.try_reserve(usize::MAX).unwrap_err();
+    ///
+    /// assert!(err.is_capacity_overflow());
+    /// ```
+    #[inline]
+    pub fn is_capacity_overflow(&self) -> bool {
+        matches!(self.kind, TryReserveErrorKind::CapacityOverflow)
+    }
+}
+
+impl fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("memory allocation failed")?;
+        let reason = match self.kind {
+            TryReserveErrorKind::CapacityOverflow => {
+                " because the computed capacity exceeded the collection's maximum"
+            }
+            TryReserveErrorKind::AllocError { .. } => {
+                " because the memory allocator returned an error"
+            }
+        };
+        f.write_str(reason)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TryReserveError {}
+
+/// The error type returned when converting UTF-16 to a [`CompactStr`], e.g. with
+/// [`CompactStr::from_utf16`].
+///
+/// Modeled after the `FromUtf16Error` from the standard library, which can't be constructed
+/// outside of `std`.
+///
+/// [`CompactStr`]: crate::CompactStr
+/// [`CompactStr::from_utf16`]: crate::CompactStr::from_utf16
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Utf16Error(());
+
+impl Utf16Error {
+    #[inline]
+    pub(crate) const fn new() -> Self {
+        Utf16Error(())
+    }
+}
+
+impl fmt::Display for Utf16Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid utf-16: lone surrogate found")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Utf16Error {}
+
+/// The error type returned when converting bytes to a [`CompactStr`], e.g. with
+/// [`CompactStr::from_utf8`], if the bytes aren't valid UTF-8.
+///
+/// Like the `FromUtf8Error` from the standard library, it gives back the bytes that were
+/// consumed, via [`FromUtf8Error::into_bytes`], and the reason the conversion failed, via
+/// [`FromUtf8Error::utf8_error`].
+///
+/// # Examples
+/// ```
+/// # use compact_str::CompactStr;
+/// // some invalid bytes, in a vector
+/// let bytes = vec![0, 159];
+///
+/// let err = CompactStr::from_utf8(bytes).unwrap_err();
+///
+/// assert_eq!(err.utf8_error().valid_up_to(), 1);
+/// assert_eq!(err.into_bytes(), vec![0, 159]);
+/// ```
+///
+/// [`CompactStr`]: crate::CompactStr
+/// [`CompactStr::from_utf8`]: crate::CompactStr::from_utf8
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FromUtf8Error {
+    bytes: Vec<u8>,
+    error: Utf8Error,
+}
+
+impl FromUtf8Error {
+    #[inline]
+    pub(crate) fn new(bytes: Vec<u8>, error: Utf8Error) -> Self {
+        FromUtf8Error { bytes, error }
+    }
+
+    /// Returns a slice of the bytes that were attempted to convert to a
+    /// [`CompactStr`](crate::CompactStr).
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the bytes that were attempted to convert to a [`CompactStr`](crate::CompactStr).
+    ///
+    /// This method is carefully constructed to avoid allocation. It will consume the error,
+    /// moving out the bytes, so that a copy of the bytes does not need to be made.
+    #[inline]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Returns a [`Utf8Error`] with more details about the conversion failure, e.g. the index of
+    /// the first invalid byte.
+    #[inline]
+    pub fn utf8_error(&self) -> Utf8Error {
+        self.error
+    }
+}
+
+impl fmt::Display for FromUtf8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FromUtf8Error {}
+
+/// Unwraps the result of a fallible allocation, panicking on capacity overflow, or calling
+/// [`handle_alloc_error`] if the allocator failed, which is the same behavior as `std`.
+///
+/// [`handle_alloc_error`]: alloc::alloc::handle_alloc_error
+#[inline]
+pub(crate) fn handle_reserve<T>(result: Result<T, TryReserveError>) -> T {
+    match result {
+        Ok(val) => val,
+        Err(TryReserveError {
+            kind: TryReserveErrorKind::CapacityOverflow,
+        }) => capacity_overflow(),
+        Err(TryReserveError {
+            kind: TryReserveErrorKind::AllocError { layout },
+        }) => alloc::alloc::handle_alloc_error(layout),
+    }
+}
+
+#[inline(never)]
+#[cold]
+fn capacity_overflow() -> ! {
+    panic!("capacity overflow");
+}
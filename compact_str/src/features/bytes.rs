@@ -1,13 +1,25 @@
-use core::str::Utf8Error;
+use core::convert::TryFrom;
+use core::fmt;
 
-use bytes::Buf;
+use bytes::buf::UninitSlice;
+use bytes::{
+    Buf,
+    BufMut,
+    Bytes,
+};
 
 use crate::{
+    CompactBytes,
     CompactStr,
+    CompactStrN,
+    FromUtf8Error,
+    Global,
     Repr,
+    Storage,
+    MAX_SIZE,
 };
 
-impl CompactStr {
+impl<S: Storage<Global>> CompactStrN<MAX_SIZE, Global, S> {
     /// Converts a buffer of bytes to a [`CompactStr`]
     ///
     /// # Examples
@@ -27,16 +39,21 @@ impl CompactStr {
     /// ### With invalid/non-UTF8 bytes
     /// ```
     /// # use compact_str::CompactStr;
+    /// # use bytes::Buf;
     /// # use std::io;
     ///
     /// // `bytes::Buf` is implemented for `std::io::Cursor<&[u8]>`
     /// let mut invalid = io::Cursor::new(&[0, 159]);
     ///
     /// // The provided buffer is invalid, so trying to create a `ComapctStr` will fail
-    /// assert!(CompactStr::from_utf8_buf(&mut invalid).is_err());
+    /// let err = CompactStr::from_utf8_buf(&mut invalid).unwrap_err();
+    ///
+    /// // The bytes have been consumed from the buffer, but are given back in the error
+    /// assert!(!invalid.has_remaining());
+    /// assert_eq!(err.into_bytes(), vec![0, 159]);
     /// ```
-    pub fn from_utf8_buf<B: Buf>(buf: &mut B) -> Result<Self, Utf8Error> {
-        Repr::from_utf8_buf(buf).map(|repr| CompactStr { repr })
+    pub fn from_utf8_buf<B: Buf>(buf: &mut B) -> Result<Self, FromUtf8Error> {
+        Repr::from_utf8_buf(buf).map(|repr| CompactStrN { repr })
     }
 
     /// Converts a buffer of bytes to a [`CompactStr`], without checking that the provided buffer is
@@ -61,14 +78,292 @@ impl CompactStr {
     /// ```
     pub unsafe fn from_utf8_buf_unchecked<B: Buf>(buf: &mut B) -> Self {
         let repr = Repr::from_utf8_buf_unchecked(buf);
-        CompactStr { repr }
+        CompactStrN { repr }
+    }
+}
+
+/// Converts [`Bytes`] to a [`CompactStr`], failing if the bytes aren't valid UTF-8.
+///
+/// Short strings are inlined. Longer strings take ownership of the buffer of the [`Bytes`], without
+/// copying, if it's the only reference to a buffer that was allocated as a `Vec<u8>`, e.g. a
+/// `BytesMut` that was frozen, otherwise they're copied.
+///
+/// Note: A heap allocated [`SharedCompactStr`](crate::SharedCompactStr) needs to store a reference
+/// count next to the string, so longer strings are always copied.
+///
+/// # Examples
+/// ```
+/// # use compact_str::CompactStr;
+/// use std::convert::TryFrom;
+///
+/// use bytes::Bytes;
+///
+/// let bytes = Bytes::from(b"text/html; charset=utf-8".to_vec());
+/// let compact = CompactStr::try_from(bytes).unwrap();
+/// assert_eq!(compact, "text/html; charset=utf-8");
+///
+/// let invalid = Bytes::from_static(&[0, 159]);
+/// let err = CompactStr::try_from(invalid).unwrap_err();
+/// assert_eq!(err.as_bytes(), &[0, 159]);
+/// ```
+impl<S: Storage<Global>> TryFrom<Bytes> for CompactStrN<MAX_SIZE, Global, S> {
+    type Error = FromUtf8Error;
+
+    fn try_from(bytes: Bytes) -> Result<Self, Self::Error> {
+        let repr = Repr::from_utf8_bytes(bytes)?;
+        Ok(CompactStrN { repr })
+    }
+}
+
+/// Converts a [`CompactStr`] into [`Bytes`].
+///
+/// Inline strings are copied. Heap allocated strings hand their buffer to the [`Bytes`] without
+/// copying, or for a [`SharedCompactStr`](crate::SharedCompactStr), share their reference counted
+/// buffer with it.
+/// Strings created from a `&'static str` stay borrowed.
+///
+/// # Examples
+/// ```
+/// # use compact_str::CompactStr;
+/// use bytes::Bytes;
+///
+/// let compact = CompactStr::new("this string is too long to be stored inline");
+/// let ptr = compact.as_ptr();
+///
+/// let bytes = Bytes::from(compact);
+/// assert_eq!(bytes, "this string is too long to be stored inline");
+/// assert_eq!(bytes.as_ptr(), ptr);
+/// ```
+impl<S: Storage<Global>> From<CompactStrN<MAX_SIZE, Global, S>> for Bytes {
+    fn from(s: CompactStrN<MAX_SIZE, Global, S>) -> Self {
+        s.repr.into_bytes()
+    }
+}
+
+/// A [`BufMut`] that writes bytes directly into a [`CompactStr`], checking that they're valid
+/// UTF-8 when it's finished.
+///
+/// Short strings are written into the inline buffer of the [`CompactStr`], and longer strings
+/// grow onto the heap as needed.
+///
+/// # Examples
+/// ```
+/// use bytes::BufMut;
+/// use compact_str::{CompactStr, CompactStrWriter};
+///
+/// let mut writer = CompactStrWriter::new();
+/// writer.put_slice(b"GET ");
+/// writer.put_slice(b"/a.html");
+///
+/// let compact = writer.finish().unwrap();
+/// assert_eq!(compact, "GET /a.html");
+/// assert!(!compact.is_heap_allocated());
+///
+/// // bytes are only checked to be valid UTF-8 once we're done writing
+/// let mut writer = CompactStrWriter::from(CompactStr::new("ok"));
+/// writer.put_u8(0xFF);
+///
+/// let err = writer.finish().unwrap_err();
+/// assert_eq!(err.utf8_error().valid_up_to(), 2);
+/// assert_eq!(err.into_bytes(), b"ok\xFF");
+/// ```
+pub struct CompactStrWriter {
+    // Note: The bytes in this `Repr` might not be valid UTF-8, until `Repr::into_utf8()` checks
+    repr: Repr,
+}
+
+impl CompactStrWriter {
+    /// Creates a new, empty [`CompactStrWriter`], which writes inline until it runs out of space.
+    #[inline]
+    pub fn new() -> Self {
+        CompactStrWriter {
+            repr: Repr::new(""),
+        }
+    }
+
+    /// Creates a new, empty [`CompactStrWriter`], which can write at least `capacity` bytes
+    /// without reallocating.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        CompactStrWriter {
+            repr: Repr::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the number of bytes that have been written.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.repr.len()
+    }
+
+    /// Returns `true` if no bytes have been written.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the bytes that have been written, which might not be valid UTF-8.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.repr.as_slice()[..self.len()]
+    }
+
+    /// Consumes the [`CompactStrWriter`], returning a [`CompactStr`] if all of the bytes written
+    /// are valid UTF-8, otherwise returning a [`FromUtf8Error`] that contains them.
+    ///
+    /// If the string is short enough, it gets stored inline.
+    #[inline]
+    pub fn finish(self) -> Result<CompactStr, FromUtf8Error> {
+        let repr = self.repr.into_utf8()?;
+        Ok(CompactStr { repr })
+    }
+}
+
+impl Default for CompactStrWriter {
+    #[inline]
+    fn default() -> Self {
+        CompactStrWriter::new()
+    }
+}
+
+impl From<CompactStr> for CompactStrWriter {
+    /// Creates a [`CompactStrWriter`] that appends to an existing [`CompactStr`].
+    #[inline]
+    fn from(s: CompactStr) -> Self {
+        CompactStrWriter { repr: s.repr }
+    }
+}
+
+impl fmt::Debug for CompactStrWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompactStrWriter")
+            .field("bytes", &self.as_bytes())
+            .finish()
+    }
+}
+
+unsafe impl BufMut for CompactStrWriter {
+    #[inline]
+    fn remaining_mut(&self) -> usize {
+        // Like a `Vec<u8>`, we can never have more than `isize::MAX` bytes
+        isize::MAX as usize - self.len()
+    }
+
+    #[inline]
+    unsafe fn advance_mut(&mut self, cnt: usize) {
+        let len = self.len();
+        let remaining = self.repr.spare_capacity();
+        assert!(
+            cnt <= remaining,
+            "cannot advance past `remaining_mut`: {:?} <= {:?}",
+            cnt,
+            remaining,
+        );
+
+        // SAFETY: The caller initialized the next `cnt` bytes, which we'll check are valid UTF-8
+        // before handing out the `Repr`
+        self.repr.set_len(len + cnt);
+    }
+
+    #[inline]
+    fn chunk_mut(&mut self) -> &mut UninitSlice {
+        // SAFETY: We check that any bytes written are valid UTF-8 in `CompactStrWriter::finish()`
+        let spare = unsafe { self.repr.spare_capacity_mut() };
+        UninitSlice::new(spare)
+    }
+}
+
+impl CompactBytes {
+    /// Copies the remaining bytes of a [`Buf`] into a new [`CompactBytes`], advancing the buffer
+    /// to its end.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactBytes;
+    /// use bytes::Buf;
+    ///
+    /// // `bytes::Buf` is implemented for chains of buffers
+    /// let mut buf = (&[0xCA, 0xFE][..]).chain(&[0xBA, 0xBE][..]);
+    /// let bytes = CompactBytes::from_buf(&mut buf);
+    ///
+    /// assert_eq!(bytes, [0xCA, 0xFE, 0xBA, 0xBE]);
+    /// assert!(!buf.has_remaining());
+    /// ```
+    pub fn from_buf<B: Buf>(buf: &mut B) -> Self {
+        let mut bytes = CompactBytes::with_capacity(buf.remaining());
+        while buf.has_remaining() {
+            let chunk = buf.chunk();
+            let chunk_len = chunk.len();
+            bytes.extend_from_slice(chunk);
+            buf.advance(chunk_len);
+        }
+        bytes
+    }
+}
+
+/// Like a `Vec<u8>`, a [`CompactBytes`] can be written into directly, growing onto the heap as
+/// needed.
+///
+/// # Examples
+/// ```
+/// # use compact_str::CompactBytes;
+/// use bytes::BufMut;
+///
+/// let mut bytes = CompactBytes::new();
+/// bytes.put_u32(0xDEADBEEF);
+/// bytes.put_slice(&[0xFF; 4]);
+///
+/// assert_eq!(bytes, [0xDE, 0xAD, 0xBE, 0xEF, 0xFF, 0xFF, 0xFF, 0xFF]);
+/// assert!(!bytes.is_heap_allocated());
+/// ```
+unsafe impl BufMut for CompactBytes {
+    #[inline]
+    fn remaining_mut(&self) -> usize {
+        // Like a `Vec<u8>`, we can never have more than `isize::MAX` bytes
+        isize::MAX as usize - self.len()
+    }
+
+    #[inline]
+    unsafe fn advance_mut(&mut self, cnt: usize) {
+        let len = self.len();
+        let remaining = self.capacity() - len;
+        assert!(
+            cnt <= remaining,
+            "cannot advance past `remaining_mut`: {:?} <= {:?}",
+            cnt,
+            remaining,
+        );
+
+        // SAFETY: The caller initialized the next `cnt` bytes, and any bytes are valid here
+        self.repr.set_len(len + cnt);
+    }
+
+    #[inline]
+    fn chunk_mut(&mut self) -> &mut UninitSlice {
+        if self.capacity() == self.len() {
+            // Note: this grows by at least the same amount as a `Vec<u8>` does
+            self.reserve(64);
+        }
+
+        let len = self.len();
+        let capacity = self.capacity();
+        // SAFETY: The capacity of an inline `CompactBytes` excludes the last byte of its `Repr`,
+        // so arbitrary bytes can be written into all of it
+        let spare = unsafe { &mut self.repr.as_mut_slice()[len..capacity] };
+        UninitSlice::new(spare)
     }
 }
 
 #[cfg(test)]
 mod test {
+    use core::convert::TryFrom;
     use std::io::Cursor;
 
+    use bytes::{
+        BufMut,
+        Bytes,
+        BytesMut,
+    };
     use proptest::prelude::*;
     use test_strategy::proptest;
 
@@ -76,7 +371,12 @@ mod test {
         rand_bytes,
         rand_unicode,
     };
-    use crate::CompactStr;
+    use crate::{
+        CompactBytes,
+        CompactStr,
+        CompactStrWriter,
+        SharedCompactStr,
+    };
 
     const MAX_SIZE: usize = core::mem::size_of::<String>();
 
@@ -112,8 +412,245 @@ mod test {
 
         match (compact_result, str_result) {
             (Ok(c), Ok(s)) => prop_assert_eq!(c, s),
-            (Err(c_err), Err(s_err)) => prop_assert_eq!(c_err, s_err),
+            (Err(c_err), Err(s_err)) => {
+                prop_assert_eq!(c_err.utf8_error(), s_err);
+                prop_assert_eq!(c_err.as_bytes(), &bytes[..]);
+            }
             _ => panic!("CompactStr and core::str read UTF-8 differently?"),
         }
     }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn test_bytes_roundtrip(#[strategy(rand_unicode())] word: String) {
+        let compact = CompactStr::try_from(Bytes::copy_from_slice(word.as_bytes())).unwrap();
+        prop_assert_eq!(&compact, &word);
+
+        if word.len() <= MAX_SIZE {
+            prop_assert!(!compact.is_heap_allocated())
+        } else {
+            prop_assert!(compact.is_heap_allocated())
+        }
+
+        let bytes = Bytes::from(compact);
+        prop_assert_eq!(bytes, word.as_bytes());
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn test_try_from_bytes_only_valid_utf8(#[strategy(rand_bytes())] bytes: Vec<u8>) {
+        let compact_result = CompactStr::try_from(Bytes::from(bytes.clone()));
+        let str_result = core::str::from_utf8(bytes.as_slice());
+
+        match (compact_result, str_result) {
+            (Ok(c), Ok(s)) => prop_assert_eq!(c, s),
+            (Err(c_err), Err(s_err)) => {
+                prop_assert_eq!(c_err.utf8_error(), s_err);
+                prop_assert_eq!(c_err.into_bytes(), bytes);
+            }
+            _ => panic!("CompactStr and core::str read UTF-8 differently?"),
+        }
+    }
+
+    #[test]
+    fn test_try_from_unique_bytes_reuses_buffer() {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"this string is too long to be stored inline");
+        let bytes = buf.freeze();
+        let ptr = bytes.as_ptr();
+
+        let compact = CompactStr::try_from(bytes).unwrap();
+        assert_eq!(compact.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn test_try_from_unique_bytes_shared_storage_copies() {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"this string is too long to be stored inline");
+        let bytes = buf.freeze();
+        let ptr = bytes.as_ptr();
+
+        let compact = SharedCompactStr::try_from(bytes).unwrap();
+        assert_eq!(compact, "this string is too long to be stored inline");
+        assert_ne!(compact.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn test_try_from_shared_bytes_copies() {
+        let bytes = Bytes::from(b"this string is too long to be stored inline".to_vec());
+        let other = bytes.clone();
+
+        let compact = CompactStr::try_from(bytes).unwrap();
+        assert_eq!(compact, "this string is too long to be stored inline");
+        assert_ne!(compact.as_ptr(), other.as_ptr());
+    }
+
+    #[test]
+    fn test_into_bytes_shares_heap_buffer() {
+        let compact = CompactStr::new("this string is too long to be stored inline");
+        let ptr = compact.as_ptr();
+
+        let bytes = Bytes::from(compact);
+        assert_eq!(bytes, "this string is too long to be stored inline");
+        assert_eq!(bytes.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn test_into_bytes_shares_arc_buffer() {
+        let compact = SharedCompactStr::new("this string is too long to be stored inline");
+        let substr = compact.substr(5..);
+
+        let bytes = Bytes::from(substr);
+        assert_eq!(bytes, "string is too long to be stored inline");
+        assert_eq!(bytes.as_ptr(), compact[5..].as_ptr());
+
+        // the buffer stays alive as long as the `Bytes` does
+        drop(compact);
+        assert_eq!(bytes, "string is too long to be stored inline");
+    }
+
+    #[test]
+    fn test_into_bytes_static_is_borrowed() {
+        const WORD: &str = "this string is too long to be stored inline";
+        let compact = CompactStr::from_static_str(WORD);
+
+        let bytes = Bytes::from(compact);
+        assert_eq!(bytes.as_ptr(), WORD.as_ptr());
+    }
+
+    #[test]
+    fn test_into_bytes_inline_copies() {
+        let compact = CompactStr::new("hello");
+        let bytes = Bytes::from(compact);
+        assert_eq!(bytes, "hello");
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn test_writer_roundtrips(
+        #[strategy(rand_unicode())] word: String,
+        #[strategy(1..8usize)] chunk_size: usize,
+    ) {
+        let mut writer = CompactStrWriter::new();
+        for chunk in word.as_bytes().chunks(chunk_size) {
+            writer.put_slice(chunk);
+        }
+        prop_assert_eq!(writer.as_bytes(), word.as_bytes());
+
+        let compact = writer.finish().unwrap();
+        prop_assert_eq!(&compact, &word);
+
+        if word.len() <= MAX_SIZE {
+            prop_assert!(!compact.is_heap_allocated())
+        } else {
+            prop_assert!(compact.is_heap_allocated())
+        }
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn test_writer_only_accepts_valid_utf8(#[strategy(rand_bytes())] bytes: Vec<u8>) {
+        let mut writer = CompactStrWriter::new();
+        for b in &bytes {
+            writer.put_u8(*b);
+        }
+
+        match (writer.finish(), core::str::from_utf8(&bytes)) {
+            (Ok(c), Ok(s)) => prop_assert_eq!(c, s),
+            (Err(c_err), Err(s_err)) => {
+                prop_assert_eq!(c_err.utf8_error(), s_err);
+                prop_assert_eq!(c_err.into_bytes(), bytes);
+            }
+            _ => panic!("CompactStr and core::str read UTF-8 differently?"),
+        }
+    }
+
+    #[test]
+    fn test_writer_never_writes_discriminant() {
+        let mut writer = CompactStrWriter::new();
+
+        // the last byte of an inline string is never handed out
+        assert_eq!(writer.chunk_mut().len(), MAX_SIZE - 1);
+
+        // write bytes that would look like a heap discriminant, if we wrote them inline
+        writer.put_bytes(0xFF, MAX_SIZE);
+        assert_eq!(writer.as_bytes(), &[0xFF; MAX_SIZE][..]);
+
+        let err = writer.finish().unwrap_err();
+        assert_eq!(err.into_bytes(), vec![0xFF; MAX_SIZE]);
+    }
+
+    #[test]
+    fn test_writer_max_inline_is_inlined() {
+        let word = "a".repeat(MAX_SIZE);
+
+        let mut writer = CompactStrWriter::new();
+        writer.put_slice(word.as_bytes());
+
+        let compact = writer.finish().unwrap();
+        assert_eq!(compact, word);
+        assert!(!compact.is_heap_allocated());
+    }
+
+    #[test]
+    fn test_writer_appends() {
+        let long = "this string is too long to be stored inline";
+        for base in [
+            CompactStr::new("short"),
+            CompactStr::new(long),
+            CompactStr::from_static_str(long),
+        ]
+        .iter()
+        {
+            let mut writer = CompactStrWriter::from(base.clone());
+            writer.put_slice(" 🦀".as_bytes());
+
+            let compact = writer.finish().unwrap();
+            assert_eq!(compact, format!("{} 🦀", base));
+            // the original isn't affected
+            assert!(!base.ends_with('🦀'));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot advance past `remaining_mut`")]
+    fn test_writer_advance_past_capacity() {
+        let mut writer = CompactStrWriter::new();
+        // SAFETY: We panic before any bytes are read
+        unsafe { writer.advance_mut(MAX_SIZE) };
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn test_compact_bytes_from_buf(#[strategy(rand_bytes())] bytes: Vec<u8>) {
+        let mut buf = &bytes[..];
+        let compact = CompactBytes::from_buf(&mut buf);
+
+        prop_assert!(buf.is_empty());
+        prop_assert_eq!(compact, bytes);
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn test_compact_bytes_buf_mut(#[strategy(rand_bytes())] bytes: Vec<u8>) {
+        let mut compact = CompactBytes::new();
+        for byte in &bytes {
+            compact.put_u8(*byte);
+        }
+
+        prop_assert_eq!(&compact, &bytes);
+        prop_assert_eq!(compact.is_heap_allocated(), bytes.len() >= MAX_SIZE);
+    }
+
+    #[test]
+    fn test_compact_bytes_never_writes_discriminant() {
+        let mut compact = CompactBytes::new();
+
+        // the last byte of an inline `Repr` is never handed out
+        assert_eq!(compact.chunk_mut().len(), MAX_SIZE - 1);
+
+        compact.put_bytes(0xFF, MAX_SIZE);
+        assert_eq!(compact, vec![0xFF; MAX_SIZE]);
+        assert!(compact.is_heap_allocated());
+    }
 }
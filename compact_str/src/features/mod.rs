@@ -1,6 +1,8 @@
 //! A module that contains the implementations for optional features. For example `serde` support
 
 #[cfg(feature = "bytes")]
-mod bytes;
+pub(crate) mod bytes;
+#[cfg(feature = "rkyv")]
+pub(crate) mod rkyv;
 #[cfg(feature = "serde")]
 mod serde;
@@ -0,0 +1,188 @@
+use core::cmp::Ordering;
+
+use rkyv::string::{
+    ArchivedString,
+    StringResolver,
+};
+use rkyv::{
+    Archive,
+    Deserialize,
+    DeserializeUnsized,
+    Fallible,
+    Serialize,
+    SerializeUnsized,
+};
+
+use crate::{
+    Allocator,
+    CompactStrN,
+    Global,
+    Storage,
+    MAX_SIZE,
+};
+
+/// The archived form of a [`CompactStr`](crate::CompactStr), or a
+/// [`SharedCompactStr`](crate::SharedCompactStr).
+///
+/// Like a `CompactStr`, short strings are stored inline in the archive, and longer strings are
+/// stored out of line, with a relative pointer to their bytes. When the `rkyv-validation` feature
+/// is enabled, it implements `CheckBytes`, so archives can be validated with
+/// `rkyv::check_archived_root`.
+///
+/// Note: `rkyv` needs one of its `size_16`, `size_32`, or `size_64` features, which you can enable
+/// on your own `rkyv` dependency, e.g. via its default features, or with our `rkyv-32` feature.
+///
+/// # Examples
+/// ```
+/// use compact_str::{ArchivedCompactStr, CompactStr};
+/// use rkyv::Deserialize;
+///
+/// let names = vec![
+///     CompactStr::new("short"),
+///     CompactStr::new("a name that's too long to be stored inline"),
+/// ];
+/// let bytes = rkyv::to_bytes::<_, 256>(&names).unwrap();
+///
+/// // SAFETY: We just serialized `names`, so `bytes` contains a valid archive
+/// let archived = unsafe { rkyv::archived_root::<Vec<CompactStr>>(&bytes) };
+/// let first: &ArchivedCompactStr = &archived[0];
+/// assert_eq!(first, "short");
+///
+/// let deserialized: Vec<CompactStr> = archived.deserialize(&mut rkyv::Infallible).unwrap();
+/// assert_eq!(deserialized, names);
+/// ```
+pub type ArchivedCompactStr = ArchivedString;
+
+impl<A: Allocator, S: Storage<A>> Archive for CompactStrN<MAX_SIZE, A, S> {
+    type Archived = ArchivedCompactStr;
+    type Resolver = StringResolver;
+
+    #[inline]
+    unsafe fn resolve(&self, pos: usize, resolver: Self::Resolver, out: *mut Self::Archived) {
+        ArchivedString::resolve_from_str(self.as_str(), pos, resolver, out);
+    }
+}
+
+impl<A: Allocator, S: Storage<A>, Ser: Fallible + ?Sized> Serialize<Ser>
+    for CompactStrN<MAX_SIZE, A, S>
+where
+    str: SerializeUnsized<Ser>,
+{
+    #[inline]
+    fn serialize(&self, serializer: &mut Ser) -> Result<Self::Resolver, Ser::Error> {
+        ArchivedString::serialize_from_str(self.as_str(), serializer)
+    }
+}
+
+impl<S: Storage<Global>, D: Fallible + ?Sized> Deserialize<CompactStrN<MAX_SIZE, Global, S>, D>
+    for ArchivedCompactStr
+where
+    str: DeserializeUnsized<str, D>,
+{
+    #[inline]
+    fn deserialize(&self, _: &mut D) -> Result<CompactStrN<MAX_SIZE, Global, S>, D::Error> {
+        Ok(CompactStrN::new(self.as_str()))
+    }
+}
+
+impl<A: Allocator, S: Storage<A>> PartialEq<CompactStrN<MAX_SIZE, A, S>> for ArchivedCompactStr {
+    #[inline]
+    fn eq(&self, other: &CompactStrN<MAX_SIZE, A, S>) -> bool {
+        PartialEq::eq(self.as_str(), other.as_str())
+    }
+}
+
+// Note: `PartialEq<ArchivedCompactStr> for CompactStr` is covered by `PartialEq<T: AsRef<str>>`
+
+impl<A: Allocator, S: Storage<A>> PartialOrd<CompactStrN<MAX_SIZE, A, S>> for ArchivedCompactStr {
+    #[inline]
+    fn partial_cmp(&self, other: &CompactStrN<MAX_SIZE, A, S>) -> Option<Ordering> {
+        self.as_str().partial_cmp(other.as_str())
+    }
+}
+
+impl<A: Allocator, S: Storage<A>> PartialOrd<ArchivedCompactStr> for CompactStrN<MAX_SIZE, A, S> {
+    #[inline]
+    fn partial_cmp(&self, other: &ArchivedCompactStr) -> Option<Ordering> {
+        self.as_str().partial_cmp(other.as_str())
+    }
+}
+
+#[cfg(all(test, feature = "rkyv-validation"))]
+mod tests {
+    use proptest::prelude::*;
+    use rkyv::Deserialize;
+    use test_strategy::proptest;
+
+    use crate::tests::{
+        rand_unicode,
+        rand_unicode_collection,
+    };
+    use crate::{
+        CompactStr,
+        SharedCompactStr,
+    };
+
+    #[test]
+    fn test_roundtrip() {
+        let value = CompactStr::new("this string is long enough to be heap allocated");
+
+        let bytes = rkyv::to_bytes::<_, 64>(&value).unwrap();
+        let archived = rkyv::check_archived_root::<CompactStr>(&bytes).unwrap();
+        assert_eq!(archived, &value);
+        assert_eq!(&value, archived);
+
+        let deserialized: CompactStr = archived.deserialize(&mut rkyv::Infallible).unwrap();
+        assert_eq!(deserialized, value);
+    }
+
+    #[test]
+    fn test_shared_roundtrip() {
+        let value = SharedCompactStr::new("this string is long enough to be heap allocated");
+
+        let bytes = rkyv::to_bytes::<_, 64>(&value).unwrap();
+        let archived = rkyv::check_archived_root::<SharedCompactStr>(&bytes).unwrap();
+        assert_eq!(archived, &value);
+
+        let deserialized: SharedCompactStr = archived.deserialize(&mut rkyv::Infallible).unwrap();
+        assert_eq!(deserialized, value);
+    }
+
+    #[test]
+    fn test_invalid_utf8_fails_validation() {
+        let value = CompactStr::new("hello");
+        let mut bytes = rkyv::to_bytes::<_, 64>(&value).unwrap();
+
+        // short strings are stored inline, so we can find and corrupt their bytes directly
+        let start = bytes.iter().position(|b| *b == b'h').unwrap();
+        bytes[start] = 0xFF;
+
+        assert!(rkyv::check_archived_root::<CompactStr>(&bytes).is_err());
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn test_strings_roundtrip(#[strategy(rand_unicode())] word: String) {
+        let value = CompactStr::new(&word);
+
+        let bytes = rkyv::to_bytes::<_, 64>(&value).unwrap();
+        let archived = rkyv::check_archived_root::<CompactStr>(&bytes).unwrap();
+        prop_assert_eq!(archived.as_str(), word.as_str());
+
+        let deserialized: CompactStr = archived.deserialize(&mut rkyv::Infallible).unwrap();
+        prop_assert_eq!(deserialized, word);
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn test_collection_roundtrip(#[strategy(rand_unicode_collection())] words: Vec<String>) {
+        let values: Vec<CompactStr> = words.iter().map(CompactStr::from).collect();
+
+        let bytes = rkyv::to_bytes::<_, 256>(&values).unwrap();
+        let archived = rkyv::check_archived_root::<Vec<CompactStr>>(&bytes).unwrap();
+        prop_assert_eq!(archived.len(), words.len());
+
+        let deserialized: Vec<CompactStr> = archived.deserialize(&mut rkyv::Infallible).unwrap();
+        prop_assert_eq!(deserialized, values);
+    }
+}
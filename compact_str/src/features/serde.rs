@@ -1,53 +1,74 @@
-use std::fmt;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
 
 use serde::de::{
     Deserializer,
     Error,
+    SeqAccess,
     Unexpected,
     Visitor,
 };
 
-use crate::CompactStr;
+use crate::{
+    CompactBytes,
+    CompactStr16,
+    CompactStrN,
+    Global,
+    InlineCapacity,
+    Storage,
+    ValidInlineCapacity,
+};
 
-fn compact_str<'de: 'a, 'a, D: Deserializer<'de>>(deserializer: D) -> Result<CompactStr, D::Error> {
-    struct CompactStrVisitor;
+/// Deserializes a string into any of our string types, e.g. [`CompactStr`](crate::CompactStr) or
+/// [`CompactStr16`]
+fn compact_str<'de: 'a, 'a, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: for<'s> From<&'s str> + From<String>,
+    D: Deserializer<'de>,
+{
+    struct CompactStrVisitor<T>(PhantomData<T>);
 
-    impl<'a> Visitor<'a> for CompactStrVisitor {
-        type Value = CompactStr;
+    impl<'a, T> Visitor<'a> for CompactStrVisitor<T>
+    where
+        T: for<'s> From<&'s str> + From<String>,
+    {
+        type Value = T;
 
         fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
             formatter.write_str("a string")
         }
 
         fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
-            Ok(CompactStr::from(v))
+            Ok(T::from(v))
         }
 
         fn visit_borrowed_str<E: Error>(self, v: &'a str) -> Result<Self::Value, E> {
-            Ok(CompactStr::from(v))
+            Ok(T::from(v))
         }
 
         fn visit_string<E: Error>(self, v: String) -> Result<Self::Value, E> {
-            Ok(CompactStr::from(v))
+            Ok(T::from(v))
         }
 
         fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Self::Value, E> {
-            match std::str::from_utf8(v) {
-                Ok(s) => Ok(CompactStr::from(s)),
+            match core::str::from_utf8(v) {
+                Ok(s) => Ok(T::from(s)),
                 Err(_) => Err(Error::invalid_value(Unexpected::Bytes(v), &self)),
             }
         }
 
         fn visit_borrowed_bytes<E: Error>(self, v: &'a [u8]) -> Result<Self::Value, E> {
-            match std::str::from_utf8(v) {
-                Ok(s) => Ok(CompactStr::from(s)),
+            match core::str::from_utf8(v) {
+                Ok(s) => Ok(T::from(s)),
                 Err(_) => Err(Error::invalid_value(Unexpected::Bytes(v), &self)),
             }
         }
 
         fn visit_byte_buf<E: Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
             match String::from_utf8(v) {
-                Ok(s) => Ok(CompactStr::from(s)),
+                Ok(s) => Ok(T::from(s)),
                 Err(e) => Err(Error::invalid_value(
                     Unexpected::Bytes(&e.into_bytes()),
                     &self,
@@ -56,17 +77,225 @@ fn compact_str<'de: 'a, 'a, D: Deserializer<'de>>(deserializer: D) -> Result<Com
         }
     }
 
-    deserializer.deserialize_str(CompactStrVisitor)
+    deserializer.deserialize_str(CompactStrVisitor(PhantomData))
+}
+
+impl<const N: usize, S: Storage<Global>> serde::Serialize for CompactStrN<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    fn serialize<Ser: serde::Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        self.as_str().serialize(serializer)
+    }
+}
+
+impl<'de, const N: usize, S: Storage<Global>> serde::Deserialize<'de> for CompactStrN<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        compact_str(deserializer)
+    }
 }
 
-impl serde::Serialize for CompactStr {
+impl serde::Serialize for CompactStr16 {
     fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         self.as_str().serialize(serializer)
     }
 }
 
-impl<'de> serde::Deserialize<'de> for CompactStr {
+impl<'de> serde::Deserialize<'de> for CompactStr16 {
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
         compact_str(deserializer)
     }
 }
+
+fn compact_bytes<'de: 'a, 'a, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<CompactBytes, D::Error> {
+    struct CompactBytesVisitor;
+
+    impl<'a> Visitor<'a> for CompactBytesVisitor {
+        type Value = CompactBytes;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a byte array")
+        }
+
+        fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+            Ok(CompactBytes::from(v))
+        }
+
+        fn visit_borrowed_bytes<E: Error>(self, v: &'a [u8]) -> Result<Self::Value, E> {
+            Ok(CompactBytes::from(v))
+        }
+
+        fn visit_byte_buf<E: Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+            Ok(CompactBytes::from(v))
+        }
+
+        fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+            Ok(CompactBytes::from(v.as_bytes()))
+        }
+
+        // Note: formats without a native byte array type, e.g. JSON, serialize bytes as a
+        // sequence of integers
+        fn visit_seq<A: SeqAccess<'a>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut bytes = CompactBytes::with_capacity(seq.size_hint().unwrap_or(0));
+            while let Some(byte) = seq.next_element::<u8>()? {
+                bytes.push(byte);
+            }
+            Ok(bytes)
+        }
+    }
+
+    deserializer.deserialize_bytes(CompactBytesVisitor)
+}
+
+impl serde::Serialize for CompactBytes {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.as_slice())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for CompactBytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        compact_bytes(deserializer)
+    }
+}
+
+#[cfg(feature = "std")]
+mod interner {
+    use core::fmt;
+    use core::hash::BuildHasher;
+
+    use serde::de::{
+        Deserializer,
+        Error,
+        SeqAccess,
+        Visitor,
+    };
+    use serde::ser::{
+        SerializeSeq,
+        Serializer,
+    };
+
+    use crate::interner::{
+        CompactStrInterner,
+        ShardedCompactStrInterner,
+        Symbol,
+    };
+    use crate::CompactStr;
+
+    impl serde::Serialize for Symbol {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_u32(self.index() as u32)
+        }
+    }
+
+    impl<'de> serde::Deserialize<'de> for Symbol {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let index = u32::deserialize(deserializer)?;
+            Symbol::from_index(index as usize)
+                .ok_or_else(|| D::Error::custom("symbol index is out of range"))
+        }
+    }
+
+    /// Serializes the strings of an interner as a sequence, ordered by their `Symbol`
+    fn serialize_strings<'a, S, I>(serializer: S, len: usize, strings: I) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        I: Iterator<Item = &'a str>,
+    {
+        let mut seq = serializer.serialize_seq(Some(len))?;
+        for text in strings {
+            seq.serialize_element(text)?;
+        }
+        seq.end()
+    }
+
+    impl<H> serde::Serialize for CompactStrInterner<H>
+    where
+        H: BuildHasher,
+    {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize_strings(serializer, self.len(), self.iter().map(|(_, text)| text))
+        }
+    }
+
+    impl<H> serde::Serialize for ShardedCompactStrInterner<H>
+    where
+        H: BuildHasher,
+    {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let strings = self.to_vec();
+            serialize_strings(
+                serializer,
+                strings.len(),
+                strings.iter().map(CompactStr::as_str),
+            )
+        }
+    }
+
+    /// Deserializes a sequence of strings into an interner, failing if any string appears more
+    /// than once, since then the symbols wouldn't line up with the serialized interner
+    fn deserialize_strings<'de, D, T, F>(deserializer: D, mut insert: F) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Default,
+        F: FnMut(&mut T, CompactStr) -> bool,
+    {
+        struct InternerVisitor<T, F>(F, core::marker::PhantomData<T>);
+
+        impl<'de, T, F> Visitor<'de> for InternerVisitor<T, F>
+        where
+            T: Default,
+            F: FnMut(&mut T, CompactStr) -> bool,
+        {
+            type Value = T;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a sequence of unique strings")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut interner = T::default();
+                while let Some(text) = seq.next_element::<CompactStr>()? {
+                    if !(self.0)(&mut interner, text) {
+                        return Err(A::Error::custom("duplicate string in interner"));
+                    }
+                }
+                Ok(interner)
+            }
+        }
+
+        deserializer.deserialize_seq(InternerVisitor(&mut insert, core::marker::PhantomData))
+    }
+
+    impl<'de, H> serde::Deserialize<'de> for CompactStrInterner<H>
+    where
+        H: BuildHasher + Default,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserialize_strings(deserializer, |interner: &mut Self, text| {
+                if interner.get(&text).is_some() {
+                    return false;
+                }
+                interner.insert(text);
+                true
+            })
+        }
+    }
+
+    impl<'de, H> serde::Deserialize<'de> for ShardedCompactStrInterner<H>
+    where
+        H: BuildHasher + Default,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserialize_strings(deserializer, |interner: &mut Self, text| {
+                let len = interner.len();
+                interner.intern(&text).index() == len
+            })
+        }
+    }
+}
@@ -0,0 +1,620 @@
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{
+    Hash,
+    Hasher,
+};
+use core::iter::FromIterator;
+use core::ops::Deref;
+use core::str::FromStr;
+
+use crate::repr::GermanRepr;
+use crate::{
+    CompactStr,
+    FromUtf8Error,
+};
+
+/// An immutable "German string", which stores its length and the first 4 bytes of the string
+/// next to the pointer to the heap, so most comparisons don't need to dereference the heap.
+///
+/// A [`GermanStr`] is 16 bytes, and stores strings of up to 12 bytes inline. Longer strings are
+/// heap allocated, like a [`Box<str>`], but their first 4 bytes, the prefix, are also stored
+/// inline. This speeds up operations that compare lots of strings, e.g. sorting or filtering:
+/// * Equality first compares the lengths and prefixes of the strings, which is enough to tell most
+///   unequal strings apart.
+/// * Ordering first compares the prefixes, only comparing the rest of the strings if the prefixes
+///   are equal.
+/// * [`GermanStr::starts_with_str`] only dereferences the heap for prefixes longer than 4 bytes.
+///
+/// The layout matches the views of an Arrow `StringView` array, except that long strings store a
+/// pointer instead of a buffer index and offset. [`GermanStr::to_view`] and
+/// [`GermanStr::from_view`] convert to and from these views.
+///
+/// # Note
+/// * A [`GermanStr`] can't be modified, convert it into a [`CompactStr`] or [`String`] instead
+/// * Strings can be at most `u32::MAX` bytes long
+///
+/// # Examples
+/// ```
+/// use compact_str::GermanStr;
+///
+/// let mut names = vec![
+///     GermanStr::new("weierstrass"),
+///     GermanStr::new("noether"),
+///     GermanStr::new("noether, emmy amalie"),
+/// ];
+/// names.sort();
+///
+/// assert_eq!(names, ["noether", "noether, emmy amalie", "weierstrass"]);
+/// assert!(names[1].starts_with_str("noe"));
+/// ```
+#[derive(Clone, PartialOrd, Ord)]
+pub struct GermanStr {
+    repr: GermanRepr,
+}
+
+crate::asserts::assert_size!(GermanStr, 16);
+
+impl GermanStr {
+    /// Creates a new [`GermanStr`] from any type that implements `AsRef<str>`. If the string is
+    /// at most 12 bytes long, it's inlined.
+    ///
+    /// # Panics
+    /// Panics if the string is longer than `u32::MAX` bytes
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::GermanStr;
+    /// let short = GermanStr::new("hello world!");
+    /// assert!(!short.is_heap_allocated());
+    ///
+    /// let long = GermanStr::new("hello world, from the heap");
+    /// assert!(long.is_heap_allocated());
+    /// ```
+    #[inline]
+    pub fn new<T: AsRef<str>>(text: T) -> Self {
+        GermanStr {
+            repr: GermanRepr::new(text.as_ref()),
+        }
+    }
+
+    /// Creates a new inline [`GermanStr`] at compile time.
+    ///
+    /// Trying to create a string longer than 12 bytes will fail to build.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::GermanStr;
+    /// const UNKNOWN: GermanStr = GermanStr::new_inline("unknown");
+    ///
+    /// assert_eq!(UNKNOWN, "unknown");
+    /// ```
+    #[inline]
+    pub const fn new_inline(text: &str) -> Self {
+        GermanStr {
+            repr: GermanRepr::new_inline(text),
+        }
+    }
+
+    /// Converts a buffer of bytes to a [`GermanStr`], failing if they're not valid UTF-8.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::GermanStr;
+    /// let german = GermanStr::from_utf8(b"hello").unwrap();
+    /// assert_eq!(german, "hello");
+    ///
+    /// assert!(GermanStr::from_utf8(&[0, 159]).is_err());
+    /// ```
+    #[inline]
+    pub fn from_utf8<B: AsRef<[u8]>>(buf: B) -> Result<Self, FromUtf8Error> {
+        let buf = buf.as_ref();
+        match core::str::from_utf8(buf) {
+            Ok(s) => Ok(GermanStr::new(s)),
+            Err(e) => Err(FromUtf8Error::new(buf.to_vec(), e)),
+        }
+    }
+
+    /// Decodes a view of an Arrow `StringView` array, copying the string from `buffers` if it's
+    /// not stored in the view itself.
+    ///
+    /// # Errors
+    /// Returns an error if the referenced bytes aren't valid UTF-8.
+    ///
+    /// # Panics
+    /// Panics if the view references a buffer, or bytes, that are out of bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::GermanStr;
+    /// let buffers = [b"a string that's too long to inline".to_vec()];
+    /// let view = GermanStr::new("a string that's too long to inline").to_view(0, 0);
+    ///
+    /// let german = GermanStr::from_view(view, &buffers).unwrap();
+    /// assert_eq!(german, "a string that's too long to inline");
+    /// ```
+    #[inline]
+    pub fn from_view<B: AsRef<[u8]>>(view: u128, buffers: &[B]) -> Result<Self, FromUtf8Error> {
+        let view = view.to_le_bytes();
+        GermanStr::from_utf8(GermanRepr::view_bytes(&view, buffers))
+    }
+
+    /// Encodes this [`GermanStr`] as a view of an Arrow `StringView` array.
+    ///
+    /// Strings of up to 12 bytes are stored in the view itself. For longer strings, the caller is
+    /// responsible for storing the string in the data buffer `buffer_index`, starting at
+    /// `offset`, e.g. with [`GermanStr::append_view`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::GermanStr;
+    /// let view = GermanStr::new("hello").to_view(0, 0);
+    ///
+    /// let bytes = view.to_le_bytes();
+    /// assert_eq!(&bytes[..4], &5u32.to_le_bytes());
+    /// assert_eq!(&bytes[4..9], b"hello");
+    /// ```
+    #[inline]
+    pub fn to_view(&self, buffer_index: u32, offset: u32) -> u128 {
+        self.repr.to_view(buffer_index, offset)
+    }
+
+    /// Encodes this [`GermanStr`] as a view of an Arrow `StringView` array, appending the string
+    /// to `buffer` if it's too long to be stored in the view itself.
+    ///
+    /// # Panics
+    /// Panics if the string would be stored past `u32::MAX` bytes into `buffer`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::GermanStr;
+    /// let strings = [GermanStr::new("short"), GermanStr::new("a string that's heap allocated")];
+    ///
+    /// let mut buffer = Vec::new();
+    /// let views: Vec<u128> = strings.iter().map(|s| s.append_view(&mut buffer, 0)).collect();
+    /// assert_eq!(buffer, b"a string that's heap allocated");
+    ///
+    /// let buffers = [buffer];
+    /// for (view, s) in views.into_iter().zip(&strings) {
+    ///     assert_eq!(&GermanStr::from_view(view, &buffers).unwrap(), s);
+    /// }
+    /// ```
+    #[inline]
+    pub fn append_view(&self, buffer: &mut Vec<u8>, buffer_index: u32) -> u128 {
+        if !self.is_heap_allocated() {
+            return self.to_view(buffer_index, 0);
+        }
+
+        let offset = buffer.len();
+        assert!(
+            matches!(offset.checked_add(self.len()), Some(end) if end <= u32::MAX as usize),
+            "buffer is too long to reference from a view"
+        );
+
+        buffer.extend_from_slice(self.as_bytes());
+        self.to_view(buffer_index, offset as u32)
+    }
+
+    /// Returns the length of the [`GermanStr`] in bytes.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::GermanStr;
+    /// assert_eq!(GermanStr::new("hello").len(), 5);
+    /// ```
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.repr.len()
+    }
+
+    /// Returns `true` if the [`GermanStr`] has a length of 0, `false` otherwise
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::GermanStr;
+    /// assert!(GermanStr::new("").is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the first 4 bytes of the string, padded with zeros if the string is shorter.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::GermanStr;
+    /// assert_eq!(GermanStr::new("hello").prefix(), *b"hell");
+    /// assert_eq!(GermanStr::new("hi").prefix(), *b"hi\0\0");
+    /// ```
+    #[inline]
+    pub fn prefix(&self) -> [u8; 4] {
+        self.repr.prefix()
+    }
+
+    /// Returns `true` if the [`GermanStr`] starts with `prefix`.
+    ///
+    /// Unlike [`str::starts_with`], if `prefix` is at most 4 bytes long this never dereferences
+    /// the heap.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::GermanStr;
+    /// let url = GermanStr::new("https://example.com/a/long/path");
+    ///
+    /// assert!(url.starts_with_str("http"));
+    /// assert!(url.starts_with_str("https://"));
+    /// assert!(!url.starts_with_str("ftp"));
+    /// ```
+    #[inline]
+    pub fn starts_with_str(&self, prefix: &str) -> bool {
+        self.repr.starts_with(prefix.as_bytes())
+    }
+
+    /// Returns a string slice containing the entire [`GermanStr`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::GermanStr;
+    /// assert_eq!(GermanStr::new("hello").as_str(), "hello");
+    /// ```
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.repr.as_str()
+    }
+
+    /// Returns a byte slice of the [`GermanStr`]'s contents.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::GermanStr;
+    /// assert_eq!(GermanStr::new("hello").as_bytes(), b"hello");
+    /// ```
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        self.repr.as_bytes()
+    }
+
+    /// Returns whether or not the [`GermanStr`] is heap allocated.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::GermanStr;
+    /// assert!(!GermanStr::new("hello").is_heap_allocated());
+    /// assert!(GermanStr::new("hello world, from the heap").is_heap_allocated());
+    /// ```
+    #[inline]
+    pub fn is_heap_allocated(&self) -> bool {
+        self.repr.is_heap_allocated()
+    }
+
+    /// Converts the [`GermanStr`] into a [`Box<str>`].
+    ///
+    /// This is `O(1)` for heap allocated strings, re-using their buffer.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::GermanStr;
+    /// let boxed: Box<str> = GermanStr::new("hello").into_boxed_str();
+    /// assert_eq!(&*boxed, "hello");
+    /// ```
+    #[inline]
+    pub fn into_boxed_str(self) -> Box<str> {
+        self.repr.into_box_str()
+    }
+
+    /// Converts the [`GermanStr`] into a [`String`].
+    ///
+    /// This is `O(1)` for heap allocated strings, re-using their buffer.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::GermanStr;
+    /// let string: String = GermanStr::new("hello").into_string();
+    /// assert_eq!(string, "hello");
+    /// ```
+    #[inline]
+    pub fn into_string(self) -> String {
+        self.into_boxed_str().into_string()
+    }
+}
+
+impl Default for GermanStr {
+    #[inline]
+    fn default() -> Self {
+        GermanStr::new_inline("")
+    }
+}
+
+impl Deref for GermanStr {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for GermanStr {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for GermanStr {
+    #[inline]
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialEq for GermanStr {
+    #[inline]
+    fn eq(&self, other: &GermanStr) -> bool {
+        self.repr == other.repr
+    }
+}
+
+impl Eq for GermanStr {}
+
+impl PartialEq<str> for GermanStr {
+    #[inline]
+    fn eq(&self, other: &str) -> bool {
+        self.repr.eq_str(other)
+    }
+}
+
+impl PartialEq<&str> for GermanStr {
+    #[inline]
+    fn eq(&self, other: &&str) -> bool {
+        self.repr.eq_str(other)
+    }
+}
+
+impl PartialEq<String> for GermanStr {
+    #[inline]
+    fn eq(&self, other: &String) -> bool {
+        self.repr.eq_str(other)
+    }
+}
+
+impl PartialEq<CompactStr> for GermanStr {
+    #[inline]
+    fn eq(&self, other: &CompactStr) -> bool {
+        self.repr.eq_str(other)
+    }
+}
+
+impl PartialEq<GermanStr> for str {
+    #[inline]
+    fn eq(&self, other: &GermanStr) -> bool {
+        other.repr.eq_str(self)
+    }
+}
+
+impl PartialEq<GermanStr> for &str {
+    #[inline]
+    fn eq(&self, other: &GermanStr) -> bool {
+        other.repr.eq_str(self)
+    }
+}
+
+impl PartialEq<GermanStr> for String {
+    #[inline]
+    fn eq(&self, other: &GermanStr) -> bool {
+        other.repr.eq_str(self)
+    }
+}
+
+impl Hash for GermanStr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl<'a> From<&'a str> for GermanStr {
+    fn from(s: &'a str) -> Self {
+        GermanStr::new(s)
+    }
+}
+
+impl From<String> for GermanStr {
+    fn from(s: String) -> Self {
+        GermanStr::from(s.into_boxed_str())
+    }
+}
+
+impl<'a> From<&'a String> for GermanStr {
+    fn from(s: &'a String) -> Self {
+        GermanStr::new(s)
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for GermanStr {
+    fn from(s: Cow<'a, str>) -> Self {
+        match s {
+            Cow::Borrowed(s) => GermanStr::new(s),
+            Cow::Owned(s) => GermanStr::from(s),
+        }
+    }
+}
+
+impl From<Box<str>> for GermanStr {
+    fn from(b: Box<str>) -> Self {
+        GermanStr {
+            repr: GermanRepr::from_box_str(b),
+        }
+    }
+}
+
+impl From<CompactStr> for GermanStr {
+    fn from(s: CompactStr) -> Self {
+        // Note: Converting a short `CompactStr` into a `Box<str>` would heap allocate
+        if !s.is_heap_allocated() {
+            GermanStr::new(s)
+        } else {
+            GermanStr::from(s.into_boxed_str())
+        }
+    }
+}
+
+impl From<GermanStr> for String {
+    fn from(s: GermanStr) -> Self {
+        s.into_string()
+    }
+}
+
+impl From<GermanStr> for Box<str> {
+    fn from(s: GermanStr) -> Self {
+        s.into_boxed_str()
+    }
+}
+
+impl From<GermanStr> for CompactStr {
+    fn from(s: GermanStr) -> Self {
+        CompactStr::from(s.into_boxed_str())
+    }
+}
+
+impl FromStr for GermanStr {
+    type Err = core::convert::Infallible;
+    fn from_str(s: &str) -> Result<GermanStr, Self::Err> {
+        Ok(GermanStr::from(s))
+    }
+}
+
+impl fmt::Debug for GermanStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for GermanStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl FromIterator<char> for GermanStr {
+    fn from_iter<T: IntoIterator<Item = char>>(iter: T) -> Self {
+        GermanStr::from(iter.into_iter().collect::<CompactStr>())
+    }
+}
+
+impl<'a> FromIterator<&'a str> for GermanStr {
+    fn from_iter<T: IntoIterator<Item = &'a str>>(iter: T) -> Self {
+        GermanStr::from(iter.into_iter().collect::<CompactStr>())
+    }
+}
+
+impl FromIterator<String> for GermanStr {
+    fn from_iter<T: IntoIterator<Item = String>>(iter: T) -> Self {
+        GermanStr::from(iter.into_iter().collect::<CompactStr>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    use proptest::prelude::*;
+    use test_strategy::proptest;
+
+    use super::GermanStr;
+    use crate::tests::{
+        rand_unicode,
+        rand_unicode_collection,
+    };
+
+    #[test]
+    fn test_heap_buffer_is_reused() {
+        let string = String::from("a string that's too long to inline");
+        let ptr = string.as_ptr();
+
+        let german = GermanStr::from(string.into_boxed_str());
+        assert_eq!(german.as_ptr(), ptr);
+        let string = german.into_string();
+        assert_eq!(string.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn test_view_inline_layout() {
+        let view = GermanStr::new("hello world!").to_view(7, 9).to_le_bytes();
+
+        assert_eq!(&view[..4], &12u32.to_le_bytes());
+        assert_eq!(&view[4..], b"hello world!");
+    }
+
+    #[test]
+    fn test_view_heap_layout() {
+        let view = GermanStr::new("hello world, again")
+            .to_view(7, 9)
+            .to_le_bytes();
+
+        assert_eq!(&view[..4], &18u32.to_le_bytes());
+        assert_eq!(&view[4..8], b"hell");
+        assert_eq!(&view[8..12], &7u32.to_le_bytes());
+        assert_eq!(&view[12..], &9u32.to_le_bytes());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_view_out_of_bounds() {
+        let view = GermanStr::new("hello world, again").to_view(0, 4);
+        let _ = GermanStr::from_view(view, &[b"hello world, again"]);
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn test_strings_roundtrip(#[strategy(rand_unicode())] word: String) {
+        let german = GermanStr::new(&word);
+
+        prop_assert_eq!(&german, &word);
+        prop_assert_eq!(german.as_str(), word.as_str());
+        prop_assert_eq!(german.is_heap_allocated(), word.len() > 12);
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn test_ordering_matches_str(
+        #[strategy(rand_unicode())] a: String,
+        #[strategy(rand_unicode())] b: String,
+    ) {
+        let (x, y) = (GermanStr::new(&a), GermanStr::new(&b));
+
+        prop_assert_eq!(x.cmp(&y), a.cmp(&b));
+        prop_assert_eq!(x == y, a == b);
+        prop_assert_eq!(x.starts_with_str(&b), a.starts_with(&b));
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn test_sort_matches_str(#[strategy(rand_unicode_collection())] mut words: Vec<String>) {
+        let mut german: Vec<GermanStr> = words.iter().map(GermanStr::new).collect();
+
+        words.sort();
+        german.sort();
+        prop_assert_eq!(german, words);
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn test_views_roundtrip(#[strategy(rand_unicode_collection())] words: Vec<String>) {
+        let mut buffer = Vec::new();
+        let views: Vec<u128> = words
+            .iter()
+            .map(|word| GermanStr::new(word).append_view(&mut buffer, 0))
+            .collect();
+
+        let buffers = [buffer];
+        for (view, word) in views.into_iter().zip(&words) {
+            prop_assert_eq!(GermanStr::from_view(view, &buffers).unwrap(), word.as_str());
+        }
+    }
+}
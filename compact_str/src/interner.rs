@@ -0,0 +1,745 @@
+//! String interners that store their strings as [`CompactStr`]s.
+//!
+//! An interner de-duplicates strings, handing out a small [`Symbol`] for each unique string, which
+//! is cheap to copy, compare, and hash, and can be resolved back to the original string. Short
+//! strings, e.g. most identifiers, are stored inline in a [`CompactStr`], so interning them
+//! doesn't require any additional heap allocations.
+//!
+//! * [`CompactStrInterner`] is a single-threaded interner, which resolves symbols to a `&str`.
+//! * [`ShardedCompactStrInterner`] can be shared between threads. Lookups of existing strings only
+//!   lock one of several shards, so threads rarely contend with one another.
+//!
+//! With the `serde` feature enabled, both interners can be serialized as a sequence of their
+//! strings, ordered by [`Symbol`], and deserializing them restores the same symbols.
+
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{
+    BuildHasher,
+    Hash,
+    Hasher,
+};
+use std::num::NonZeroU32;
+use std::sync::{
+    PoisonError,
+    RwLock,
+    RwLockReadGuard,
+    RwLockWriteGuard,
+};
+
+use crate::CompactStr;
+
+/// A handle to a string stored in an interner.
+///
+/// Symbols are handed out in increasing order, starting at `0`, and an `Option<Symbol>` is the same
+/// size as a `Symbol`, 4 bytes.
+///
+/// Note: A [`Symbol`] can only be resolved by the interner that created it.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(NonZeroU32);
+
+impl Symbol {
+    /// The maximum number of strings an interner can hold
+    const MAX_INDEX: usize = u32::MAX as usize - 1;
+
+    /// Returns the [`Symbol`] with the provided index, or `None` if the index is too large.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::interner::Symbol;
+    /// let symbol = Symbol::from_index(42).unwrap();
+    /// assert_eq!(symbol.index(), 42);
+    ///
+    /// assert!(Symbol::from_index(u32::MAX as usize).is_none());
+    /// ```
+    #[inline]
+    pub fn from_index(index: usize) -> Option<Self> {
+        if index > Self::MAX_INDEX {
+            return None;
+        }
+        // Note: `index` is at most `u32::MAX - 1`, so adding one can't overflow, and is never zero
+        NonZeroU32::new(index as u32 + 1).map(Symbol)
+    }
+
+    /// Returns the index of this [`Symbol`], i.e. the number of unique strings that were interned
+    /// before it.
+    #[inline]
+    pub fn index(self) -> usize {
+        (self.0.get() - 1) as usize
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Symbol").field(&self.index()).finish()
+    }
+}
+
+/// Returns the [`Symbol`] of the string at `index`, panicking if the index is too large
+#[inline]
+fn symbol_at(index: usize) -> Symbol {
+    Symbol::from_index(index).expect("too many strings in the interner")
+}
+
+/// Hashes `text` with `hasher`
+// Note: `BuildHasher::hash_one` was stabilized after our MSRV
+#[allow(clippy::manual_hash_one)]
+#[inline]
+fn hash_str<S: BuildHasher>(hasher: &S, text: &str) -> u64 {
+    let mut hasher = hasher.build_hasher();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// An open addressing hash table of [`Symbol`]s, which are compared by the string they refer to.
+///
+/// A [`HashMap`](std::collections::HashMap) keyed by the strings would store every string twice,
+/// once as a key and once in the list of strings we resolve symbols with. Instead the table only
+/// stores each symbol along with the hash of its string, and looks the string up in the list.
+#[derive(Clone, Default)]
+struct SymbolTable {
+    /// The lower 32 bits of each string's hash and its symbol, the number of slots is always zero
+    /// or a power of two
+    slots: Vec<Option<(u32, Symbol)>>,
+    /// The number of occupied slots
+    len: usize,
+}
+
+impl SymbolTable {
+    /// The smallest number of slots we allocate
+    const MIN_SLOTS: usize = 8;
+
+    #[inline]
+    fn with_capacity(capacity: usize) -> Self {
+        let mut table = SymbolTable::default();
+        if capacity > 0 {
+            table.resize(Self::slots_for(capacity));
+        }
+        table
+    }
+
+    /// Returns the number of slots needed to hold `len` symbols, keeping the table at most 3/4 full
+    #[inline]
+    fn slots_for(len: usize) -> usize {
+        let slots = len
+            .checked_add(len / 3 + 1)
+            .and_then(usize::checked_next_power_of_two)
+            .expect("capacity overflow");
+        core::cmp::max(slots, Self::MIN_SLOTS)
+    }
+
+    /// Returns the [`Symbol`] of `text`, if it's in the table. `strings` is the list of strings
+    /// that the symbols in this table index into
+    #[inline]
+    fn get(&self, hash: u64, text: &str, strings: &[CompactStr]) -> Option<Symbol> {
+        if self.slots.is_empty() {
+            return None;
+        }
+
+        let hash = hash as u32;
+        let mask = self.slots.len() - 1;
+        let mut index = hash as usize & mask;
+        // Note: The table is never full, so we'll always find an empty slot eventually
+        loop {
+            match self.slots[index] {
+                None => return None,
+                Some((h, symbol)) if h == hash && strings[symbol.index()] == text => {
+                    return Some(symbol)
+                }
+                Some(_) => index = (index + 1) & mask,
+            }
+        }
+    }
+
+    /// Inserts `symbol`, whose string has the provided `hash`, and isn't in the table yet
+    #[inline]
+    fn insert(&mut self, hash: u64, symbol: Symbol) {
+        if self.slots.len() < Self::slots_for(self.len + 1) {
+            self.resize(Self::slots_for(self.len + 1));
+        }
+        Self::insert_slot(&mut self.slots, hash as u32, symbol);
+        self.len += 1;
+    }
+
+    #[inline]
+    fn insert_slot(slots: &mut [Option<(u32, Symbol)>], hash: u32, symbol: Symbol) {
+        let mask = slots.len() - 1;
+        let mut index = hash as usize & mask;
+        while slots[index].is_some() {
+            index = (index + 1) & mask;
+        }
+        slots[index] = Some((hash, symbol));
+    }
+
+    /// Moves every symbol into a new table with `num_slots` slots, which needs to be a power of two
+    /// larger than the current number of slots. Because we store the hashes, this doesn't need
+    /// to look at the strings.
+    fn resize(&mut self, num_slots: usize) {
+        debug_assert!(num_slots.is_power_of_two() && num_slots > self.slots.len());
+
+        let mut slots = Vec::new();
+        slots.resize(num_slots, None);
+        for (hash, symbol) in self.slots.iter().flatten() {
+            Self::insert_slot(&mut slots, *hash, *symbol);
+        }
+        self.slots = slots;
+    }
+}
+
+/// A single-threaded string interner, see the [module level docs](self) for more info.
+///
+/// # Examples
+/// ```
+/// use compact_str::interner::CompactStrInterner;
+///
+/// let mut interner = CompactStrInterner::new();
+///
+/// let a = interner.intern("foo");
+/// let b = interner.intern("bar");
+/// let c = interner.intern("foo");
+///
+/// assert_eq!(a, c);
+/// assert_ne!(a, b);
+/// assert_eq!(interner.len(), 2);
+///
+/// assert_eq!(interner.resolve(a), Some("foo"));
+/// assert_eq!(interner.get("bar"), Some(b));
+/// assert_eq!(interner.get("baz"), None);
+/// ```
+#[derive(Clone)]
+pub struct CompactStrInterner<S = RandomState> {
+    hasher: S,
+    table: SymbolTable,
+    strings: Vec<CompactStr>,
+}
+
+impl CompactStrInterner {
+    /// Creates an empty [`CompactStrInterner`].
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+
+    /// Creates an empty [`CompactStrInterner`] with space for at least `capacity` strings.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<S: BuildHasher> CompactStrInterner<S> {
+    /// Creates an empty [`CompactStrInterner`] which uses `hasher` to hash strings.
+    #[inline]
+    pub fn with_hasher(hasher: S) -> Self {
+        Self::with_capacity_and_hasher(0, hasher)
+    }
+
+    /// Creates an empty [`CompactStrInterner`] with space for at least `capacity` strings, which
+    /// uses `hasher` to hash strings.
+    #[inline]
+    pub fn with_capacity_and_hasher(capacity: usize, hasher: S) -> Self {
+        CompactStrInterner {
+            hasher,
+            table: SymbolTable::with_capacity(capacity),
+            strings: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Interns `text`, returning the [`Symbol`] of the existing string if it has been interned
+    /// before.
+    ///
+    /// # Panics
+    /// Panics if the interner already holds `u32::MAX` strings.
+    #[inline]
+    pub fn intern(&mut self, text: &str) -> Symbol {
+        match self.get(text) {
+            Some(symbol) => symbol,
+            None => self.insert(CompactStr::new(text)),
+        }
+    }
+
+    /// Returns the [`Symbol`] of `text`, if it has been interned.
+    #[inline]
+    pub fn get(&self, text: &str) -> Option<Symbol> {
+        let hash = hash_str(&self.hasher, text);
+        self.table.get(hash, text, &self.strings)
+    }
+
+    /// Returns the string `symbol` refers to, or `None` if this interner didn't create `symbol`.
+    #[inline]
+    pub fn resolve(&self, symbol: Symbol) -> Option<&str> {
+        self.strings.get(symbol.index()).map(CompactStr::as_str)
+    }
+
+    /// Returns the number of unique strings in the interner.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Returns `true` if the interner doesn't contain any strings.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Returns an iterator over every [`Symbol`] and the string it refers to, in the order the
+    /// strings were interned.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::interner::CompactStrInterner;
+    /// let mut interner = CompactStrInterner::new();
+    /// interner.intern("foo");
+    /// interner.intern("bar");
+    ///
+    /// let strings: Vec<&str> = interner.iter().map(|(_, s)| s).collect();
+    /// assert_eq!(strings, ["foo", "bar"]);
+    /// ```
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (Symbol, &str)> + '_ {
+        self.strings
+            .iter()
+            .enumerate()
+            .map(|(index, s)| (symbol_at(index), s.as_str()))
+    }
+
+    /// Inserts a string that we know isn't in the interner yet.
+    #[inline]
+    pub(crate) fn insert(&mut self, text: CompactStr) -> Symbol {
+        let symbol = symbol_at(self.strings.len());
+        let hash = hash_str(&self.hasher, &text);
+        self.table.insert(hash, symbol);
+        self.strings.push(text);
+
+        symbol
+    }
+}
+
+impl<S: BuildHasher + Default> Default for CompactStrInterner<S> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<S> fmt::Debug for CompactStrInterner<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.strings.iter()).finish()
+    }
+}
+
+/// The number of shards a [`ShardedCompactStrInterner`] uses by default
+const DEFAULT_SHARDS: usize = 16;
+
+/// A string interner that can be shared between threads, see the [module level docs](self) for
+/// more info.
+///
+/// Symbols are split across several shards by the hash of their string, so looking up strings
+/// that have already been interned only needs to lock a single shard, and the list of all strings,
+/// for reading. Interning a new string also briefly locks the list of all strings for writing,
+/// which keeps the symbols dense, i.e. the same as a
+/// [`CompactStrInterner`] would hand out when interning the same strings in the same order.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// use compact_str::interner::ShardedCompactStrInterner;
+///
+/// let interner = Arc::new(ShardedCompactStrInterner::new());
+///
+/// let handles: Vec<_> = (0..4)
+///     .map(|_| {
+///         let interner = Arc::clone(&interner);
+///         thread::spawn(move || interner.intern("foo"))
+///     })
+///     .collect();
+/// let symbols: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+///
+/// // every thread got the same symbol
+/// assert!(symbols.iter().all(|s| *s == symbols[0]));
+/// assert_eq!(interner.len(), 1);
+/// assert_eq!(interner.resolve(symbols[0]).unwrap(), "foo");
+/// ```
+pub struct ShardedCompactStrInterner<S = RandomState> {
+    hasher: S,
+    shards: Box<[RwLock<SymbolTable>]>,
+    strings: RwLock<Vec<CompactStr>>,
+}
+
+impl ShardedCompactStrInterner {
+    /// Creates an empty [`ShardedCompactStrInterner`].
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_shards(DEFAULT_SHARDS)
+    }
+
+    /// Creates an empty [`ShardedCompactStrInterner`] with at least `shards` shards.
+    ///
+    /// More shards reduce contention between threads. The number of shards is rounded up to the
+    /// next power of two.
+    #[inline]
+    pub fn with_shards(shards: usize) -> Self {
+        Self::with_shards_and_hasher(shards, RandomState::new())
+    }
+}
+
+impl<S: BuildHasher> ShardedCompactStrInterner<S> {
+    /// Creates an empty [`ShardedCompactStrInterner`] with at least `shards` shards, which uses
+    /// `hasher` to hash strings.
+    #[inline]
+    pub fn with_shards_and_hasher(shards: usize, hasher: S) -> Self {
+        let num_shards = core::cmp::max(shards, 1).next_power_of_two();
+        let shards = (0..num_shards)
+            .map(|_| RwLock::new(SymbolTable::default()))
+            .collect();
+
+        ShardedCompactStrInterner {
+            hasher,
+            shards,
+            strings: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Interns `text`, returning the [`Symbol`] of the existing string if it has been interned
+    /// before.
+    ///
+    /// # Panics
+    /// Panics if the interner already holds `u32::MAX` strings.
+    #[inline]
+    pub fn intern(&self, text: &str) -> Symbol {
+        let hash = hash_str(&self.hasher, text);
+        let shard = self.shard(hash);
+        if let Some(symbol) = read(shard).get(hash, text, &read(&self.strings)) {
+            return symbol;
+        }
+
+        let mut shard = write(shard);
+        let mut strings = write(&self.strings);
+        // Another thread could have interned `text` while we were waiting for the locks
+        if let Some(symbol) = shard.get(hash, text, &strings) {
+            return symbol;
+        }
+
+        let symbol = symbol_at(strings.len());
+        strings.push(CompactStr::new(text));
+        shard.insert(hash, symbol);
+
+        symbol
+    }
+
+    /// Returns the [`Symbol`] of `text`, if it has been interned.
+    #[inline]
+    pub fn get(&self, text: &str) -> Option<Symbol> {
+        let hash = hash_str(&self.hasher, text);
+        read(self.shard(hash)).get(hash, text, &read(&self.strings))
+    }
+
+    /// Returns the string `symbol` refers to, or `None` if this interner didn't create `symbol`.
+    ///
+    /// Note: The interner could be modified while the string is in use, so this returns a clone of
+    /// the string, which for short strings is just a copy of the inline [`CompactStr`]. See
+    /// [`ShardedCompactStrInterner::resolve_with`] to borrow the string instead.
+    #[inline]
+    pub fn resolve(&self, symbol: Symbol) -> Option<CompactStr> {
+        read(&self.strings).get(symbol.index()).cloned()
+    }
+
+    /// Calls `f` with the string `symbol` refers to, returning `None` if this interner didn't
+    /// create `symbol`.
+    ///
+    /// Note: Other threads can't intern new strings until `f` returns.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::interner::ShardedCompactStrInterner;
+    /// let interner = ShardedCompactStrInterner::new();
+    /// let symbol = interner.intern("hello world");
+    ///
+    /// assert_eq!(interner.resolve_with(symbol, str::len), Some(11));
+    /// ```
+    #[inline]
+    pub fn resolve_with<R, F: FnOnce(&str) -> R>(&self, symbol: Symbol, f: F) -> Option<R> {
+        read(&self.strings)
+            .get(symbol.index())
+            .map(|text| f(text.as_str()))
+    }
+
+    /// Returns the number of unique strings in the interner.
+    #[inline]
+    pub fn len(&self) -> usize {
+        read(&self.strings).len()
+    }
+
+    /// Returns `true` if the interner doesn't contain any strings.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        read(&self.strings).is_empty()
+    }
+
+    /// Returns all of the strings in the interner, ordered by their [`Symbol`].
+    #[inline]
+    pub fn to_vec(&self) -> Vec<CompactStr> {
+        read(&self.strings).clone()
+    }
+
+    #[inline]
+    fn shard(&self, hash: u64) -> &RwLock<SymbolTable> {
+        // Note: The lower bits of the hash pick a slot within the shard's table, so we pick the
+        // shard with the upper bits. The number of shards is always a power of two
+        let index = (hash >> 32) as usize & (self.shards.len() - 1);
+        &self.shards[index]
+    }
+}
+
+impl<S: BuildHasher + Default> Default for ShardedCompactStrInterner<S> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_shards_and_hasher(DEFAULT_SHARDS, S::default())
+    }
+}
+
+impl<S> fmt::Debug for ShardedCompactStrInterner<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(read(&self.strings).iter()).finish()
+    }
+}
+
+// Note: We never leave the interner in an inconsistent state while holding a lock, e.g. we insert a
+// symbol into its shard only after its string has been added to the list of strings, so it's fine
+// to ignore lock poisoning. We always lock a shard before the list of strings, so threads can't
+// deadlock
+
+#[inline]
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[inline]
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::{
+        BuildHasherDefault,
+        Hasher,
+    };
+    use std::sync::Arc;
+    use std::thread;
+
+    use proptest::prelude::*;
+    use test_strategy::proptest;
+
+    use super::{
+        CompactStrInterner,
+        ShardedCompactStrInterner,
+        Symbol,
+    };
+    use crate::tests::rand_unicode_collection;
+
+    #[test]
+    fn test_symbol_size() {
+        assert_eq!(core::mem::size_of::<Symbol>(), 4);
+        assert_eq!(core::mem::size_of::<Option<Symbol>>(), 4);
+    }
+
+    #[test]
+    fn test_symbol_index_roundtrips() {
+        for index in [0, 1, 1000, u32::MAX as usize - 1].iter().copied() {
+            assert_eq!(Symbol::from_index(index).unwrap().index(), index);
+        }
+        assert!(Symbol::from_index(u32::MAX as usize).is_none());
+    }
+
+    #[test]
+    fn test_intern_short_strings_are_inline() {
+        let mut interner = CompactStrInterner::new();
+        let symbol = interner.intern("short_ident");
+
+        assert!(!interner.strings[symbol.index()].is_heap_allocated());
+    }
+
+    #[test]
+    fn test_colliding_hashes() {
+        /// A hasher that gives every string the same hash
+        #[derive(Default)]
+        struct ZeroHasher;
+
+        impl Hasher for ZeroHasher {
+            fn finish(&self) -> u64 {
+                0
+            }
+
+            fn write(&mut self, _bytes: &[u8]) {}
+        }
+
+        let mut interner =
+            CompactStrInterner::with_hasher(BuildHasherDefault::<ZeroHasher>::default());
+        let sharded = ShardedCompactStrInterner::with_shards_and_hasher(
+            4,
+            BuildHasherDefault::<ZeroHasher>::default(),
+        );
+
+        // note: enough strings to grow the table a few times
+        let words: Vec<String> = (0..100).map(|i| format!("word_{}", i)).collect();
+        for word in &words {
+            let symbol = interner.intern(word);
+            assert_eq!(sharded.intern(word), symbol);
+        }
+
+        assert_eq!(interner.len(), 100);
+        for (index, word) in words.iter().enumerate() {
+            let symbol = Symbol::from_index(index).unwrap();
+            assert_eq!(interner.get(word), Some(symbol));
+            assert_eq!(sharded.get(word), Some(symbol));
+            assert_eq!(interner.resolve(symbol), Some(word.as_str()));
+        }
+        assert_eq!(interner.get("word_100"), None);
+        assert_eq!(sharded.get("word_100"), None);
+    }
+
+    #[test]
+    fn test_resolve_unknown_symbol() {
+        let mut interner = CompactStrInterner::new();
+        interner.intern("foo");
+
+        let mut other = CompactStrInterner::new();
+        other.intern("foo");
+        let unknown = other.intern("bar");
+
+        assert_eq!(interner.resolve(unknown), None);
+    }
+
+    #[test]
+    fn test_sharded_single_shard() {
+        let interner = ShardedCompactStrInterner::with_shards(0);
+        assert_eq!(interner.shards.len(), 1);
+
+        let a = interner.intern("foo");
+        let b = interner.intern("bar");
+        assert_eq!(interner.intern("foo"), a);
+        assert_eq!(interner.resolve(b).unwrap(), "bar");
+    }
+
+    #[test]
+    fn test_sharded_many_threads() {
+        let interner = Arc::new(ShardedCompactStrInterner::with_shards(4));
+        let words: Vec<String> = (0..100).map(|i| format!("identifier_{}", i % 50)).collect();
+        let words = Arc::new(words);
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let interner = Arc::clone(&interner);
+                let words = Arc::clone(&words);
+                thread::spawn(move || {
+                    words
+                        .iter()
+                        .map(|word| interner.intern(word))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let results: Vec<Vec<Symbol>> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        // every thread should have gotten the same symbols, which are dense
+        assert!(results.iter().all(|symbols| symbols == &results[0]));
+        assert_eq!(interner.len(), 50);
+        for (word, symbol) in words.iter().zip(results[0].iter()) {
+            assert!(symbol.index() < 50);
+            assert_eq!(interner.resolve(*symbol).unwrap(), word.as_str());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_deserialize() {
+        use serde::de::value::{
+            Error,
+            SeqDeserializer,
+        };
+        use serde::Deserialize;
+
+        let words = vec![
+            "foo",
+            "bar",
+            "this identifier is long enough to be heap allocated",
+        ];
+
+        let de = SeqDeserializer::<_, Error>::new(words.clone().into_iter());
+        let interner: CompactStrInterner = Deserialize::deserialize(de).unwrap();
+        let de = SeqDeserializer::<_, Error>::new(words.clone().into_iter());
+        let sharded: ShardedCompactStrInterner = Deserialize::deserialize(de).unwrap();
+
+        for (index, word) in words.iter().enumerate() {
+            let symbol = Symbol::from_index(index).unwrap();
+            assert_eq!(interner.get(word), Some(symbol));
+            assert_eq!(sharded.get(word), Some(symbol));
+            assert_eq!(interner.resolve(symbol), Some(*word));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_deserialize_duplicates_fails() {
+        use serde::de::value::{
+            Error,
+            SeqDeserializer,
+        };
+        use serde::Deserialize;
+
+        let words = vec!["foo", "bar", "foo"];
+
+        let de = SeqDeserializer::<_, Error>::new(words.clone().into_iter());
+        let interner: Result<CompactStrInterner, _> = Deserialize::deserialize(de);
+        assert!(interner.is_err());
+
+        let de = SeqDeserializer::<_, Error>::new(words.into_iter());
+        let sharded: Result<ShardedCompactStrInterner, _> = Deserialize::deserialize(de);
+        assert!(sharded.is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_deserialize_symbol() {
+        use serde::de::value::Error;
+        use serde::de::IntoDeserializer;
+        use serde::Deserialize;
+
+        let de: serde::de::value::U32Deserializer<Error> = 42u32.into_deserializer();
+        assert_eq!(Symbol::deserialize(de).unwrap().index(), 42);
+
+        let de: serde::de::value::U32Deserializer<Error> = u32::MAX.into_deserializer();
+        assert!(Symbol::deserialize(de).is_err());
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn test_intern_roundtrips(#[strategy(rand_unicode_collection())] words: Vec<String>) {
+        let mut interner = CompactStrInterner::new();
+        let sharded = ShardedCompactStrInterner::new();
+
+        for word in &words {
+            let symbol = interner.intern(word);
+
+            // both interners should hand out the same symbols
+            prop_assert_eq!(sharded.intern(word), symbol);
+            prop_assert_eq!(interner.resolve(symbol), Some(word.as_str()));
+            prop_assert_eq!(sharded.resolve(symbol).unwrap(), word.as_str());
+            prop_assert_eq!(interner.get(word), Some(symbol));
+        }
+
+        let mut unique = words.clone();
+        unique.sort();
+        unique.dedup();
+
+        prop_assert_eq!(interner.len(), unique.len());
+        prop_assert_eq!(sharded.len(), unique.len());
+
+        let strings: Vec<&str> = interner.iter().map(|(_, s)| s).collect();
+        prop_assert_eq!(sharded.to_vec(), strings);
+    }
+}
@@ -0,0 +1,343 @@
+//! Helpers for reading [`CompactStr`]s from a [`Read`] or a [`BufRead`].
+//!
+//! Like [`Read::read_to_string`] and [`BufRead::read_line`], these check that the bytes they read
+//! are valid UTF-8, but instead of reading into a [`String`] they read directly into a
+//! [`CompactStr`]. Short strings, e.g. one-word lines or config keys, are stored inline, and longer
+//! strings move onto the heap once they run out of space.
+//!
+//! * [`read_to_compact_str`] reads everything from a [`Read`] into a [`CompactStr`].
+//! * [`read_line_compact`] appends a single line from a [`BufRead`] to a [`CompactStr`].
+//! * [`CompactLines`] is an iterator over the lines of a [`BufRead`], like [`std::io::Lines`].
+
+use std::io::{
+    self,
+    BufRead,
+    Read,
+};
+
+use crate::CompactStr;
+
+/// Reads all of the bytes from `reader` into a new [`CompactStr`].
+///
+/// This is like [`Read::read_to_string`], but the bytes are read directly into the [`CompactStr`],
+/// so short strings don't require a heap allocation.
+///
+/// # Errors
+/// Returns an error if reading from `reader` fails, or if the bytes aren't valid UTF-8, in which
+/// case the error has the kind [`io::ErrorKind::InvalidData`].
+///
+/// # Examples
+/// ```
+/// use compact_str::io::read_to_compact_str;
+///
+/// let mut reader: &[u8] = b"hello world";
+/// let compact = read_to_compact_str(&mut reader).unwrap();
+///
+/// assert_eq!(compact, "hello world");
+/// assert!(!compact.is_heap_allocated());
+///
+/// let mut invalid: &[u8] = &[0, 159];
+/// let err = read_to_compact_str(&mut invalid).unwrap_err();
+/// assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+/// ```
+pub fn read_to_compact_str<R: Read>(mut reader: R) -> io::Result<CompactStr> {
+    let mut compact = CompactStr::default();
+    compact.repr.read_to_end_utf8(&mut reader)?;
+    Ok(compact)
+}
+
+/// Reads bytes from `reader` until a newline (the `0xA` byte) is reached, and appends them to
+/// `buf`, returning how many bytes were read.
+///
+/// This is like [`BufRead::read_line`], the newline is included in `buf`, and `Ok(0)` means we've
+/// reached the end of `reader`.
+///
+/// # Errors
+/// Returns an error if reading from `reader` fails, or if the bytes aren't valid UTF-8, in which
+/// case the error has the kind [`io::ErrorKind::InvalidData`]. If this returns an error, `buf` is
+/// left unchanged.
+///
+/// # Examples
+/// ```
+/// use compact_str::io::read_line_compact;
+/// use compact_str::CompactStr;
+///
+/// let mut reader: &[u8] = b"key = value\nother = 42\n";
+/// let mut line = CompactStr::default();
+///
+/// assert_eq!(read_line_compact(&mut reader, &mut line).unwrap(), 12);
+/// assert_eq!(line, "key = value\n");
+///
+/// line.clear();
+/// assert_eq!(read_line_compact(&mut reader, &mut line).unwrap(), 11);
+/// assert_eq!(line, "other = 42\n");
+///
+/// line.clear();
+/// assert_eq!(read_line_compact(&mut reader, &mut line).unwrap(), 0);
+/// assert_eq!(line, "");
+/// ```
+pub fn read_line_compact<R: BufRead + ?Sized>(
+    reader: &mut R,
+    buf: &mut CompactStr,
+) -> io::Result<usize> {
+    buf.repr.read_line_utf8(reader)
+}
+
+/// An iterator over the lines of a [`BufRead`], as [`CompactStr`]s.
+///
+/// This is like [`std::io::Lines`], each line has its trailing newline, `\n` or `\r\n`, removed,
+/// and short lines are stored inline.
+///
+/// # Examples
+/// ```
+/// use compact_str::io::CompactLines;
+///
+/// let reader: &[u8] = b"alpha\r\nbeta\ngamma";
+/// let lines: Vec<_> = CompactLines::new(reader).collect::<Result<_, _>>().unwrap();
+///
+/// assert_eq!(lines, ["alpha", "beta", "gamma"]);
+/// assert!(lines.iter().all(|line| !line.is_heap_allocated()));
+/// ```
+#[derive(Debug)]
+pub struct CompactLines<R> {
+    reader: R,
+}
+
+impl<R: BufRead> CompactLines<R> {
+    /// Creates a new [`CompactLines`], which reads lines from `reader`.
+    #[inline]
+    pub fn new(reader: R) -> Self {
+        CompactLines { reader }
+    }
+
+    /// Returns a reference to the underlying reader.
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns a mutable reference to the underlying reader.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Consumes the [`CompactLines`], returning the underlying reader.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: BufRead> Iterator for CompactLines<R> {
+    type Item = io::Result<CompactStr>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = CompactStr::default();
+        match read_line_compact(&mut self.reader, &mut line) {
+            Ok(0) => None,
+            Ok(_) => {
+                if line.ends_with('\n') {
+                    line.pop();
+                    if line.ends_with('\r') {
+                        line.pop();
+                    }
+                }
+
+                // A line that only fits inline without its newline moved onto the heap while we
+                // were reading it
+                if line.len() <= core::mem::size_of::<String>() {
+                    line.shrink_to_fit();
+                }
+                Some(Ok(line))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{
+        self,
+        BufRead,
+        BufReader,
+        ErrorKind,
+        Read,
+    };
+
+    use proptest::prelude::*;
+    use test_strategy::proptest;
+
+    use super::{
+        read_line_compact,
+        read_to_compact_str,
+        CompactLines,
+    };
+    use crate::tests::{
+        rand_bytes,
+        rand_unicode,
+        rand_unicode_collection,
+    };
+    use crate::CompactStr;
+
+    const MAX_SIZE: usize = core::mem::size_of::<String>();
+
+    /// A [`Read`] that returns at most `chunk_size` bytes at a time, so characters get split
+    /// across reads
+    struct ChunkedReader<'a> {
+        data: &'a [u8],
+        chunk_size: usize,
+    }
+
+    impl Read for ChunkedReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let count = self.chunk_size.min(buf.len()).min(self.data.len());
+            buf[..count].copy_from_slice(&self.data[..count]);
+            self.data = &self.data[count..];
+            Ok(count)
+        }
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn test_read_to_compact_str_roundtrips(
+        #[strategy(rand_unicode())] word: String,
+        #[strategy(1..8usize)] chunk_size: usize,
+    ) {
+        let reader = ChunkedReader {
+            data: word.as_bytes(),
+            chunk_size,
+        };
+        let compact = read_to_compact_str(reader).unwrap();
+
+        prop_assert_eq!(&compact, &word);
+        prop_assert_eq!(compact.is_heap_allocated(), word.len() > MAX_SIZE);
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn test_read_to_compact_str_only_valid_utf8(
+        #[strategy(rand_bytes())] bytes: Vec<u8>,
+        #[strategy(1..8usize)] chunk_size: usize,
+    ) {
+        let reader = ChunkedReader {
+            data: &bytes,
+            chunk_size,
+        };
+        let compact_result = read_to_compact_str(reader);
+
+        let mut string = String::new();
+        let std_result = (&bytes[..]).read_to_string(&mut string);
+
+        match (compact_result, std_result) {
+            (Ok(c), Ok(_)) => prop_assert_eq!(c, string),
+            (Err(c_err), Err(s_err)) => prop_assert_eq!(c_err.kind(), s_err.kind()),
+            _ => panic!("CompactStr and String read UTF-8 differently?"),
+        }
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn test_lines_match_std(
+        #[strategy(rand_unicode_collection())] words: Vec<String>,
+        #[strategy(1..8usize)] capacity: usize,
+    ) {
+        let text = words.join("\n");
+
+        let reader = BufReader::with_capacity(capacity, text.as_bytes());
+        let compact: Vec<CompactStr> = CompactLines::new(reader).collect::<Result<_, _>>().unwrap();
+        let std: Vec<String> = text.as_bytes().lines().collect::<Result<_, _>>().unwrap();
+
+        prop_assert_eq!(&compact, &std);
+        for line in &compact {
+            prop_assert_eq!(line.is_heap_allocated(), line.len() > MAX_SIZE);
+        }
+    }
+
+    #[test]
+    fn test_read_line_split_across_buffers() {
+        let text = "🦀🦀🦀 this line is long enough to be heap allocated\nnext";
+        let mut reader = BufReader::with_capacity(3, text.as_bytes());
+
+        let mut line = CompactStr::default();
+        let count = read_line_compact(&mut reader, &mut line).unwrap();
+
+        assert_eq!(count, text.find('\n').unwrap() + 1);
+        assert_eq!(
+            line,
+            "🦀🦀🦀 this line is long enough to be heap allocated\n"
+        );
+    }
+
+    #[test]
+    fn test_read_line_appends() {
+        let mut reader: &[u8] = b"world\n";
+        let mut line = CompactStr::new("hello ");
+
+        assert_eq!(read_line_compact(&mut reader, &mut line).unwrap(), 6);
+        assert_eq!(line, "hello world\n");
+    }
+
+    #[test]
+    fn test_read_line_max_inline_is_inlined() {
+        let word = "a".repeat(MAX_SIZE);
+        let mut reader = word.as_bytes();
+
+        let mut line = CompactStr::default();
+        read_line_compact(&mut reader, &mut line).unwrap();
+
+        assert_eq!(line, word);
+        assert!(!line.is_heap_allocated());
+    }
+
+    #[test]
+    fn test_read_line_invalid_utf8_leaves_buf_unchanged() {
+        let mut reader: &[u8] = b"ok\xFF\nnext\n";
+        let mut line = CompactStr::new("prefix ");
+
+        let err = read_line_compact(&mut reader, &mut line).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(line, "prefix ");
+
+        // the invalid line was consumed
+        assert_eq!(read_line_compact(&mut reader, &mut line).unwrap(), 5);
+        assert_eq!(line, "prefix next\n");
+    }
+
+    #[test]
+    fn test_read_line_truncated_char() {
+        let mut reader: &[u8] = "🦀".as_bytes().split_last().unwrap().1;
+        let mut line = CompactStr::default();
+
+        let err = read_line_compact(&mut reader, &mut line).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(line, "");
+    }
+
+    #[test]
+    fn test_read_error_leaves_buf_unchanged() {
+        struct FailingReader;
+
+        impl Read for FailingReader {
+            fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::new(ErrorKind::BrokenPipe, "oh no"))
+            }
+        }
+
+        let mut reader = BufReader::new(FailingReader);
+        let mut line = CompactStr::new("hello");
+
+        let err = read_line_compact(&mut reader, &mut line).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::BrokenPipe);
+        assert_eq!(line, "hello");
+    }
+
+    #[test]
+    fn test_lines_crlf() {
+        let reader: &[u8] = b"a\r\n\r\nb\rc\n";
+        let lines: Vec<CompactStr> = CompactLines::new(reader).collect::<Result<_, _>>().unwrap();
+
+        assert_eq!(lines, ["a", "", "b\rc"]);
+    }
+}
@@ -12,27 +12,145 @@
 //! architectures). For small strings, e.g. <= 24 characters, instead of storing a pointer, length,
 //! and capacity on the stack, you store the string itself! This avoids the need to heap allocate
 //! which reduces the amount of memory used, and improves performance.
+//!
+//! ### Two Word Strings
+//! When 24 bytes per string is still too much, e.g. in a columnar index, [`CompactStr16`] has
+//! almost the same API as [`CompactStr`] in only two words. It stores up to 15 bytes inline, and
+//! heap allocated strings store their capacity on the heap, directly before the string.
+//!
+//! When your strings are a little longer, [`CompactStrN`] lets you pick the inline capacity, e.g.
+//! a `CompactStrN<40>` stores strings of up to 40 bytes inline. A [`CompactStr`] is the
+//! `CompactStrN` with the inline capacity of a [`String`].
+//!
+//! If you clone long strings a lot, [`SharedCompactStr`] stores heap allocated strings in an
+//! atomically reference counted buffer, so cloning them, or taking a substring, is `O(1)`.
+//!
+//! For sorting and comparing lots of strings, [`GermanStr`] is an immutable "German string", which
+//! stores the first 4 bytes of the string next to the heap pointer, so most comparisons don't
+//! dereference the heap. It also converts to and from the views of an Arrow `StringView` array.
+//!
+//! ### Bytes
+//! For binary data, e.g. hashes or protobuf messages, [`CompactBytes`] is a [`Vec<u8>`]-like type
+//! that shares the same storage as [`CompactStr`]. It stores up to 23 bytes inline, and converts
+//! to a [`CompactStr`] without copying, via [`CompactBytes::into_compact_str`], once the bytes
+//! have been checked to be valid UTF-8.
+//!
+//! On unix, [`CompactOsString`] and [`CompactPathBuf`] are built on [`CompactBytes`], and store
+//! short path components inline, e.g. when walking a file system.
+//!
+//! ### Formatting
+//! [`CompactStr`] implements [`core::fmt::Write`], and the [`format_compact!`] macro or the
+//! [`ToCompactStr`] trait can be used in place of [`format!`] or [`ToString`] to format values
+//! directly into a [`CompactStr`], without an intermediate heap allocated [`String`].
+//!
+//! [`ToString`]: alloc::string::ToString
+//!
+//! ### Custom Allocators
+//! By default a [`CompactStr`] heap allocates from the global allocator. A [`CompactStr`] can
+//! instead heap allocate from any type that implements [`Allocator`], e.g. an arena, via
+//! [`CompactStr::new_in`] or [`CompactStr::with_capacity_in`].
+//!
+//! ### Interning
+//! With the default `std` feature enabled, the [`interner`] module provides string interners that
+//! de-duplicate strings into small `Copy` symbols, storing short strings inline.
+//!
+//! ### Reading
+//! With the default `std` feature enabled, the [`io`] module provides helpers that read from a
+//! [`std::io::Read`] or [`std::io::BufRead`] directly into a [`CompactStr`], e.g. an iterator over
+//! the lines of a file.
+//!
+//! ### `no_std`
+//! `compact_str` only requires [`alloc`], and can be used in `no_std` environments by disabling the
+//! default `std` feature.
 
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+
+extern crate alloc;
+
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
 use core::borrow::Borrow;
 use core::cmp::Ordering;
+use core::convert::TryFrom;
 use core::fmt;
 use core::hash::{
     Hash,
     Hasher,
 };
-use core::iter::FromIterator;
-use core::ops::Deref;
+use core::iter::{
+    FromIterator,
+    FusedIterator,
+};
+use core::ops::{
+    Deref,
+    DerefMut,
+    Index,
+    IndexMut,
+    RangeBounds,
+};
+use core::slice::SliceIndex;
 use core::str::{
+    Chars,
     FromStr,
-    Utf8Error,
 };
-use std::borrow::Cow;
 
+mod allocator;
+pub use allocator::{
+    AllocError,
+    Allocator,
+    Global,
+};
 mod asserts;
+mod compact16;
+pub use compact16::{
+    CompactStr16,
+    Drain16,
+};
+mod compact_bytes;
+pub use compact_bytes::CompactBytes;
+mod error;
+pub use error::{
+    FromUtf8Error,
+    TryReserveError,
+    Utf16Error,
+};
 mod features;
+mod german;
+#[cfg(feature = "bytes")]
+pub use features::bytes::CompactStrWriter;
+#[cfg(feature = "rkyv")]
+pub use features::rkyv::ArchivedCompactStr;
+pub use german::GermanStr;
+#[cfg(feature = "std")]
+pub mod interner;
+#[cfg(feature = "std")]
+pub mod io;
+mod macros;
+#[cfg(all(feature = "std", unix))]
+mod os;
+#[cfg(all(feature = "std", unix))]
+pub use os::{
+    CompactOsString,
+    CompactPathBuf,
+};
+
+mod traits;
+pub use traits::ToCompactStr;
 
 mod repr;
-use repr::Repr;
+pub use repr::{
+    Boxed,
+    InlineCapacity,
+    Shared,
+    Storage,
+    ValidInlineCapacity,
+};
+use repr::{
+    Repr,
+    MAX_SIZE,
+};
 
 #[cfg(test)]
 mod tests;
@@ -75,12 +193,188 @@ mod tests;
 /// assert_eq!(CompactStr::new("chicago"), "chicago");
 /// assert_eq!(CompactStr::new("houston"), String::from("houston"));
 /// ```
+///
+/// ## Custom Allocators
+/// A `CompactStr<A>` heap allocates from the [`Allocator`] `A`, which defaults to the [`Global`]
+/// allocator. Zero-sized allocators, like [`Global`], don't change the size of a `CompactStr`.
+///
+/// ## Inline Capacity
+/// A `CompactStr` is the [`CompactStrN`] with an inline capacity of `size_of::<String>()` bytes,
+/// i.e. the same size as a [`String`]. All of its methods are documented on [`CompactStrN`].
+///
+/// ## Heap Storage
+/// A `CompactStr` uniquely owns its heap allocated buffer, see [`Boxed`], so converting from and
+/// into a [`String`] re-uses the buffer. If you'd rather share the buffer between clones, see
+/// [`SharedCompactStr`].
+pub type CompactStr<A = Global> = CompactStrN<MAX_SIZE, A>;
+
+/// A [`SharedCompactStr`] is a [`CompactStr`] that shares its heap allocated buffer between
+/// clones, like an `Arc<str>`, see [`Shared`].
+///
+/// Short strings are still stored inline, but cloning a heap allocated string, or taking a
+/// substring of one, only increments a reference count, and mutating a shared buffer copies it
+/// first. Unlike a [`CompactStr`], converting from or into a [`String`], [`Box<str>`], or
+/// [`Vec<u8>`] always copies, since the reference count is stored next to the string data.
+///
+/// # Examples
+/// ```
+/// use compact_str::SharedCompactStr;
+///
+/// let long = SharedCompactStr::new("this string is too long to be stored inline");
+/// let clone = long.clone();
+///
+/// // the clone shares the heap allocated buffer of `long`
+/// assert_eq!(clone.as_ptr(), long.as_ptr());
+///
+/// // ...until one of them gets mutated
+/// let mut clone = clone;
+/// clone.push('!');
+/// assert_ne!(clone.as_ptr(), long.as_ptr());
+/// assert_eq!(long, "this string is too long to be stored inline");
+/// ```
+pub type SharedCompactStr<A = Global> = CompactStrN<MAX_SIZE, A, Shared>;
+
+/// A [`CompactStrN`] is a [`CompactStr`] with an inline capacity of `N` bytes, which you can pick
+/// to fit your strings, e.g. 40 byte keys which would be heap allocated by a [`CompactStr`],
+/// especially on 32-bit architectures where it only stores 12 bytes inline.
+///
+/// Strings of up to `N` bytes are stored inline, and longer strings are heap allocated, the same
+/// way as a [`CompactStr`]. `N` can be anything from `size_of::<String>()` up to 56 bytes, see
+/// [`ValidInlineCapacity`], otherwise using a `CompactStrN<N>` fails to build.
+///
+/// ```compile_fail
+/// # use compact_str::CompactStrN;
+/// // `N` is too large for the last byte to store the length of a shorter inline string
+/// let huge = CompactStrN::<64>::new("hello");
+/// ```
+///
+/// A `CompactStrN<N>` is `N` bytes, rounded up to the alignment of a pointer, followed by its
+/// [`Allocator`]. Like a [`CompactStr`], the unused values of the last byte are a niche, so an
+/// `Option<CompactStrN<N>>` is the same size as a `CompactStrN<N>`.
+///
+/// A `CompactStrN<N, A, S>` stores longer strings in the [`Storage`] `S`, which defaults to a
+/// uniquely owned [`Boxed`] buffer, or can be a reference counted [`Shared`] buffer.
+///
+/// Note: [`CompactStr::new_inline`] and [`CompactStr::from_static_str`] are `const fn`s, which
+/// are only available for a [`CompactStr`] and a [`SharedCompactStr`].
+///
+/// # Examples
+/// ```
+/// use compact_str::CompactStrN;
+///
+/// let mut key = CompactStrN::<40>::new("7f3c1a9e-4b2d-4e8f-9c6a");
+/// assert!(!key.is_heap_allocated());
+///
+/// key.push_str("-0d5b2e7f1a3c");
+/// assert_eq!(key, "7f3c1a9e-4b2d-4e8f-9c6a-0d5b2e7f1a3c");
+/// assert!(!key.is_heap_allocated());
+///
+/// assert_eq!(std::mem::size_of::<CompactStrN<40>>(), 40);
+/// assert_eq!(std::mem::size_of::<Option<CompactStrN<40>>>(), 40);
+/// ```
 #[derive(Clone)]
-pub struct CompactStr {
-    repr: Repr,
+pub struct CompactStrN<const N: usize, A: Allocator = Global, S: Storage<A> = Boxed>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    repr: Repr<N, A, S>,
 }
 
 impl CompactStr {
+    /// Creates a new inline [`CompactStr`] at compile time.
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::CompactStr;
+    ///
+    /// const DEFAULT_NAME: CompactStr = CompactStr::new_inline("untitled");
+    /// ```
+    ///
+    /// Note: Trying to create a long string that can't be inlined, will fail to build.
+    /// ```compile_fail
+    /// # use compact_str::CompactStr;
+    /// const LONG: CompactStr = CompactStr::new_inline("this is a long string that can't be stored on the stack");
+    /// ```
+    #[inline]
+    pub const fn new_inline(text: &str) -> Self {
+        CompactStr {
+            repr: <Repr>::new_const(text),
+        }
+    }
+
+    /// Creates a new [`CompactStr`] from a `&'static str` at compile time, without copying.
+    ///
+    /// Short strings are stored inline, like [`CompactStr::new_inline`], but unlike
+    /// `new_inline`, longer strings are also supported. For these we store a pointer to the
+    /// `&'static str` and borrow it, instead of heap allocating. The string is copied into a heap
+    /// allocation the first time it's mutated.
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::CompactStr;
+    ///
+    /// const ERROR: CompactStr =
+    ///     CompactStr::from_static_str("this is a long error message that can't be stored inline");
+    ///
+    /// let mut err = ERROR;
+    /// assert_eq!(err, "this is a long error message that can't be stored inline");
+    /// assert!(!err.is_heap_allocated());
+    ///
+    /// // mutating the string copies it onto the heap
+    /// err.push('!');
+    /// assert_eq!(err, "this is a long error message that can't be stored inline!");
+    /// assert!(err.is_heap_allocated());
+    /// ```
+    #[inline]
+    pub const fn from_static_str(text: &'static str) -> Self {
+        CompactStr {
+            repr: <Repr>::from_static_str(text),
+        }
+    }
+}
+
+impl SharedCompactStr {
+    /// Creates a new inline [`SharedCompactStr`] at compile time, see [`CompactStr::new_inline`].
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::SharedCompactStr;
+    ///
+    /// const DEFAULT_NAME: SharedCompactStr = SharedCompactStr::new_inline("untitled");
+    /// ```
+    #[inline]
+    pub const fn new_inline(text: &str) -> Self {
+        SharedCompactStr {
+            repr: Repr::<MAX_SIZE, Global, Shared>::new_const(text),
+        }
+    }
+
+    /// Creates a new [`SharedCompactStr`] from a `&'static str` at compile time, without copying,
+    /// see [`CompactStr::from_static_str`].
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::SharedCompactStr;
+    ///
+    /// const ERROR: SharedCompactStr =
+    ///     SharedCompactStr::from_static_str("this is a long error message that can't be stored inline");
+    ///
+    /// let err = ERROR;
+    /// assert_eq!(err, "this is a long error message that can't be stored inline");
+    /// assert!(!err.is_heap_allocated());
+    /// ```
+    #[inline]
+    pub const fn from_static_str(text: &'static str) -> Self {
+        SharedCompactStr {
+            repr: Repr::<MAX_SIZE, Global, Shared>::from_static_str(text),
+        }
+    }
+}
+
+impl<const N: usize, S: Storage<Global>> CompactStrN<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     /// Creates a new [`CompactStr`] from any type that implements `AsRef<str>`.
     /// If the string is short enough, then it will be inlined on the stack!
     ///
@@ -133,32 +427,11 @@ impl CompactStr {
     /// ```
     #[inline]
     pub fn new<T: AsRef<str>>(text: T) -> Self {
-        CompactStr {
+        CompactStrN {
             repr: Repr::new(text),
         }
     }
 
-    /// Creates a new inline [`CompactStr`] at compile time.
-    ///
-    /// # Examples
-    /// ```
-    /// use compact_str::CompactStr;
-    ///
-    /// const DEFAULT_NAME: CompactStr = CompactStr::new_inline("untitled");
-    /// ```
-    ///
-    /// Note: Trying to create a long string that can't be inlined, will fail to build.
-    /// ```compile_fail
-    /// # use compact_str::CompactStr;
-    /// const LONG: CompactStr = CompactStr::new_inline("this is a long string that can't be stored on the stack");
-    /// ```
-    #[inline]
-    pub const fn new_inline(text: &str) -> Self {
-        CompactStr {
-            repr: Repr::new_const(text),
-        }
-    }
-
     /// Creates a new empty [`CompactStr`] with the capacity to fit at least `capacity` bytes.
     ///
     /// A `CompactStr` will inline strings on the stack, if they're small enough. Specifically, if
@@ -193,255 +466,1011 @@ impl CompactStr {
     /// assert!(!empty.is_heap_allocated());
     /// ```
     ///
-    /// ### Heap Allocating
+    /// ### Heap Allocating
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// // If you create a `CompactStr` with a capacity greater than
+    /// // `std::mem::size_of::<String>`, it will heap allocated
+    ///
+    /// let heap_size = std::mem::size_of::<String>() + 1;
+    /// let empty = CompactStr::with_capacity(heap_size);
+    ///
+    /// assert_eq!(empty.capacity(), heap_size);
+    /// assert!(empty.is_heap_allocated());
+    /// ```
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        CompactStrN {
+            repr: Repr::with_capacity(capacity),
+        }
+    }
+
+    /// Fallible version of [`CompactStr::with_capacity`].
+    ///
+    /// Instead of aborting or panicking, returns an error if the capacity overflows, or if the
+    /// allocator fails.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let compact = CompactStr::try_with_capacity(100).unwrap();
+    /// assert!(compact.capacity() >= 100);
+    ///
+    /// // we can't allocate a buffer this large
+    /// assert!(CompactStr::try_with_capacity(usize::MAX).is_err());
+    /// ```
+    #[inline]
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+        let repr = Repr::try_with_capacity(capacity)?;
+        Ok(CompactStrN { repr })
+    }
+
+    /// Convert a slice of bytes into a [`CompactStr`].
+    ///
+    /// A [`CompactStr`] is a contiguous collection of bytes (`u8`s) that is valid [`UTF-8`](https://en.wikipedia.org/wiki/UTF-8).
+    /// This method converts from an arbitrary contiguous collection of bytes into a [`CompactStr`],
+    /// failing if the provided bytes are not `UTF-8`.
+    ///
+    /// If the bytes aren't valid UTF-8, the returned [`FromUtf8Error`] contains a copy of them,
+    /// which can be retrieved with [`FromUtf8Error::into_bytes`]. To convert an owned [`Vec<u8>`]
+    /// without copying, use `TryFrom<Vec<u8>>`.
+    ///
+    /// Note: If you want to create a [`CompactStr`] from a non-contiguous collection of bytes,
+    /// enable the `bytes` feature of this crate, and checkout [`CompactStr::from_utf8_buf`]
+    ///
+    /// # Examples
+    /// ### Valid UTF-8
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let bytes = vec![240, 159, 166, 128, 240, 159, 146, 175];
+    /// let compact = CompactStr::from_utf8(bytes).expect("valid UTF-8");
+    ///
+    /// assert_eq!(compact, "🦀💯");
+    /// ```
+    ///
+    /// ### Invalid UTF-8
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let bytes = vec![255, 255, 255];
+    /// let err = CompactStr::from_utf8(bytes).unwrap_err();
+    ///
+    /// assert_eq!(err.utf8_error().valid_up_to(), 0);
+    /// assert_eq!(err.as_bytes(), &[255, 255, 255]);
+    /// ```
+    #[inline]
+    pub fn from_utf8<B: AsRef<[u8]>>(buf: B) -> Result<Self, FromUtf8Error> {
+        let repr = Repr::from_utf8(buf)?;
+        Ok(CompactStrN { repr })
+    }
+
+    /// Converts a slice of bytes into a [`CompactStr`], without checking that the bytes are valid
+    /// UTF-8.
+    ///
+    /// See the safe version, [`CompactStr::from_utf8`], for more details.
+    ///
+    /// # Safety
+    /// The bytes passed in must be valid UTF-8. Creating a [`CompactStr`] from invalid UTF-8 is
+    /// undefined behavior.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let bytes = vec![240, 159, 166, 128, 240, 159, 146, 175];
+    /// let compact = unsafe { CompactStr::from_utf8_unchecked(bytes) };
+    ///
+    /// assert_eq!(compact, "🦀💯");
+    /// ```
+    #[inline]
+    pub unsafe fn from_utf8_unchecked<B: AsRef<[u8]>>(buf: B) -> Self {
+        let repr = Repr::from_utf8_unchecked(buf);
+        CompactStrN { repr }
+    }
+
+    /// Converts a slice of bytes into a [`CompactStr`], replacing any invalid UTF-8 sequences with
+    /// [`U+FFFD REPLACEMENT CHARACTER`][U+FFFD], which looks like this: �
+    ///
+    /// This behaves like [`String::from_utf8_lossy`], but instead of returning a [`Cow`], the
+    /// decoded string is written directly into a [`CompactStr`], so if it's short enough it gets
+    /// inlined without any heap allocations.
+    ///
+    /// [U+FFFD]: core::char::REPLACEMENT_CHARACTER
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let bytes = b"Hi \xF0\x90\x80there";
+    /// let compact = CompactStr::from_utf8_lossy(bytes);
+    ///
+    /// assert_eq!(compact, "Hi �there");
+    /// assert!(!compact.is_heap_allocated());
+    /// ```
+    #[inline]
+    pub fn from_utf8_lossy(v: &[u8]) -> Self {
+        let repr = Repr::from_utf8_lossy(v);
+        CompactStrN { repr }
+    }
+
+    /// Decodes a slice of UTF-16 code units into a [`CompactStr`], returning an [`Utf16Error`] if
+    /// it contains any unpaired surrogates.
+    ///
+    /// The decoded string is written directly into the [`CompactStr`], so if it's short enough it
+    /// gets inlined without any heap allocations.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// // 𝄞music
+    /// let v = &[0xD834, 0xDD1E, 0x006d, 0x0075, 0x0073, 0x0069, 0x0063];
+    /// assert_eq!(CompactStr::from_utf16(v).unwrap(), "𝄞music");
+    ///
+    /// // 𝄞mu<invalid>ic
+    /// let v = &[0xD834, 0xDD1E, 0x006d, 0x0075, 0xD800, 0x0069, 0x0063];
+    /// assert!(CompactStr::from_utf16(v).is_err());
+    /// ```
+    #[inline]
+    pub fn from_utf16<B: AsRef<[u16]>>(buf: B) -> Result<Self, Utf16Error> {
+        let repr = Repr::from_utf16(buf.as_ref().iter().copied())?;
+        Ok(CompactStrN { repr })
+    }
+
+    /// Decodes a slice of UTF-16 code units into a [`CompactStr`], replacing any unpaired
+    /// surrogates with [`U+FFFD REPLACEMENT CHARACTER`][U+FFFD].
+    ///
+    /// [U+FFFD]: core::char::REPLACEMENT_CHARACTER
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// // 𝄞mus<invalid>ic<invalid>
+    /// let v = &[0xD834, 0xDD1E, 0x006d, 0x0075, 0x0073, 0xDD1E, 0x0069, 0x0063, 0xD834];
+    /// assert_eq!(CompactStr::from_utf16_lossy(v), "𝄞mus\u{FFFD}ic\u{FFFD}");
+    /// ```
+    #[inline]
+    pub fn from_utf16_lossy<B: AsRef<[u16]>>(buf: B) -> Self {
+        let repr = Repr::from_utf16_lossy(buf.as_ref().iter().copied());
+        CompactStrN { repr }
+    }
+
+    /// Decodes a slice of bytes containing little endian UTF-16 into a [`CompactStr`], returning
+    /// an [`Utf16Error`] if the slice has an odd length, or contains any unpaired surrogates.
+    ///
+    /// This is useful when the UTF-16 comes from an external source, e.g. a file name on Windows,
+    /// and isn't aligned to be read as a slice of `u16`s.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let bytes = [0x68, 0x00, 0x69, 0x00, 0x3D, 0xD8, 0x00, 0xDE];
+    /// assert_eq!(CompactStr::from_utf16le(bytes).unwrap(), "hi😀");
+    ///
+    /// // odd length
+    /// assert!(CompactStr::from_utf16le([0x68, 0x00, 0x69]).is_err());
+    /// ```
+    #[inline]
+    pub fn from_utf16le<B: AsRef<[u8]>>(buf: B) -> Result<Self, Utf16Error> {
+        let repr = Repr::from_utf16_bytes(buf.as_ref(), u16::from_le_bytes)?;
+        Ok(CompactStrN { repr })
+    }
+
+    /// Decodes a slice of bytes containing little endian UTF-16 into a [`CompactStr`], replacing
+    /// any unpaired surrogates, or a trailing odd byte, with
+    /// [`U+FFFD REPLACEMENT CHARACTER`][U+FFFD].
+    ///
+    /// [U+FFFD]: core::char::REPLACEMENT_CHARACTER
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let bytes = [0x68, 0x00, 0x00, 0xD8, 0x69];
+    /// assert_eq!(CompactStr::from_utf16le_lossy(bytes), "h\u{FFFD}\u{FFFD}");
+    /// ```
+    #[inline]
+    pub fn from_utf16le_lossy<B: AsRef<[u8]>>(buf: B) -> Self {
+        let repr = Repr::from_utf16_bytes_lossy(buf.as_ref(), u16::from_le_bytes);
+        CompactStrN { repr }
+    }
+
+    /// Decodes a slice of bytes containing big endian UTF-16 into a [`CompactStr`], returning an
+    /// [`Utf16Error`] if the slice has an odd length, or contains any unpaired surrogates.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let bytes = [0x00, 0x68, 0x00, 0x69, 0xD8, 0x3D, 0xDE, 0x00];
+    /// assert_eq!(CompactStr::from_utf16be(bytes).unwrap(), "hi😀");
+    ///
+    /// // unpaired surrogate
+    /// assert!(CompactStr::from_utf16be([0x00, 0x68, 0xD8, 0x3D]).is_err());
+    /// ```
+    #[inline]
+    pub fn from_utf16be<B: AsRef<[u8]>>(buf: B) -> Result<Self, Utf16Error> {
+        let repr = Repr::from_utf16_bytes(buf.as_ref(), u16::from_be_bytes)?;
+        Ok(CompactStrN { repr })
+    }
+
+    /// Decodes a slice of bytes containing big endian UTF-16 into a [`CompactStr`], replacing
+    /// any unpaired surrogates, or a trailing odd byte, with
+    /// [`U+FFFD REPLACEMENT CHARACTER`][U+FFFD].
+    ///
+    /// [U+FFFD]: core::char::REPLACEMENT_CHARACTER
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let bytes = [0x00, 0x68, 0xD8, 0x00, 0x69];
+    /// assert_eq!(CompactStr::from_utf16be_lossy(bytes), "h\u{FFFD}\u{FFFD}");
+    /// ```
+    #[inline]
+    pub fn from_utf16be_lossy<B: AsRef<[u8]>>(buf: B) -> Self {
+        let repr = Repr::from_utf16_bytes_lossy(buf.as_ref(), u16::from_be_bytes);
+        CompactStrN { repr }
+    }
+
+    /// Creates a [`CompactStr`] from a [`String`], always re-using the `String`'s heap allocated
+    /// buffer, even if the string is short enough to be inlined.
+    ///
+    /// Converting via [`From<String>`] inlines short strings and frees the source buffer, which
+    /// saves memory. If instead you want to hold onto the spare capacity of the `String`, e.g.
+    /// because you're about to append to it, this conversion is `O(1)`.
+    ///
+    /// Note: A heap allocated [`SharedCompactStr`] needs to store a reference count next to the
+    /// string, so the buffer is always copied.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let mut s = String::with_capacity(100);
+    /// s.push_str("hello");
+    ///
+    /// let compact = CompactStr::from_string_preserve_capacity(s);
+    /// assert_eq!(compact, "hello");
+    /// assert_eq!(compact.capacity(), 100);
+    /// assert!(compact.is_heap_allocated());
+    ///
+    /// // by default short strings get inlined
+    /// let compact = CompactStr::from(String::from("hello"));
+    /// assert!(!compact.is_heap_allocated());
+    /// ```
+    #[inline]
+    pub fn from_string_preserve_capacity(s: String) -> Self {
+        let repr = Repr::from_string_preserve_capacity(s);
+        CompactStrN { repr }
+    }
+
+    /// Converts this [`CompactStr`] into a [`String`].
+    ///
+    /// If the `CompactStr` is heap allocated, the underlying buffer is handed over to the
+    /// `String` without copying, i.e. this is `O(1)`. Inline strings need to be copied into a new
+    /// heap allocation.
+    ///
+    /// Note: A heap allocated [`SharedCompactStr`] also needs to be copied.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let compact = CompactStr::new("this is a long string that will be heap allocated");
+    /// let ptr = compact.as_ptr();
+    ///
+    /// let s = compact.into_string();
+    /// assert_eq!(s, "this is a long string that will be heap allocated");
+    /// assert_eq!(s.as_ptr(), ptr);
+    /// ```
+    #[inline]
+    pub fn into_string(self) -> String {
+        self.repr.into_string()
+    }
+
+    /// Converts this [`CompactStr`] into a [`Box<str>`].
+    ///
+    /// Like [`CompactStr::into_string`], this re-uses the heap allocation if possible, but will
+    /// drop any excess capacity.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let compact = CompactStr::new("hello world");
+    /// let b: Box<str> = compact.into_boxed_str();
+    ///
+    /// assert_eq!(&*b, "hello world");
+    /// ```
+    #[inline]
+    pub fn into_boxed_str(self) -> Box<str> {
+        self.into_string().into_boxed_str()
+    }
+
+    /// Converts this [`CompactStr`] into a vector of bytes.
+    ///
+    /// Like [`CompactStr::into_string`], this re-uses the heap allocation if possible.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let compact = CompactStr::new("hello");
+    /// let bytes = compact.into_bytes();
+    ///
+    /// assert_eq!(&[104, 101, 108, 108, 111][..], &bytes[..]);
+    /// ```
+    #[inline]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.into_string().into_bytes()
+    }
+}
+
+impl<const N: usize, A: Allocator, S: Storage<A>> CompactStrN<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    /// Creates a new [`CompactStr`] from any type that implements `AsRef<str>`, which heap
+    /// allocates from the provided [`Allocator`], if the string is too long to be inlined.
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::{CompactStr, Global};
+    ///
+    /// let short = CompactStr::new_in("hello world", Global);
+    /// assert!(!short.is_heap_allocated());
+    ///
+    /// let long = CompactStr::new_in("I am a longer string that will be allocated on the heap", Global);
+    /// assert!(long.is_heap_allocated());
+    /// ```
+    #[inline]
+    pub fn new_in<T: AsRef<str>>(text: T, alloc: A) -> Self {
+        CompactStrN {
+            repr: Repr::new_in(text, alloc),
+        }
+    }
+
+    /// Creates a new empty [`CompactStr`] with the capacity to fit at least `capacity` bytes,
+    /// which heap allocates from the provided [`Allocator`].
+    ///
+    /// See [`CompactStr::with_capacity`] for more details.
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::{CompactStr, Global};
+    ///
+    /// let empty = CompactStr::with_capacity_in(0, Global);
+    /// assert!(!empty.is_heap_allocated());
+    ///
+    /// let heap = CompactStr::with_capacity_in(128, Global);
+    /// assert_eq!(heap.capacity(), 128);
+    /// assert!(heap.is_heap_allocated());
+    /// ```
+    #[inline]
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        CompactStrN {
+            repr: Repr::with_capacity_in(capacity, alloc),
+        }
+    }
+
+    /// Fallible version of [`CompactStr::with_capacity_in`].
+    ///
+    /// Instead of aborting or panicking, returns an error if the capacity overflows, or if the
+    /// allocator fails.
+    #[inline]
+    pub fn try_with_capacity_in(capacity: usize, alloc: A) -> Result<Self, TryReserveError> {
+        let repr = Repr::try_with_capacity_in(capacity, alloc)?;
+        Ok(CompactStrN { repr })
+    }
+
+    /// Returns a reference to the [`Allocator`] this [`CompactStr`] heap allocates from.
+    #[inline]
+    pub fn allocator(&self) -> &A {
+        self.repr.allocator()
+    }
+
+    /// Returns the length of the [`CompactStr`] in `bytes`, not [`char`]s or graphemes.
+    ///
+    /// When using `UTF-8` encoding (which all strings in Rust do) a single character will be 1 to 4
+    /// bytes long, therefore the return value of this method might not be what a human considers
+    /// the length of the string.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let ascii = CompactStr::new("hello world");
+    /// assert_eq!(ascii.len(), 11);
+    ///
+    /// let emoji = CompactStr::new("👱");
+    /// assert_eq!(emoji.len(), 4);
+    /// ```
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.repr.len()
+    }
+
+    /// Returns `true` if the [`CompactStr`] has a length of 0, `false` otherwise
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let mut msg = CompactStr::new("");
+    /// assert!(msg.is_empty());
+    ///
+    /// // add some characters
+    /// msg.push_str("hello reader!");
+    /// assert!(!msg.is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the capacity of the [`CompactStr`], in bytes.
+    ///
+    /// # Note
+    /// * A `CompactStr` will always have a capacity of at least `std::mem::size_of::<String>()`
+    ///
+    /// # Examples
+    /// ### Minimum Size
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let min_size = std::mem::size_of::<String>();
+    /// let compact = CompactStr::new("");
+    ///
+    /// assert!(compact.capacity() >= min_size);
+    /// ```
+    ///
+    /// ### Heap Allocated
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let compact = CompactStr::with_capacity(128);
+    /// assert_eq!(compact.capacity(), 128);
+    /// ```
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.repr.capacity()
+    }
+
+    /// Ensures that this [`CompactStr`]'s capacity is at least `additional` bytes longer than its
+    /// length. The capacity may be increased by more than `additional` bytes if it chooses, to
+    /// prevent frequent reallocations.
+    ///
+    /// # Note
+    /// * A `CompactStr` will always have at least a capacity of `std::mem::size_of::<String>()`
+    /// * Reserving additional bytes may cause the `CompactStr` to become heap allocated
+    ///
+    /// # Panics
+    /// Panics if the new capacity overflows `usize`
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    ///
+    /// const WORD: usize = std::mem::size_of::<usize>();
+    /// let mut compact = CompactStr::default();
+    /// assert!(compact.capacity() >= (WORD * 3) - 1);
+    ///
+    /// compact.reserve(200);
+    /// assert!(compact.is_heap_allocated());
+    /// assert!(compact.capacity() >= 200);
+    /// ```
+
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        self.repr.reserve(additional)
+    }
+
+    /// Tries to reserve capacity for at least `additional` more bytes, like
+    /// [`CompactStr::reserve`].
+    ///
+    /// Instead of aborting or panicking, returns an error if the capacity overflows, or if the
+    /// allocator fails. If an error is returned, the `CompactStr` is unchanged.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let mut compact = CompactStr::new("hello");
+    ///
+    /// compact.try_reserve(200).unwrap();
+    /// assert!(compact.capacity() >= 205);
+    ///
+    /// // reserving this much space overflows the capacity
+    /// assert!(compact.try_reserve(usize::MAX).is_err());
+    /// assert_eq!(compact, "hello");
+    /// ```
+    #[inline]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.repr.try_reserve(additional)
+    }
+
+    /// Shrinks the capacity of this [`CompactStr`] to match its length.
+    ///
+    /// If the string is short enough to be inlined, this moves it back onto the stack and frees
+    /// its heap allocation.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let mut compact = CompactStr::new("this is a long string that will be heap allocated");
+    /// compact.reserve(100);
+    /// assert!(compact.capacity() >= compact.len() + 100);
+    ///
+    /// compact.shrink_to_fit();
+    /// assert_eq!(compact.capacity(), compact.len());
+    ///
+    /// // once the string is short enough, it's moved back onto the stack
+    /// compact.truncate(4);
+    /// compact.shrink_to_fit();
+    /// assert_eq!(compact, "this");
+    /// assert!(!compact.is_heap_allocated());
+    /// ```
+    #[inline]
+    pub fn shrink_to_fit(&mut self) {
+        self.repr.shrink_to_fit()
+    }
+
+    /// Shrinks the capacity of this [`CompactStr`] with a lower bound.
+    ///
+    /// The capacity will remain at least as large as both the length and the supplied value. If
+    /// the current capacity is less than the lower bound, this is a no-op.
+    ///
+    /// If the string, and the lower bound, are short enough to be inlined, this moves the string
+    /// back onto the stack and frees its heap allocation.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let mut compact = CompactStr::with_capacity(100);
+    /// compact.push_str("hello world");
+    ///
+    /// compact.shrink_to(50);
+    /// assert_eq!(compact.capacity(), 50);
+    /// assert!(compact.is_heap_allocated());
+    ///
+    /// compact.shrink_to(0);
+    /// assert!(!compact.is_heap_allocated());
+    /// assert_eq!(compact, "hello world");
+    /// ```
+    #[inline]
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        self.repr.shrink_to(min_capacity)
+    }
+
+    /// Returns a string slice containing the entire [`CompactStr`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let s = CompactStr::new("hello");
+    ///
+    /// assert_eq!(s.as_str(), "hello");
+    /// ```
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.repr.as_str()
+    }
+
+    /// Returns a byte slice of the [`CompactStr`]'s contents.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let s = CompactStr::new("hello");
+    ///
+    /// assert_eq!(&[104, 101, 108, 108, 111], s.as_bytes());
+    /// ```
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.repr.as_slice()[..self.len()]
+    }
+
+    /// Returns a mutable string slice containing the entire [`CompactStr`].
+    ///
+    /// Note: If the [`CompactStr`] was created from a `&'static str`, or shares its heap buffer
+    /// with other strings, it will first be copied into a buffer it uniquely owns.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let mut s = CompactStr::new("hello");
+    /// s.as_mut_str().make_ascii_uppercase();
+    ///
+    /// assert_eq!(s, "HELLO");
+    /// ```
+    #[inline]
+    pub fn as_mut_str(&mut self) -> &mut str {
+        self.repr.as_mut_str()
+    }
+
+    // TODO: Implement a `try_as_mut_slice(...)` that will fail if it results in cloning?
+    //
+    /// Provides a mutable reference to the underlying buffer of bytes.
+    ///
+    /// The returned slice spans the entire capacity of the [`CompactStr`], not just its length.
+    /// Like [`CompactStr::as_mut_str`], if the buffer is borrowed or shared it will first be
+    /// copied.
+    ///
+    /// # Safety
+    /// * All Rust strings, including `CompactStr`, must be valid UTF-8. The caller must guarantee
+    /// that any modifications made to the underlying buffer are valid UTF-8.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let mut s = CompactStr::new("hello");
+    ///
+    /// let slice = unsafe { s.as_mut_bytes() };
+    /// // copy bytes into our string
+    /// slice[5..11].copy_from_slice(" world".as_bytes());
+    /// // set the len of the string
+    /// unsafe { s.set_len(11) };
+    ///
+    /// assert_eq!(s, "hello world");
+    /// ```
+    #[inline]
+    pub unsafe fn as_mut_bytes(&mut self) -> &mut [u8] {
+        self.repr.as_mut_slice()
+    }
+
+    /// Appends the given [`char`] to the end of this [`CompactStr`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let mut s = CompactStr::new("foo");
+    ///
+    /// s.push('b');
+    /// s.push('a');
+    /// s.push('r');
+    ///
+    /// assert_eq!("foobar", s);
+    /// ```
+    #[inline]
+    pub fn push(&mut self, ch: char) {
+        self.repr.push(ch)
+    }
+
+    /// Removes the last character from the [`CompactStr`] and returns it.
+    /// Returns `None` if this `ComapctStr` is empty.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let mut s = CompactStr::new("abc");
+    ///
+    /// assert_eq!(s.pop(), Some('c'));
+    /// assert_eq!(s.pop(), Some('b'));
+    /// assert_eq!(s.pop(), Some('a'));
+    ///
+    /// assert_eq!(s.pop(), None);
+    /// ```
+    #[inline]
+    pub fn pop(&mut self) -> Option<char> {
+        self.repr.pop()
+    }
+
+    /// Appends a given string slice onto the end of this [`CompactStr`]
+    ///
+    /// # Examples
     /// ```
     /// # use compact_str::CompactStr;
-    /// // If you create a `CompactStr` with a capacity greater than
-    /// // `std::mem::size_of::<String>`, it will heap allocated
+    /// let mut s = CompactStr::new("abc");
     ///
-    /// let heap_size = std::mem::size_of::<String>() + 1;
-    /// let empty = CompactStr::with_capacity(heap_size);
+    /// s.push_str("123");
     ///
-    /// assert_eq!(empty.capacity(), heap_size);
-    /// assert!(empty.is_heap_allocated());
+    /// assert_eq!("abc123", s);
     /// ```
     #[inline]
-    pub fn with_capacity(capacity: usize) -> Self {
-        CompactStr {
-            repr: Repr::with_capacity(capacity),
-        }
+    pub fn push_str(&mut self, s: &str) {
+        self.repr.push_str(s)
     }
 
-    /// Convert a slice of bytes into a [`CompactStr`].
-    ///
-    /// A [`CompactStr`] is a contiguous collection of bytes (`u8`s) that is valid [`UTF-8`](https://en.wikipedia.org/wiki/UTF-8).
-    /// This method converts from an arbitrary contiguous collection of bytes into a [`CompactStr`],
-    /// failing if the provided bytes are not `UTF-8`.
+    /// Fallible version of [`CompactStr::push_str`].
     ///
-    /// Note: If you want to create a [`CompactStr`] from a non-contiguous collection of bytes,
-    /// enable the `bytes` feature of this crate, and checkout [`CompactStr::from_utf8_buf`]
+    /// Instead of aborting or panicking, returns an error if growing the `CompactStr` fails. If an
+    /// error is returned, the `CompactStr` is unchanged.
     ///
     /// # Examples
-    /// ### Valid UTF-8
     /// ```
     /// # use compact_str::CompactStr;
-    /// let bytes = vec![240, 159, 166, 128, 240, 159, 146, 175];
-    /// let compact = CompactStr::from_utf8(bytes).expect("valid UTF-8");
+    /// let mut s = CompactStr::new("abc");
     ///
-    /// assert_eq!(compact, "🦀💯");
+    /// s.try_push_str("123").unwrap();
+    ///
+    /// assert_eq!("abc123", s);
     /// ```
+    #[inline]
+    pub fn try_push_str(&mut self, s: &str) -> Result<(), TryReserveError> {
+        self.repr.try_push_str(s)
+    }
+
+    /// Shortens this [`CompactStr`] to the specified length.
     ///
-    /// ### Invalid UTF-8
+    /// If `new_len` is greater than the string's current length, this has no effect.
+    ///
+    /// Note that this method has no effect on the allocated capacity of the string.
+    ///
+    /// # Panics
+    /// Panics if `new_len` does not lie on a [`char`] boundary.
+    ///
+    /// # Examples
     /// ```
     /// # use compact_str::CompactStr;
-    /// let bytes = vec![255, 255, 255];
-    /// let result = CompactStr::from_utf8(bytes);
+    /// let mut s = CompactStr::new("hello");
+    /// s.truncate(2);
     ///
-    /// assert!(result.is_err());
+    /// assert_eq!(s, "he");
     /// ```
     #[inline]
-    pub fn from_utf8<B: AsRef<[u8]>>(buf: B) -> Result<Self, Utf8Error> {
-        let repr = Repr::from_utf8(buf)?;
-        Ok(CompactStr { repr })
+    pub fn truncate(&mut self, new_len: usize) {
+        self.repr.truncate(new_len)
     }
 
-    /// Returns the length of the [`CompactStr`] in `bytes`, not [`char`]s or graphemes.
+    /// Truncates this [`CompactStr`], removing all contents.
     ///
-    /// When using `UTF-8` encoding (which all strings in Rust do) a single character will be 1 to 4
-    /// bytes long, therefore the return value of this method might not be what a human considers
-    /// the length of the string.
+    /// While this means the string will have a length of zero, it does not touch its capacity.
     ///
     /// # Examples
     /// ```
     /// # use compact_str::CompactStr;
-    /// let ascii = CompactStr::new("hello world");
-    /// assert_eq!(ascii.len(), 11);
+    /// let mut s = CompactStr::new("this is a long string that will be heap allocated");
+    /// let capacity = s.capacity();
     ///
-    /// let emoji = CompactStr::new("👱");
-    /// assert_eq!(emoji.len(), 4);
+    /// s.clear();
+    ///
+    /// assert!(s.is_empty());
+    /// assert_eq!(s.capacity(), capacity);
     /// ```
     #[inline]
-    pub fn len(&self) -> usize {
-        self.repr.len()
+    pub fn clear(&mut self) {
+        self.repr.clear()
     }
 
-    /// Returns `true` if the [`CompactStr`] has a length of 0, `false` otherwise
+    /// Inserts a character into this [`CompactStr`] at a byte position.
+    ///
+    /// This is an `O(n)` operation as it requires copying every element in the buffer.
+    ///
+    /// # Panics
+    /// Panics if `idx` is larger than the string's length, or if it does not lie on a [`char`]
+    /// boundary.
     ///
     /// # Examples
     /// ```
     /// # use compact_str::CompactStr;
-    /// let mut msg = CompactStr::new("");
-    /// assert!(msg.is_empty());
+    /// let mut s = CompactStr::new("bar");
     ///
-    /// // add some characters
-    /// msg.push_str("hello reader!");
-    /// assert!(!msg.is_empty());
+    /// s.insert(0, 'f');
+    /// s.insert(1, 'o');
+    /// s.insert(2, 'o');
+    ///
+    /// assert_eq!(s, "foobar");
     /// ```
     #[inline]
-    pub fn is_empty(&self) -> bool {
-        self.len() == 0
+    pub fn insert(&mut self, idx: usize, ch: char) {
+        self.repr.insert(idx, ch)
     }
 
-    /// Returns the capacity of the [`CompactStr`], in bytes.
+    /// Inserts a string slice into this [`CompactStr`] at a byte position.
     ///
-    /// # Note
-    /// * A `CompactStr` will always have a capacity of at least `std::mem::size_of::<String>()`
+    /// This is an `O(n)` operation as it requires copying every element in the buffer.
+    ///
+    /// # Panics
+    /// Panics if `idx` is larger than the string's length, or if it does not lie on a [`char`]
+    /// boundary.
     ///
     /// # Examples
-    /// ### Minimum Size
     /// ```
     /// # use compact_str::CompactStr;
-    /// let min_size = std::mem::size_of::<String>();
-    /// let compact = CompactStr::new("");
+    /// let mut s = CompactStr::new("bar");
     ///
-    /// assert!(compact.capacity() >= min_size);
-    /// ```
+    /// s.insert_str(0, "foo");
     ///
-    /// ### Heap Allocated
-    /// ```
-    /// # use compact_str::CompactStr;
-    /// let compact = CompactStr::with_capacity(128);
-    /// assert_eq!(compact.capacity(), 128);
+    /// assert_eq!(s, "foobar");
     /// ```
     #[inline]
-    pub fn capacity(&self) -> usize {
-        self.repr.capacity()
+    pub fn insert_str(&mut self, idx: usize, s: &str) {
+        self.repr.insert_str(idx, s)
     }
 
-    /// Ensures that this [`CompactStr`]'s capacity is at least `additional` bytes longer than its
-    /// length. The capacity may be increased by more than `additional` bytes if it chooses, to
-    /// prevent frequent reallocations.
+    /// Removes a [`char`] from this [`CompactStr`] at a byte position and returns it.
     ///
-    /// # Note
-    /// * A `CompactStr` will always have at least a capacity of `std::mem::size_of::<String>()`
-    /// * Reserving additional bytes may cause the `CompactStr` to become heap allocated
+    /// This is an `O(n)` operation, as it requires copying every element in the buffer.
     ///
     /// # Panics
-    /// Panics if the new capacity overflows `usize`
+    /// Panics if `idx` is larger than or equal to the string's length, or if it does not lie on a
+    /// [`char`] boundary.
     ///
     /// # Examples
     /// ```
     /// # use compact_str::CompactStr;
+    /// let mut s = CompactStr::new("foo");
     ///
-    /// const WORD: usize = std::mem::size_of::<usize>();
-    /// let mut compact = CompactStr::default();
-    /// assert!(compact.capacity() >= (WORD * 3) - 1);
+    /// assert_eq!(s.remove(0), 'f');
+    /// assert_eq!(s.remove(1), 'o');
+    /// assert_eq!(s.remove(0), 'o');
     ///
-    /// compact.reserve(200);
-    /// assert!(compact.is_heap_allocated());
-    /// assert!(compact.capacity() >= 200);
+    /// assert!(s.is_empty());
     /// ```
-
     #[inline]
-    pub fn reserve(&mut self, additional: usize) {
-        self.repr.reserve(additional)
+    pub fn remove(&mut self, idx: usize) -> char {
+        self.repr.remove(idx)
     }
 
-    /// Returns a string slice containing the entire [`CompactStr`].
+    /// Retains only the characters specified by the predicate.
+    ///
+    /// In other words, remove all characters `c` such that `f(c)` returns `false`. This method
+    /// operates in place, visiting each character exactly once in the original order, and
+    /// preserves the order of the retained characters.
     ///
     /// # Examples
     /// ```
     /// # use compact_str::CompactStr;
-    /// let s = CompactStr::new("hello");
+    /// let mut s = CompactStr::new("f_o_ob_ar");
     ///
-    /// assert_eq!(s.as_str(), "hello");
+    /// s.retain(|c| c != '_');
+    ///
+    /// assert_eq!(s, "foobar");
     /// ```
     #[inline]
-    pub fn as_str(&self) -> &str {
-        self.repr.as_str()
+    pub fn retain<F: FnMut(char) -> bool>(&mut self, predicate: F) {
+        self.repr.retain(predicate)
     }
 
-    /// Returns a byte slice of the [`CompactStr`]'s contents.
+    /// Removes the specified range from this [`CompactStr`] in bulk, returning all removed
+    /// characters as an iterator.
+    ///
+    /// The returned iterator keeps a mutable borrow on the string to optimize its implementation.
+    /// The range is removed when the iterator is dropped, even if it was not fully consumed. If the
+    /// iterator is leaked, e.g. with [`core::mem::forget`], the string is left unmodified.
+    ///
+    /// # Panics
+    /// Panics if the starting point or end point do not lie on a [`char`] boundary, or if they're
+    /// out of bounds.
     ///
     /// # Examples
     /// ```
     /// # use compact_str::CompactStr;
-    /// let s = CompactStr::new("hello");
+    /// let mut s = CompactStr::new("α is alpha, β is beta");
+    /// let beta_offset = s.find('β').unwrap_or(s.len());
     ///
-    /// assert_eq!(&[104, 101, 108, 108, 111], s.as_bytes());
+    /// // Remove the range up until the β from the string
+    /// let t: CompactStr = s.drain(..beta_offset).collect();
+    /// assert_eq!(t, "α is alpha, ");
+    /// assert_eq!(s, "β is beta");
+    ///
+    /// // A full range clears the string
+    /// s.drain(..);
+    /// assert_eq!(s, "");
     /// ```
     #[inline]
-    pub fn as_bytes(&self) -> &[u8] {
-        &self.repr.as_slice()[..self.len()]
+    pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_, N, A, S> {
+        let (start, end) = self.repr.char_range(range);
+        let repr: *mut Repr<N, A, S> = &mut self.repr;
+
+        // SAFETY: `char_range(...)` asserted that `start` and `end` are in bounds, and fall on char
+        // boundaries. `Drain` holds a mutable borrow of `self`, so the underlying buffer can't be
+        // modified until the `Drain` gets dropped
+        let chars = unsafe { (*repr).as_str().get_unchecked(start..end) }.chars();
+
+        Drain {
+            repr,
+            start,
+            end,
+            chars,
+        }
     }
 
-    // TODO: Implement a `try_as_mut_slice(...)` that will fail if it results in cloning?
-    //
-    /// Provides a mutable reference to the underlying buffer of bytes.
+    /// Removes the specified range in this [`CompactStr`], and replaces it with the given string.
+    /// The given string doesn't need to be the same length as the range.
     ///
-    /// # Safety
-    /// * All Rust strings, including `CompactStr`, must be valid UTF-8. The caller must guarantee
-    /// that any modifications made to the underlying buffer are valid UTF-8.
+    /// # Panics
+    /// Panics if the starting point or end point do not lie on a [`char`] boundary, or if they're
+    /// out of bounds.
     ///
     /// # Examples
     /// ```
     /// # use compact_str::CompactStr;
-    /// let mut s = CompactStr::new("hello");
-    ///
-    /// let slice = unsafe { s.as_mut_bytes() };
-    /// // copy bytes into our string
-    /// slice[5..11].copy_from_slice(" world".as_bytes());
-    /// // set the len of the string
-    /// unsafe { s.set_len(11) };
+    /// let mut s = CompactStr::new("α is alpha, β is beta");
+    /// let beta_offset = s.find('β').unwrap_or(s.len());
     ///
-    /// assert_eq!(s, "hello world");
+    /// // Replace the range up until the β from the string
+    /// s.replace_range(..beta_offset, "Α is capital alpha; ");
+    /// assert_eq!(s, "Α is capital alpha; β is beta");
     /// ```
     #[inline]
-    pub unsafe fn as_mut_bytes(&mut self) -> &mut [u8] {
-        self.repr.as_mut_slice()
+    pub fn replace_range<R: RangeBounds<usize>>(&mut self, range: R, replace_with: &str) {
+        self.repr.replace_range(range, replace_with)
     }
 
-    /// Appends the given [`char`] to the end of this [`CompactStr`].
+    /// Splits this [`CompactStr`] into two at the given byte index.
+    ///
+    /// Returns a newly allocated [`CompactStr`]. `self` contains bytes `[0, at)`, and the returned
+    /// [`CompactStr`] contains bytes `[at, len)`. `at` must be on the boundary of a UTF-8 code
+    /// point.
+    ///
+    /// Note that the capacity of `self` does not change.
+    ///
+    /// # Panics
+    /// Panics if `at` is not on a [`char`] boundary, or if it is beyond the last code point of the
+    /// string.
     ///
     /// # Examples
     /// ```
     /// # use compact_str::CompactStr;
-    /// let mut s = CompactStr::new("foo");
-    ///
-    /// s.push('b');
-    /// s.push('a');
-    /// s.push('r');
+    /// let mut hello = CompactStr::new("Hello, World!");
+    /// let world = hello.split_off(7);
     ///
-    /// assert_eq!("foobar", s);
+    /// assert_eq!(hello, "Hello, ");
+    /// assert_eq!(world, "World!");
     /// ```
     #[inline]
-    pub fn push(&mut self, ch: char) {
-        self.repr.push(ch)
+    #[must_use = "use `.truncate()` if you don't need the other half"]
+    pub fn split_off(&mut self, at: usize) -> Self {
+        let repr = self.repr.split_off(at);
+        CompactStrN { repr }
     }
 
-    /// Removes the last character from the [`CompactStr`] and returns it.
-    /// Returns `None` if this `ComapctStr` is empty.
+    /// Returns a new [`CompactStr`] containing the bytes in `range`.
+    ///
+    /// Short substrings are inlined. A longer substring of a heap allocated [`SharedCompactStr`] is
+    /// `O(1)`: it points into the same reference counted buffer, keeping it alive. A longer
+    /// substring of a heap allocated [`CompactStr`] gets copied.
+    ///
+    /// # Panics
+    /// Panics if the starting point or end point do not lie on a [`char`] boundary, or if they're
+    /// out of bounds.
     ///
     /// # Examples
     /// ```
-    /// # use compact_str::CompactStr;
-    /// let mut s = CompactStr::new("abc");
+    /// # use compact_str::{CompactStr, SharedCompactStr};
+    /// let text = CompactStr::new("the quick brown fox jumps over the lazy dog");
     ///
-    /// assert_eq!(s.pop(), Some('c'));
-    /// assert_eq!(s.pop(), Some('b'));
-    /// assert_eq!(s.pop(), Some('a'));
+    /// let fox = text.substr(16..19);
+    /// assert_eq!(fox, "fox");
+    /// assert!(!fox.is_heap_allocated());
     ///
-    /// assert_eq!(s.pop(), None);
+    /// let tail = text.substr(4..);
+    /// assert_eq!(tail, "quick brown fox jumps over the lazy dog");
+    ///
+    /// let text = SharedCompactStr::new("the quick brown fox jumps over the lazy dog");
+    /// let tail = text.substr(4..);
+    /// assert_eq!(tail.as_ptr(), text[4..].as_ptr());
     /// ```
     #[inline]
-    pub fn pop(&mut self) -> Option<char> {
-        self.repr.pop()
+    pub fn substr<R: RangeBounds<usize>>(&self, range: R) -> Self {
+        let (start, end) = self.repr.char_range(range);
+        let repr = self.repr.substr(start, end);
+        CompactStrN { repr }
     }
 
-    /// Appends a given string slice onto the end of this [`CompactStr`]
+    /// Returns a new [`CompactStr`] equivalent to `subset`, which must be a slice of `self`.
+    ///
+    /// This is useful when you have a `&str` borrowed from a [`CompactStr`], e.g. from
+    /// [`str::split_whitespace`], and want to hold onto it, see [`CompactStr::substr`].
+    ///
+    /// # Panics
+    /// Panics if `subset` is not contained within `self`. An empty `subset` is always accepted.
     ///
     /// # Examples
     /// ```
     /// # use compact_str::CompactStr;
-    /// let mut s = CompactStr::new("abc");
-    ///
-    /// s.push_str("123");
-    ///
-    /// assert_eq!("abc123", s);
+    /// let text = CompactStr::new("the quick brown fox jumps over the lazy dog");
+    /// let words: Vec<CompactStr> = text
+    ///     .split_whitespace()
+    ///     .map(|word| text.slice_ref(word))
+    ///     .collect();
+    ///
+    /// assert_eq!(words.len(), 9);
+    /// assert_eq!(words[3], "fox");
     /// ```
     #[inline]
-    pub fn push_str(&mut self, s: &str) {
-        self.repr.push_str(s)
+    pub fn slice_ref(&self, subset: &str) -> Self {
+        // An empty slice could point anywhere, e.g. it could be dangling, so we don't check it
+        if subset.is_empty() {
+            return Self::new_in("", self.allocator().clone());
+        }
+
+        let self_ptr = self.as_str().as_ptr() as usize;
+        let subset_ptr = subset.as_ptr() as usize;
+
+        assert!(
+            subset_ptr >= self_ptr && subset_ptr + subset.len() <= self_ptr + self.len(),
+            "subset is not contained within this CompactStr"
+        );
+
+        let start = subset_ptr - self_ptr;
+        self.substr(start..start + subset.len())
     }
 
     /// Forces the length of the [`CompactStr`] to `new_len`.
@@ -482,14 +1511,20 @@ impl CompactStr {
     }
 }
 
-impl Default for CompactStr {
+impl<const N: usize, S: Storage<Global>> Default for CompactStrN<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     #[inline]
     fn default() -> Self {
-        CompactStr::new("")
+        Self::new("")
     }
 }
 
-impl Deref for CompactStr {
+impl<const N: usize, A: Allocator, S: Storage<A>> Deref for CompactStrN<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     type Target = str;
 
     #[inline]
@@ -498,184 +1533,475 @@ impl Deref for CompactStr {
     }
 }
 
-impl AsRef<str> for CompactStr {
+impl<const N: usize, A: Allocator, S: Storage<A>> DerefMut for CompactStrN<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    #[inline]
+    fn deref_mut(&mut self) -> &mut str {
+        self.as_mut_str()
+    }
+}
+
+impl<I: SliceIndex<str>, const N: usize, A: Allocator, S: Storage<A>> Index<I>
+    for CompactStrN<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    type Output = I::Output;
+
+    #[inline]
+    fn index(&self, index: I) -> &Self::Output {
+        &self.as_str()[index]
+    }
+}
+
+impl<I: SliceIndex<str>, const N: usize, A: Allocator, S: Storage<A>> IndexMut<I>
+    for CompactStrN<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    #[inline]
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        &mut self.as_mut_str()[index]
+    }
+}
+
+impl<const N: usize, A: Allocator, S: Storage<A>> AsRef<str> for CompactStrN<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     #[inline]
     fn as_ref(&self) -> &str {
         self.as_str()
     }
 }
 
-impl Borrow<str> for CompactStr {
+impl<const N: usize, A: Allocator, S: Storage<A>> Borrow<str> for CompactStrN<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     #[inline]
     fn borrow(&self) -> &str {
         self.as_str()
     }
 }
 
-impl Eq for CompactStr {}
+impl<const N: usize, A: Allocator, S: Storage<A>> Eq for CompactStrN<N, A, S> where
+    InlineCapacity<N>: ValidInlineCapacity
+{
+}
 
-impl<T: AsRef<str>> PartialEq<T> for CompactStr {
+impl<T: AsRef<str>, const N: usize, A: Allocator, S: Storage<A>> PartialEq<T>
+    for CompactStrN<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn eq(&self, other: &T) -> bool {
         self.as_str() == other.as_ref()
     }
 }
 
-impl PartialEq<CompactStr> for String {
-    fn eq(&self, other: &CompactStr) -> bool {
+impl<const N: usize, A: Allocator, S: Storage<A>> PartialEq<CompactStrN<N, A, S>> for String
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    fn eq(&self, other: &CompactStrN<N, A, S>) -> bool {
         self.as_str() == other.as_str()
     }
 }
 
-impl PartialEq<CompactStr> for &str {
-    fn eq(&self, other: &CompactStr) -> bool {
+impl<const N: usize, A: Allocator, S: Storage<A>> PartialEq<CompactStrN<N, A, S>> for &str
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    fn eq(&self, other: &CompactStrN<N, A, S>) -> bool {
         *self == other.as_str()
     }
 }
 
-impl<'a> PartialEq<CompactStr> for Cow<'a, str> {
-    fn eq(&self, other: &CompactStr) -> bool {
+impl<'a, const N: usize, A: Allocator, S: Storage<A>> PartialEq<CompactStrN<N, A, S>>
+    for Cow<'a, str>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    fn eq(&self, other: &CompactStrN<N, A, S>) -> bool {
         *self == other.as_str()
     }
 }
 
-impl Ord for CompactStr {
+impl<const N: usize, A: Allocator, S: Storage<A>> Ord for CompactStrN<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn cmp(&self, other: &Self) -> Ordering {
         self.as_str().cmp(other.as_str())
     }
 }
 
-impl PartialOrd for CompactStr {
+impl<const N: usize, A: Allocator, S: Storage<A>> PartialOrd for CompactStrN<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl Hash for CompactStr {
+impl<const N: usize, A: Allocator, S: Storage<A>> Hash for CompactStrN<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.as_str().hash(state)
     }
 }
 
-impl<'a> From<&'a str> for CompactStr {
+impl<'a, const N: usize, S: Storage<Global>> From<&'a str> for CompactStrN<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn from(s: &'a str) -> Self {
-        CompactStr::new(s)
+        Self::new(s)
     }
 }
 
-impl From<String> for CompactStr {
+impl<const N: usize, S: Storage<Global>> From<String> for CompactStrN<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn from(s: String) -> Self {
         let repr = Repr::from_string(s);
-        CompactStr { repr }
+        CompactStrN { repr }
     }
 }
 
-impl<'a> From<&'a String> for CompactStr {
+/// Converts a vector of bytes to a [`CompactStr`], failing if the bytes aren't valid UTF-8.
+///
+/// Like [`From<String>`], short strings are inlined, and longer strings re-use the allocation of
+/// the [`Vec`] in `O(1)` time. If the conversion fails, the [`Vec`] is given back, without copying,
+/// via [`FromUtf8Error::into_bytes`].
+///
+/// Note: A heap allocated [`SharedCompactStr`] needs to store a reference count next to the string,
+/// so the buffer is always copied.
+///
+/// # Examples
+/// ```
+/// # use compact_str::CompactStr;
+/// use std::convert::TryFrom;
+///
+/// let bytes = b"this string is too long to be stored inline".to_vec();
+/// let compact = CompactStr::try_from(bytes).unwrap();
+/// assert_eq!(compact, "this string is too long to be stored inline");
+///
+/// let invalid = vec![b'a', 255, b'b'];
+/// let err = CompactStr::try_from(invalid).unwrap_err();
+/// assert_eq!(err.into_bytes(), vec![b'a', 255, b'b']);
+/// ```
+impl<const N: usize, S: Storage<Global>> TryFrom<Vec<u8>> for CompactStrN<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    type Error = FromUtf8Error;
+
+    fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
+        match String::from_utf8(bytes) {
+            Ok(s) => Ok(Self::from(s)),
+            Err(e) => {
+                let error = e.utf8_error();
+                Err(FromUtf8Error::new(e.into_bytes(), error))
+            }
+        }
+    }
+}
+
+impl<'a, const N: usize, S: Storage<Global>> From<&'a String> for CompactStrN<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn from(s: &'a String) -> Self {
-        CompactStr::new(&s)
+        Self::new(&s)
     }
 }
 
-impl<'a> From<Cow<'a, str>> for CompactStr {
+impl<'a, const N: usize, S: Storage<Global>> From<Cow<'a, str>> for CompactStrN<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn from(s: Cow<'a, str>) -> Self {
-        CompactStr::new(s)
+        Self::new(s)
     }
 }
 
-impl From<Box<str>> for CompactStr {
+impl<const N: usize, S: Storage<Global>> From<Box<str>> for CompactStrN<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn from(b: Box<str>) -> Self {
         let repr = Repr::from_box_str(b);
-        CompactStr { repr }
+        CompactStrN { repr }
     }
 }
 
-impl FromStr for CompactStr {
+impl<const N: usize, S: Storage<Global>> From<CompactStrN<N, Global, S>> for String
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    fn from(s: CompactStrN<N, Global, S>) -> Self {
+        s.into_string()
+    }
+}
+
+impl<const N: usize, S: Storage<Global>> FromStr for CompactStrN<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     type Err = core::convert::Infallible;
-    fn from_str(s: &str) -> Result<CompactStr, Self::Err> {
-        Ok(CompactStr::from(s))
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(s))
     }
 }
 
-impl fmt::Debug for CompactStr {
+impl<const N: usize, A: Allocator, S: Storage<A>> fmt::Debug for CompactStrN<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Debug::fmt(self.as_str(), f)
     }
 }
 
-impl fmt::Display for CompactStr {
+impl<const N: usize, A: Allocator, S: Storage<A>> fmt::Display for CompactStrN<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Display::fmt(self.as_str(), f)
     }
 }
 
-impl FromIterator<char> for CompactStr {
+impl<const N: usize, A: Allocator, S: Storage<A>> fmt::Write for CompactStrN<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+
+    #[inline]
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        self.push(c);
+        Ok(())
+    }
+}
+
+impl<const N: usize, S: Storage<Global>> FromIterator<char> for CompactStrN<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn from_iter<T: IntoIterator<Item = char>>(iter: T) -> Self {
         let repr = iter.into_iter().collect();
-        CompactStr { repr }
+        CompactStrN { repr }
     }
 }
 
-impl<'a> FromIterator<&'a char> for CompactStr {
+impl<'a, const N: usize, S: Storage<Global>> FromIterator<&'a char> for CompactStrN<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn from_iter<T: IntoIterator<Item = &'a char>>(iter: T) -> Self {
         let repr = iter.into_iter().collect();
-        CompactStr { repr }
+        CompactStrN { repr }
     }
 }
 
-impl<'a> FromIterator<&'a str> for CompactStr {
+impl<'a, const N: usize, S: Storage<Global>> FromIterator<&'a str> for CompactStrN<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn from_iter<T: IntoIterator<Item = &'a str>>(iter: T) -> Self {
         let repr = iter.into_iter().collect();
-        CompactStr { repr }
+        CompactStrN { repr }
     }
 }
 
-impl FromIterator<Box<str>> for CompactStr {
+impl<const N: usize, S: Storage<Global>> FromIterator<Box<str>> for CompactStrN<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn from_iter<T: IntoIterator<Item = Box<str>>>(iter: T) -> Self {
         let repr = iter.into_iter().collect();
-        CompactStr { repr }
+        CompactStrN { repr }
     }
 }
 
-impl FromIterator<String> for CompactStr {
+impl<const N: usize, S: Storage<Global>> FromIterator<String> for CompactStrN<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn from_iter<T: IntoIterator<Item = String>>(iter: T) -> Self {
         let repr = iter.into_iter().collect();
-        CompactStr { repr }
+        CompactStrN { repr }
     }
 }
 
-impl Extend<char> for CompactStr {
+impl<const N: usize, A: Allocator, S: Storage<A>> Extend<char> for CompactStrN<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn extend<T: IntoIterator<Item = char>>(&mut self, iter: T) {
         self.repr.extend(iter)
     }
 }
 
-impl<'a> Extend<&'a char> for CompactStr {
+impl<'a, const N: usize, A: Allocator, S: Storage<A>> Extend<&'a char> for CompactStrN<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn extend<T: IntoIterator<Item = &'a char>>(&mut self, iter: T) {
         self.repr.extend(iter)
     }
 }
 
-impl<'a> Extend<&'a str> for CompactStr {
+impl<'a, const N: usize, A: Allocator, S: Storage<A>> Extend<&'a str> for CompactStrN<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn extend<T: IntoIterator<Item = &'a str>>(&mut self, iter: T) {
         self.repr.extend(iter)
     }
 }
 
-impl Extend<Box<str>> for CompactStr {
+impl<const N: usize, A: Allocator, S: Storage<A>> Extend<Box<str>> for CompactStrN<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn extend<T: IntoIterator<Item = Box<str>>>(&mut self, iter: T) {
         self.repr.extend(iter)
     }
 }
 
-impl<'a> Extend<Cow<'a, str>> for CompactStr {
+impl<'a, const N: usize, A: Allocator, S: Storage<A>> Extend<Cow<'a, str>> for CompactStrN<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn extend<T: IntoIterator<Item = Cow<'a, str>>>(&mut self, iter: T) {
         iter.into_iter().for_each(move |s| self.push_str(&s));
     }
 }
 
-impl Extend<String> for CompactStr {
+impl<const N: usize, A: Allocator, S: Storage<A>> Extend<String> for CompactStrN<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn extend<T: IntoIterator<Item = String>>(&mut self, iter: T) {
         self.repr.extend(iter)
     }
 }
 
+/// A draining iterator for [`CompactStr`].
+///
+/// This struct is created by the [`drain`](CompactStr::drain) method on [`CompactStr`]. See its
+/// documentation for more.
+pub struct Drain<'a, const N: usize = MAX_SIZE, A: Allocator = Global, S: Storage<A> = Boxed>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    repr: *mut Repr<N, A, S>,
+    start: usize,
+    end: usize,
+    chars: Chars<'a>,
+}
+
+// SAFETY: `Drain` only exposes the `Repr` it mutably borrows via `chars`, which is `Send + Sync`
+unsafe impl<const N: usize, A: Allocator + Send + Sync, S: Storage<A>> Send for Drain<'_, N, A, S> where
+    InlineCapacity<N>: ValidInlineCapacity
+{
+}
+unsafe impl<const N: usize, A: Allocator + Send + Sync, S: Storage<A>> Sync for Drain<'_, N, A, S> where
+    InlineCapacity<N>: ValidInlineCapacity
+{
+}
+
+impl<'a, const N: usize, A: Allocator, S: Storage<A>> Drain<'a, N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    /// Returns the remaining (sub)string of this iterator as a slice.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactStr;
+    /// let mut s = CompactStr::new("abc");
+    /// let mut drain = s.drain(..);
+    /// assert_eq!(drain.as_str(), "abc");
+    ///
+    /// let _ = drain.next().unwrap();
+    /// assert_eq!(drain.as_str(), "bc");
+    /// ```
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.chars.as_str()
+    }
+}
+
+impl<const N: usize, A: Allocator, S: Storage<A>> fmt::Debug for Drain<'_, N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Drain").field(&self.as_str()).finish()
+    }
+}
+
+impl<const N: usize, A: Allocator, S: Storage<A>> Drop for Drain<'_, N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    fn drop(&mut self) {
+        // SAFETY: `Drain` holds the only (mutable) borrow of the `Repr`, and `CompactStr::drain`
+        // asserted that `start` and `end` are in bounds, and fall on char boundaries
+        unsafe { (*self.repr).replace_range(self.start..self.end, "") };
+    }
+}
+
+impl<const N: usize, A: Allocator, S: Storage<A>> Iterator for Drain<'_, N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    type Item = char;
+
+    #[inline]
+    fn next(&mut self) -> Option<char> {
+        self.chars.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chars.size_hint()
+    }
+
+    #[inline]
+    fn last(mut self) -> Option<char> {
+        self.next_back()
+    }
+}
+
+impl<const N: usize, A: Allocator, S: Storage<A>> DoubleEndedIterator for Drain<'_, N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<char> {
+        self.chars.next_back()
+    }
+}
+
+impl<const N: usize, A: Allocator, S: Storage<A>> FusedIterator for Drain<'_, N, A, S> where
+    InlineCapacity<N>: ValidInlineCapacity
+{
+}
+
 crate::asserts::assert_size_eq!(CompactStr, String);
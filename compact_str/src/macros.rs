@@ -0,0 +1,48 @@
+/// Creates a `CompactStr` using interpolation of runtime expressions.
+///
+/// The first argument `format_compact!` receives is a format string. This must be a string
+/// literal. The power of the formatting string is in the `{}`s contained.
+///
+/// Additional parameters passed to `format_compact!` replace the `{}`s within the formatting
+/// string in the order given unless named or positional parameters are used; see
+/// [`std::fmt`](https://doc.rust-lang.org/std/fmt/) for more information.
+///
+/// Unlike [`format!`], the output is written directly into a [`CompactStr`], so no heap
+/// allocation occurs unless the output is longer than can be stored inline.
+///
+/// # Panics
+/// `format_compact!` panics if a formatting trait implementation returns an error. This indicates
+/// an incorrect implementation since writing to a [`CompactStr`] never fails.
+///
+/// # Examples
+/// ```
+/// # use compact_str::format_compact;
+/// let s = format_compact!("{}-{}", "hello", 42);
+///
+/// assert_eq!(s, "hello-42");
+/// assert!(!s.is_heap_allocated());
+/// ```
+///
+/// [`CompactStr`]: crate::CompactStr
+#[macro_export]
+macro_rules! format_compact {
+    ($($arg:tt)*) => {
+        $crate::ToCompactStr::to_compact_str(&::core::format_args!($($arg)*))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::CompactStr;
+
+    #[test]
+    fn test_macros() {
+        assert_eq!(format_compact!("2"), "2");
+        assert_eq!(format_compact!("{}", 2), "2");
+        assert_eq!(format_compact!("{value:>5}", value = "a"), "    a");
+
+        let long = format_compact!("{}{}", "a".repeat(40), 1);
+        assert_eq!(long, CompactStr::new("a".repeat(40) + "1"));
+        assert!(long.is_heap_allocated());
+    }
+}
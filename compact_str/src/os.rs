@@ -0,0 +1,876 @@
+//! [`CompactOsString`] and [`CompactPathBuf`], compact versions of [`OsString`] and [`PathBuf`].
+//!
+//! On unix an [`OsStr`] is an arbitrary slice of bytes, so both types are built on a
+//! [`CompactBytes`], which shares the inline and heap storage of a [`CompactStr`]. Other platforms
+//! encode an [`OsStr`] differently, e.g. as WTF-8 on Windows, so these types are only available on
+//! unix.
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::ffi::{
+    OsStr,
+    OsString,
+};
+use std::fmt;
+use std::hash::{
+    Hash,
+    Hasher,
+};
+use std::ops::Deref;
+use std::os::unix::ffi::{
+    OsStrExt,
+    OsStringExt,
+};
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+use crate::{
+    CompactBytes,
+    CompactStr,
+};
+
+/// A [`CompactOsString`] is a compact [`OsString`], which stores short strings inline.
+///
+/// It can store up to 23 bytes inline (11 bytes on 32-bit architectures), like a
+/// [`CompactBytes`], and derefs to an [`OsStr`], so all of the methods on `&OsStr` are available.
+///
+/// Note: [`CompactOsString`] is only available on unix, where an [`OsStr`] is a slice of bytes.
+///
+/// # Examples
+/// ```
+/// use std::ffi::OsStr;
+///
+/// use compact_str::CompactOsString;
+///
+/// let mut name = CompactOsString::from("hello");
+/// name.push(OsStr::new(" world"));
+///
+/// assert_eq!(name, OsStr::new("hello world"));
+/// assert!(!name.is_heap_allocated());
+/// assert_eq!(name.to_str(), Some("hello world"));
+/// ```
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CompactOsString {
+    bytes: CompactBytes,
+}
+
+crate::asserts::assert_size_eq!(CompactOsString, Option<CompactOsString>, OsString);
+
+impl CompactOsString {
+    /// Creates a new, empty [`CompactOsString`].
+    #[inline]
+    pub fn new() -> Self {
+        CompactOsString::default()
+    }
+
+    /// Creates a new, empty [`CompactOsString`] that can store at least `capacity` bytes without
+    /// reallocating. If `capacity` is small enough, no heap allocation occurs.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        CompactOsString {
+            bytes: CompactBytes::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the [`OsStr`] this [`CompactOsString`] contains.
+    #[inline]
+    pub fn as_os_str(&self) -> &OsStr {
+        OsStr::from_bytes(self.bytes.as_slice())
+    }
+
+    /// Returns the length of the [`CompactOsString`] in bytes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns `true` if the [`CompactOsString`] has a length of 0, `false` otherwise.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Returns the capacity of the [`CompactOsString`], see [`CompactBytes::capacity`].
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.bytes.capacity()
+    }
+
+    /// Ensures that this [`CompactOsString`]'s capacity is at least `additional` bytes longer than
+    /// its length.
+    ///
+    /// # Panics
+    /// This method panics if the new capacity overflows `usize`.
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        self.bytes.reserve(additional);
+    }
+
+    /// Shrinks the capacity of this [`CompactOsString`] as much as possible, moving it inline if
+    /// it's short enough.
+    #[inline]
+    pub fn shrink_to_fit(&mut self) {
+        self.bytes.shrink_to_fit();
+    }
+
+    /// Appends `s` to the end of this [`CompactOsString`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactOsString;
+    /// use std::ffi::OsString;
+    ///
+    /// let mut name = CompactOsString::from("foo");
+    /// name.push(OsString::from(".txt"));
+    /// assert_eq!(name, "foo.txt");
+    /// ```
+    #[inline]
+    pub fn push<T: AsRef<OsStr>>(&mut self, s: T) {
+        self.bytes.extend_from_slice(s.as_ref().as_bytes());
+    }
+
+    /// Truncates this [`CompactOsString`] to zero length.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+    }
+
+    /// Converts this [`CompactOsString`] into a [`CompactStr`], without copying, if it's valid
+    /// UTF-8. Otherwise the original [`CompactOsString`] is returned.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactOsString;
+    /// use std::ffi::OsStr;
+    /// use std::os::unix::ffi::OsStrExt;
+    ///
+    /// let valid = CompactOsString::from("config.toml");
+    /// assert_eq!(valid.into_compact_str().unwrap(), "config.toml");
+    ///
+    /// let invalid = CompactOsString::from(OsStr::from_bytes(b"fo\xFFo"));
+    /// assert!(invalid.into_compact_str().is_err());
+    /// ```
+    #[inline]
+    pub fn into_compact_str(self) -> Result<CompactStr, CompactOsString> {
+        if core::str::from_utf8(self.bytes.as_slice()).is_err() {
+            return Err(self);
+        }
+
+        // Note: We just checked the bytes are valid UTF-8, so we can re-use our `Repr` as is. Its
+        // last byte when inline is still our length, see `CompactBytes`
+        Ok(CompactStr {
+            repr: self.bytes.repr,
+        })
+    }
+
+    /// Converts this [`CompactOsString`] into an [`OsString`].
+    ///
+    /// Like [`CompactBytes::into_vec`], this re-uses the heap allocation if possible.
+    #[inline]
+    pub fn into_os_string(self) -> OsString {
+        OsString::from_vec(self.bytes.into_vec())
+    }
+
+    /// Converts this [`CompactOsString`] into a [`CompactBytes`], without copying.
+    #[inline]
+    pub fn into_compact_bytes(self) -> CompactBytes {
+        self.bytes
+    }
+
+    /// Returns `true` if the [`CompactOsString`] is heap allocated, `false` otherwise.
+    #[inline]
+    pub fn is_heap_allocated(&self) -> bool {
+        self.bytes.is_heap_allocated()
+    }
+}
+
+impl Deref for CompactOsString {
+    type Target = OsStr;
+
+    #[inline]
+    fn deref(&self) -> &OsStr {
+        self.as_os_str()
+    }
+}
+
+impl AsRef<OsStr> for CompactOsString {
+    #[inline]
+    fn as_ref(&self) -> &OsStr {
+        self.as_os_str()
+    }
+}
+
+impl AsRef<Path> for CompactOsString {
+    #[inline]
+    fn as_ref(&self) -> &Path {
+        Path::new(self.as_os_str())
+    }
+}
+
+impl Borrow<OsStr> for CompactOsString {
+    #[inline]
+    fn borrow(&self) -> &OsStr {
+        self.as_os_str()
+    }
+}
+
+// Note: `OsStr` hashes the same as its bytes, which is what `CompactBytes` hashes, but we go
+// through `OsStr` to make sure our `Borrow<OsStr>` impl stays correct
+impl Hash for CompactOsString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_os_str().hash(state)
+    }
+}
+
+impl PartialEq<OsStr> for CompactOsString {
+    fn eq(&self, other: &OsStr) -> bool {
+        self.as_os_str() == other
+    }
+}
+
+impl<'a> PartialEq<&'a OsStr> for CompactOsString {
+    fn eq(&self, other: &&'a OsStr) -> bool {
+        self.as_os_str() == *other
+    }
+}
+
+impl PartialEq<OsString> for CompactOsString {
+    fn eq(&self, other: &OsString) -> bool {
+        self.as_os_str() == other.as_os_str()
+    }
+}
+
+impl PartialEq<str> for CompactOsString {
+    fn eq(&self, other: &str) -> bool {
+        self.as_os_str() == other
+    }
+}
+
+impl<'a> PartialEq<&'a str> for CompactOsString {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.as_os_str() == *other
+    }
+}
+
+impl PartialEq<CompactOsString> for OsStr {
+    fn eq(&self, other: &CompactOsString) -> bool {
+        self == other.as_os_str()
+    }
+}
+
+impl PartialEq<CompactOsString> for OsString {
+    fn eq(&self, other: &CompactOsString) -> bool {
+        self.as_os_str() == other.as_os_str()
+    }
+}
+
+impl<'a> From<&'a OsStr> for CompactOsString {
+    fn from(s: &'a OsStr) -> Self {
+        CompactOsString {
+            bytes: CompactBytes::from(s.as_bytes()),
+        }
+    }
+}
+
+/// Converts an [`OsString`] into a [`CompactOsString`], inlining it if it's short enough,
+/// otherwise re-using the heap allocated buffer, see [`CompactBytes`].
+impl From<OsString> for CompactOsString {
+    fn from(s: OsString) -> Self {
+        CompactOsString {
+            bytes: CompactBytes::from(s.into_vec()),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for CompactOsString {
+    fn from(s: &'a str) -> Self {
+        CompactOsString::from(OsStr::new(s))
+    }
+}
+
+impl From<String> for CompactOsString {
+    fn from(s: String) -> Self {
+        CompactOsString::from(s.as_str())
+    }
+}
+
+/// Converts a [`CompactStr`] into a [`CompactOsString`], without copying, unless the string is
+/// inline and exactly 24 bytes long, see [`CompactBytes`].
+impl From<CompactStr> for CompactOsString {
+    fn from(s: CompactStr) -> Self {
+        CompactOsString {
+            bytes: CompactBytes::from(s),
+        }
+    }
+}
+
+impl From<CompactOsString> for OsString {
+    fn from(s: CompactOsString) -> Self {
+        s.into_os_string()
+    }
+}
+
+impl fmt::Debug for CompactOsString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_os_str(), f)
+    }
+}
+
+/// A [`CompactPathBuf`] is a compact [`PathBuf`], which stores short paths inline.
+///
+/// It can store up to 23 bytes inline (11 bytes on 32-bit architectures), which fits most path
+/// components, and derefs to a [`Path`], so all of the methods on `&Path` are available, e.g.
+/// [`Path::file_name`].
+///
+/// Note: [`CompactPathBuf`] is only available on unix, where an [`OsStr`] is a slice of bytes.
+///
+/// # Examples
+/// ```
+/// use std::ffi::OsStr;
+/// use std::path::Path;
+///
+/// use compact_str::CompactPathBuf;
+///
+/// let mut path = CompactPathBuf::from("src");
+/// path.push("lib.rs");
+///
+/// assert_eq!(path, Path::new("src/lib.rs"));
+/// assert_eq!(path.file_name(), Some(OsStr::new("lib.rs")));
+/// assert!(!path.is_heap_allocated());
+/// ```
+#[derive(Clone, Default)]
+pub struct CompactPathBuf {
+    inner: CompactOsString,
+}
+
+crate::asserts::assert_size_eq!(CompactPathBuf, Option<CompactPathBuf>, PathBuf);
+
+impl CompactPathBuf {
+    /// Creates a new, empty [`CompactPathBuf`].
+    #[inline]
+    pub fn new() -> Self {
+        CompactPathBuf::default()
+    }
+
+    /// Creates a new, empty [`CompactPathBuf`] that can store at least `capacity` bytes without
+    /// reallocating. If `capacity` is small enough, no heap allocation occurs.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        CompactPathBuf {
+            inner: CompactOsString::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the [`Path`] this [`CompactPathBuf`] contains.
+    #[inline]
+    pub fn as_path(&self) -> &Path {
+        Path::new(self.inner.as_os_str())
+    }
+
+    /// Returns the capacity of the [`CompactPathBuf`], see [`CompactBytes::capacity`].
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Extends this [`CompactPathBuf`] with `path`, like [`PathBuf::push`].
+    ///
+    /// If `path` is absolute, it replaces the current path. Otherwise it's appended, with a
+    /// separator if we don't already end with one.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactPathBuf;
+    /// let mut path = CompactPathBuf::from("/tmp");
+    /// path.push("file.bk");
+    /// assert_eq!(path, "/tmp/file.bk");
+    ///
+    /// path.push("/etc");
+    /// assert_eq!(path, "/etc");
+    /// ```
+    pub fn push<P: AsRef<Path>>(&mut self, path: P) {
+        let path = path.as_ref().as_os_str().as_bytes();
+
+        if path.first() == Some(&b'/') {
+            self.inner.clear();
+        } else if matches!(self.inner.as_bytes().last(), Some(b) if *b != b'/') {
+            self.inner.push("/");
+        }
+        self.inner.bytes.extend_from_slice(path);
+    }
+
+    /// Truncates this [`CompactPathBuf`] to its parent, like [`PathBuf::pop`].
+    ///
+    /// Returns `false` and does nothing if there is no parent, otherwise returns `true`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactPathBuf;
+    /// let mut path = CompactPathBuf::from("/spirited/away.rs");
+    ///
+    /// assert!(path.pop());
+    /// assert_eq!(path, "/spirited");
+    /// assert!(path.pop());
+    /// assert_eq!(path, "/");
+    /// assert!(!path.pop());
+    /// ```
+    pub fn pop(&mut self) -> bool {
+        match self.as_path().parent().map(|p| p.as_os_str().len()) {
+            Some(len) => {
+                self.inner.bytes.truncate(len);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Updates the file name of this [`CompactPathBuf`] to `file_name`, like
+    /// [`PathBuf::set_file_name`].
+    ///
+    /// If we don't have a file name, this is the same as [`CompactPathBuf::push`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactPathBuf;
+    /// let mut path = CompactPathBuf::from("/src/main.rs");
+    /// path.set_file_name("lib.rs");
+    /// assert_eq!(path, "/src/lib.rs");
+    /// ```
+    pub fn set_file_name<S: AsRef<OsStr>>(&mut self, file_name: S) {
+        if self.file_name().is_some() {
+            self.pop();
+        }
+        self.push(file_name.as_ref());
+    }
+
+    /// Creates a new [`CompactPathBuf`] with `path` joined onto this one, see
+    /// [`CompactPathBuf::push`].
+    ///
+    /// Note: Unlike [`Path::join`], this returns a [`CompactPathBuf`] instead of a [`PathBuf`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use compact_str::CompactPathBuf;
+    /// let dir = CompactPathBuf::from("/etc");
+    /// let file = dir.join("passwd");
+    ///
+    /// assert_eq!(file, "/etc/passwd");
+    /// assert!(!file.is_heap_allocated());
+    /// ```
+    pub fn join<P: AsRef<Path>>(&self, path: P) -> CompactPathBuf {
+        let mut joined = self.clone();
+        joined.push(path);
+        joined
+    }
+
+    /// Truncates this [`CompactPathBuf`] to zero length.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    /// Converts this [`CompactPathBuf`] into a [`CompactStr`], without copying, if it's valid
+    /// UTF-8. Otherwise the original [`CompactPathBuf`] is returned.
+    #[inline]
+    pub fn into_compact_str(self) -> Result<CompactStr, CompactPathBuf> {
+        self.inner
+            .into_compact_str()
+            .map_err(|inner| CompactPathBuf { inner })
+    }
+
+    /// Converts this [`CompactPathBuf`] into a [`CompactOsString`], without copying.
+    #[inline]
+    pub fn into_compact_os_string(self) -> CompactOsString {
+        self.inner
+    }
+
+    /// Converts this [`CompactPathBuf`] into a [`PathBuf`].
+    ///
+    /// Like [`CompactBytes::into_vec`], this re-uses the heap allocation if possible.
+    #[inline]
+    pub fn into_path_buf(self) -> PathBuf {
+        PathBuf::from(self.inner.into_os_string())
+    }
+
+    /// Returns `true` if the [`CompactPathBuf`] is heap allocated, `false` otherwise.
+    #[inline]
+    pub fn is_heap_allocated(&self) -> bool {
+        self.inner.is_heap_allocated()
+    }
+}
+
+impl Deref for CompactPathBuf {
+    type Target = Path;
+
+    #[inline]
+    fn deref(&self) -> &Path {
+        self.as_path()
+    }
+}
+
+impl AsRef<Path> for CompactPathBuf {
+    #[inline]
+    fn as_ref(&self) -> &Path {
+        self.as_path()
+    }
+}
+
+impl AsRef<OsStr> for CompactPathBuf {
+    #[inline]
+    fn as_ref(&self) -> &OsStr {
+        self.inner.as_os_str()
+    }
+}
+
+impl Borrow<Path> for CompactPathBuf {
+    #[inline]
+    fn borrow(&self) -> &Path {
+        self.as_path()
+    }
+}
+
+// Note: Like a `PathBuf`, we compare, order, and hash by the components of our path, e.g. "a/b" and
+// "a//b" are equal
+impl Eq for CompactPathBuf {}
+
+impl PartialEq for CompactPathBuf {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_path() == other.as_path()
+    }
+}
+
+impl Ord for CompactPathBuf {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_path().cmp(other.as_path())
+    }
+}
+
+impl PartialOrd for CompactPathBuf {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Hash for CompactPathBuf {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_path().hash(state)
+    }
+}
+
+impl PartialEq<Path> for CompactPathBuf {
+    fn eq(&self, other: &Path) -> bool {
+        self.as_path() == other
+    }
+}
+
+impl<'a> PartialEq<&'a Path> for CompactPathBuf {
+    fn eq(&self, other: &&'a Path) -> bool {
+        self.as_path() == *other
+    }
+}
+
+impl PartialEq<PathBuf> for CompactPathBuf {
+    fn eq(&self, other: &PathBuf) -> bool {
+        self.as_path() == other.as_path()
+    }
+}
+
+impl<'a> PartialEq<&'a str> for CompactPathBuf {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.as_path() == Path::new(other)
+    }
+}
+
+impl PartialEq<CompactPathBuf> for Path {
+    fn eq(&self, other: &CompactPathBuf) -> bool {
+        self == other.as_path()
+    }
+}
+
+impl PartialEq<CompactPathBuf> for PathBuf {
+    fn eq(&self, other: &CompactPathBuf) -> bool {
+        self.as_path() == other.as_path()
+    }
+}
+
+impl<'a> From<&'a Path> for CompactPathBuf {
+    fn from(path: &'a Path) -> Self {
+        CompactPathBuf {
+            inner: CompactOsString::from(path.as_os_str()),
+        }
+    }
+}
+
+impl<'a> From<&'a OsStr> for CompactPathBuf {
+    fn from(s: &'a OsStr) -> Self {
+        CompactPathBuf::from(Path::new(s))
+    }
+}
+
+impl<'a> From<&'a str> for CompactPathBuf {
+    fn from(s: &'a str) -> Self {
+        CompactPathBuf::from(Path::new(s))
+    }
+}
+
+/// Converts a [`PathBuf`] into a [`CompactPathBuf`], inlining it if it's short enough, otherwise
+/// re-using the heap allocated buffer, see [`CompactBytes`].
+impl From<PathBuf> for CompactPathBuf {
+    fn from(path: PathBuf) -> Self {
+        CompactPathBuf {
+            inner: CompactOsString::from(path.into_os_string()),
+        }
+    }
+}
+
+impl From<CompactOsString> for CompactPathBuf {
+    fn from(inner: CompactOsString) -> Self {
+        CompactPathBuf { inner }
+    }
+}
+
+/// Converts a [`CompactStr`] into a [`CompactPathBuf`], without copying, unless the string is
+/// inline and exactly 24 bytes long, see [`CompactBytes`].
+impl From<CompactStr> for CompactPathBuf {
+    fn from(s: CompactStr) -> Self {
+        CompactPathBuf {
+            inner: CompactOsString::from(s),
+        }
+    }
+}
+
+impl From<CompactPathBuf> for PathBuf {
+    fn from(path: CompactPathBuf) -> Self {
+        path.into_path_buf()
+    }
+}
+
+impl<P: AsRef<Path>> Extend<P> for CompactPathBuf {
+    fn extend<T: IntoIterator<Item = P>>(&mut self, iter: T) {
+        iter.into_iter().for_each(move |p| self.push(p.as_ref()));
+    }
+}
+
+impl<P: AsRef<Path>> core::iter::FromIterator<P> for CompactPathBuf {
+    fn from_iter<T: IntoIterator<Item = P>>(iter: T) -> Self {
+        let mut path = CompactPathBuf::new();
+        path.extend(iter);
+        path
+    }
+}
+
+impl fmt::Debug for CompactPathBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_path(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::ffi::{
+        OsStr,
+        OsString,
+    };
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{
+        Path,
+        PathBuf,
+    };
+
+    use proptest::prelude::*;
+    use test_strategy::proptest;
+
+    use super::{
+        CompactOsString,
+        CompactPathBuf,
+    };
+    use crate::tests::{
+        rand_bytes,
+        rand_unicode,
+        rand_unicode_collection,
+    };
+    use crate::CompactStr;
+
+    const MAX_SIZE: usize = core::mem::size_of::<String>();
+
+    #[test]
+    fn test_sizes() {
+        assert_eq!(
+            core::mem::size_of::<CompactOsString>(),
+            core::mem::size_of::<OsString>()
+        );
+        assert_eq!(
+            core::mem::size_of::<Option<CompactPathBuf>>(),
+            core::mem::size_of::<PathBuf>()
+        );
+    }
+
+    #[test]
+    fn test_non_utf8() {
+        let bytes = b"caf\xE9";
+        let os_str = OsStr::from_bytes(bytes);
+
+        let compact = CompactOsString::from(os_str);
+        assert_eq!(compact, os_str);
+        assert_eq!(compact.to_str(), None);
+        assert!(!compact.is_heap_allocated());
+
+        let compact = compact.into_compact_str().unwrap_err();
+        assert_eq!(compact.into_os_string(), os_str);
+    }
+
+    #[test]
+    fn test_compact_str_roundtrip() {
+        let long = "a/path/that/is/long/enough/to/be/heap/allocated";
+        let compact = CompactStr::new(long);
+        let ptr = compact.as_ptr();
+
+        let path = CompactPathBuf::from(compact);
+        assert_eq!(path, Path::new(long));
+        assert_eq!(path.as_os_str().as_bytes().as_ptr(), ptr);
+
+        let compact = path.into_compact_str().unwrap();
+        assert_eq!(compact, long);
+        assert_eq!(compact.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn test_push_matches_path_buf() {
+        let cases: &[(&str, &[&str])] = &[
+            ("", &["a", "b"]),
+            ("/", &["usr", "lib"]),
+            ("a/", &["b/", "c"]),
+            ("a", &["/b", "c"]),
+            ("a", &["", "b"]),
+        ];
+
+        for (base, parts) in cases.iter() {
+            let mut compact = CompactPathBuf::from(*base);
+            let mut std = PathBuf::from(*base);
+
+            for part in parts.iter() {
+                compact.push(part);
+                std.push(part);
+                assert_eq!(compact.as_os_str(), std.as_os_str());
+            }
+        }
+    }
+
+    #[test]
+    fn test_push_onto_small_capacity_heap() {
+        // a heap allocated `CompactStr` can have less capacity than a `CompactOsString` can store
+        // inline, which must not move back inline once the last byte isn't valid UTF-8
+        let base = "abcdefghijklmnopqrstuv";
+        let name = OsStr::from_bytes(b"\xFF");
+
+        let compact = CompactStr::from_string_preserve_capacity(base.into());
+        let mut path = CompactPathBuf::from(compact);
+        path.push(name);
+        assert_eq!(path.as_os_str(), PathBuf::from(base).join(name).as_os_str());
+
+        let compact = CompactStr::from_string_preserve_capacity(base.into());
+        let mut os_string = CompactOsString::from(compact);
+        os_string.push("/");
+        os_string.push(name);
+        assert_eq!(os_string, path.as_os_str());
+    }
+
+    #[test]
+    fn test_pop_and_set_file_name() {
+        let mut compact = CompactPathBuf::from("/home/user/notes.txt");
+        let mut std = PathBuf::from("/home/user/notes.txt");
+
+        compact.set_file_name("todo.md");
+        std.set_file_name("todo.md");
+        assert_eq!(compact.as_os_str(), std.as_os_str());
+
+        while std.pop() {
+            assert!(compact.pop());
+            assert_eq!(compact.as_os_str(), std.as_os_str());
+        }
+        assert!(!compact.pop());
+
+        let mut compact = CompactPathBuf::from("relative");
+        assert!(compact.pop());
+        assert_eq!(compact, "");
+        compact.set_file_name("file");
+        assert_eq!(compact, "file");
+    }
+
+    #[test]
+    fn test_eq_and_hash_by_components() {
+        let a = CompactPathBuf::from("a/b");
+        let b = CompactPathBuf::from("a//b/");
+        assert_eq!(a, b);
+
+        let mut set = HashSet::new();
+        set.insert(a);
+        assert!(set.contains(Path::new("a/./b")));
+        assert!(!set.insert(b));
+    }
+
+    #[test]
+    fn test_std_roundtrip_reuses_allocation() {
+        let long = PathBuf::from("a/path/that/is/long/enough/to/be/heap/allocated");
+        let ptr = long.as_os_str().as_bytes().as_ptr();
+
+        let path = CompactPathBuf::from(long);
+        assert_eq!(path.as_os_str().as_bytes().as_ptr(), ptr);
+        let long = path.into_path_buf();
+        assert_eq!(long.as_os_str().as_bytes().as_ptr(), ptr);
+
+        let os_string = CompactOsString::from(long.into_os_string());
+        assert_eq!(os_string.as_os_str().as_bytes().as_ptr(), ptr);
+        let long = os_string.into_os_string();
+        assert_eq!(long.as_bytes().as_ptr(), ptr);
+
+        // short strings are still inlined
+        let short = CompactOsString::from(OsString::from("short"));
+        assert!(!short.is_heap_allocated());
+    }
+
+    #[test]
+    fn test_max_inline_component() {
+        let name = "a".repeat(MAX_SIZE - 1);
+        let path = CompactPathBuf::new().join(&name);
+        assert!(!path.is_heap_allocated());
+        assert_eq!(path.file_name(), Some(OsStr::new(&name)));
+
+        let path = path.join("b");
+        assert!(path.is_heap_allocated());
+        assert_eq!(path, PathBuf::from(&name).join("b"));
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_os_string_roundtrip(#[strategy(rand_bytes())] bytes: Vec<u8>) {
+        let os_str = OsStr::from_bytes(&bytes);
+        let compact = CompactOsString::from(os_str);
+
+        prop_assert_eq!(&compact, os_str);
+        prop_assert_eq!(compact.is_heap_allocated(), bytes.len() >= MAX_SIZE);
+
+        match (compact.clone().into_compact_str(), os_str.to_str()) {
+            (Ok(c), Some(s)) => prop_assert_eq!(c, s),
+            (Err(c), None) => prop_assert_eq!(c, compact),
+            _ => panic!("CompactOsString and OsStr read UTF-8 differently?"),
+        }
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn proptest_join_matches_path_buf(
+        #[strategy(rand_unicode())] base: String,
+        #[strategy(rand_unicode_collection())] parts: Vec<String>,
+    ) {
+        let compact: CompactPathBuf = core::iter::once(&base).chain(parts.iter()).collect();
+        let std: PathBuf = core::iter::once(&base).chain(parts.iter()).collect();
+
+        prop_assert_eq!(compact.as_os_str(), std.as_os_str());
+        prop_assert_eq!(compact.file_name(), std.file_name());
+        prop_assert_eq!(compact.into_path_buf(), std);
+    }
+}
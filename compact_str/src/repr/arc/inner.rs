@@ -1,23 +1,34 @@
+use alloc::alloc;
 use core::sync::atomic::AtomicUsize;
 use core::{
     ptr,
     slice,
 };
-use std::alloc;
+
+use crate::{
+    Allocator,
+    TryReserveError,
+};
 
 const UNKNOWN: usize = 0;
 pub type StrBuffer = [u8; UNKNOWN];
 
 #[repr(C)]
-pub struct ArcStringInner {
+pub struct ArcStringInner<A: Allocator> {
     pub ref_count: AtomicUsize,
     pub capacity: usize,
+    // The allocator this `ArcStringInner` was allocated from, which is shared by every
+    // `ArcString` that points to it
+    pub alloc: A,
     pub str_buffer: StrBuffer,
 }
 
-impl ArcStringInner {
-    pub fn with_capacity(capacity: usize) -> ptr::NonNull<ArcStringInner> {
-        let mut ptr = Self::alloc(capacity);
+impl<A: Allocator> ArcStringInner<A> {
+    pub fn try_with_capacity(
+        capacity: usize,
+        alloc: A,
+    ) -> Result<ptr::NonNull<ArcStringInner<A>>, TryReserveError> {
+        let mut ptr = Self::try_alloc(capacity, &alloc)?;
 
         // SAFETY: We just allocated an instance of `ArcStringInner` and checked to make sure it
         // wasn't null, so we know it's aligned properly, that it points to an instance of
@@ -25,8 +36,11 @@ impl ArcStringInner {
         unsafe { ptr.as_mut().ref_count = AtomicUsize::new(1) };
         // SAFTEY: Same as above
         unsafe { ptr.as_mut().capacity = capacity };
+        // SAFETY: Same as above, but the allocator field is uninitialized, so we need to make sure
+        // we don't drop the old value
+        unsafe { ptr::write(&mut ptr.as_mut().alloc, alloc) };
 
-        ptr
+        Ok(ptr)
     }
 
     #[inline]
@@ -53,38 +67,50 @@ impl ArcStringInner {
     }
 
     fn layout(capacity: usize) -> alloc::Layout {
-        let buffer_layout = alloc::Layout::array::<u8>(capacity).unwrap();
-        alloc::Layout::new::<Self>()
+        // Note: We successfully allocated with this capacity, so we know the layout is valid
+        Self::try_layout(capacity).expect("valid layout")
+    }
+
+    fn try_layout(capacity: usize) -> Result<alloc::Layout, TryReserveError> {
+        let buffer_layout = alloc::Layout::array::<u8>(capacity)
+            .map_err(|_| TryReserveError::capacity_overflow())?;
+        let layout = alloc::Layout::new::<Self>()
             .extend(buffer_layout)
-            .unwrap()
+            .map_err(|_| TryReserveError::capacity_overflow())?
             .0
-            .pad_to_align()
+            .pad_to_align();
+
+        Ok(layout)
     }
 
-    pub fn alloc(capacity: usize) -> ptr::NonNull<ArcStringInner> {
-        let layout = Self::layout(capacity);
+    pub fn try_alloc(
+        capacity: usize,
+        alloc: &A,
+    ) -> Result<ptr::NonNull<ArcStringInner<A>>, TryReserveError> {
+        let layout = Self::try_layout(capacity)?;
+        // Note: We never request zero-sized allocations from an `Allocator`, and we know the size
+        // of the layout is greater than 0 because we define it
         debug_assert!(layout.size() > 0);
 
-        // SAFETY: `alloc(...)` has undefined behavior if the layout is zero-sized, but we know the
-        // size of the layout is greater than 0 because we define it (and check for it above)
-        let raw_ptr = unsafe { alloc::alloc(layout) as *mut ArcStringInner };
-
-        // Check to make sure our pointer is non-null, some allocators return null pointers instead
-        // of panicking
-        match ptr::NonNull::new(raw_ptr) {
-            Some(ptr) => ptr,
-            None => alloc::handle_alloc_error(layout),
-        }
+        alloc
+            .allocate(layout)
+            .map(ptr::NonNull::cast)
+            .map_err(|_| TryReserveError::alloc_error(layout))
     }
 
-    pub fn dealloc(ptr: ptr::NonNull<ArcStringInner>) {
+    pub fn dealloc(ptr: ptr::NonNull<ArcStringInner<A>>) {
         // SAFETY: We know the pointer is non-null and it is properly aligned
         let capacity = unsafe { ptr.as_ref().capacity };
         let layout = Self::layout(capacity);
 
+        // Move the allocator out of the `ArcStringInner`, since we're about to deallocate it
+        //
+        // SAFETY: We know the pointer is valid, and we never touch the `ArcStringInner` again
+        let alloc = unsafe { ptr::read(&ptr.as_ref().alloc) };
+
         // SAFETY: There is only one way to allocate an ArcStringInner, and it uses the same layout
-        // we defined above. Also we know the pointer is non-null and we use the same global
-        // allocator as we did in `Self::alloc(...)`
-        unsafe { alloc::dealloc(ptr.as_ptr() as *mut u8, layout) };
+        // we defined above. Also we know the pointer is non-null and we use the same allocator as
+        // we did in `Self::alloc(...)`
+        unsafe { alloc.deallocate(ptr.cast(), layout) };
     }
 }
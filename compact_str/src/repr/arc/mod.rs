@@ -1,9 +1,8 @@
-// TODO: Use this module
-#![allow(dead_code)]
-
-use std::iter::Extend;
-use std::sync::atomic::Ordering;
-use std::{
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::iter::Extend;
+use core::sync::atomic::Ordering;
+use core::{
     fmt,
     mem,
     ptr,
@@ -12,36 +11,60 @@ use std::{
 
 mod inner;
 use inner::ArcStringInner;
+mod offset;
+use offset::Offset;
 mod writer;
 use writer::ArcStringWriter;
 
+use crate::error::handle_reserve;
+use crate::{
+    Allocator,
+    Global,
+    TryReserveError,
+};
+
 /// A soft limit on the amount of references that may be made to an `Arc`.
 ///
 /// Going above this limit will abort your program (although not
 /// necessarily) at _exactly_ `MAX_REFCOUNT + 1` references.
 const MAX_REFCOUNT: usize = (isize::MAX) as usize;
 
+/// A reference counted string, which is a view of `len` bytes starting at `offset` into the
+/// buffer of a shared `ArcStringInner`.
+///
+/// Note: The last byte of `offset` is always `HEAP_MASK`, which is the discriminant of a `Repr`
 #[repr(C)]
-pub struct ArcString {
+pub struct ArcString<A: Allocator = Global> {
     len: usize,
-    ptr: ptr::NonNull<ArcStringInner>,
+    ptr: ptr::NonNull<ArcStringInner<A>>,
+    offset: Offset,
 }
-unsafe impl Sync for ArcString {}
-unsafe impl Send for ArcString {}
+// Note: Like an `Arc`, clones of an `ArcString` can be dropped on any thread, which can deallocate
+// the underlying buffer with the shared allocator
+unsafe impl<A: Allocator + Send + Sync> Sync for ArcString<A> {}
+unsafe impl<A: Allocator + Send + Sync> Send for ArcString<A> {}
+
+impl<A: Allocator> ArcString<A> {
+    #[inline]
+    pub fn new(bytes: &[u8], additional: usize, alloc: A) -> Self {
+        handle_reserve(ArcString::try_new(bytes, additional, alloc))
+    }
 
-impl ArcString {
+    /// Creates an [`ArcString`] from the provided `bytes` with `additional` capacity, returning an
+    /// error if the capacity overflows or the allocator fails
     #[inline]
-    pub fn new(text: &str, additional: usize) -> Self {
-        let len = text.len();
+    pub fn try_new(bytes: &[u8], additional: usize, alloc: A) -> Result<Self, TryReserveError> {
+        let len = bytes.len();
 
-        let required = len + additional;
+        let required = len
+            .checked_add(additional)
+            .ok_or_else(TryReserveError::capacity_overflow)?;
         let amortized = 3 * len / 2;
         let new_capacity = core::cmp::max(amortized, required);
 
-        // TODO: Handle overflows in the case of __very__ large Strings
         debug_assert!(new_capacity >= len);
 
-        let mut ptr = ArcStringInner::with_capacity(new_capacity);
+        let mut ptr = ArcStringInner::try_with_capacity(new_capacity, alloc)?;
 
         // SAFETY: We just created the `ArcStringInner` so we know the pointer is properly aligned,
         // it is non-null, points to an instance of `ArcStringInner`, and the `str_buffer`
@@ -50,21 +73,76 @@ impl ArcString {
         // SAFETY: We know both `src` and `dest` are valid for respectively reads and writes of
         // length `len` because `len` comes from `src`, and `dest` was allocated to be at least that
         // length. We also know they're non-overlapping because `dest` is newly allocated
-        unsafe { buffer_ptr.copy_from_nonoverlapping(text.as_ptr(), len) };
+        unsafe { buffer_ptr.copy_from_nonoverlapping(bytes.as_ptr(), len) };
 
-        ArcString { len, ptr }
+        Ok(ArcString {
+            len,
+            ptr,
+            offset: Offset::ZERO,
+        })
     }
 
+    /// Creates an [`ArcString`] with the provided capacity, returning an error if the capacity
+    /// overflows or the allocator fails
     #[inline]
-    pub fn with_capacity(capacity: usize) -> Self {
+    pub fn try_with_capacity(capacity: usize, alloc: A) -> Result<Self, TryReserveError> {
         // We should never be able to programatically create an `ArcString` with a capacity less
         // than our max inline size, since then the string should be inlined
         debug_assert!(capacity >= super::MAX_SIZE);
 
         let len = 0;
-        let ptr = ArcStringInner::with_capacity(capacity);
+        let ptr = ArcStringInner::try_with_capacity(capacity, alloc)?;
+
+        Ok(ArcString {
+            len,
+            ptr,
+            offset: Offset::ZERO,
+        })
+    }
+
+    /// Shrinks the capacity of the buffer to `max(self.len(), min_capacity)` bytes, if it's
+    /// currently larger.
+    ///
+    /// Note: If we share our underlying buffer with another `ArcString`, this will copy it
+    #[inline]
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        let len = self.len();
+        let new_capacity = core::cmp::max(len, min_capacity);
+
+        // We're already small enough, so there is no work to do
+        if self.capacity() <= new_capacity {
+            return;
+        }
 
-        ArcString { len, ptr }
+        let alloc = self.inner().alloc.clone();
+        let mut new = handle_reserve(ArcString::try_with_capacity(new_capacity, alloc));
+
+        // SAFETY: We're writing a &str which we know is valid UTF-8, and we just created `new`, so
+        // no other references to its buffer exist
+        let buffer = unsafe { new.make_mut_slice() };
+        buffer[..len].copy_from_slice(self.as_slice());
+        // SAFETY: We just wrote `len` bytes into our buffer
+        unsafe { new.set_len(len) };
+
+        // Set our new ArcString as self, which decrements the ref count of the old buffer
+        *self = new;
+    }
+
+    /// Returns a new [`ArcString`] of the bytes `start..end` of `self`, which shares our
+    /// underlying buffer, or `None` if we can't represent the new offset into the buffer.
+    ///
+    /// Note: The caller must make sure `start..end` is in bounds and falls on char boundaries
+    #[inline]
+    pub fn slice(&self, start: usize, end: usize) -> Option<Self> {
+        debug_assert!(start <= end);
+        debug_assert!(end <= self.len);
+
+        let offset = Offset::new(self.offset() + start)?;
+        let mut substr = self.clone();
+        substr.len = end - start;
+        substr.offset = offset;
+
+        Some(substr)
     }
 
     #[inline]
@@ -72,9 +150,16 @@ impl ArcString {
         self.len
     }
 
+    /// Returns the capacity of the buffer that's available to us, i.e. excluding the bytes before
+    /// our offset
     #[inline]
     pub fn capacity(&self) -> usize {
-        self.inner().capacity
+        self.inner().capacity - self.offset()
+    }
+
+    #[inline(always)]
+    fn offset(&self) -> usize {
+        self.offset.as_usize()
     }
 
     #[inline]
@@ -91,7 +176,8 @@ impl ArcString {
 
     #[inline(always)]
     pub fn as_slice(&self) -> &[u8] {
-        &self.inner().as_bytes()[..self.len]
+        let offset = self.offset();
+        &self.inner().as_bytes()[offset..offset + self.len]
     }
 
     /// Returns a mutable reference to the underlying buffer of bytes
@@ -103,19 +189,29 @@ impl ArcString {
         self.writer().into_mut_slice()
     }
 
+    /// Fallible version of `ArcString::make_mut_slice()`, which returns an error if we need to
+    /// copy a shared buffer and the allocator fails
+    ///
+    /// # SAFETY:
+    /// * The caller must guarantee any modifications made to the buffer are valid UTF-8
+    #[inline]
+    pub unsafe fn try_make_mut_slice(&mut self) -> Result<&mut [u8], TryReserveError> {
+        Ok(ArcStringWriter::try_new(self)?.into_mut_slice())
+    }
+
     #[inline]
     pub unsafe fn set_len(&mut self, length: usize) {
         self.len = length;
     }
 
     #[inline]
-    fn writer(&mut self) -> ArcStringWriter<'_> {
+    fn writer(&mut self) -> ArcStringWriter<'_, A> {
         ArcStringWriter::new(self)
     }
 
     /// Returns a shared reference to the heap allocated `ArcStringInner`
     #[inline]
-    fn inner(&self) -> &ArcStringInner {
+    fn inner(&self) -> &ArcStringInner<A> {
         // SAFETY: If we still have an instance of `ArcString` then we know the pointer to
         // `ArcStringInner` is valid for at least as long as the provided ref to `self`
         unsafe { self.ptr.as_ref() }
@@ -127,7 +223,7 @@ impl ArcString {
     }
 }
 
-impl Clone for ArcString {
+impl<A: Allocator> Clone for ArcString<A> {
     fn clone(&self) -> Self {
         let old_count = self.inner().ref_count.fetch_add(1, Ordering::Relaxed);
         assert!(
@@ -139,23 +235,24 @@ impl Clone for ArcString {
         ArcString {
             len: self.len,
             ptr: self.ptr,
+            offset: self.offset,
         }
     }
 }
 
-impl Drop for ArcString {
+impl<A: Allocator> Drop for ArcString<A> {
     fn drop(&mut self) {
         // This was copied from the implementation of `std::sync::Arc`
         // TODO: Better document the safety invariants here
         if self.inner().ref_count.fetch_sub(1, Ordering::Release) != 1 {
             return;
         }
-        std::sync::atomic::fence(Ordering::Acquire);
+        core::sync::atomic::fence(Ordering::Acquire);
         unsafe { self.drop_inner() }
     }
 }
 
-impl fmt::Debug for ArcString {
+impl<A: Allocator> fmt::Debug for ArcString<A> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Debug::fmt(self.as_str(), f)
     }
@@ -164,39 +261,39 @@ impl fmt::Debug for ArcString {
 impl From<&str> for ArcString {
     #[inline]
     fn from(text: &str) -> Self {
-        ArcString::new(text, 0)
+        ArcString::new(text.as_bytes(), 0, Global)
     }
 }
 
-impl Extend<char> for ArcString {
+impl<A: Allocator> Extend<char> for ArcString<A> {
     #[inline]
     fn extend<T: IntoIterator<Item = char>>(&mut self, iter: T) {
         self.writer().extend(iter);
     }
 }
 
-impl<'c> Extend<&'c char> for ArcString {
+impl<'c, A: Allocator> Extend<&'c char> for ArcString<A> {
     #[inline]
     fn extend<T: IntoIterator<Item = &'c char>>(&mut self, iter: T) {
         self.writer().extend(iter);
     }
 }
 
-impl<'s> Extend<&'s str> for ArcString {
+impl<'s, A: Allocator> Extend<&'s str> for ArcString<A> {
     #[inline]
     fn extend<T: IntoIterator<Item = &'s str>>(&mut self, iter: T) {
         self.writer().extend(iter);
     }
 }
 
-impl Extend<Box<str>> for ArcString {
+impl<A: Allocator> Extend<Box<str>> for ArcString<A> {
     #[inline]
     fn extend<T: IntoIterator<Item = Box<str>>>(&mut self, iter: T) {
         self.writer().extend(iter);
     }
 }
 
-impl Extend<String> for ArcString {
+impl<A: Allocator> Extend<String> for ArcString<A> {
     #[inline]
     fn extend<T: IntoIterator<Item = String>>(&mut self, iter: T) {
         self.writer().extend(iter);
@@ -205,11 +302,14 @@ impl Extend<String> for ArcString {
 
 #[cfg(test)]
 mod test {
+    use std::sync::atomic::Ordering;
+
     use proptest::prelude::*;
     use test_strategy::proptest;
 
     use super::ArcString;
     use crate::tests::rand_unicode;
+    use crate::Global;
 
     #[test]
     fn test_empty() {
@@ -246,6 +346,50 @@ mod test {
         assert_eq!(arc_str_2.len, example.len());
     }
 
+    #[test]
+    fn test_clone_shares_buffer() {
+        let example = "hello world!";
+        let arc_str_1 = ArcString::from(example);
+        let arc_str_2 = arc_str_1.clone();
+
+        // cloning should only increment the ref count, not copy the buffer
+        assert_eq!(arc_str_1.as_str().as_ptr(), arc_str_2.as_str().as_ptr());
+        assert_eq!(arc_str_1.inner().ref_count.load(Ordering::Relaxed), 2);
+
+        drop(arc_str_1);
+        assert_eq!(arc_str_2.inner().ref_count.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_copy_on_write() {
+        let example = "hello world";
+        let arc_str_1 = ArcString::from(example);
+        let mut arc_str_2 = arc_str_1.clone();
+
+        // mutating a shared buffer should make a copy
+        arc_str_2.push('!');
+
+        assert_eq!(arc_str_1.as_str(), "hello world");
+        assert_eq!(arc_str_2.as_str(), "hello world!");
+        assert_ne!(arc_str_1.as_str().as_ptr(), arc_str_2.as_str().as_ptr());
+
+        // both instances are now unique
+        assert_eq!(arc_str_1.inner().ref_count.load(Ordering::Relaxed), 1);
+        assert_eq!(arc_str_2.inner().ref_count.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_unique_mutate_does_not_copy() {
+        let mut arc_str = ArcString::new(b"hello world", 8, Global);
+        let ptr = arc_str.as_str().as_ptr();
+
+        // we have enough capacity, and we're the only reference, so we should write in place
+        arc_str.push('!');
+
+        assert_eq!(arc_str.as_str(), "hello world!");
+        assert_eq!(arc_str.as_str().as_ptr(), ptr);
+    }
+
     #[test]
     fn test_extend_chars() {
         let example = "hello";
@@ -278,12 +422,87 @@ mod test {
         assert_eq!(arc_str.len, example.len());
     }
 
+    #[test]
+    fn test_slice_shares_buffer() {
+        let example = "hello world, this is a long string";
+        let arc_str = ArcString::from(example);
+        let substr = arc_str.slice(6, 11).unwrap();
+
+        assert_eq!(substr.as_str(), "world");
+        assert_eq!(substr.as_str().as_ptr(), arc_str.as_str()[6..].as_ptr());
+        assert_eq!(substr.capacity(), arc_str.capacity() - 6);
+        assert_eq!(arc_str.inner().ref_count.load(Ordering::Relaxed), 2);
+
+        // the substring keeps the buffer alive
+        drop(arc_str);
+        assert_eq!(substr.as_str(), "world");
+        assert_eq!(substr.inner().ref_count.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_slice_of_slice() {
+        let arc_str = ArcString::from("hello world, this is a long string");
+        let a = arc_str.slice(6, 34).unwrap();
+        let b = a.slice(7, 11).unwrap();
+
+        assert_eq!(a.as_str(), "world, this is a long string");
+        assert_eq!(b.as_str(), "this");
+        assert_eq!(b.offset(), 13);
+    }
+
+    #[test]
+    fn test_slice_then_mutate() {
+        let arc_str = ArcString::from("hello world");
+        let mut substr = arc_str.slice(0, 5).unwrap();
+
+        // mutating a shared substring should copy it, and not clobber the parent
+        substr.push('!');
+
+        assert_eq!(arc_str.as_str(), "hello world");
+        assert_eq!(substr.as_str(), "hello!");
+        assert_eq!(substr.offset(), 0);
+        assert_eq!(arc_str.inner().ref_count.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_unique_slice_mutates_in_place() {
+        let arc_str = ArcString::new(b"hello world", 8, Global);
+        let mut substr = arc_str.slice(6, 11).unwrap();
+        drop(arc_str);
+
+        // we're the only reference to the buffer, so we can write in place after our offset
+        let ptr = substr.as_str().as_ptr();
+        substr.push('!');
+
+        assert_eq!(substr.as_str(), "world!");
+        assert_eq!(substr.as_str().as_ptr(), ptr);
+        assert_eq!(substr.offset(), 6);
+    }
+
     #[proptest]
     #[cfg_attr(miri, ignore)]
     fn test_strings_roundtrip(#[strategy(rand_unicode())] word: String) {
         let arc_str = ArcString::from(word.as_str());
         prop_assert_eq!(&word, arc_str.as_str());
     }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn test_clone_then_extend(
+        #[strategy(rand_unicode())] word: String,
+        #[strategy(rand_unicode())] extend: String,
+    ) {
+        let arc_str_1 = ArcString::from(word.as_str());
+        let mut arc_str_2 = arc_str_1.clone();
+
+        arc_str_2.extend(vec![extend.as_str()]);
+
+        let mut control = word.clone();
+        control.push_str(&extend);
+
+        prop_assert_eq!(&word, arc_str_1.as_str());
+        prop_assert_eq!(&control, arc_str_2.as_str());
+    }
 }
 
-crate::asserts::assert_size!(ArcString, 2 * mem::size_of::<usize>());
+crate::asserts::assert_size!(ArcString, 3 * mem::size_of::<usize>());
@@ -0,0 +1,98 @@
+use crate::repr::HEAP_MASK;
+
+// how many bytes a `usize` occupies
+const USIZE_SIZE: usize = core::mem::size_of::<usize>();
+
+// how many bytes we can use for the offset
+const SPACE_FOR_OFFSET: usize = USIZE_SIZE - 1;
+// the maximum value we're able to store, e.g. on 64-bit arch this is 2^56 - 1
+//
+// note: See `boxed::capacity::MAX_VALUE` for why we need to allow this lint
+#[allow(clippy::precedence)]
+pub const MAX_VALUE: usize = (1 << SPACE_FOR_OFFSET * 8) - 1;
+
+/// An integer type that uses `core::mem::size_of::<usize>() - 1` bytes to store the offset of an
+/// [`super::ArcString`] into its shared buffer.
+///
+/// Assuming a 64-bit arch, an [`super::ArcString`] uses 8 bytes for a length, 8 bytes for a
+/// pointer, and then needs 1 byte for a discriminant. That leaves us with 7 unused bytes, which
+/// [`Offset`] uses to store where in the shared buffer our string starts, so substrings can point
+/// into the buffer of their parent.
+///
+/// # Max Values
+/// * __64-bit:__ `(2 ^ (7 * 8)) - 1 = 72_057_594_037_927_935 ~= 64 petabytes`
+/// * __32-bit:__ `(2 ^ (3 * 8)) - 1 = 16_777_215             ~= 16 megabytes`
+///
+/// If a substring starts further into the buffer than we can store, it gets copied instead.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Offset {
+    _buf: [u8; USIZE_SIZE],
+}
+
+impl Offset {
+    /// An [`Offset`] pointing at the start of the buffer
+    pub const ZERO: Offset = Offset::encode(0);
+
+    #[inline]
+    pub fn new(offset: usize) -> Option<Self> {
+        if offset > MAX_VALUE {
+            None
+        } else {
+            Some(Offset::encode(offset))
+        }
+    }
+
+    #[inline]
+    const fn encode(offset: usize) -> Self {
+        let mut bytes = offset.to_le_bytes();
+        // Set the last byte to be our `HEAP_MASK` for our discriminant, using the leading bytes to
+        // store the actual value
+        bytes[USIZE_SIZE - 1] = HEAP_MASK;
+        Offset { _buf: bytes }
+    }
+
+    #[inline(always)]
+    pub fn as_usize(&self) -> usize {
+        let mut usize_buf = [0u8; USIZE_SIZE];
+        usize_buf[..SPACE_FOR_OFFSET].copy_from_slice(&self._buf[..SPACE_FOR_OFFSET]);
+        usize::from_le_bytes(usize_buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Offset,
+        MAX_VALUE,
+    };
+    use crate::repr::HEAP_MASK;
+
+    #[test]
+    fn test_zero() {
+        assert_eq!(Offset::ZERO.as_usize(), 0);
+        assert_eq!(Offset::new(0), Some(Offset::ZERO));
+    }
+
+    #[test]
+    fn test_roundtrips() {
+        for og in [1, 24, 255, 256, 65_536, MAX_VALUE].iter().copied() {
+            let offset = Offset::new(og).unwrap();
+            assert_eq!(offset.as_usize(), og);
+        }
+    }
+
+    #[test]
+    fn test_max_value() {
+        let available_bytes = (core::mem::size_of::<usize>() - 1) as u32;
+        let max_value = 2usize.pow(available_bytes * 8) - 1;
+
+        assert_eq!(MAX_VALUE, max_value);
+        assert!(Offset::new(MAX_VALUE + 1).is_none());
+    }
+
+    #[test]
+    fn test_last_byte_is_discriminant() {
+        let offset = Offset::new(MAX_VALUE).unwrap();
+        assert_eq!(offset._buf[core::mem::size_of::<usize>() - 1], HEAP_MASK);
+    }
+}
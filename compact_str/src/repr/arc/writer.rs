@@ -1,7 +1,14 @@
+use alloc::boxed::Box;
+use alloc::string::String;
 use core::iter::Extend;
 use core::sync::atomic::Ordering;
 
 use super::ArcString;
+use crate::error::handle_reserve;
+use crate::{
+    Allocator,
+    TryReserveError,
+};
 
 /// An `ArcStringWriter` provides safe mutable access to the underlying buffer of an `ArcString`.
 ///
@@ -9,13 +16,20 @@ use super::ArcString;
 /// underlying buffer of an `ArcString`, if we don't then we create one. Knowing we hold a unique
 /// reference to the `ArcString` allows us to make multiple modifications to the buffer, this is
 /// particularly beneficial for something like the `core::iter::Extend` trait
-pub struct ArcStringWriter<'a> {
-    arc_string: &'a mut ArcString,
+pub struct ArcStringWriter<'a, A: Allocator> {
+    arc_string: &'a mut ArcString<A>,
 }
 
-impl<'a> ArcStringWriter<'a> {
+impl<'a, A: Allocator> ArcStringWriter<'a, A> {
     #[inline]
-    pub fn new(arc_string: &'a mut ArcString) -> Self {
+    pub fn new(arc_string: &'a mut ArcString<A>) -> Self {
+        handle_reserve(ArcStringWriter::try_new(arc_string))
+    }
+
+    /// Fallible version of `ArcStringWriter::new()`, which returns an error if we need to copy a
+    /// shared buffer and the allocator fails, leaving `arc_string` unchanged
+    #[inline]
+    pub fn try_new(arc_string: &'a mut ArcString<A>) -> Result<Self, TryReserveError> {
         if arc_string
             .inner()
             .ref_count
@@ -27,7 +41,8 @@ impl<'a> ArcStringWriter<'a> {
 
             // Make a new instance with the same capacity as self
             let additional = arc_string.capacity() - arc_string.len();
-            let new = ArcString::new(arc_string.as_str(), additional);
+            let alloc = arc_string.inner().alloc.clone();
+            let new = ArcString::try_new(arc_string.as_slice(), additional, alloc)?;
 
             // Assign arc_string to our new instsance, this drops the old ArcString, which
             // decrements its ref count
@@ -37,7 +52,7 @@ impl<'a> ArcStringWriter<'a> {
             arc_string.inner().ref_count.store(1, Ordering::Release);
         }
 
-        Self { arc_string }
+        Ok(Self { arc_string })
     }
 
     /// Reserve space for at least `additional` bytes
@@ -53,7 +68,8 @@ impl<'a> ArcStringWriter<'a> {
 
         // Create a new `ArcString` with enough space for at least `additional` bytes, dropping the
         // old one
-        *self.arc_string = ArcString::new(self.arc_string.as_str(), additional);
+        let alloc = self.arc_string.inner().alloc.clone();
+        *self.arc_string = ArcString::new(self.arc_string.as_slice(), additional, alloc);
     }
 
     #[inline]
@@ -102,7 +118,8 @@ impl<'a> ArcStringWriter<'a> {
     /// * Callers must guarantee that any modifications they make to the slice are valid UTF-8
     #[inline]
     pub unsafe fn as_mut_slice(&mut self) -> &mut [u8] {
-        self.arc_string.ptr.as_mut().as_mut_bytes()
+        let offset = self.arc_string.offset();
+        &mut self.arc_string.ptr.as_mut().as_mut_bytes()[offset..]
     }
 
     /// Transforms the `ArcStringWriter<'a>` into a mutable slice of bytes with lifetime `'a`
@@ -113,11 +130,12 @@ impl<'a> ArcStringWriter<'a> {
     pub unsafe fn into_mut_slice(self) -> &'a mut [u8] {
         // SAFETY: If we still have an instance of `ArcString` then we know the pointer to
         // `ArcStringInner` is valid for at least as long as the provided ref to `self`
-        self.arc_string.ptr.as_mut().as_mut_bytes()
+        let offset = self.arc_string.offset();
+        &mut self.arc_string.ptr.as_mut().as_mut_bytes()[offset..]
     }
 }
 
-impl<'a> Extend<char> for ArcStringWriter<'a> {
+impl<'a, A: Allocator> Extend<char> for ArcStringWriter<'a, A> {
     #[inline]
     fn extend<T: IntoIterator<Item = char>>(&mut self, iter: T) {
         let iterator = iter.into_iter();
@@ -127,28 +145,28 @@ impl<'a> Extend<char> for ArcStringWriter<'a> {
     }
 }
 
-impl<'c, 'a> Extend<&'c char> for ArcStringWriter<'a> {
+impl<'c, 'a, A: Allocator> Extend<&'c char> for ArcStringWriter<'a, A> {
     #[inline]
     fn extend<T: IntoIterator<Item = &'c char>>(&mut self, iter: T) {
         self.extend(iter.into_iter().copied());
     }
 }
 
-impl<'s, 'a> Extend<&'s str> for ArcStringWriter<'a> {
+impl<'s, 'a, A: Allocator> Extend<&'s str> for ArcStringWriter<'a, A> {
     #[inline]
     fn extend<T: IntoIterator<Item = &'s str>>(&mut self, iter: T) {
         iter.into_iter().for_each(|s| self.push_str(s));
     }
 }
 
-impl<'a> Extend<Box<str>> for ArcStringWriter<'a> {
+impl<'a, A: Allocator> Extend<Box<str>> for ArcStringWriter<'a, A> {
     #[inline]
     fn extend<T: IntoIterator<Item = Box<str>>>(&mut self, iter: T) {
         iter.into_iter().for_each(move |s| self.push_str(&s));
     }
 }
 
-impl<'a> Extend<String> for ArcStringWriter<'a> {
+impl<'a, A: Allocator> Extend<String> for ArcStringWriter<'a, A> {
     #[inline]
     fn extend<T: IntoIterator<Item = String>>(&mut self, iter: T) {
         iter.into_iter().for_each(move |s| self.push_str(&s));
@@ -3,32 +3,34 @@ pub type StrBuffer = [u8; UNKNOWN];
 
 #[cfg(not(target_pointer_width = "64"))]
 pub mod heap_capacity {
+    use alloc::alloc;
     use core::ptr;
-    use std::alloc;
 
     use super::StrBuffer;
+    use crate::{
+        Allocator,
+        TryReserveError,
+    };
 
-    pub fn alloc(capacity: usize) -> ptr::NonNull<u8> {
-        let layout = layout(capacity);
+    pub fn try_alloc<A: Allocator>(
+        capacity: usize,
+        allocator: &A,
+    ) -> Result<ptr::NonNull<u8>, TryReserveError> {
+        let layout = try_layout(capacity)?;
+        // Note: We know the layout can't be zero-sized because we're always at least allocating
+        // one `usize`
         debug_assert!(layout.size() > 0);
 
-        // SAFETY: `alloc(...)` has undefined behavior if the layout is zero-sized. We know the
-        // layout can't be zero-sized though because we're always at least allocating one `usize`
-        let raw_ptr = unsafe { alloc::alloc(layout) };
-
-        // Check to make sure our pointer is non-null, some allocators return null pointers instead
-        // of panicking
-        match ptr::NonNull::new(raw_ptr) {
-            Some(ptr) => ptr,
-            None => alloc::handle_alloc_error(layout),
-        }
+        allocator
+            .allocate(layout)
+            .map_err(|_| TryReserveError::alloc_error(layout))
     }
 
-    pub unsafe fn dealloc(ptr: ptr::NonNull<u8>, capacity: usize) {
+    pub unsafe fn dealloc<A: Allocator>(ptr: ptr::NonNull<u8>, capacity: usize, allocator: &A) {
         let layout = layout(capacity);
 
         // SAFETY: TODO
-        alloc::dealloc(ptr.as_ptr(), layout);
+        allocator.deallocate(ptr, layout);
     }
 
     #[repr(C)]
@@ -38,45 +40,55 @@ pub mod heap_capacity {
     }
 
     fn layout(capacity: usize) -> alloc::Layout {
-        let buffer_layout = alloc::Layout::array::<u8>(capacity).expect("valid capacity");
-        alloc::Layout::new::<BoxStringInnerHeapCapacity>()
+        // Note: We successfully allocated with this capacity, so we know the layout is valid
+        try_layout(capacity).expect("valid layout")
+    }
+
+    fn try_layout(capacity: usize) -> Result<alloc::Layout, TryReserveError> {
+        let buffer_layout = alloc::Layout::array::<u8>(capacity)
+            .map_err(|_| TryReserveError::capacity_overflow())?;
+        let layout = alloc::Layout::new::<BoxStringInnerHeapCapacity>()
             .extend(buffer_layout)
-            .expect("valid layout")
+            .map_err(|_| TryReserveError::capacity_overflow())?
             .0
-            .pad_to_align()
+            .pad_to_align();
+
+        Ok(layout)
     }
 }
 
 pub mod inline_capacity {
+    use alloc::alloc;
     use core::ptr;
-    use std::alloc;
 
     use super::StrBuffer;
+    use crate::{
+        Allocator,
+        TryReserveError,
+    };
 
     /// # Safety
     /// * `capacity` must be > 0
-    pub unsafe fn alloc(capacity: usize) -> ptr::NonNull<u8> {
-        let layout = layout(capacity);
-        debug_assert!(layout.size() > 0);
-
-        // SAFETY: `alloc(...)` has undefined behavior if the layout is zero-sized. We specify that
+    pub unsafe fn try_alloc<A: Allocator>(
+        capacity: usize,
+        allocator: &A,
+    ) -> Result<ptr::NonNull<u8>, TryReserveError> {
+        let layout = try_layout(capacity)?;
+        // Note: We never request zero-sized allocations from an `Allocator`. We specify that
         // `capacity` must be > 0 as a constraint to uphold the safety of this method. If capacity
         // is greater than 0, then our layout will be non-zero-sized.
-        let raw_ptr = alloc::alloc(layout);
-
-        // Check to make sure our pointer is non-null, some allocators return null pointers instead
-        // of panicking
-        match ptr::NonNull::new(raw_ptr) {
-            Some(ptr) => ptr,
-            None => alloc::handle_alloc_error(layout),
-        }
+        debug_assert!(layout.size() > 0);
+
+        allocator
+            .allocate(layout)
+            .map_err(|_| TryReserveError::alloc_error(layout))
     }
 
-    pub unsafe fn dealloc(ptr: ptr::NonNull<u8>, capacity: usize) {
+    pub unsafe fn dealloc<A: Allocator>(ptr: ptr::NonNull<u8>, capacity: usize, allocator: &A) {
         let layout = layout(capacity);
 
         // SAFETY: TODO
-        alloc::dealloc(ptr.as_ptr(), layout);
+        allocator.deallocate(ptr, layout);
     }
 
     #[repr(C)]
@@ -85,11 +97,19 @@ pub mod inline_capacity {
     }
 
     fn layout(capacity: usize) -> alloc::Layout {
-        let buffer_layout = alloc::Layout::array::<u8>(capacity).expect("valid capacity");
-        alloc::Layout::new::<BoxStringInnerInlineCapacity>()
+        // Note: We successfully allocated with this capacity, so we know the layout is valid
+        try_layout(capacity).expect("valid layout")
+    }
+
+    fn try_layout(capacity: usize) -> Result<alloc::Layout, TryReserveError> {
+        let buffer_layout = alloc::Layout::array::<u8>(capacity)
+            .map_err(|_| TryReserveError::capacity_overflow())?;
+        let layout = alloc::Layout::new::<BoxStringInnerInlineCapacity>()
             .extend(buffer_layout)
-            .expect("valid layout")
+            .map_err(|_| TryReserveError::capacity_overflow())?
             .0
-            .pad_to_align()
+            .pad_to_align();
+
+        Ok(layout)
     }
 }
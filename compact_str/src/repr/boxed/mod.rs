@@ -1,4 +1,6 @@
-use core::iter::Extend;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
 use core::{
     fmt,
     ptr,
@@ -11,8 +13,21 @@ use capacity::Capacity;
 
 mod inner;
 
+use crate::error::handle_reserve;
+use crate::{
+    Allocator,
+    Global,
+    TryReserveError,
+};
+
 const MIN_SIZE: usize = core::mem::size_of::<usize>() / 2;
 
+/// A heap allocated buffer of bytes, the size of a `String`.
+///
+/// A [`BoxString`] doesn't store the [`Allocator`] it was allocated from, the allocator of a `Repr`
+/// is stored after its inline buffer instead, which can be longer than a `BoxString`. So every
+/// method that allocates or deallocates takes the allocator, which must be the one we were
+/// allocated from, and a `BoxString` has to be explicitly deallocated via `BoxString::dealloc()`.
 #[repr(C)]
 pub struct BoxString {
     ptr: ptr::NonNull<u8>,
@@ -25,7 +40,95 @@ unsafe impl Send for BoxString {}
 impl BoxString {
     #[inline]
     pub fn new(text: &str) -> Self {
-        let len = text.len();
+        BoxString::new_in(text.as_bytes(), &Global)
+    }
+
+    #[inline]
+    pub fn from_string(s: String) -> Self {
+        BoxString::from_vec(s.into_bytes())
+    }
+
+    /// Creates a [`BoxString`] from a [`Vec<u8>`], re-using its buffer if we can store its
+    /// capacity inline.
+    ///
+    /// Note: the bytes might not be valid UTF-8, in which case we must never be used as a `str`
+    #[inline]
+    pub fn from_vec(v: Vec<u8>) -> Self {
+        match Capacity::new(v.capacity()) {
+            // Note: We should never hit this case when using BoxString with CompactStr
+            Ok(_) if v.capacity() == 0 => BoxString::new(""),
+            Ok(cap) => {
+                let len = v.len();
+                let raw_ptr = v.as_ptr() as *mut u8;
+
+                let ptr = ptr::NonNull::new(raw_ptr).expect("vec with capacity has null ptr?");
+                // "forget" `v` so we don't call Drop and deallocate the underlying buffer
+                core::mem::forget(v);
+                // create a new BoxString with our parts!
+                BoxString { len, ptr, cap }
+            }
+            Err(_) => BoxString::new_in(&v, &Global),
+        }
+    }
+
+    #[inline]
+    pub fn from_box_str(b: Box<str>) -> Self {
+        match Capacity::new(b.len()) {
+            // Note: We should never hit this case when using BoxString with CompactStr
+            Ok(_) if b.len() == 0 => BoxString::new(""),
+            Ok(cap) => {
+                let len = b.len();
+                let raw_ptr = b.as_ptr() as *mut u8;
+
+                let ptr = ptr::NonNull::new(raw_ptr).expect("string with capacity has null ptr?");
+                // "forget" `s` so we don't call Drop and deallocate the underlying buffer
+                core::mem::forget(b);
+                // create a new BoxString with our parts!
+                BoxString { len, ptr, cap }
+            }
+            Err(_) => BoxString::new(&b),
+        }
+    }
+
+    /// Converts this [`BoxString`] into a [`String`], see `BoxString::into_vec()`
+    ///
+    /// # Safety
+    /// * We must have been allocated from the [`Global`] allocator
+    #[inline]
+    pub unsafe fn into_string(self) -> String {
+        // SAFETY: The only way you can construct a `BoxString` is via a `&str` so it must be valid
+        // UTF-8, or the caller has manually made those guarantees
+        String::from_utf8_unchecked(self.into_vec())
+    }
+
+    /// Converts this [`BoxString`] into a [`Vec<u8>`].
+    ///
+    /// If our capacity is stored inline, then our buffer was allocated with the same layout that a
+    /// `Vec<u8>` uses, so we hand the allocation over to the `Vec<u8>` in `O(1)`. Otherwise our
+    /// capacity is stored at the start of the buffer, and we need to copy.
+    ///
+    /// # Safety
+    /// * We must have been allocated from the [`Global`] allocator
+    #[inline]
+    pub unsafe fn into_vec(mut self) -> Vec<u8> {
+        match self.cap.as_usize() {
+            // SAFETY: Our buffer was allocated by the global allocator with an alignment of 1 and a
+            // size of `cap`, which is the same layout a `Vec<u8>` uses. We also know the first
+            // `len` bytes are initialized
+            Ok(cap) => Vec::from_raw_parts(self.ptr.as_ptr(), self.len, cap),
+            Err(_) => {
+                let vec = self.as_slice().to_vec();
+                self.dealloc(&Global);
+                vec
+            }
+        }
+    }
+}
+
+impl BoxString {
+    #[inline]
+    pub fn new_in<A: Allocator>(bytes: &[u8], alloc: &A) -> Self {
+        let len = bytes.len();
 
         // Always allocate at least a few bytes
         //
@@ -35,14 +138,14 @@ impl BoxString {
 
         // SAFETY: `Self::alloc_ptr(...)` requires that capacity is non-zero. Above we set capacity
         // to be at least size_of::<usize>, so we know it'll be non-zero.
-        let (cap, ptr) = unsafe { BoxString::alloc_ptr(capacity) };
+        let (cap, ptr) = unsafe { BoxString::alloc_ptr(capacity, alloc) };
 
         // SAFETY: We know both `src` and `dest` are valid for respectively reads and writes of
         // length `len` because `len` comes from `src`, and `dest` was allocated to be at least that
         // length. We also know they're non-overlapping because `dest` is newly allocated
         #[cfg(target_pointer_width = "64")]
         unsafe {
-            ptr.as_ptr().copy_from_nonoverlapping(text.as_ptr(), len)
+            ptr.as_ptr().copy_from_nonoverlapping(bytes.as_ptr(), len)
         };
 
         #[cfg(not(target_pointer_width = "64"))]
@@ -52,14 +155,24 @@ impl BoxString {
             } else {
                 ptr.as_ptr()
             };
-            unsafe { write_ptr.copy_from_nonoverlapping(text.as_ptr(), len) };
+            unsafe { write_ptr.copy_from_nonoverlapping(bytes.as_ptr(), len) };
         }
 
         BoxString { len, ptr, cap }
     }
 
     #[inline]
-    pub fn with_capacity(capacity: usize) -> Self {
+    pub fn with_capacity_in<A: Allocator>(capacity: usize, alloc: &A) -> Self {
+        handle_reserve(BoxString::try_with_capacity_in(capacity, alloc))
+    }
+
+    /// Creates a [`BoxString`] with the provided capacity, returning an error if the capacity
+    /// overflows or the allocator fails
+    #[inline]
+    pub fn try_with_capacity_in<A: Allocator>(
+        capacity: usize,
+        alloc: &A,
+    ) -> Result<Self, TryReserveError> {
         let len = 0;
 
         // Always allocate at least a few bytes
@@ -68,32 +181,44 @@ impl BoxString {
         // short strings
         let capacity = core::cmp::max(capacity, MIN_SIZE);
 
-        // SAFETY: `Self::alloc_ptr(...)` requires that capacity is non-zero. Above we set capacity
-        // to be at least size_of::<usize>, so we know it'll be non-zero.
-        let (cap, ptr) = unsafe { BoxString::alloc_ptr(capacity) };
+        // SAFETY: `Self::try_alloc_ptr(...)` requires that capacity is non-zero. Above we set
+        // capacity to be at least size_of::<usize>, so we know it'll be non-zero.
+        let (cap, ptr) = unsafe { BoxString::try_alloc_ptr(capacity, alloc)? };
 
-        BoxString { len, ptr, cap }
+        Ok(BoxString { len, ptr, cap })
+    }
+
+    #[inline(always)]
+    unsafe fn alloc_ptr<A: Allocator>(capacity: usize, alloc: &A) -> (Capacity, ptr::NonNull<u8>) {
+        handle_reserve(BoxString::try_alloc_ptr(capacity, alloc))
     }
 
     #[inline(always)]
-    unsafe fn alloc_ptr(capacity: usize) -> (Capacity, ptr::NonNull<u8>) {
+    unsafe fn try_alloc_ptr<A: Allocator>(
+        capacity: usize,
+        alloc: &A,
+    ) -> Result<(Capacity, ptr::NonNull<u8>), TryReserveError> {
         #[cfg(target_pointer_width = "64")]
         let (cap, ptr) = {
-            debug_assert!(capacity <= capacity::MAX_VALUE);
+            // We can't store a capacity this large, practically this should never happen because
+            // no allocator can provide 64 petabytes
+            if capacity > capacity::MAX_VALUE {
+                return Err(TryReserveError::capacity_overflow());
+            }
 
             let cap = Capacity::new_unchecked(capacity);
-            let ptr = inner::inline_capacity::alloc(capacity);
+            let ptr = inner::inline_capacity::try_alloc(capacity, alloc)?;
             (cap, ptr)
         };
 
         #[cfg(not(target_pointer_width = "64"))]
         let (cap, ptr) = match Capacity::new(capacity) {
             Ok(cap) => {
-                let ptr = inner::inline_capacity::alloc(capacity);
+                let ptr = inner::inline_capacity::try_alloc(capacity, alloc)?;
                 (cap, ptr)
             }
             Err(cap) => {
-                let ptr = inner::heap_capacity::alloc(capacity);
+                let ptr = inner::heap_capacity::try_alloc(capacity, alloc)?;
                 // write our capacity onto the heap
                 core::ptr::copy_nonoverlapping(
                     capacity.to_le_bytes().as_ptr(),
@@ -104,88 +229,113 @@ impl BoxString {
             }
         };
 
-        (cap, ptr)
+        Ok((cap, ptr))
     }
 
     #[inline]
-    pub fn with_additional(text: &str, additional: usize) -> Self {
-        let len = text.len();
+    pub fn with_additional_in<A: Allocator>(bytes: &[u8], additional: usize, alloc: &A) -> Self {
+        handle_reserve(BoxString::try_with_additional_in(bytes, additional, alloc))
+    }
 
-        let required = len + additional;
+    /// Creates a [`BoxString`] from the provided `bytes` with `additional` capacity, returning an
+    /// error if the capacity overflows or the allocator fails
+    #[inline]
+    pub fn try_with_additional_in<A: Allocator>(
+        bytes: &[u8],
+        additional: usize,
+        alloc: &A,
+    ) -> Result<Self, TryReserveError> {
+        let len = bytes.len();
+
+        let required = len
+            .checked_add(additional)
+            .ok_or_else(TryReserveError::capacity_overflow)?;
         let amortized = 3 * len / 2;
         let new_capacity = core::cmp::max(amortized, required);
 
-        // TODO: Handle overflows in the case of __very__ large Strings
         debug_assert!(new_capacity >= len);
 
         // Create the `BoxString` with our determined capacity
-        let mut new = BoxString::with_capacity(new_capacity);
+        let mut new = BoxString::try_with_capacity_in(new_capacity, alloc)?;
 
-        // SAFETY: We're writing a &str which is valid UTF-8
+        // SAFETY: We're writing the bytes of a `CompactStr` or `CompactBytes`, which are only
+        // required to be valid UTF-8 for a `CompactStr`
         let buffer = unsafe { new.as_mut_slice() };
-        buffer[..len].copy_from_slice(text.as_bytes());
+        buffer[..len].copy_from_slice(bytes);
 
         // SAFETY: We just wrote `len` bytes into our buffer
         unsafe { new.set_len(len) };
 
-        new
+        Ok(new)
     }
 
+    /// Reserve space for at least `additional` bytes
+    ///
+    /// # Safety
+    /// * We must have been allocated from `alloc`
     #[inline]
-    pub fn from_string(s: String) -> Self {
-        match Capacity::new(s.capacity()) {
-            // Note: We should never hit this case when using BoxString with CompactStr
-            Ok(_) if s.capacity() == 0 => BoxString::new(""),
-            Ok(cap) => {
-                let len = s.len();
-                let raw_ptr = s.as_ptr() as *mut u8;
-
-                let ptr = ptr::NonNull::new(raw_ptr).expect("string with capacity has null ptr?");
-                // "forget" `s` so we don't call Drop and deallocate the underlying buffer
-                core::mem::forget(s);
-                // create a new BoxString with our parts!
-                BoxString { len, ptr, cap }
-            }
-            Err(_) => BoxString::new(s.as_str()),
-        }
+    pub unsafe fn reserve<A: Allocator>(&mut self, additional: usize, alloc: &A) {
+        handle_reserve(self.try_reserve(additional, alloc))
     }
 
+    /// Reserve space for at least `additional` bytes, returning an error if the capacity
+    /// overflows or the allocator fails
+    ///
+    /// # Safety
+    /// * We must have been allocated from `alloc`
     #[inline]
-    pub fn from_box_str(b: Box<str>) -> Self {
-        match Capacity::new(b.len()) {
-            // Note: We should never hit this case when using BoxString with CompactStr
-            Ok(_) if b.len() == 0 => BoxString::new(""),
-            Ok(cap) => {
-                let len = b.len();
-                let raw_ptr = b.as_ptr() as *mut u8;
+    pub unsafe fn try_reserve<A: Allocator>(
+        &mut self,
+        additional: usize,
+        alloc: &A,
+    ) -> Result<(), TryReserveError> {
+        // We need at least this much space
+        let len = self.len();
+        let required = len
+            .checked_add(additional)
+            .ok_or_else(TryReserveError::capacity_overflow)?;
 
-                let ptr = ptr::NonNull::new(raw_ptr).expect("string with capacity has null ptr?");
-                // "forget" `s` so we don't call Drop and deallocate the underlying buffer
-                core::mem::forget(b);
-                // create a new BoxString with our parts!
-                BoxString { len, ptr, cap }
-            }
-            Err(_) => BoxString::new(&b),
+        // We have enough space, so there is no work to do
+        if self.capacity() >= required {
+            return Ok(());
         }
+
+        // We need to reserve additional space, so create a new BoxString with additional space
+        let new = BoxString::try_with_additional_in(self.as_slice(), additional, alloc)?;
+
+        // Set our new BoxString as self, and deallocate the old buffer
+        core::mem::replace(self, new).dealloc(alloc);
+
+        Ok(())
     }
 
-    /// Reserve space for at least `additional` bytes
+    /// Shrinks the capacity of the buffer to `max(self.len(), min_capacity)` bytes, if it's
+    /// currently larger
+    ///
+    /// # Safety
+    /// * We must have been allocated from `alloc`
     #[inline]
-    pub fn reserve(&mut self, additional: usize) {
-        // We need at least this much space
+    pub unsafe fn shrink_to<A: Allocator>(&mut self, min_capacity: usize, alloc: &A) {
         let len = self.len();
-        let required = len + additional;
+        let new_capacity = core::cmp::max(len, min_capacity);
 
-        // We have enough space, so there is no work to do
-        if self.capacity() >= required {
+        // We're already small enough, so there is no work to do
+        if self.capacity() <= new_capacity {
             return;
         }
 
-        // We need to reserve additional space, so create a new BoxString with additional space
-        let new = BoxString::with_additional(self.as_str(), additional);
+        // Note: Instead of shrinking in place, we make a new allocation, which determines whether
+        // our new capacity gets stored inline or at the start of the buffer
+        let mut new = BoxString::with_capacity_in(new_capacity, alloc);
 
-        // Set our new BoxString as self
-        *self = new;
+        // SAFETY: We're writing a &str which we know is valid UTF-8
+        let buffer = new.as_mut_slice();
+        buffer[..len].copy_from_slice(self.as_slice());
+        // SAFETY: We just wrote `len` bytes into our buffer
+        new.set_len(len);
+
+        // Set our new BoxString as self, and deallocate the old buffer
+        core::mem::replace(self, new).dealloc(alloc);
     }
 
     #[inline]
@@ -223,16 +373,18 @@ impl BoxString {
         }
     }
 
+    /// # Safety
+    /// * We must have been allocated from `alloc`
     #[inline]
-    pub fn push(&mut self, ch: char) {
+    pub unsafe fn push<A: Allocator>(&mut self, ch: char, alloc: &A) {
         let len = self.len();
         let char_len = ch.len_utf8();
 
         // Reserve at least enough space for the new char
-        self.reserve(char_len);
+        self.reserve(char_len, alloc);
 
         // SAFETY: We're writing a char into the slice, which is valid UTF-8
-        let slice = unsafe { self.as_mut_slice() };
+        let slice = self.as_mut_slice();
 
         // Write our char into the slice
         ch.encode_utf8(&mut slice[len..]);
@@ -241,27 +393,7 @@ impl BoxString {
         //
         // SAFETY: We just wrote `char_len` bytes into the buffer, so we know this new length is
         // valid
-        unsafe { self.set_len(len + char_len) };
-    }
-
-    #[inline]
-    pub fn push_str(&mut self, s: &str) {
-        let len = self.len();
-        let str_len = s.len();
-
-        // Reserve at least enough space for the new str
-        self.reserve(str_len);
-
-        // SAFETY: We're writing a &str into the slice, which is valid UTF-8
-        let slice = unsafe { self.as_mut_slice() };
-        let buffer = &mut slice[len..len + str_len];
-
-        debug_assert_eq!(buffer.len(), s.as_bytes().len());
-
-        // Copy the string into our buffer
-        buffer.copy_from_slice(s.as_bytes());
-        // Incrament the length of our string
-        unsafe { self.set_len(len + str_len) };
+        self.set_len(len + char_len);
     }
 
     #[inline]
@@ -364,16 +496,37 @@ impl BoxString {
         }
     }
 
+    /// Creates a copy of this [`BoxString`] with the same capacity, allocated from `alloc`
+    #[inline]
+    pub fn clone_in<A: Allocator>(&self, alloc: &A) -> Self {
+        // Create a new BoxString
+        let len = self.len();
+        let mut new = Self::with_capacity_in(self.capacity(), alloc);
+
+        // Write the existing String into it
+        // SAFETY: We're writing a &str which we know is valid UTF-8
+        let buffer = unsafe { new.as_mut_slice() };
+        buffer[..len].copy_from_slice(self.as_slice());
+        // SAFETY: We just wrote `len` bytes into our buffer
+        unsafe { new.set_len(len) };
+
+        new
+    }
+
+    /// Deallocates our buffer, after which this [`BoxString`] must never be used again
+    ///
+    /// # Safety
+    /// * We must have been allocated from `alloc`
     #[inline(never)]
-    unsafe fn drop_inner(&mut self) {
+    pub unsafe fn dealloc<A: Allocator>(&mut self, alloc: &A) {
         #[cfg(target_pointer_width = "64")]
         {
-            inner::inline_capacity::dealloc(self.ptr, self.capacity())
+            inner::inline_capacity::dealloc(self.ptr, self.capacity(), alloc)
         }
 
         #[cfg(not(target_pointer_width = "64"))]
         match self.cap.as_usize() {
-            Ok(cap) => inner::inline_capacity::dealloc(self.ptr, cap),
+            Ok(cap) => inner::inline_capacity::dealloc(self.ptr, cap, alloc),
             Err(_) => {
                 // read our first few bytes to get our capacity
                 let mut usize_buf = [0u8; core::mem::size_of::<usize>()];
@@ -384,29 +537,12 @@ impl BoxString {
                 );
                 let cap = usize::from_le_bytes(usize_buf);
 
-                inner::heap_capacity::dealloc(self.ptr, cap)
+                inner::heap_capacity::dealloc(self.ptr, cap, alloc)
             }
         }
     }
 }
 
-impl Clone for BoxString {
-    fn clone(&self) -> Self {
-        // Create a new BoxString
-        let len = self.len();
-        let mut new = Self::with_capacity(self.capacity());
-
-        // Write the existing String into it
-        // SAFETY: We're writing a &str which we know is valid UTF-8
-        let buffer = unsafe { new.as_mut_slice() };
-        buffer[..len].copy_from_slice(self.as_slice());
-        // SAFETY: We just wrote `len` bytes into our buffer
-        unsafe { new.set_len(len) };
-
-        new
-    }
-}
-
 impl fmt::Debug for BoxString {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Debug::fmt(self.as_str(), f)
@@ -420,64 +556,83 @@ impl From<&str> for BoxString {
     }
 }
 
-impl Drop for BoxString {
-    fn drop(&mut self) {
-        unsafe { self.drop_inner() }
-    }
-}
+#[cfg(test)]
+mod tests {
+    use core::mem::ManuallyDrop;
+    use core::ops::{
+        Deref,
+        DerefMut,
+    };
 
-impl Extend<char> for BoxString {
-    #[inline]
-    fn extend<T: IntoIterator<Item = char>>(&mut self, iter: T) {
-        let iterator = iter.into_iter();
-        let (lower_bound, _) = iterator.size_hint();
-        self.reserve(lower_bound);
-        iterator.for_each(|c| self.push(c));
-    }
-}
+    use proptest::prelude::*;
+    use test_strategy::proptest;
 
-impl<'c> Extend<&'c char> for BoxString {
-    #[inline]
-    fn extend<T: IntoIterator<Item = &'c char>>(&mut self, iter: T) {
-        self.extend(iter.into_iter().copied());
-    }
-}
+    use super::BoxString;
+    use crate::tests::rand_unicode;
+    use crate::Global;
 
-impl<'s> Extend<&'s str> for BoxString {
-    #[inline]
-    fn extend<T: IntoIterator<Item = &'s str>>(&mut self, iter: T) {
-        iter.into_iter().for_each(|s| self.push_str(s));
+    const SIXTEEN_MB: usize = 16 * 1024 * 1024;
+
+    /// Owns a [`BoxString`] that was allocated from the [`Global`] allocator, and deallocates it
+    /// when dropped
+    struct Owned(BoxString);
+
+    impl Owned {
+        fn from_string(s: String) -> Self {
+            Owned(BoxString::from_string(s))
+        }
+
+        fn push(&mut self, ch: char) {
+            unsafe { self.0.push(ch, &Global) }
+        }
+
+        fn push_str(&mut self, s: &str) {
+            unsafe { self.0.reserve(s.len(), &Global) };
+            s.chars().for_each(|ch| self.push(ch));
+        }
+
+        fn into_string(self) -> String {
+            let this = ManuallyDrop::new(self);
+            unsafe { core::ptr::read(&this.0).into_string() }
+        }
     }
-}
 
-impl Extend<Box<str>> for BoxString {
-    #[inline]
-    fn extend<T: IntoIterator<Item = Box<str>>>(&mut self, iter: T) {
-        iter.into_iter().for_each(|s| self.push_str(&s));
+    impl From<&str> for Owned {
+        fn from(text: &str) -> Self {
+            Owned(BoxString::from(text))
+        }
     }
-}
 
-impl Extend<String> for BoxString {
-    #[inline]
-    fn extend<T: IntoIterator<Item = String>>(&mut self, iter: T) {
-        iter.into_iter().for_each(move |s| self.push_str(&s));
+    impl Clone for Owned {
+        fn clone(&self) -> Self {
+            Owned(self.0.clone_in(&Global))
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use proptest::prelude::*;
-    use test_strategy::proptest;
+    impl Deref for Owned {
+        type Target = BoxString;
 
-    use super::BoxString;
-    use crate::tests::rand_unicode;
+        fn deref(&self) -> &BoxString {
+            &self.0
+        }
+    }
 
-    const SIXTEEN_MB: usize = 16 * 1024 * 1024;
+    impl DerefMut for Owned {
+        fn deref_mut(&mut self) -> &mut BoxString {
+            &mut self.0
+        }
+    }
+
+    impl Drop for Owned {
+        fn drop(&mut self) {
+            unsafe { self.0.dealloc(&Global) }
+        }
+    }
 
     #[test]
     fn test_sanity() {
         let example = "hello world!";
-        let box_str = BoxString::from(example);
+        let box_str = Owned::from(example);
 
         assert_eq!(box_str.as_str(), example);
         assert_eq!(box_str.len(), example.len());
@@ -485,14 +640,14 @@ mod tests {
 
     #[test]
     fn test_empty() {
-        let box_string = BoxString::new("");
+        let box_string = Owned::from("");
         assert_eq!(box_string.as_str(), "");
     }
 
     #[test]
     fn test_push() {
         let example = "hello world";
-        let mut boxed = BoxString::from(example);
+        let mut boxed = Owned::from(example);
 
         boxed.push('!');
         assert_eq!(boxed.as_str(), "hello world!");
@@ -502,7 +657,7 @@ mod tests {
     #[test]
     fn test_push_str() {
         let example = "hello";
-        let mut boxed = BoxString::from(example);
+        let mut boxed = Owned::from(example);
 
         boxed.push_str(" world!");
         assert_eq!(boxed.as_str(), "hello world!");
@@ -513,7 +668,7 @@ mod tests {
     #[test]
     fn test_clone_and_drop() {
         let example = "nyc";
-        let one = BoxString::from(example);
+        let one = Owned::from(example);
         let two = one.clone();
 
         assert_eq!(one.as_str(), example);
@@ -524,7 +679,7 @@ mod tests {
     #[test]
     fn test_box_string_capacity() {
         let example = "hello";
-        let mut boxed = BoxString::from(example);
+        let mut boxed = Owned::from(example);
 
         // Starts with a capacity equal to length
         assert_eq!(boxed.capacity(), 5);
@@ -573,10 +728,30 @@ mod tests {
         std_string.push('!');
     }
 
+    #[test]
+    fn test_try_with_capacity_overflow() {
+        let err = BoxString::try_with_capacity_in(usize::MAX, &Global).unwrap_err();
+        assert!(err.is_capacity_overflow());
+    }
+
+    #[test]
+    fn test_try_reserve_overflow() {
+        let mut box_str = Owned::from("i am a long string that is heap allocated");
+
+        let err = unsafe { box_str.try_reserve(usize::MAX, &Global) }.unwrap_err();
+        assert!(err.is_capacity_overflow());
+
+        // the string should be left unchanged
+        assert_eq!(
+            box_str.as_str(),
+            "i am a long string that is heap allocated"
+        );
+    }
+
     #[test]
     fn test_from_string_parts() {
         let s = String::from("hello world!");
-        let box_string = BoxString::from_string(s.clone());
+        let box_string = Owned::from_string(s.clone());
 
         assert_eq!(s.as_str(), box_string.as_str());
     }
@@ -584,11 +759,38 @@ mod tests {
     #[test]
     fn test_from_string_parts_empty() {
         let s = String::from("");
-        let box_string = BoxString::from_string(s.clone());
+        let box_string = Owned::from_string(s.clone());
 
         assert_eq!(s.as_str(), box_string.as_str());
     }
 
+    #[test]
+    fn test_into_string() {
+        let example = "hello world!";
+        let mut boxed = Owned::from(example);
+        boxed.push_str(" how are you?");
+
+        let ptr = boxed.as_str().as_ptr();
+        let capacity = boxed.capacity();
+        let s = boxed.into_string();
+
+        assert_eq!(s, "hello world! how are you?");
+        assert_eq!(s.as_ptr(), ptr);
+        assert_eq!(s.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_from_string_into_string_roundtrip() {
+        let s = String::with_capacity(128) + "hello world!";
+        let ptr = s.as_ptr();
+
+        let s = Owned::from_string(s).into_string();
+
+        assert_eq!(s, "hello world!");
+        assert_eq!(s.as_ptr(), ptr);
+        assert_eq!(s.capacity(), 128);
+    }
+
     #[test]
     fn test_32_bit_max_inline_cap() {
         // 65 is the ASCII value of 'A'
@@ -596,7 +798,7 @@ mod tests {
         let word_buf = vec![65; SIXTEEN_MB - 2];
         let string = String::from_utf8(word_buf).unwrap();
 
-        let box_string = BoxString::new(&string);
+        let box_string = Owned::from(string.as_str());
 
         // make sure the capacity was able to be stored inline
         assert_eq!(box_string.cap.as_usize(), Ok(SIXTEEN_MB - 2));
@@ -611,7 +813,7 @@ mod tests {
         let word_buf = vec![65; SIXTEEN_MB - 2];
         let string = String::from_utf8(word_buf).unwrap();
 
-        let mut box_string = BoxString::new(&string);
+        let mut box_string = Owned::from(string.as_str());
 
         // make sure the capacity was able to be stored inline
         assert_eq!(box_string.cap.as_usize(), Ok(SIXTEEN_MB - 2));
@@ -652,7 +854,7 @@ mod tests {
         let word_buf = vec![65; SIXTEEN_MB - 1];
         let string = String::from_utf8(word_buf).unwrap();
 
-        let mut box_string = BoxString::new(&string);
+        let mut box_string = Owned::from(string.as_str());
 
         // on 32-bit archs the capacity will be stored on the heap
         #[cfg(target_pointer_width = "32")]
@@ -694,7 +896,7 @@ mod tests {
     #[proptest]
     #[cfg_attr(miri, ignore)]
     fn test_strings_roundtrip(#[strategy(rand_unicode())] word: String) {
-        let box_str = BoxString::from(word.as_str());
+        let box_str = Owned::from(word.as_str());
         prop_assert_eq!(&word, box_str.as_str());
     }
 
@@ -702,10 +904,17 @@ mod tests {
     #[cfg_attr(miri, ignore)]
     fn test_from_string(#[strategy(rand_unicode())] word: String) {
         let s: String = word.clone();
-        let box_str = BoxString::from_string(s);
+        let box_str = Owned::from_string(s);
 
         prop_assert_eq!(&word, box_str.as_str());
     }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn test_into_string_roundtrips(#[strategy(rand_unicode())] word: String) {
+        let box_str = Owned::from(word.as_str());
+        prop_assert_eq!(&word, &box_str.into_string());
+    }
 }
 
 crate::asserts::assert_size_eq!(BoxString, String);
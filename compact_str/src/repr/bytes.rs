@@ -1,22 +1,34 @@
-use core::str::Utf8Error;
+use alloc::vec::Vec;
 
-use bytes::Buf;
+use bytes::{
+    Buf,
+    Bytes,
+};
 
+use super::heap::HeapString;
 use super::{
     Repr,
+    Storage,
+    StrongRepr,
     MAX_SIZE,
 };
+use crate::{
+    FromUtf8Error,
+    Global,
+};
 
-impl Repr {
+impl<S: Storage<Global>> Repr<MAX_SIZE, Global, S> {
     /// Converts a [`Buf`] of bytes to a [`Repr`], checking that the provided bytes are valid UTF-8
-    pub fn from_utf8_buf<B: Buf>(buf: &mut B) -> Result<Self, Utf8Error> {
+    pub fn from_utf8_buf<B: Buf>(buf: &mut B) -> Result<Self, FromUtf8Error> {
         // SAFETY: We check below to make sure the provided buffer is valid UTF-8
         let (repr, bytes_written) = unsafe { Self::collect_buf(buf) };
+        let bytes = &repr.as_slice()[..bytes_written];
 
         // Check to make sure the provided bytes are valid UTF-8, return the Repr if they are!
-        match core::str::from_utf8(&repr.as_slice()[..bytes_written]) {
+        match core::str::from_utf8(bytes) {
             Ok(_) => Ok(repr),
-            Err(e) => Err(e),
+            // The bytes have already been consumed from the buffer, so hand them back
+            Err(e) => Err(FromUtf8Error::new(bytes.to_vec(), e)),
         }
     }
 
@@ -29,10 +41,74 @@ impl Repr {
         repr
     }
 
+    /// Converts [`Bytes`] to a [`Repr`], checking that the provided bytes are valid UTF-8.
+    ///
+    /// Short strings get inlined, longer strings take ownership of the underlying buffer if
+    /// possible, otherwise they get copied
+    pub fn from_utf8_bytes(bytes: Bytes) -> Result<Self, FromUtf8Error> {
+        let s = match core::str::from_utf8(&bytes) {
+            Ok(s) => s,
+            // Note: this re-uses the buffer if the `Bytes` is the only reference to it
+            Err(e) => return Err(FromUtf8Error::new(Vec::from(bytes), e)),
+        };
+
+        if s.len() <= MAX_SIZE {
+            return Ok(Repr::new(s));
+        }
+
+        // An `ArcString` needs to store a reference count next to the string, so we can't re-use
+        // the buffer from the `Bytes`
+        if HeapString::<Global, S>::SHARED {
+            return Ok(Repr::new(s));
+        }
+
+        // Note: if the `Bytes` is the only reference to a buffer that was allocated by a `Vec`,
+        // this is `O(1)`, otherwise it copies
+        let buf = Vec::from(bytes);
+        // SAFETY: We checked above that the bytes are valid UTF-8
+        let s = unsafe { alloc::string::String::from_utf8_unchecked(buf) };
+        Ok(Repr::from_string(s))
+    }
+
+    /// Converts a [`Repr`] into [`Bytes`], copying inline strings, and sharing the buffer of
+    /// static and heap allocated strings
+    pub fn into_bytes(self) -> Bytes {
+        match self.cast() {
+            StrongRepr::Inline(inline) => return Bytes::copy_from_slice(inline.as_bytes()),
+            StrongRepr::Static(static_str) => return Bytes::from_static(static_str.as_slice()),
+            StrongRepr::Heap(_) => (),
+        }
+
+        // The `Bytes` holds onto our `Repr`, and shares its reference counted buffer
+        if HeapString::<Global, S>::SHARED {
+            return Bytes::from_owner(BytesOwner(self));
+        }
+
+        // Move the buffer into the `Bytes`, without copying
+        Bytes::from(self.into_string())
+    }
+
+    /// Checks that a [`Repr`] that was written into via `Repr::spare_capacity_mut()` contains
+    /// valid UTF-8, inlining it if it's short enough
+    pub fn into_utf8(mut self) -> Result<Self, FromUtf8Error> {
+        let len = self.len();
+        let bytes = &self.as_slice()[..len];
+
+        if let Err(e) = core::str::from_utf8(bytes) {
+            return Err(FromUtf8Error::new(bytes.to_vec(), e));
+        }
+
+        // We might have moved onto the heap to avoid writing the last byte of an inline string
+        if len <= MAX_SIZE {
+            self.shrink_to_fit();
+        }
+        Ok(self)
+    }
+
     /// Collects the bytes from a [`Buf`] into a [`Repr`]
     unsafe fn collect_buf<B: Buf>(buf: &mut B) -> (Self, usize) {
         // Get an empty Repr we can write into
-        let mut repr = super::EMPTY;
+        let mut repr = Self::empty_in(Global);
         let mut bytes_written = 0;
 
         debug_assert_eq!(repr.len(), bytes_written);
@@ -76,16 +152,24 @@ impl Repr {
     }
 }
 
+/// Wraps a [`Repr`] so it can own the memory of a [`Bytes`]
+struct BytesOwner<S: Storage<Global>>(Repr<MAX_SIZE, Global, S>);
+
+impl<S: Storage<Global>> AsRef<[u8]> for BytesOwner<S> {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use std::io::Cursor;
-
-    use super::Repr;
+    type Repr = super::Repr;
 
     #[test]
     fn test_smoke() {
         let word = "hello world";
-        let mut buf = Cursor::new(word.as_bytes());
+        let mut buf = word.as_bytes();
 
         let repr = Repr::from_utf8_buf(&mut buf).unwrap();
         assert_eq!(repr.as_str(), word);
@@ -94,7 +178,7 @@ mod test {
     #[test]
     fn test_heap_allocated() {
         let word = "hello, this is a long string which should be heap allocated";
-        let mut buf = Cursor::new(word.as_bytes());
+        let mut buf = word.as_bytes();
 
         let repr = Repr::from_utf8_buf(&mut buf).unwrap();
         assert_eq!(repr.as_str(), word);
@@ -102,7 +186,7 @@ mod test {
 
     #[test]
     fn test_empty() {
-        let mut buf: Cursor<&[u8]> = Cursor::new(&[]);
+        let mut buf: &[u8] = &[];
 
         let repr = Repr::from_utf8_buf(&mut buf).unwrap();
         assert_eq!(repr.len(), 0);
@@ -116,7 +200,7 @@ mod test {
         #[cfg(target_pointer_width = "32")]
         let packed = "i am 12 char";
 
-        let mut buf = Cursor::new(packed.as_bytes());
+        let mut buf = packed.as_bytes();
 
         let repr = Repr::from_utf8_buf(&mut buf).unwrap();
         assert_eq!(repr.as_str(), packed);
@@ -130,7 +214,7 @@ mod test {
         let bytes = &[
             255, 255, 255, 255, 255, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 1, 12, 0, 0, 96,
         ];
-        let mut buf: Cursor<&[u8]> = Cursor::new(bytes);
+        let mut buf: &[u8] = bytes;
 
         assert!(Repr::from_utf8_buf(&mut buf).is_err());
     }
@@ -140,7 +224,7 @@ mod test {
         let bytes = &[
             0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 192,
         ];
-        let mut buf: Cursor<&[u8]> = Cursor::new(bytes);
+        let mut buf: &[u8] = bytes;
 
         assert!(Repr::from_utf8_buf(&mut buf).is_err());
     }
@@ -150,7 +234,7 @@ mod test {
         let bytes = &[
             0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 255,
         ];
-        let mut buf: Cursor<&[u8]> = Cursor::new(bytes);
+        let mut buf: &[u8] = bytes;
 
         assert!(Repr::from_utf8_buf(&mut buf).is_err());
     }
@@ -182,7 +266,7 @@ mod test {
     #[should_panic(expected = "Utf8Error")]
     fn test_invalid_utf8() {
         let invalid = &[0, 159];
-        let mut buf: Cursor<&[u8]> = Cursor::new(invalid);
+        let mut buf: &[u8] = invalid;
 
         Repr::from_utf8_buf(&mut buf).unwrap();
     }
@@ -0,0 +1,280 @@
+use core::char::{
+    decode_utf16,
+    REPLACEMENT_CHARACTER,
+};
+
+use super::{
+    InlineCapacity,
+    Repr,
+    Storage,
+    ThinRepr,
+    ValidInlineCapacity,
+};
+use crate::{
+    Global,
+    Utf16Error,
+};
+
+/// The operations decoding needs from a string representation, so [`Repr`] and [`ThinRepr`] can
+/// share the decoders below
+trait Decode: Sized {
+    fn with_capacity(capacity: usize) -> Self;
+    fn push(&mut self, ch: char);
+    fn push_str(&mut self, s: &str);
+}
+
+impl<const N: usize, S: Storage<Global>> Decode for Repr<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    #[inline]
+    fn with_capacity(capacity: usize) -> Self {
+        Repr::with_capacity(capacity)
+    }
+
+    #[inline]
+    fn push(&mut self, ch: char) {
+        Repr::push(self, ch)
+    }
+
+    #[inline]
+    fn push_str(&mut self, s: &str) {
+        Repr::push_str(self, s)
+    }
+}
+
+impl Decode for ThinRepr {
+    #[inline]
+    fn with_capacity(capacity: usize) -> Self {
+        ThinRepr::with_capacity(capacity)
+    }
+
+    #[inline]
+    fn push(&mut self, ch: char) {
+        ThinRepr::push(self, ch)
+    }
+
+    #[inline]
+    fn push_str(&mut self, s: &str) {
+        ThinRepr::push_str(self, s)
+    }
+}
+
+fn from_utf8_lossy<R: Decode>(buf: &[u8]) -> R {
+    // Every input byte produces at least one output byte, so the decoded string is at least
+    // as long as the input. If it fits inline, this doesn't allocate
+    let mut repr = R::with_capacity(buf.len());
+    let mut remaining = buf;
+
+    loop {
+        match core::str::from_utf8(remaining) {
+            Ok(valid) => {
+                repr.push_str(valid);
+                return repr;
+            }
+            Err(e) => {
+                let (valid, invalid) = remaining.split_at(e.valid_up_to());
+                // SAFETY: `Utf8Error::valid_up_to()` tells us how many bytes are valid UTF-8
+                repr.push_str(unsafe { core::str::from_utf8_unchecked(valid) });
+                repr.push(REPLACEMENT_CHARACTER);
+
+                match e.error_len() {
+                    Some(error_len) => remaining = &invalid[error_len..],
+                    // The input ended in the middle of a character
+                    None => return repr,
+                }
+            }
+        }
+    }
+}
+
+fn from_utf16<R: Decode, I: IntoIterator<Item = u16>>(units: I) -> Result<R, Utf16Error> {
+    let units = units.into_iter();
+
+    // Every code unit produces at least one output byte
+    let mut repr = R::with_capacity(units.size_hint().0);
+    for ch in decode_utf16(units) {
+        let ch = ch.map_err(|_| Utf16Error::new())?;
+        repr.push(ch);
+    }
+
+    Ok(repr)
+}
+
+fn from_utf16_lossy<R: Decode, I: IntoIterator<Item = u16>>(units: I) -> R {
+    let units = units.into_iter();
+
+    let mut repr = R::with_capacity(units.size_hint().0);
+    for ch in decode_utf16(units) {
+        repr.push(ch.unwrap_or(REPLACEMENT_CHARACTER));
+    }
+
+    repr
+}
+
+fn from_utf16_bytes<R: Decode>(
+    buf: &[u8],
+    from_bytes: fn([u8; 2]) -> u16,
+) -> Result<R, Utf16Error> {
+    let chunks = buf.chunks_exact(2);
+    if !chunks.remainder().is_empty() {
+        return Err(Utf16Error::new());
+    }
+    from_utf16(chunks.map(|c| from_bytes([c[0], c[1]])))
+}
+
+fn from_utf16_bytes_lossy<R: Decode>(buf: &[u8], from_bytes: fn([u8; 2]) -> u16) -> R {
+    let chunks = buf.chunks_exact(2);
+    let has_remainder = !chunks.remainder().is_empty();
+
+    let mut repr: R = from_utf16_lossy(chunks.map(|c| from_bytes([c[0], c[1]])));
+    if has_remainder {
+        repr.push(REPLACEMENT_CHARACTER);
+    }
+    repr
+}
+
+impl<const N: usize, S: Storage<Global>> Repr<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    /// Converts a slice of bytes to a [`Repr`], replacing any invalid UTF-8 sequences with
+    /// [`REPLACEMENT_CHARACTER`], the same way as [`String::from_utf8_lossy`]
+    ///
+    /// [`String::from_utf8_lossy`]: alloc::string::String::from_utf8_lossy
+    pub fn from_utf8_lossy(buf: &[u8]) -> Self {
+        from_utf8_lossy(buf)
+    }
+
+    /// Converts an iterator of UTF-16 code units to a [`Repr`], failing if it contains any
+    /// unpaired surrogates
+    pub fn from_utf16<I: IntoIterator<Item = u16>>(units: I) -> Result<Self, Utf16Error> {
+        from_utf16(units)
+    }
+
+    /// Converts an iterator of UTF-16 code units to a [`Repr`], replacing any unpaired surrogates
+    /// with [`REPLACEMENT_CHARACTER`]
+    pub fn from_utf16_lossy<I: IntoIterator<Item = u16>>(units: I) -> Self {
+        from_utf16_lossy(units)
+    }
+
+    /// Converts a slice of bytes containing UTF-16 code units to a [`Repr`], using `from_bytes`
+    /// to read each code unit, failing if the input has an odd length or contains any unpaired
+    /// surrogates
+    pub fn from_utf16_bytes(
+        buf: &[u8],
+        from_bytes: fn([u8; 2]) -> u16,
+    ) -> Result<Self, Utf16Error> {
+        from_utf16_bytes(buf, from_bytes)
+    }
+
+    /// Converts a slice of bytes containing UTF-16 code units to a [`Repr`], using `from_bytes`
+    /// to read each code unit, replacing any unpaired surrogates, or a trailing odd byte, with
+    /// [`REPLACEMENT_CHARACTER`]
+    pub fn from_utf16_bytes_lossy(buf: &[u8], from_bytes: fn([u8; 2]) -> u16) -> Self {
+        from_utf16_bytes_lossy(buf, from_bytes)
+    }
+}
+
+/// The same decoders as [`Repr`], see above
+impl ThinRepr {
+    pub fn from_utf8_lossy(buf: &[u8]) -> Self {
+        from_utf8_lossy(buf)
+    }
+
+    pub fn from_utf16<I: IntoIterator<Item = u16>>(units: I) -> Result<Self, Utf16Error> {
+        from_utf16(units)
+    }
+
+    pub fn from_utf16_lossy<I: IntoIterator<Item = u16>>(units: I) -> Self {
+        from_utf16_lossy(units)
+    }
+
+    pub fn from_utf16_bytes(
+        buf: &[u8],
+        from_bytes: fn([u8; 2]) -> u16,
+    ) -> Result<Self, Utf16Error> {
+        from_utf16_bytes(buf, from_bytes)
+    }
+
+    pub fn from_utf16_bytes_lossy(buf: &[u8], from_bytes: fn([u8; 2]) -> u16) -> Self {
+        from_utf16_bytes_lossy(buf, from_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    use super::ThinRepr;
+
+    type Repr = super::Repr;
+
+    #[test]
+    fn test_from_utf8_lossy_short_is_inline() {
+        // note: short enough to be inlined on 32-bit architectures too
+        let repr = Repr::from_utf8_lossy(b"hi \xF0\x90\x80you");
+        assert_eq!(repr.as_str(), "hi \u{FFFD}you");
+        assert!(!repr.is_heap_allocated());
+    }
+
+    #[test]
+    fn test_from_utf8_lossy_grows_onto_heap() {
+        // each invalid byte becomes a 3 byte replacement character
+        let bytes = [0xFF; 10];
+        let repr = Repr::from_utf8_lossy(&bytes);
+
+        assert_eq!(repr.as_str(), String::from_utf8_lossy(&bytes));
+        assert!(repr.is_heap_allocated());
+    }
+
+    #[test]
+    fn test_from_utf8_lossy_truncated_char() {
+        let repr = Repr::from_utf8_lossy(b"abc\xF0\x9F\xA6");
+        assert_eq!(repr.as_str(), "abc\u{FFFD}");
+    }
+
+    #[test]
+    fn test_from_utf16_short_is_inline() {
+        let units: Vec<u16> = "hello 🦀".encode_utf16().collect();
+        let repr = Repr::from_utf16(units).unwrap();
+
+        assert_eq!(repr.as_str(), "hello 🦀");
+        assert!(!repr.is_heap_allocated());
+    }
+
+    #[test]
+    fn test_from_utf16_lone_surrogate() {
+        assert!(Repr::from_utf16([0x0061, 0xD800, 0x0062].iter().copied()).is_err());
+
+        let repr = Repr::from_utf16_lossy([0x0061, 0xD800, 0x0062].iter().copied());
+        assert_eq!(repr.as_str(), "a\u{FFFD}b");
+    }
+
+    #[test]
+    fn test_from_utf16_bytes_odd_length() {
+        let bytes = [b'a', 0, b'b'];
+
+        assert!(Repr::from_utf16_bytes(&bytes, u16::from_le_bytes).is_err());
+        let repr = Repr::from_utf16_bytes_lossy(&bytes, u16::from_le_bytes);
+        assert_eq!(repr.as_str(), "a\u{FFFD}");
+    }
+
+    #[test]
+    fn test_thin_decoders() {
+        let thin = ThinRepr::from_utf8_lossy(b"hi \xFF");
+        assert_eq!(thin.as_str(), "hi \u{FFFD}");
+        assert!(!thin.is_heap_allocated());
+
+        let units: Vec<u16> = "a longer string with a 🦀 in it".encode_utf16().collect();
+        let thin = ThinRepr::from_utf16(units.iter().copied()).unwrap();
+        assert_eq!(thin.as_str(), "a longer string with a 🦀 in it");
+        assert!(thin.is_heap_allocated());
+
+        let bytes = [b'a', 0, 0x00, 0xD8, b'b'];
+        assert!(ThinRepr::from_utf16_bytes(&bytes, u16::from_le_bytes).is_err());
+        let thin = ThinRepr::from_utf16_bytes_lossy(&bytes, u16::from_le_bytes);
+        assert_eq!(thin.as_str(), "a\u{FFFD}\u{FFFD}");
+    }
+}
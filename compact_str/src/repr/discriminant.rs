@@ -1,32 +1,121 @@
 use super::{
     HEAP_MASK,
-    MAX_SIZE,
+    STATIC_MASK,
 };
 
-const PADDING_SIZE: usize = MAX_SIZE - std::mem::size_of::<u8>();
-
 #[derive(Debug, Copy, Clone)]
 pub enum Discriminant {
     Heap,
     Inline,
+    Static,
 }
 
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
-pub struct DiscriminantMask {
-    _padding: [u8; PADDING_SIZE],
-    val: u8,
+macro_rules! last_byte {
+    ($($name:ident = $val:literal,)*) => {
+        /// Every valid value of the last byte of a `Repr`.
+        ///
+        /// The last byte is either the final byte of a full inline string, which can only be a
+        /// UTF-8 byte in the range `0..=191`, the length of a shorter inline string tagged with
+        /// `LENGTH_MASK`, `HEAP_MASK`, or `STATIC_MASK`. Because the remaining values aren't variants of this
+        /// enum, the compiler can use them as a niche, e.g. to represent `None` for an
+        /// `Option<CompactStr>`.
+        #[allow(dead_code)]
+        #[repr(u8)]
+        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        pub enum LastByte {
+            $($name = $val,)*
+            Static = STATIC_MASK,
+            Heap = HEAP_MASK,
+        }
+
+        impl LastByte {
+            /// Converts a `u8` into a [`LastByte`], usable in a `const` context.
+            ///
+            /// Note: `core::mem::transmute` isn't a `const fn` on our MSRV, which is why we need
+            /// to match on every possible value
+            #[inline]
+            pub const fn from_u8(byte: u8) -> Self {
+                match byte {
+                    $($val => LastByte::$name,)*
+                    STATIC_MASK => LastByte::Static,
+                    HEAP_MASK => LastByte::Heap,
+                    _ => {
+                        // HACK: This allows us to make assertions within a `const fn` without
+                        // requiring nightly, see unstable `const_panic` feature. This results in a
+                        // build failure, not a runtime panic
+                        #[allow(clippy::no_effect)]
+                        #[allow(unconditional_panic)]
+                        ["Provided byte is not a valid last byte of a Repr!"][42];
+                        LastByte::Heap
+                    }
+                }
+            }
+        }
+    };
+}
+
+last_byte! {
+    // The last byte of a full inline string
+    U0 = 0, U1 = 1, U2 = 2, U3 = 3, U4 = 4, U5 = 5, U6 = 6, U7 = 7,
+    U8 = 8, U9 = 9, U10 = 10, U11 = 11, U12 = 12, U13 = 13, U14 = 14, U15 = 15,
+    U16 = 16, U17 = 17, U18 = 18, U19 = 19, U20 = 20, U21 = 21, U22 = 22, U23 = 23,
+    U24 = 24, U25 = 25, U26 = 26, U27 = 27, U28 = 28, U29 = 29, U30 = 30, U31 = 31,
+    U32 = 32, U33 = 33, U34 = 34, U35 = 35, U36 = 36, U37 = 37, U38 = 38, U39 = 39,
+    U40 = 40, U41 = 41, U42 = 42, U43 = 43, U44 = 44, U45 = 45, U46 = 46, U47 = 47,
+    U48 = 48, U49 = 49, U50 = 50, U51 = 51, U52 = 52, U53 = 53, U54 = 54, U55 = 55,
+    U56 = 56, U57 = 57, U58 = 58, U59 = 59, U60 = 60, U61 = 61, U62 = 62, U63 = 63,
+    U64 = 64, U65 = 65, U66 = 66, U67 = 67, U68 = 68, U69 = 69, U70 = 70, U71 = 71,
+    U72 = 72, U73 = 73, U74 = 74, U75 = 75, U76 = 76, U77 = 77, U78 = 78, U79 = 79,
+    U80 = 80, U81 = 81, U82 = 82, U83 = 83, U84 = 84, U85 = 85, U86 = 86, U87 = 87,
+    U88 = 88, U89 = 89, U90 = 90, U91 = 91, U92 = 92, U93 = 93, U94 = 94, U95 = 95,
+    U96 = 96, U97 = 97, U98 = 98, U99 = 99, U100 = 100, U101 = 101, U102 = 102, U103 = 103,
+    U104 = 104, U105 = 105, U106 = 106, U107 = 107, U108 = 108, U109 = 109, U110 = 110, U111 = 111,
+    U112 = 112, U113 = 113, U114 = 114, U115 = 115, U116 = 116, U117 = 117, U118 = 118, U119 = 119,
+    U120 = 120, U121 = 121, U122 = 122, U123 = 123, U124 = 124, U125 = 125, U126 = 126, U127 = 127,
+    U128 = 128, U129 = 129, U130 = 130, U131 = 131, U132 = 132, U133 = 133, U134 = 134, U135 = 135,
+    U136 = 136, U137 = 137, U138 = 138, U139 = 139, U140 = 140, U141 = 141, U142 = 142, U143 = 143,
+    U144 = 144, U145 = 145, U146 = 146, U147 = 147, U148 = 148, U149 = 149, U150 = 150, U151 = 151,
+    U152 = 152, U153 = 153, U154 = 154, U155 = 155, U156 = 156, U157 = 157, U158 = 158, U159 = 159,
+    U160 = 160, U161 = 161, U162 = 162, U163 = 163, U164 = 164, U165 = 165, U166 = 166, U167 = 167,
+    U168 = 168, U169 = 169, U170 = 170, U171 = 171, U172 = 172, U173 = 173, U174 = 174, U175 = 175,
+    U176 = 176, U177 = 177, U178 = 178, U179 = 179, U180 = 180, U181 = 181, U182 = 182, U183 = 183,
+    U184 = 184, U185 = 185, U186 = 186, U187 = 187, U188 = 188, U189 = 189, U190 = 190, U191 = 191,
+    // The length of an inline string, tagged with `LENGTH_MASK`
+    L0 = 192, L1 = 193, L2 = 194, L3 = 195, L4 = 196, L5 = 197, L6 = 198, L7 = 199,
+    L8 = 200, L9 = 201, L10 = 202, L11 = 203, L12 = 204, L13 = 205, L14 = 206, L15 = 207,
+    L16 = 208, L17 = 209, L18 = 210, L19 = 211, L20 = 212, L21 = 213, L22 = 214, L23 = 215,
+    // Only a `Repr<N>` with an inline capacity `N` of more than `MAX_SIZE` bytes stores these lengths
+    L24 = 216, L25 = 217, L26 = 218, L27 = 219, L28 = 220, L29 = 221, L30 = 222, L31 = 223,
+    L32 = 224, L33 = 225, L34 = 226, L35 = 227, L36 = 228, L37 = 229, L38 = 230, L39 = 231,
+    L40 = 232, L41 = 233, L42 = 234, L43 = 235, L44 = 236, L45 = 237, L46 = 238, L47 = 239,
+    L48 = 240, L49 = 241, L50 = 242, L51 = 243, L52 = 244, L53 = 245, L54 = 246, L55 = 247,
 }
 
-impl DiscriminantMask {
+impl LastByte {
     #[inline(always)]
-    pub const fn discriminant(&self) -> Discriminant {
-        if self.val == HEAP_MASK {
-            Discriminant::Heap
-        } else {
-            Discriminant::Inline
+    pub const fn discriminant(self) -> Discriminant {
+        match self {
+            LastByte::Heap => Discriminant::Heap,
+            LastByte::Static => Discriminant::Static,
+            _ => Discriminant::Inline,
         }
     }
 }
 
-crate::asserts::assert_size_eq!(DiscriminantMask, String);
+crate::asserts::assert_size!(LastByte, 1);
+
+#[cfg(test)]
+mod tests {
+    use super::LastByte;
+    use crate::repr::{
+        HEAP_MASK,
+        STATIC_MASK,
+    };
+
+    #[test]
+    fn test_from_u8_roundtrips() {
+        for byte in (0..=247).chain([STATIC_MASK, HEAP_MASK].iter().copied()) {
+            assert_eq!(LastByte::from_u8(byte) as u8, byte);
+        }
+    }
+}
@@ -0,0 +1,347 @@
+//! An immutable "German string", used by [`GermanStr`](crate::GermanStr).
+//!
+//! Modeled after the string representation of the Umbra database, a [`GermanRepr`] stores its
+//! length and the first 4 bytes of the string, its prefix, before the rest of the string, which
+//! is either stored inline, or behind a pointer to the heap. Comparisons can usually be resolved
+//! by looking at the length and prefix, without dereferencing the heap.
+//!
+//! ```text
+//! inline: [ len<4> | prefix<4> | rest of the string, zero padded<8> ]
+//! heap:   [ len<4> | prefix<4> | ptr to the entire string<8>        ]
+//! ```
+//!
+//! This matches the layout of a view in Arrow's `StringView` array, except that instead of a
+//! buffer index and offset, we store a pointer.
+
+use alloc::boxed::Box;
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+
+/// The maximum number of bytes we can store inline
+pub const MAX_INLINE: usize = 12;
+/// The number of bytes of the string we store next to the length
+pub const PREFIX_SIZE: usize = 4;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+union Suffix {
+    // Bytes `4..12` of an inline string, zero padded
+    inline: [u8; MAX_INLINE - PREFIX_SIZE],
+    // A pointer to the bytes of a heap allocated string, including its prefix
+    heap: *const u8,
+}
+
+#[repr(C)]
+pub struct GermanRepr {
+    len: u32,
+    // The first 4 bytes of the string, zero padded
+    prefix: [u8; PREFIX_SIZE],
+    suffix: Suffix,
+}
+// SAFETY: A `GermanRepr` uniquely owns its heap allocation, which it never mutates
+unsafe impl Send for GermanRepr {}
+unsafe impl Sync for GermanRepr {}
+
+crate::asserts::assert_size!(GermanRepr, 16);
+
+impl GermanRepr {
+    #[inline]
+    pub fn new(text: &str) -> Self {
+        if text.len() <= MAX_INLINE {
+            GermanRepr::new_inline(text)
+        } else {
+            GermanRepr::from_box_str(Box::from(text))
+        }
+    }
+
+    /// Creates an inline [`GermanRepr`], usable in a `const` context
+    #[inline]
+    pub const fn new_inline(text: &str) -> Self {
+        let len = text.len();
+
+        if len > MAX_INLINE {
+            // HACK: This allows us to make assertions within a `const fn` without requiring
+            // nightly, see unstable `const_panic` feature. This results in a build
+            // failure, not a runtime panic
+            #[allow(clippy::no_effect)]
+            #[allow(unconditional_panic)]
+            ["Provided string has a length greater than MAX_INLINE!"][42];
+        }
+
+        // Note: for loops aren't allowed in `const fn`, hence the while
+        let mut buffer = [0u8; MAX_INLINE];
+        let mut i = 0;
+        while i < len {
+            buffer[i] = text.as_bytes()[i];
+            i += 1;
+        }
+
+        let prefix = [buffer[0], buffer[1], buffer[2], buffer[3]];
+        let inline = [
+            buffer[4], buffer[5], buffer[6], buffer[7], buffer[8], buffer[9], buffer[10],
+            buffer[11],
+        ];
+
+        GermanRepr {
+            len: len as u32,
+            prefix,
+            suffix: Suffix { inline },
+        }
+    }
+
+    /// Creates a [`GermanRepr`] from a [`Box<str>`], inlining the string if it's short enough,
+    /// otherwise re-using the heap allocated buffer
+    ///
+    /// # Panics
+    /// Panics if the string is longer than `u32::MAX` bytes
+    #[inline]
+    pub fn from_box_str(b: Box<str>) -> Self {
+        if b.len() <= MAX_INLINE {
+            return GermanRepr::new_inline(&b);
+        }
+
+        let len = u32::try_from(b.len()).expect("string is longer than u32::MAX bytes");
+        let bytes = b.as_bytes();
+        let prefix = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        let heap = Box::into_raw(b.into_boxed_bytes()) as *const u8;
+
+        GermanRepr {
+            len,
+            prefix,
+            suffix: Suffix { heap },
+        }
+    }
+
+    /// Converts this [`GermanRepr`] into a [`Box<str>`], re-using the heap allocated buffer if
+    /// there is one
+    #[inline]
+    pub fn into_box_str(self) -> Box<str> {
+        if !self.is_heap_allocated() {
+            return Box::from(self.as_str());
+        }
+
+        let this = core::mem::ManuallyDrop::new(self);
+        // SAFETY: We're heap allocated, so `heap` points to a `Box<str>` of `len` bytes, and we
+        // won't drop `self`, so ownership of the box moves to the caller
+        unsafe {
+            let bytes =
+                core::ptr::slice_from_raw_parts_mut(this.suffix.heap as *mut u8, this.len());
+            alloc::str::from_boxed_utf8_unchecked(Box::from_raw(bytes))
+        }
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    #[inline(always)]
+    pub fn prefix(&self) -> [u8; PREFIX_SIZE] {
+        self.prefix
+    }
+
+    #[inline(always)]
+    pub fn is_heap_allocated(&self) -> bool {
+        self.len() > MAX_INLINE
+    }
+
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        let ptr = if self.is_heap_allocated() {
+            // SAFETY: We're heap allocated, so the suffix is a pointer to the string
+            unsafe { self.suffix.heap }
+        } else {
+            // Note: We offset from `self` instead of borrowing `prefix`, so the pointer covers
+            // both the prefix and the inline suffix, which directly follows it
+            unsafe { (self as *const GermanRepr as *const u8).add(PREFIX_SIZE) }
+        };
+
+        // SAFETY: `ptr` points to `len` initialized bytes, which live as long as `self`
+        unsafe { core::slice::from_raw_parts(ptr, self.len()) }
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        // SAFETY: We only ever store valid UTF-8
+        unsafe { core::str::from_utf8_unchecked(self.as_bytes()) }
+    }
+
+    /// Returns `true` if this string starts with `prefix`, only dereferencing the heap if
+    /// `prefix` is longer than [`PREFIX_SIZE`]
+    #[inline]
+    pub fn starts_with(&self, prefix: &[u8]) -> bool {
+        if prefix.len() > self.len() {
+            return false;
+        }
+
+        let n = core::cmp::min(prefix.len(), PREFIX_SIZE);
+        if self.prefix[..n] != prefix[..n] {
+            return false;
+        }
+
+        prefix.len() <= PREFIX_SIZE || self.as_bytes().starts_with(prefix)
+    }
+
+    /// Returns `true` if this string has the same length and prefix as `other`, which is a
+    /// necessary but not sufficient condition for them to be equal
+    #[inline(always)]
+    fn len_and_prefix_eq(&self, len: usize, prefix: &[u8]) -> bool {
+        self.len() == len && self.prefix[..core::cmp::min(len, PREFIX_SIZE)] == *prefix
+    }
+
+    #[inline]
+    pub fn eq_str(&self, other: &str) -> bool {
+        let other = other.as_bytes();
+        let n = core::cmp::min(other.len(), PREFIX_SIZE);
+        self.len_and_prefix_eq(other.len(), &other[..n]) && self.as_bytes() == other
+    }
+
+    /// Encodes this string as a view of an Arrow `StringView` array.
+    ///
+    /// If the string is longer than [`MAX_INLINE`], the caller is responsible for storing the
+    /// string in the data buffer `buffer_index`, starting at `offset`.
+    #[inline]
+    pub fn to_view(&self, buffer_index: u32, offset: u32) -> u128 {
+        let mut view = [0u8; 16];
+        view[..4].copy_from_slice(&self.len.to_le_bytes());
+
+        if self.is_heap_allocated() {
+            view[4..8].copy_from_slice(&self.prefix);
+            view[8..12].copy_from_slice(&buffer_index.to_le_bytes());
+            view[12..16].copy_from_slice(&offset.to_le_bytes());
+        } else {
+            view[4..4 + self.len()].copy_from_slice(self.as_bytes());
+        }
+
+        u128::from_le_bytes(view)
+    }
+
+    /// Returns the bytes referenced by a view of an Arrow `StringView` array.
+    ///
+    /// # Panics
+    /// Panics if the view references a buffer or bytes that are out of bounds
+    #[inline]
+    pub fn view_bytes<'a, B: AsRef<[u8]>>(view: &'a [u8; 16], buffers: &'a [B]) -> &'a [u8] {
+        let len = u32::from_le_bytes([view[0], view[1], view[2], view[3]]) as usize;
+
+        if len <= MAX_INLINE {
+            &view[4..4 + len]
+        } else {
+            let buffer_index = u32::from_le_bytes([view[8], view[9], view[10], view[11]]);
+            let offset = u32::from_le_bytes([view[12], view[13], view[14], view[15]]) as usize;
+
+            let buffer = buffers[buffer_index as usize].as_ref();
+            let end = offset.checked_add(len).expect("view offset overflows");
+            &buffer[offset..end]
+        }
+    }
+}
+
+impl Clone for GermanRepr {
+    #[inline]
+    fn clone(&self) -> Self {
+        if self.is_heap_allocated() {
+            GermanRepr::from_box_str(Box::from(self.as_str()))
+        } else {
+            // SAFETY: An inline `GermanRepr` is only bytes, which we can copy
+            unsafe { core::ptr::read(self) }
+        }
+    }
+}
+
+impl Drop for GermanRepr {
+    fn drop(&mut self) {
+        if self.is_heap_allocated() {
+            // SAFETY: We're heap allocated, so `heap` points to a `Box<[u8]>` of `len` bytes
+            unsafe {
+                let bytes =
+                    core::ptr::slice_from_raw_parts_mut(self.suffix.heap as *mut u8, self.len());
+                drop(Box::from_raw(bytes));
+            }
+        }
+    }
+}
+
+impl PartialEq for GermanRepr {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        if self.len != other.len || self.prefix != other.prefix {
+            return false;
+        }
+
+        if self.is_heap_allocated() {
+            // We already know the prefixes are equal, so only compare the rest of the string
+            self.as_bytes()[PREFIX_SIZE..] == other.as_bytes()[PREFIX_SIZE..]
+        } else {
+            // SAFETY: Both strings are inline, and inline strings are zero padded
+            unsafe { self.suffix.inline == other.suffix.inline }
+        }
+    }
+}
+
+impl Eq for GermanRepr {}
+
+impl Ord for GermanRepr {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Note: Prefixes are zero padded, so if they differ, comparing them gives the same result
+        // as comparing the entire strings. If they're equal, we need to look at the whole string
+        match self.prefix.cmp(&other.prefix) {
+            Ordering::Equal => self.as_bytes().cmp(other.as_bytes()),
+            ordering => ordering,
+        }
+    }
+}
+
+impl PartialOrd for GermanRepr {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        GermanRepr,
+        MAX_INLINE,
+    };
+
+    #[test]
+    fn test_inline_is_zero_padded() {
+        let a = GermanRepr::new("hello");
+        let mut b = GermanRepr::new("hello world!");
+        assert!(!b.is_heap_allocated());
+
+        // equality of inline strings compares the padding, so make sure it's always zeroed
+        b = GermanRepr::new(&b.as_str()[..5]);
+        assert!(a == b);
+    }
+
+    #[test]
+    fn test_prefix_with_nul_bytes() {
+        let a = GermanRepr::new("ab");
+        let b = GermanRepr::new("ab\0");
+        let c = GermanRepr::new("ab\0\0this is heap allocated");
+
+        assert_eq!(a.prefix(), b.prefix());
+        assert!(a < b);
+        assert!(b < c);
+        assert!(a != b);
+    }
+
+    #[test]
+    fn test_box_str_roundtrip() {
+        let text = "a string that is heap allocated";
+        let boxed: alloc::boxed::Box<str> = text.into();
+        let ptr = boxed.as_ptr();
+
+        let repr = GermanRepr::from_box_str(boxed);
+        assert!(repr.len() > MAX_INLINE);
+        assert_eq!(repr.as_str().as_ptr(), ptr);
+
+        let boxed = repr.into_box_str();
+        assert_eq!(boxed.as_ptr(), ptr);
+        assert_eq!(&*boxed, text);
+    }
+}
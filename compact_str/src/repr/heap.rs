@@ -1,57 +1,478 @@
-// use super::arc::ArcString;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use super::arc::ArcString;
 use super::boxed::BoxString;
+use super::storage::{
+    Boxed,
+    HeapStorage,
+    Storage,
+};
+use crate::{
+    Allocator,
+    Global,
+    TryReserveError,
+};
+
+/// The heap allocated buffer of a [`Storage`], i.e. a [`BoxString`] or an [`ArcString`].
+///
+/// Note: A buffer is the size of a `String`, and stores the discriminant of a `Repr` in its last
+/// byte. The allocator we allocate from is owned by the `Repr`, and stored after its inline buffer,
+/// so every method that might allocate or deallocate takes the allocator of the `Repr`
+pub trait HeapBuffer<A: Allocator>: Sized {
+    /// Whether clones of this buffer share the same allocation
+    #[cfg(feature = "bytes")]
+    const SHARED: bool;
+
+    fn new(bytes: &[u8], alloc: &A) -> Self;
+    fn with_additional(bytes: &[u8], additional: usize, alloc: &A) -> Self;
+    fn try_with_additional(
+        bytes: &[u8],
+        additional: usize,
+        alloc: &A,
+    ) -> Result<Self, TryReserveError>;
+    fn try_with_capacity(capacity: usize, alloc: &A) -> Result<Self, TryReserveError>;
+    fn clone_in(&self, alloc: &A) -> Self;
+
+    /// Returns a buffer of the bytes `start..end`, which shares our underlying buffer, or `None`
+    /// if we can't share it
+    fn slice(&self, start: usize, end: usize) -> Option<Self>;
+
+    fn len(&self) -> usize;
+    fn capacity(&self) -> usize;
+    fn as_str(&self) -> &str;
+    fn as_slice(&self) -> &[u8];
+
+    /// # Safety
+    /// * We must have been allocated from `alloc`
+    unsafe fn push(&mut self, ch: char, alloc: &A);
+    /// # Safety
+    /// * We must have been allocated from `alloc`
+    unsafe fn extend<I: Iterator<Item = char>>(&mut self, iter: I, alloc: &A);
+    /// # Safety
+    /// * We must have been allocated from `alloc`
+    unsafe fn shrink_to(&mut self, min_capacity: usize, alloc: &A);
+    /// # Safety
+    /// * We must have been allocated from `alloc`, and must never be used again
+    unsafe fn dealloc(&mut self, alloc: &A);
+
+    /// # Safety
+    /// * The caller must guarantee any modifications made to the buffer are valid UTF-8
+    unsafe fn make_mut_slice(&mut self) -> &mut [u8];
+    /// # Safety
+    /// * The caller must guarantee any modifications made to the buffer are valid UTF-8
+    unsafe fn try_make_mut_slice(&mut self) -> Result<&mut [u8], TryReserveError>;
+    /// # Safety
+    /// * The first `length` bytes of the buffer must be initialized, valid UTF-8
+    unsafe fn set_len(&mut self, length: usize);
 
-#[repr(C)]
-#[derive(Debug, Clone)]
-pub struct HeapString {
-    pub string: BoxString,
+    /// # Safety
+    /// * Memory allocated by the [`Global`] allocator must be deallocatable by `alloc`
+    unsafe fn from_string(s: String, alloc: &A) -> Self;
+    /// # Safety
+    /// * Memory allocated by the [`Global`] allocator must be deallocatable by `alloc`
+    unsafe fn from_box_str(b: Box<str>, alloc: &A) -> Self;
+    /// # Safety
+    /// * Memory allocated by the [`Global`] allocator must be deallocatable by `alloc`
+    unsafe fn from_vec(v: Vec<u8>, alloc: &A) -> Self;
+    /// # Safety
+    /// * We must have been allocated from `alloc`, which must be the [`Global`] allocator
+    unsafe fn into_string(self, alloc: &A) -> String;
+    /// # Safety
+    /// * We must have been allocated from `alloc`, which must be the [`Global`] allocator
+    unsafe fn into_vec(self, alloc: &A) -> Vec<u8>;
 }
 
-impl HeapString {
-    /// Creates a [`HeapString`] from the provided `text`.
+impl<A: Allocator> HeapBuffer<A> for BoxString {
+    #[cfg(feature = "bytes")]
+    const SHARED: bool = false;
+
+    #[inline]
+    fn new(bytes: &[u8], alloc: &A) -> Self {
+        BoxString::new_in(bytes, alloc)
+    }
+
+    #[inline]
+    fn with_additional(bytes: &[u8], additional: usize, alloc: &A) -> Self {
+        BoxString::with_additional_in(bytes, additional, alloc)
+    }
+
+    #[inline]
+    fn try_with_additional(
+        bytes: &[u8],
+        additional: usize,
+        alloc: &A,
+    ) -> Result<Self, TryReserveError> {
+        BoxString::try_with_additional_in(bytes, additional, alloc)
+    }
+
+    #[inline]
+    fn try_with_capacity(capacity: usize, alloc: &A) -> Result<Self, TryReserveError> {
+        BoxString::try_with_capacity_in(capacity, alloc)
+    }
+
+    #[inline]
+    fn clone_in(&self, alloc: &A) -> Self {
+        BoxString::clone_in(self, alloc)
+    }
+
+    #[inline]
+    fn slice(&self, _start: usize, _end: usize) -> Option<Self> {
+        None
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        BoxString::len(self)
+    }
+
+    #[inline]
+    fn capacity(&self) -> usize {
+        BoxString::capacity(self)
+    }
+
+    #[inline]
+    fn as_str(&self) -> &str {
+        BoxString::as_str(self)
+    }
+
+    #[inline]
+    fn as_slice(&self) -> &[u8] {
+        BoxString::as_slice(self)
+    }
+
+    #[inline]
+    unsafe fn push(&mut self, ch: char, alloc: &A) {
+        BoxString::push(self, ch, alloc)
+    }
+
+    #[inline]
+    unsafe fn extend<I: Iterator<Item = char>>(&mut self, iter: I, alloc: &A) {
+        let (lower_bound, _) = iter.size_hint();
+        self.reserve(lower_bound, alloc);
+        iter.for_each(|ch| BoxString::push(self, ch, alloc));
+    }
+
+    #[inline]
+    unsafe fn shrink_to(&mut self, min_capacity: usize, alloc: &A) {
+        BoxString::shrink_to(self, min_capacity, alloc)
+    }
+
+    #[inline]
+    unsafe fn dealloc(&mut self, alloc: &A) {
+        BoxString::dealloc(self, alloc)
+    }
+
+    #[inline]
+    unsafe fn make_mut_slice(&mut self) -> &mut [u8] {
+        self.as_mut_slice()
+    }
+
+    #[inline]
+    unsafe fn try_make_mut_slice(&mut self) -> Result<&mut [u8], TryReserveError> {
+        Ok(self.as_mut_slice())
+    }
+
+    #[inline]
+    unsafe fn set_len(&mut self, length: usize) {
+        BoxString::set_len(self, length)
+    }
+
+    #[inline]
+    unsafe fn from_string(s: String, _alloc: &A) -> Self {
+        BoxString::from_string(s)
+    }
+
+    #[inline]
+    unsafe fn from_box_str(b: Box<str>, _alloc: &A) -> Self {
+        BoxString::from_box_str(b)
+    }
+
+    #[inline]
+    unsafe fn from_vec(v: Vec<u8>, _alloc: &A) -> Self {
+        BoxString::from_vec(v)
+    }
+
+    #[inline]
+    unsafe fn into_string(self, _alloc: &A) -> String {
+        BoxString::into_string(self)
+    }
+
+    #[inline]
+    unsafe fn into_vec(self, _alloc: &A) -> Vec<u8> {
+        BoxString::into_vec(self)
+    }
+}
+
+// Note: An `ArcString` stores a copy of our allocator next to the string data, so it can
+// deallocate the buffer when the last reference to it gets dropped, and ignores the allocator of
+// the `Repr`. It also stores its reference count next to the string data, so unlike a
+// `BoxString`, it can't re-use the buffer of a `String`, `Box<str>`, or `Vec<u8>`, and copies
+impl<A: Allocator> HeapBuffer<A> for ArcString<A> {
+    #[cfg(feature = "bytes")]
+    const SHARED: bool = true;
+
+    #[inline]
+    fn new(bytes: &[u8], alloc: &A) -> Self {
+        ArcString::new(bytes, 0, alloc.clone())
+    }
+
+    #[inline]
+    fn with_additional(bytes: &[u8], additional: usize, alloc: &A) -> Self {
+        ArcString::new(bytes, additional, alloc.clone())
+    }
+
+    #[inline]
+    fn try_with_additional(
+        bytes: &[u8],
+        additional: usize,
+        alloc: &A,
+    ) -> Result<Self, TryReserveError> {
+        ArcString::try_new(bytes, additional, alloc.clone())
+    }
+
+    #[inline]
+    fn try_with_capacity(capacity: usize, alloc: &A) -> Result<Self, TryReserveError> {
+        ArcString::try_with_capacity(capacity, alloc.clone())
+    }
+
+    #[inline]
+    fn clone_in(&self, _alloc: &A) -> Self {
+        self.clone()
+    }
+
+    #[inline]
+    fn slice(&self, start: usize, end: usize) -> Option<Self> {
+        ArcString::slice(self, start, end)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        ArcString::len(self)
+    }
+
+    #[inline]
+    fn capacity(&self) -> usize {
+        ArcString::capacity(self)
+    }
+
+    #[inline]
+    fn as_str(&self) -> &str {
+        ArcString::as_str(self)
+    }
+
+    #[inline]
+    fn as_slice(&self) -> &[u8] {
+        ArcString::as_slice(self)
+    }
+
+    #[inline]
+    unsafe fn push(&mut self, ch: char, _alloc: &A) {
+        ArcString::push(self, ch)
+    }
+
+    #[inline]
+    unsafe fn extend<I: Iterator<Item = char>>(&mut self, iter: I, _alloc: &A) {
+        Extend::extend(self, iter)
+    }
+
+    #[inline]
+    unsafe fn shrink_to(&mut self, min_capacity: usize, _alloc: &A) {
+        ArcString::shrink_to(self, min_capacity)
+    }
+
+    #[inline]
+    unsafe fn dealloc(&mut self, _alloc: &A) {
+        // Drops our reference to the underlying buffer
+        core::ptr::drop_in_place(self)
+    }
+
+    #[inline]
+    unsafe fn make_mut_slice(&mut self) -> &mut [u8] {
+        // Note: If we share our underlying buffer with another `ArcString`, this will copy it
+        ArcString::make_mut_slice(self)
+    }
+
+    #[inline]
+    unsafe fn try_make_mut_slice(&mut self) -> Result<&mut [u8], TryReserveError> {
+        ArcString::try_make_mut_slice(self)
+    }
+
+    #[inline]
+    unsafe fn set_len(&mut self, length: usize) {
+        ArcString::set_len(self, length)
+    }
+
+    #[inline]
+    unsafe fn from_string(s: String, alloc: &A) -> Self {
+        HeapBuffer::new(s.as_bytes(), alloc)
+    }
+
+    #[inline]
+    unsafe fn from_box_str(b: Box<str>, alloc: &A) -> Self {
+        HeapBuffer::new(b.as_bytes(), alloc)
+    }
+
+    #[inline]
+    unsafe fn from_vec(v: Vec<u8>, alloc: &A) -> Self {
+        HeapBuffer::new(&v, alloc)
+    }
+
+    #[inline]
+    unsafe fn into_string(self, _alloc: &A) -> String {
+        String::from(self.as_str())
+    }
+
+    #[inline]
+    unsafe fn into_vec(self, _alloc: &A) -> Vec<u8> {
+        self.as_slice().to_vec()
+    }
+}
+
+/// The first `MAX_SIZE` bytes of a heap allocated `Repr`, i.e. the [`HeapBuffer`] of the
+/// [`Storage`] `S`
+#[repr(transparent)]
+pub struct HeapString<A: Allocator = Global, S: Storage<A> = Boxed> {
+    pub string: <S as HeapStorage<A>>::Buffer,
+}
+
+impl<A: Allocator, S: Storage<A>> HeapString<A, S> {
+    /// Whether clones of a [`HeapString`] share the same underlying buffer
+    #[cfg(feature = "bytes")]
+    pub const SHARED: bool = <<S as HeapStorage<A>>::Buffer as HeapBuffer<A>>::SHARED;
+
+    /// Creates a [`HeapString`] from the provided `bytes`.
     ///
     /// If you need to allocate a [`HeapString`] with additional capacity, see
     /// `HeapString::with_additional()`
     #[inline]
-    pub fn new(text: &str) -> Self {
-        let string = BoxString::new(text);
+    pub fn new(bytes: &[u8], alloc: &A) -> Self {
+        let string = HeapBuffer::new(bytes, alloc);
         HeapString { string }
     }
 
-    /// Creates a [`HeapString`] from the provided `text` and allocates the underlying buffer with
+    /// Creates a [`HeapString`] from the provided `bytes` and allocates the underlying buffer with
     /// `additional` capacity
     #[inline]
-    pub fn with_additional(text: &str, additional: usize) -> Self {
-        let string = BoxString::with_additional(text, additional);
+    pub fn with_additional(bytes: &[u8], additional: usize, alloc: &A) -> Self {
+        let string = HeapBuffer::with_additional(bytes, additional, alloc);
         HeapString { string }
     }
 
-    /// Creates a [`HeapString`] with the provided capacity.
+    /// Fallible version of `HeapString::with_additional()`
     #[inline]
-    pub fn with_capacity(capacity: usize) -> Self {
-        let string = BoxString::with_capacity(capacity);
-        HeapString { string }
+    pub fn try_with_additional(
+        bytes: &[u8],
+        additional: usize,
+        alloc: &A,
+    ) -> Result<Self, TryReserveError> {
+        let string = HeapBuffer::try_with_additional(bytes, additional, alloc)?;
+        Ok(HeapString { string })
     }
 
+    /// Creates a [`HeapString`] with the provided capacity, returning an error if the capacity
+    /// overflows or the allocator fails
     #[inline]
-    pub fn from_string(s: String) -> Self {
-        let string = BoxString::from_string(s);
-        HeapString { string }
+    pub fn try_with_capacity(capacity: usize, alloc: &A) -> Result<Self, TryReserveError> {
+        let string = HeapBuffer::try_with_capacity(capacity, alloc)?;
+        Ok(HeapString { string })
     }
 
+    /// Creates a copy of this [`HeapString`] allocated from `alloc`, or for [`Shared`] storage
+    /// one that shares our underlying buffer
+    ///
+    /// [`Shared`]: super::storage::Shared
     #[inline]
-    pub fn from_box_str(b: Box<str>) -> Self {
-        let string = BoxString::from_box_str(b);
+    pub fn clone_in(&self, alloc: &A) -> Self {
+        let string = self.string.clone_in(alloc);
         HeapString { string }
     }
 
+    /// Returns a [`HeapString`] of the bytes `start..end`, which shares our underlying buffer, or
+    /// `None` if our storage can't share it, or the substring can't point into our buffer
+    #[inline]
+    pub fn slice(&self, start: usize, end: usize) -> Option<Self> {
+        let string = self.string.slice(start, end)?;
+        Some(HeapString { string })
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.string.len()
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.string.capacity()
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.string.as_str()
+    }
+
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        self.string.as_slice()
+    }
+
+    /// Appends `ch`, growing or copying the underlying buffer if needed
+    ///
+    /// # Safety
+    /// * We must have been allocated from `alloc`
+    #[inline]
+    pub unsafe fn push(&mut self, ch: char, alloc: &A) {
+        self.string.push(ch, alloc)
+    }
+
+    /// Appends every char of `iter`, growing or copying the underlying buffer if needed
+    ///
+    /// # Safety
+    /// * We must have been allocated from `alloc`
+    #[inline]
+    pub unsafe fn extend<I: Iterator<Item = char>>(&mut self, iter: I, alloc: &A) {
+        self.string.extend(iter, alloc)
+    }
+
+    /// Shrinks the capacity of the underlying buffer to `max(len, min_capacity)` bytes
+    ///
+    /// # Safety
+    /// * We must have been allocated from `alloc`
+    #[inline]
+    pub unsafe fn shrink_to(&mut self, min_capacity: usize, alloc: &A) {
+        self.string.shrink_to(min_capacity, alloc)
+    }
+
+    /// Deallocates, or drops our reference to, the underlying buffer, after which this
+    /// [`HeapString`] must never be used again
+    ///
+    /// # Safety
+    /// * We must have been allocated from `alloc`
+    #[inline]
+    pub unsafe fn dealloc(&mut self, alloc: &A) {
+        self.string.dealloc(alloc)
+    }
+
     /// Makes a mutable reference to the underlying buffer.
     ///
+    /// Note: If we share our underlying buffer, this will copy it
+    ///
     /// # Invariants
     /// * Please see `super::Repr` for all invariants
     #[inline]
     pub unsafe fn make_mut_slice(&mut self) -> &mut [u8] {
-        self.string.as_mut_slice()
+        self.string.make_mut_slice()
+    }
+
+    /// Fallible version of `HeapString::make_mut_slice()`, which returns an error if we share our
+    /// underlying buffer and fail to copy it
+    ///
+    /// # Invariants
+    /// * Please see `super::Repr` for all invariants
+    #[inline]
+    pub unsafe fn try_make_mut_slice(&mut self) -> Result<&mut [u8], TryReserveError> {
+        self.string.try_make_mut_slice()
     }
 
     #[inline]
@@ -60,4 +481,51 @@ impl HeapString {
     }
 }
 
+impl<S: Storage<Global>> HeapString<Global, S> {
+    /// Creates a [`HeapString`] from a [`String`], re-using its buffer if our storage can
+    #[inline]
+    pub fn from_string(s: String) -> Self {
+        // SAFETY: `s` was allocated from the `Global` allocator
+        let string = unsafe { HeapBuffer::from_string(s, &Global) };
+        HeapString { string }
+    }
+
+    /// Creates a [`HeapString`] from a [`Box<str>`], re-using its buffer if our storage can
+    #[inline]
+    pub fn from_box_str(b: Box<str>) -> Self {
+        // SAFETY: `b` was allocated from the `Global` allocator
+        let string = unsafe { HeapBuffer::from_box_str(b, &Global) };
+        HeapString { string }
+    }
+
+    /// Creates a [`HeapString`] from a [`Vec<u8>`], re-using its buffer if our storage can
+    #[inline]
+    pub fn from_vec(v: Vec<u8>) -> Self {
+        // SAFETY: `v` was allocated from the `Global` allocator
+        let string = unsafe { HeapBuffer::from_vec(v, &Global) };
+        HeapString { string }
+    }
+
+    /// Converts the [`HeapString`] into a [`String`], re-using the underlying buffer if possible
+    #[inline]
+    pub fn into_string(self) -> String {
+        // SAFETY: A `HeapString<Global, S>` is allocated from the `Global` allocator
+        unsafe { self.string.into_string(&Global) }
+    }
+
+    /// Converts the [`HeapString`] into a [`Vec<u8>`], re-using the underlying buffer if possible
+    #[inline]
+    pub fn into_vec(self) -> Vec<u8> {
+        // SAFETY: A `HeapString<Global, S>` is allocated from the `Global` allocator
+        unsafe { self.string.into_vec(&Global) }
+    }
+}
+
+impl<A: Allocator, S: Storage<A>> core::fmt::Debug for HeapString<A, S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
 crate::asserts::assert_size_eq!(HeapString, String);
+crate::asserts::assert_size_eq!(HeapString<Global, super::storage::Shared>, String);
@@ -1,37 +1,44 @@
+use alloc::string::String;
+
 use super::MAX_SIZE;
 
-const LENGTH_MASK: u8 = 0b11000000;
+pub const LENGTH_MASK: u8 = 0b11000000;
 
+/// A string of up to `N` bytes, stored inline.
+///
+/// When the string is shorter than `N` bytes, the last byte stores its length tagged with
+/// `LENGTH_MASK`, otherwise the last byte is the final byte of the string, which as UTF-8 is never
+/// a tagged length.
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
-pub struct InlineString {
-    buffer: [u8; MAX_SIZE],
+pub struct InlineString<const N: usize> {
+    buffer: [u8; N],
 }
 
-impl InlineString {
+impl<const N: usize> InlineString<N> {
     #[inline]
-    pub fn new(text: &str) -> Self {
-        debug_assert!(text.len() <= MAX_SIZE);
+    pub fn new(bytes: &[u8]) -> Self {
+        debug_assert!(bytes.len() <= N);
 
-        let len = text.len();
-        let mut buffer = [0u8; MAX_SIZE];
+        let len = bytes.len();
+        let mut buffer = [0u8; N];
 
         // set the length
-        buffer[MAX_SIZE - 1] = len as u8 | LENGTH_MASK;
+        buffer[N - 1] = len as u8 | LENGTH_MASK;
 
-        // copy the string
+        // copy the bytes
         //
-        // note: in the case where len == MAX_SIZE, we'll overwrite the len, but that's okay because
-        // when reading the length we can detect that the last byte is part of UTF-8 and return a
-        // length of MAX_SIZE
-        unsafe { std::ptr::copy_nonoverlapping(text.as_ptr(), buffer.as_mut_ptr(), len) };
+        // note: in the case where len == N, we'll overwrite the len, but that's okay because when
+        // reading the length we can detect that the last byte is part of UTF-8 and return a length
+        // of N
+        unsafe { core::ptr::copy_nonoverlapping(bytes.as_ptr(), buffer.as_mut_ptr(), len) };
 
         InlineString { buffer }
     }
 
     #[inline]
     pub const fn new_const(text: &str) -> Self {
-        if text.len() > MAX_SIZE {
+        if text.len() > N {
             // HACK: This allows us to make assertions within a `const fn` without requiring
             // nightly, see unstable `const_panic` feature. This results in a build
             // failure, not a runtime panic
@@ -41,10 +48,10 @@ impl InlineString {
         }
 
         let len = text.len();
-        let mut buffer = [0u8; MAX_SIZE];
+        let mut buffer = [0u8; N];
 
         // set the length
-        buffer[MAX_SIZE - 1] = len as u8 | LENGTH_MASK;
+        buffer[N - 1] = len as u8 | LENGTH_MASK;
 
         // Note: for loops aren't allowed in `const fn`, hence the while
         let mut i = 0;
@@ -56,30 +63,43 @@ impl InlineString {
         InlineString { buffer }
     }
 
+    /// Consumes the [`InlineString`] returning the underlying buffer
+    #[inline]
+    pub const fn into_buffer(self) -> [u8; N] {
+        self.buffer
+    }
+
     /// Creates an [`InlineString`] from raw parts without checking that it's valid UTF-8
     #[inline]
-    pub const unsafe fn from_parts(len: usize, mut buffer: [u8; MAX_SIZE]) -> Self {
-        if len != MAX_SIZE {
-            buffer[MAX_SIZE - 1] = len as u8 | LENGTH_MASK;
+    pub const unsafe fn from_parts(len: usize, mut buffer: [u8; N]) -> Self {
+        if len != N {
+            buffer[N - 1] = len as u8 | LENGTH_MASK;
         }
         InlineString { buffer }
     }
 
     #[inline(always)]
     pub fn len(&self) -> usize {
-        let last_byte = self.buffer[MAX_SIZE - 1];
-        core::cmp::min((last_byte.wrapping_sub(LENGTH_MASK)) as usize, MAX_SIZE)
+        let last_byte = self.buffer[N - 1];
+        // Note: the final byte of a full string is in the range `0..=191`, which wraps around to at
+        // least 64, so this is `N` as long as `N <= 64`
+        core::cmp::min((last_byte.wrapping_sub(LENGTH_MASK)) as usize, N)
     }
 
     #[inline]
     pub const fn capacity(&self) -> usize {
-        MAX_SIZE
+        N
     }
 
     #[inline]
     pub fn as_str(&self) -> &str {
-        // SAFETY: You can only construct an InlineString via a &str
-        unsafe { ::std::str::from_utf8_unchecked(&self.as_slice()[..self.len()]) }
+        // SAFETY: A `Repr` is only used as a `str` if its bytes are valid UTF-8
+        unsafe { core::str::from_utf8_unchecked(self.as_bytes()) }
+    }
+
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buffer[..self.len()]
     }
 
     #[inline]
@@ -98,18 +118,17 @@ impl InlineString {
 
     #[inline]
     pub unsafe fn set_len(&mut self, length: usize) {
-        debug_assert!(length <= MAX_SIZE);
+        debug_assert!(length <= N);
 
-        // If `length` == MAX_SIZE, then we infer the length to be the capacity of the buffer. We
-        // can infer this because the way we encode length doesn't overlap with any valid UTF-8
-        // bytes
-        if length < MAX_SIZE {
-            self.buffer[MAX_SIZE - 1] = length as u8 | LENGTH_MASK;
+        // If `length` == N, then we infer the length to be the capacity of the buffer. We can infer
+        // this because the way we encode length doesn't overlap with any valid UTF-8 bytes
+        if length < N {
+            self.buffer[N - 1] = length as u8 | LENGTH_MASK;
         }
     }
 }
 
-crate::asserts::assert_size_eq!(InlineString, String);
+crate::asserts::assert_size_eq!(InlineString<MAX_SIZE>, String);
 
 #[cfg(test)]
 mod tests {
@@ -127,7 +146,7 @@ mod tests {
     #[test]
     fn test_sanity() {
         let hello = "hello world!";
-        let inline = InlineString::new(hello);
+        let inline = InlineString::<MAX_SIZE>::new(hello.as_bytes());
 
         assert_eq!(inline.as_str(), hello);
         assert_eq!(inline.len(), hello.len());
@@ -137,7 +156,7 @@ mod tests {
     #[proptest]
     #[cfg_attr(miri, ignore)]
     fn test_roundtrip(#[strategy(rand_unicode_with_max_len(MAX_SIZE))] s: String) {
-        let inline = InlineString::new(&s);
+        let inline = InlineString::<MAX_SIZE>::new(s.as_bytes());
 
         prop_assert_eq!(inline.len(), s.len());
         prop_assert_eq!(inline.as_str(), s);
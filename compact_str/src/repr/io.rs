@@ -0,0 +1,191 @@
+use std::io::{
+    self,
+    BufRead,
+    ErrorKind,
+    Read,
+};
+
+use super::{
+    Repr,
+    MAX_SIZE,
+};
+
+impl Repr {
+    /// Reads all of the bytes from `reader` and appends them to a [`Repr`], returning how many
+    /// bytes were read, or an error if they're not valid UTF-8.
+    ///
+    /// If this returns an error the [`Repr`] is left unchanged, but bytes might have been consumed
+    /// from `reader`
+    pub fn read_to_end_utf8<R: Read + ?Sized>(&mut self, reader: &mut R) -> io::Result<usize> {
+        let mut appender = Utf8Appender::new(self);
+
+        // The spare capacity of a heap allocated buffer might not be initialized, so before we
+        // hand it to `reader` we zero it. This tracks how much of it we've already zeroed
+        let mut initialized = appender.repr.len();
+
+        loop {
+            let len = appender.repr.len();
+            // SAFETY: `Utf8Appender` checks all of the bytes we write are valid UTF-8
+            let spare = unsafe { appender.repr.spare_capacity_mut() };
+
+            let initialized_spare = initialized.saturating_sub(len);
+            if initialized_spare < spare.len() {
+                for byte in &mut spare[initialized_spare..] {
+                    *byte = 0;
+                }
+                initialized = len + spare.len();
+            }
+
+            match reader.read(spare) {
+                Ok(0) => return appender.finish(),
+                Ok(n) => {
+                    assert!(
+                        n <= spare.len(),
+                        "`Read::read` returned more bytes than the buffer"
+                    );
+                    // SAFETY: `reader` just wrote `n` bytes into our spare capacity
+                    unsafe { appender.repr.set_len(len + n) };
+                    appender.check();
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Reads bytes from `reader` up to and including the next `\n`, and appends them to a
+    /// [`Repr`], returning how many bytes were read, or an error if they're not valid UTF-8.
+    ///
+    /// If this returns an error the [`Repr`] is left unchanged, but bytes might have been consumed
+    /// from `reader`
+    pub fn read_line_utf8<R: BufRead + ?Sized>(&mut self, reader: &mut R) -> io::Result<usize> {
+        let mut appender = Utf8Appender::new(self);
+
+        loop {
+            let (done, used) = {
+                let available = match reader.fill_buf() {
+                    Ok(available) => available,
+                    Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e),
+                };
+
+                match available.iter().position(|b| *b == b'\n') {
+                    Some(i) => {
+                        appender.push(&available[..=i]);
+                        (true, i + 1)
+                    }
+                    None => {
+                        appender.push(available);
+                        (available.is_empty(), available.len())
+                    }
+                }
+            };
+            reader.consume(used);
+
+            if done {
+                return appender.finish();
+            }
+        }
+    }
+}
+
+/// Appends bytes to a [`Repr`], checking that they're valid UTF-8 as they're written.
+///
+/// If the bytes aren't valid UTF-8, or we return early, e.g. because of an error or a panic, the
+/// [`Repr`] gets reset when this is dropped
+struct Utf8Appender<'a> {
+    repr: &'a mut Repr,
+    /// The length of the [`Repr`] before we appended anything
+    start: usize,
+    /// How many bytes of the [`Repr`] we've checked are valid UTF-8
+    checked: usize,
+    /// Whether we've found bytes that aren't valid UTF-8
+    invalid: bool,
+    /// Whether the [`Repr`] was inline before we appended anything
+    was_inline: bool,
+    /// Whether all of the appended bytes are valid UTF-8, and should be kept
+    done: bool,
+}
+
+impl<'a> Utf8Appender<'a> {
+    fn new(repr: &'a mut Repr) -> Self {
+        let start = repr.len();
+        let was_inline = !repr.is_heap_allocated();
+
+        Utf8Appender {
+            repr,
+            start,
+            checked: start,
+            invalid: false,
+            was_inline,
+            done: false,
+        }
+    }
+
+    /// Copies `bytes` into the [`Repr`], and checks them
+    fn push(&mut self, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            let len = self.repr.len();
+            // SAFETY: We check below that the bytes we write are valid UTF-8
+            let spare = unsafe { self.repr.spare_capacity_mut() };
+
+            let count = core::cmp::min(spare.len(), bytes.len());
+            spare[..count].copy_from_slice(&bytes[..count]);
+            // SAFETY: We just wrote `count` bytes into our spare capacity
+            unsafe { self.repr.set_len(len + count) };
+
+            bytes = &bytes[count..];
+        }
+        self.check();
+    }
+
+    /// Checks that the bytes written since the last check are valid UTF-8.
+    ///
+    /// Note: a character can be split across two writes, so we only check up to the start of a
+    /// trailing incomplete character, which gets checked again with the next write
+    fn check(&mut self) {
+        if self.invalid {
+            return;
+        }
+
+        let len = self.repr.len();
+        match core::str::from_utf8(&self.repr.as_slice()[self.checked..len]) {
+            Ok(_) => self.checked = len,
+            Err(e) => {
+                self.checked += e.valid_up_to();
+                // `error_len()` is `None` if we ended in the middle of a character
+                self.invalid = e.error_len().is_some();
+            }
+        }
+    }
+
+    fn finish(mut self) -> io::Result<usize> {
+        let len = self.repr.len();
+
+        // Note: if we haven't checked everything, we ended in the middle of a character
+        if self.invalid || self.checked != len {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "stream did not contain valid UTF-8",
+            ));
+        }
+
+        self.done = true;
+        Ok(len - self.start)
+    }
+}
+
+impl Drop for Utf8Appender<'_> {
+    fn drop(&mut self) {
+        if !self.done {
+            // SAFETY: The first `start` bytes of the `Repr` were valid UTF-8 before we appended to
+            // it, and we don't touch them
+            unsafe { self.repr.set_len(self.start) };
+        }
+
+        // We might have moved onto the heap to avoid writing the last byte of an inline string
+        if self.was_inline && self.repr.len() <= MAX_SIZE {
+            self.repr.shrink_to_fit();
+        }
+    }
+}
@@ -1,37 +1,42 @@
 //! Implementations of the [`FromIterator`] trait to make building `CompactStr`s more ergonomic
 
+use alloc::boxed::Box;
+use alloc::string::String;
 use core::iter::FromIterator;
-use core::mem::ManuallyDrop;
 
 use super::{
     HeapString,
+    InlineCapacity,
     InlineString,
     Repr,
-    MAX_SIZE,
+    Storage,
+    ValidInlineCapacity,
 };
+use crate::Global;
 
-impl FromIterator<char> for Repr {
+impl<const N: usize, S: Storage<Global>> FromIterator<char> for Repr<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn from_iter<T: IntoIterator<Item = char>>(iter: T) -> Self {
         let mut iter = iter.into_iter();
 
         // If the size hint indicates we can't store this inline, then create a heap string
         let (size_hint, _) = iter.size_hint();
-        if size_hint > MAX_SIZE {
+        if size_hint > N {
             let heap = HeapString::from_string(iter.collect());
-            return Repr {
-                heap: ManuallyDrop::new(heap),
-            };
+            return Repr::from_heap(heap, Global);
         }
 
         // Otherwise, continuously pull chars from the iterator
         let mut curr_len = 0;
-        let mut inline_buf = [0u8; MAX_SIZE];
+        let mut inline_buf = [0u8; N];
         while let Some(c) = iter.next() {
             let char_len = c.len_utf8();
 
             // If this new character is too large to fit into the inline buffer, then create a heap
             // string
-            if char_len + curr_len > MAX_SIZE {
+            if char_len + curr_len > N {
                 let (min_remaining, _) = iter.size_hint();
                 let mut heap_buf = String::with_capacity(char_len + curr_len + min_remaining);
 
@@ -45,9 +50,7 @@ impl FromIterator<char> for Repr {
                 heap_buf.extend(iter);
 
                 let heap = HeapString::from_string(heap_buf);
-                return Repr {
-                    heap: ManuallyDrop::new(heap),
-                };
+                return Repr::from_heap(heap, Global);
             }
 
             // write the current char into a slice of the unoccupied space
@@ -57,22 +60,27 @@ impl FromIterator<char> for Repr {
 
         // SAFETY: We know `inline_buf` is valid UTF-8 because it consists entriely of `char`s
         let inline = unsafe { InlineString::from_parts(curr_len, inline_buf) };
-        Repr { inline }
+        Repr::from_inline(inline, Global)
     }
 }
 
-impl<'a> FromIterator<&'a char> for Repr {
+impl<'a, const N: usize, S: Storage<Global>> FromIterator<&'a char> for Repr<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn from_iter<T: IntoIterator<Item = &'a char>>(iter: T) -> Self {
         iter.into_iter().copied().collect()
     }
 }
 
-fn from_as_ref_str_iterator<S, I>(mut iter: I) -> Repr
+fn from_as_ref_str_iterator<const N: usize, S, T, I>(mut iter: I) -> Repr<N, Global, S>
 where
-    S: AsRef<str>,
-    I: Iterator<Item = S>,
-    String: core::iter::Extend<S>,
-    String: FromIterator<S>,
+    InlineCapacity<N>: ValidInlineCapacity,
+    S: Storage<Global>,
+    T: AsRef<str>,
+    I: Iterator<Item = T>,
+    String: core::iter::Extend<T>,
+    String: FromIterator<T>,
 {
     // Note: We don't check the lower bound here like we do in the character iterator because it's
     // possible for the iterator to be full of empty strings! In which case checking the lower bound
@@ -80,13 +88,13 @@ where
 
     // Continuously pull strings from the iterator
     let mut curr_len = 0;
-    let mut inline_buf = [0u8; MAX_SIZE];
+    let mut inline_buf = [0u8; N];
     while let Some(s) = iter.next() {
         let str_slice = s.as_ref();
         let bytes_len = str_slice.len();
 
         // this new string is too large to fit into our inline buffer, so heap allocate the rest
-        if bytes_len + curr_len > MAX_SIZE {
+        if bytes_len + curr_len > N {
             let (min_remaining, _) = iter.size_hint();
             let mut heap_buf = String::with_capacity(bytes_len + curr_len + min_remaining);
 
@@ -99,9 +107,7 @@ where
             heap_buf.extend(iter);
 
             let heap = HeapString::from_string(heap_buf);
-            return Repr {
-                heap: ManuallyDrop::new(heap),
-            };
+            return Repr::from_heap(heap, Global);
         }
 
         // write the current string into a slice of the unoccupied space
@@ -111,22 +117,31 @@ where
 
     // SAFETY: We know `inline_buf` is valid UTF-8 because it consists entriely of `&str`s
     let inline = unsafe { InlineString::from_parts(curr_len, inline_buf) };
-    Repr { inline }
+    Repr::from_inline(inline, Global)
 }
 
-impl<'a> FromIterator<&'a str> for Repr {
+impl<'a, const N: usize, S: Storage<Global>> FromIterator<&'a str> for Repr<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn from_iter<T: IntoIterator<Item = &'a str>>(iter: T) -> Self {
         from_as_ref_str_iterator(iter.into_iter())
     }
 }
 
-impl FromIterator<Box<str>> for Repr {
+impl<const N: usize, S: Storage<Global>> FromIterator<Box<str>> for Repr<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn from_iter<T: IntoIterator<Item = Box<str>>>(iter: T) -> Self {
         from_as_ref_str_iterator(iter.into_iter())
     }
 }
 
-impl FromIterator<String> for Repr {
+impl<const N: usize, S: Storage<Global>> FromIterator<String> for Repr<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn from_iter<T: IntoIterator<Item = String>>(iter: T) -> Self {
         from_as_ref_str_iterator(iter.into_iter())
     }
@@ -0,0 +1,50 @@
+//! The inline capacities a [`Repr`](super::Repr) supports
+
+use super::USIZE_SIZE;
+
+/// The inline capacity `N` of a [`CompactStrN<N>`](crate::CompactStrN), see
+/// [`ValidInlineCapacity`].
+#[derive(Debug, Clone, Copy)]
+pub struct InlineCapacity<const N: usize>;
+
+/// Implemented by [`InlineCapacity<N>`] for every inline capacity `N` that a
+/// [`CompactStrN<N>`](crate::CompactStrN) supports.
+///
+/// `N` can be anything from `size_of::<String>()`, i.e. 24 bytes on 64-bit architectures and 12
+/// bytes on 32-bit architectures, up to 56 bytes. Above 56 bytes, the last byte of an inline
+/// string would no longer leave room for the discriminants of heap allocated and static strings,
+/// and the niche that makes `Option<CompactStrN<N>>` the same size as a `CompactStrN<N>`.
+///
+/// This trait is sealed, so it can't be implemented outside of `compact_str`.
+pub trait ValidInlineCapacity: Layout {}
+
+/// The sizes of the buffers that make up a `Repr<N>`.
+///
+/// Note: Without the unstable `generic_const_exprs` feature we can't compute these from `N`, so we
+/// implement this for every supported `N` instead
+pub trait Layout {
+    /// `[u8; N - USIZE_SIZE - 1]`, i.e. the bytes of a `RawRepr<N>` between its pointer and its
+    /// last byte
+    type Data: Copy;
+}
+
+macro_rules! inline_capacities {
+    ($($n:literal)*) => {
+        $(
+            impl Layout for InlineCapacity<$n> {
+                type Data = [u8; $n - USIZE_SIZE - 1];
+            }
+            impl ValidInlineCapacity for InlineCapacity<$n> {}
+        )*
+    };
+}
+
+// Note: These are smaller than the first `MAX_SIZE` bytes of a `Repr` on 64-bit architectures,
+// which have to fit a heap allocated or static string
+#[cfg(target_pointer_width = "32")]
+inline_capacities! { 12 13 14 15 16 17 18 19 20 21 22 23 }
+
+inline_capacities! {
+    24 25 26 27 28 29 30 31 32 33 34 35 36 37 38 39
+    40 41 42 43 44 45 46 47 48 49 50 51 52 53 54 55 56
+}
@@ -1,113 +1,383 @@
-use std::borrow::Cow;
-use std::iter::Extend;
-use std::mem::ManuallyDrop;
-use std::str::Utf8Error;
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::iter::Extend;
+use core::marker::PhantomData;
+use core::mem::{
+    ManuallyDrop,
+    MaybeUninit,
+};
+use core::ops::{
+    Bound,
+    RangeBounds,
+};
+use core::ptr;
+
+use crate::error::handle_reserve;
+use crate::{
+    Allocator,
+    FromUtf8Error,
+    Global,
+    TryReserveError,
+};
 
 #[cfg(feature = "bytes")]
 mod bytes;
 
+mod decode;
+#[cfg(feature = "std")]
+mod io;
 mod iter;
 
 mod arc;
 mod boxed;
 mod discriminant;
+mod german;
 mod heap;
 mod inline;
+mod layout;
+mod static_str;
+mod storage;
+mod thin;
 
 use discriminant::{
     Discriminant,
-    DiscriminantMask,
+    LastByte,
 };
+pub use german::GermanRepr;
 use heap::HeapString;
 use inline::InlineString;
-
-const MAX_SIZE: usize = std::mem::size_of::<String>();
-const EMPTY: Repr = Repr {
-    inline: InlineString::new_const(""),
+use layout::Layout;
+pub use layout::{
+    InlineCapacity,
+    ValidInlineCapacity,
 };
+use static_str::StaticStr;
+pub use storage::{
+    Boxed,
+    Shared,
+    Storage,
+};
+pub use thin::ThinRepr;
+
+pub const MAX_SIZE: usize = core::mem::size_of::<String>();
+const USIZE_SIZE: usize = core::mem::size_of::<usize>();
+const EMPTY: RawRepr<MAX_SIZE> = RawRepr::from_inline_const(InlineString::new_const(""));
 
 // Used as a discriminant to identify different variants
 pub const HEAP_MASK: u8 = 0b11111111;
+pub const STATIC_MASK: u8 = 0b11111110;
+
+/// A `Repr` is an inline buffer of `N` bytes, which is either an [`InlineString`], a
+/// [`HeapString`], or a [`StaticStr`] that we discriminate between by inspecting the last byte,
+/// followed by the [`Allocator`] we heap allocate from.
+///
+/// A [`HeapString`] or [`StaticStr`] is only `MAX_SIZE` bytes long, so when `N` is larger than
+/// `MAX_SIZE` we store its discriminant in both its own last byte and our last byte.
+#[repr(C)]
+pub struct Repr<const N: usize = MAX_SIZE, A: Allocator = Global, S: Storage<A> = Boxed>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    raw: RawRepr<N>,
+    alloc: A,
+    storage: PhantomData<S>,
+}
+// SAFETY: `InlineString`, `HeapString`, and `StaticStr` are all `Send` and `Sync`, as long as the
+// allocator can be shared between threads
+unsafe impl<const N: usize, A: Allocator + Send + Sync, S: Storage<A>> Send for Repr<N, A, S> where
+    InlineCapacity<N>: ValidInlineCapacity
+{
+}
+unsafe impl<const N: usize, A: Allocator + Send + Sync, S: Storage<A>> Sync for Repr<N, A, S> where
+    InlineCapacity<N>: ValidInlineCapacity
+{
+}
 
-pub union Repr {
-    mask: DiscriminantMask,
-    heap: ManuallyDrop<HeapString>,
-    inline: InlineString,
+/// The first `N` bytes of a [`Repr`].
+///
+/// Instead of a `union`, we model these bytes as a pointer and buffer of bytes followed by a
+/// [`LastByte`]. The compiler knows not every value of the last byte is valid, which allows it to
+/// use the unused values as a niche, e.g. `Option<Repr>` is the same size as `Repr`.
+#[repr(C)]
+struct RawRepr<const N: usize>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    // Gives `Repr` the same alignment as `HeapString`, so we can cast between the two, and allows
+    // us to store the pointer of a `StaticStr` in a `const` context
+    ptr: *const (),
+    // The remaining bytes of an `InlineString`, `HeapString`, or `StaticStr`
+    data: MaybeUninit<<InlineCapacity<N> as Layout>::Data>,
+    // Either the last byte of an `InlineString`, or the discriminant of a `HeapString` or
+    // `StaticStr`
+    last_byte: LastByte,
 }
 
 impl Repr {
     #[inline]
-    pub fn new<T: AsRef<str>>(text: T) -> Self {
-        let text = text.as_ref();
-        let len = text.len();
+    pub const fn new_const(text: &str) -> Self {
+        Repr {
+            raw: RawRepr::new_const(text),
+            alloc: Global,
+            storage: PhantomData,
+        }
+    }
 
-        if len == 0 {
-            EMPTY
-        } else if len <= MAX_SIZE {
-            let inline = InlineString::new(text);
-            Repr { inline }
-        } else {
-            let heap = ManuallyDrop::new(HeapString::new(text));
-            Repr { heap }
+    #[inline]
+    pub const fn from_static_str(text: &'static str) -> Self {
+        Repr {
+            raw: RawRepr::from_static_str(text),
+            alloc: Global,
+            storage: PhantomData,
         }
     }
+}
 
+// Note: `const fn`s with trait bounds aren't stable on our MSRV, so we can't be generic over the
+// storage `S` here
+impl Repr<MAX_SIZE, Global, Shared> {
     #[inline]
     pub const fn new_const(text: &str) -> Self {
-        let len = text.len();
+        Repr {
+            raw: RawRepr::new_const(text),
+            alloc: Global,
+            storage: PhantomData,
+        }
+    }
 
-        if len <= MAX_SIZE {
-            let inline = InlineString::new_const(text);
-            Repr { inline }
-        } else {
-            // HACK: This allows us to make assertions within a `const fn` without requiring
-            // nightly, see unstable `const_panic` feature. This results in a build
-            // failure, not a runtime panic
-            #[allow(clippy::no_effect)]
-            #[allow(unconditional_panic)]
-            ["Trying to create a non-inline-able string at compile time!"][42];
-            EMPTY
+    #[inline]
+    pub const fn from_static_str(text: &'static str) -> Self {
+        Repr {
+            raw: RawRepr::from_static_str(text),
+            alloc: Global,
+            storage: PhantomData,
         }
     }
+}
+
+impl<const N: usize, S: Storage<Global>> Repr<N, Global, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    #[inline]
+    pub fn new<T: AsRef<str>>(text: T) -> Self {
+        Self::new_in(text, Global)
+    }
 
     #[inline]
     pub fn with_capacity(capacity: usize) -> Self {
-        if capacity <= MAX_SIZE {
-            EMPTY
-        } else {
-            let heap = ManuallyDrop::new(HeapString::with_capacity(capacity));
-            Repr { heap }
+        Self::with_capacity_in(capacity, Global)
+    }
+
+    #[inline]
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+        Self::try_with_capacity_in(capacity, Global)
+    }
+
+    #[inline]
+    pub fn from_utf8<B: AsRef<[u8]>>(buf: B) -> Result<Self, FromUtf8Error> {
+        let buf = buf.as_ref();
+        // Get a &str from the buffer, failing if it's not valid UTF-8
+        match core::str::from_utf8(buf) {
+            // Construct a Repr from the &str
+            Ok(s) => Ok(Self::new(s)),
+            // Note: We only borrow the buffer, so we need to copy it to give the bytes back
+            Err(e) => Err(FromUtf8Error::new(buf.to_vec(), e)),
         }
     }
 
+    /// Creates a [`Repr`] from a slice of bytes, without checking that they're valid UTF-8
+    ///
+    /// # Safety
+    /// * The provided bytes must be valid UTF-8
     #[inline]
-    pub fn from_utf8<B: AsRef<[u8]>>(buf: B) -> Result<Self, Utf8Error> {
-        // Get a &str from the Vec, failing if it's not valid UTF-8
-        let s = core::str::from_utf8(buf.as_ref())?;
-        // Construct a Repr from the &str
-        Ok(Self::new(s))
+    pub unsafe fn from_utf8_unchecked<B: AsRef<[u8]>>(buf: B) -> Self {
+        Self::new(core::str::from_utf8_unchecked(buf.as_ref()))
     }
 
+    /// Creates a [`Repr`] from a [`String`], inlining the string if it's short enough, otherwise
+    /// re-using the heap allocated buffer
     #[inline]
     pub fn from_string(s: String) -> Self {
+        if s.len() <= N {
+            // Note: `s` gets dropped here, freeing its buffer
+            Self::new(&s)
+        } else {
+            Self::from_string_preserve_capacity(s)
+        }
+    }
+
+    /// Creates a [`Repr`] from a [`String`], always re-using its heap allocated buffer, even if
+    /// the string is short enough to be inlined
+    #[inline]
+    pub fn from_string_preserve_capacity(s: String) -> Self {
         if s.capacity() == 0 {
-            EMPTY
+            Self::empty_in(Global)
         } else {
-            let heap = ManuallyDrop::new(HeapString::from_string(s));
-            Repr { heap }
+            let heap = HeapString::from_string(s);
+            Self::from_heap(heap, Global)
         }
     }
 
+    /// Creates a [`Repr`] from a [`Box<str>`], inlining the string if it's short enough, otherwise
+    /// re-using the heap allocated buffer
     #[inline]
     pub fn from_box_str(b: Box<str>) -> Self {
-        if b.len() == 0 {
-            EMPTY
+        if b.len() <= N {
+            // Note: `b` gets dropped here, freeing its buffer
+            Self::new(&b)
+        } else {
+            let heap = HeapString::from_box_str(b);
+            Self::from_heap(heap, Global)
+        }
+    }
+
+    /// Creates a [`Repr`] from a [`Vec<u8>`], which might not be valid UTF-8, inlining the bytes
+    /// if they're short enough, see `Repr::from_bytes_in()`, otherwise re-using the heap
+    /// allocated buffer if possible
+    #[inline]
+    pub fn from_vec(v: Vec<u8>) -> Self {
+        if v.len() < N {
+            // Note: `v` gets dropped here, freeing its buffer
+            Self::from_bytes_in(&v, Global)
+        } else {
+            let heap = HeapString::from_vec(v);
+            Self::from_heap(heap, Global)
+        }
+    }
+
+    /// Returns how many bytes can be written into a [`Repr`] via `Repr::spare_capacity_mut()`,
+    /// without reserving more space
+    #[cfg(any(feature = "bytes", feature = "std"))]
+    pub fn spare_capacity(&self) -> usize {
+        // Until the bytes are checked, they might not be valid UTF-8, see `Repr::bytes_capacity()`
+        self.bytes_capacity().saturating_sub(self.len())
+    }
+
+    /// Returns the spare capacity of a [`Repr`], that arbitrary bytes can be written into, e.g.
+    /// from a `BufMut` or `Read`, reserving more space if there isn't any
+    ///
+    /// # Safety
+    /// * Any bytes written must be checked to be valid UTF-8 before the [`Repr`] is used as a `str`
+    /// * Bytes past the end of a heap allocated string might not be initialized
+    #[cfg(any(feature = "bytes", feature = "std"))]
+    pub unsafe fn spare_capacity_mut(&mut self) -> &mut [u8] {
+        if self.spare_capacity() == 0 {
+            // Note: this grows by at least the same amount as a `Vec<u8>` does
+            self.reserve(64);
+        }
+
+        let len = self.len();
+        let end = len + self.spare_capacity();
+        &mut self.as_mut_slice()[len..end]
+    }
+
+    #[inline]
+    pub fn into_string(self) -> String {
+        // We're moving out of `self`, so we need to make sure we don't call Drop
+        let this = ManuallyDrop::new(self);
+
+        match this.discriminant() {
+            Discriminant::Heap => {
+                // SAFETY: We checked the discriminant to make sure the `Repr` is a `HeapString`,
+                // and we won't touch `this` again, so moving the `HeapString` out is fine
+                let heap = unsafe { ptr::read(this.as_heap()) };
+                heap.into_string()
+            }
+            Discriminant::Inline | Discriminant::Static => String::from(this.as_str()),
+        }
+    }
+
+    /// Converts a [`Repr`] into a [`Vec<u8>`], re-using the heap allocated buffer if possible
+    #[inline]
+    pub fn into_vec(self) -> Vec<u8> {
+        // We're moving out of `self`, so we need to make sure we don't call Drop
+        let this = ManuallyDrop::new(self);
+
+        match this.discriminant() {
+            Discriminant::Heap => {
+                // SAFETY: We checked the discriminant to make sure the `Repr` is a `HeapString`,
+                // and we won't touch `this` again, so moving the `HeapString` out is fine
+                let heap = unsafe { ptr::read(this.as_heap()) };
+                heap.into_vec()
+            }
+            Discriminant::Inline | Discriminant::Static => this.as_bytes().to_vec(),
+        }
+    }
+}
+
+impl<const N: usize, A: Allocator, S: Storage<A>> Repr<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    /// The maximum number of bytes that might not be valid UTF-8 we store inline, see
+    /// `Repr::bytes_capacity()`
+    const MAX_INLINE_BYTES: usize = N - 1;
+
+    #[inline]
+    pub fn new_in<T: AsRef<str>>(text: T, alloc: A) -> Self {
+        let text = text.as_ref();
+        let len = text.len();
+
+        if len == 0 {
+            Self::empty_in(alloc)
+        } else if len <= N {
+            let inline = InlineString::new(text.as_bytes());
+            Self::from_inline(inline, alloc)
+        } else {
+            let heap = HeapString::new(text.as_bytes(), &alloc);
+            Self::from_heap(heap, alloc)
+        }
+    }
+
+    /// Creates a [`Repr`] from a slice of bytes, which might not be valid UTF-8.
+    ///
+    /// Unlike `Repr::new_in()`, we only inline `bytes` if they're shorter than `N`. The last byte
+    /// of an inline buffer stores our length and discriminant, which only valid UTF-8 can safely
+    /// overwrite.
+    #[inline]
+    pub fn from_bytes_in(bytes: &[u8], alloc: A) -> Self {
+        let len = bytes.len();
+
+        if len == 0 {
+            Self::empty_in(alloc)
+        } else if len < N {
+            let inline = InlineString::new(bytes);
+            Self::from_inline(inline, alloc)
+        } else {
+            let heap = HeapString::new(bytes, &alloc);
+            Self::from_heap(heap, alloc)
+        }
+    }
+
+    #[inline]
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        handle_reserve(Self::try_with_capacity_in(capacity, alloc))
+    }
+
+    #[inline]
+    pub fn try_with_capacity_in(capacity: usize, alloc: A) -> Result<Self, TryReserveError> {
+        if capacity <= N {
+            Ok(Self::empty_in(alloc))
         } else {
-            let heap = ManuallyDrop::new(HeapString::from_box_str(b));
-            Repr { heap }
+            let heap = HeapString::try_with_capacity(capacity, &alloc)?;
+            Ok(Self::from_heap(heap, alloc))
         }
     }
 
+    #[inline]
+    fn empty_in(alloc: A) -> Self {
+        Self::from_inline(InlineString::new(b""), alloc)
+    }
+
+    /// Returns a reference to the [`Allocator`] we heap allocate from
+    #[inline]
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
     #[inline]
     pub fn len(&self) -> usize {
         self.cast().len()
@@ -120,26 +390,125 @@ impl Repr {
 
     #[inline]
     pub fn reserve(&mut self, additional: usize) {
+        handle_reserve(self.try_reserve(additional))
+    }
+
+    #[inline]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.try_reserve_inline_up_to(additional, N)
+    }
+
+    /// Returns the capacity of a [`Repr`] that stores bytes which might not be valid UTF-8.
+    ///
+    /// When inline, the last byte of our buffer stores our length and discriminant. Writing an
+    /// arbitrary byte there could make us look like a heap allocated or static string, so we only
+    /// ever store `MAX_INLINE_BYTES` bytes inline.
+    #[inline]
+    pub fn bytes_capacity(&self) -> usize {
+        if self.is_heap_allocated() {
+            self.capacity()
+        } else {
+            Self::MAX_INLINE_BYTES
+        }
+    }
+
+    /// Fallible version of `Repr::reserve()` for bytes that might not be valid UTF-8, which never
+    /// moves more than `MAX_INLINE_BYTES` bytes inline, see `Repr::bytes_capacity()`
+    #[inline]
+    pub fn try_reserve_bytes(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.try_reserve_inline_up_to(additional, Self::MAX_INLINE_BYTES)
+    }
+
+    #[inline]
+    fn try_reserve_inline_up_to(
+        &mut self,
+        additional: usize,
+        max_inline: usize,
+    ) -> Result<(), TryReserveError> {
         // We want at least enough capacity to store length + additional
-        let new_capacity = self.len() + additional;
+        let new_capacity = self
+            .len()
+            .checked_add(additional)
+            .ok_or_else(TryReserveError::capacity_overflow)?;
 
         // We already have at least `additional` capacity, so we don't need to do anything
-        if self.capacity() >= new_capacity {
-            return;
+        let capacity = if self.is_heap_allocated() {
+            self.capacity()
+        } else {
+            core::cmp::min(self.capacity(), max_inline)
+        };
+        if capacity >= new_capacity {
+            return Ok(());
         }
 
-        if new_capacity <= MAX_SIZE {
+        if new_capacity <= max_inline {
             // It's possible to have a `CompactStr` that is heap allocated with a capacity less than
-            // MAX_SIZE, if that `CompactStr` was created From a String or Box<str>.
-            let inline = InlineString::new(self.as_str());
-            *self = Repr { inline }
+            // N, if that `CompactStr` was created From a String or Box<str>.
+            let inline = InlineString::new(self.as_bytes());
+            *self = Self::from_inline(inline, self.allocator().clone());
         } else {
             // Create a `HeapString` with `text.len() + additional` capacity
-            let heap = HeapString::with_additional(self.as_str(), additional);
+            let alloc = self.allocator().clone();
+            let heap = HeapString::try_with_additional(self.as_bytes(), additional, &alloc)?;
 
             // Replace `self` with the new Repr
-            let heap = ManuallyDrop::new(heap);
-            *self = Repr { heap };
+            *self = Self::from_heap(heap, alloc);
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink_to(0)
+    }
+
+    #[inline]
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        self.shrink_to_inline_up_to(min_capacity, N)
+    }
+
+    /// Version of `Repr::shrink_to()` for bytes that might not be valid UTF-8, which never moves
+    /// more than `MAX_INLINE_BYTES` bytes inline, see `Repr::bytes_capacity()`
+    #[inline]
+    pub fn shrink_to_bytes(&mut self, min_capacity: usize) {
+        self.shrink_to_inline_up_to(min_capacity, Self::MAX_INLINE_BYTES)
+    }
+
+    /// Prepares a [`Repr`] that stores valid UTF-8 for storing arbitrary bytes, by moving an `N`
+    /// byte long inline or static string onto the heap, see `Repr::bytes_capacity()`
+    #[inline]
+    pub fn into_bytes_repr(self) -> Self {
+        if self.len() > Self::MAX_INLINE_BYTES && !self.is_heap_allocated() {
+            // Note: this also copies a long `&'static str` onto the heap, which could otherwise
+            // be truncated to `N` bytes and then copied inline when we first mutate it
+            Self::from_bytes_in(self.as_bytes(), self.allocator().clone())
+        } else {
+            self
+        }
+    }
+
+    #[inline]
+    fn shrink_to_inline_up_to(&mut self, min_capacity: usize, max_inline: usize) {
+        let new_capacity = core::cmp::max(self.len(), min_capacity);
+
+        match self.discriminant() {
+            // Only heap allocated strings can have excess capacity, and shrinking a `StaticStr`
+            // shouldn't copy it
+            Discriminant::Inline | Discriminant::Static => {}
+            Discriminant::Heap if new_capacity <= max_inline => {
+                // We fit inline, so move back onto the stack, which deallocates our buffer
+                let inline = InlineString::new(self.as_bytes());
+                *self = Self::from_inline(inline, self.allocator().clone());
+            }
+            Discriminant::Heap => {
+                // SAFETY: We checked the discriminant to make sure the `Repr` is a `HeapString`,
+                // which we allocated from our allocator
+                unsafe {
+                    let heap = self.raw.as_heap_mut::<A, S>();
+                    heap.shrink_to(new_capacity, &self.alloc);
+                }
+            }
         }
     }
 
@@ -153,11 +522,33 @@ impl Repr {
         self.cast().into_slice()
     }
 
+    /// Returns the first `len` bytes of our buffer, which unlike `Repr::as_str()`, don't need to
+    /// be valid UTF-8
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.as_slice()[..self.len()]
+    }
+
     #[inline]
     pub unsafe fn as_mut_slice(&mut self) -> &mut [u8] {
         self.cast_mut().into_mut_slice()
     }
 
+    /// Fallible version of `Repr::as_mut_slice()`, which returns an error if we share our heap
+    /// allocated buffer, and fail to copy it
+    #[inline]
+    pub unsafe fn try_as_mut_slice(&mut self) -> Result<&mut [u8], TryReserveError> {
+        self.cast_mut().try_into_mut_slice()
+    }
+
+    #[inline]
+    pub fn as_mut_str(&mut self) -> &mut str {
+        let len = self.len();
+        // SAFETY: We only expose the first `len` bytes, which are valid UTF-8, and safe code can
+        // only modify a `&mut str` in ways that keep it valid UTF-8
+        unsafe { core::str::from_utf8_unchecked_mut(&mut self.as_mut_slice()[..len]) }
+    }
+
     #[inline]
     pub fn push(&mut self, ch: char) {
         let len = self.len();
@@ -187,13 +578,19 @@ impl Repr {
 
     #[inline]
     pub fn push_str(&mut self, s: &str) {
+        handle_reserve(self.try_push_str(s))
+    }
+
+    #[inline]
+    pub fn try_push_str(&mut self, s: &str) -> Result<(), TryReserveError> {
         let len = self.len();
         let str_len = s.len();
 
         // Reserve at least enough space for our str, possibly causing a heap allocation
-        self.reserve(str_len);
+        self.try_reserve(str_len)?;
 
-        let slice = unsafe { self.as_mut_slice() };
+        // Note: This might still need to allocate, if we share our buffer with another `Repr`
+        let slice = unsafe { self.try_as_mut_slice()? };
         let buffer = &mut slice[len..len + str_len];
 
         debug_assert_eq!(buffer.len(), s.as_bytes().len());
@@ -202,11 +599,220 @@ impl Repr {
         buffer.copy_from_slice(s.as_bytes());
         // Incrament the length of our string
         unsafe { self.set_len(len + str_len) };
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn truncate(&mut self, new_len: usize) {
+        if new_len <= self.len() {
+            assert!(self.as_str().is_char_boundary(new_len));
+
+            // SAFETY: `new_len` is less than or equal to our current length, and it falls on a
+            // char boundary, so the remaining string is still valid UTF-8
+            unsafe { self.set_len(new_len) };
+        }
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        // SAFETY: A length of 0 is always valid, and an empty string is valid UTF-8
+        unsafe { self.set_len(0) };
+    }
+
+    #[inline]
+    pub fn insert(&mut self, idx: usize, ch: char) {
+        let mut buf = [0u8; 4];
+        let s = ch.encode_utf8(&mut buf);
+
+        self.insert_str(idx, s);
+    }
+
+    #[inline]
+    pub fn insert_str(&mut self, idx: usize, s: &str) {
+        assert!(self.as_str().is_char_boundary(idx));
+
+        // SAFETY: We just asserted `idx` falls on a char boundary, and we're inserting a `&str`
+        // which is valid UTF-8
+        unsafe { self.splice(idx, idx, s.as_bytes()) };
+    }
+
+    #[inline]
+    pub fn remove(&mut self, idx: usize) -> char {
+        let ch = match self.as_str()[idx..].chars().next() {
+            Some(ch) => ch,
+            None => panic!("cannot remove a char from the end of a string"),
+        };
+
+        // SAFETY: `idx` is the start of `ch`, and `idx + ch.len_utf8()` is the start of the next
+        // char, so removing the bytes between them leaves us with valid UTF-8
+        unsafe { self.splice(idx, idx + ch.len_utf8(), &[]) };
+
+        ch
+    }
+
+    #[inline]
+    pub fn retain<F: FnMut(char) -> bool>(&mut self, mut predicate: F) {
+        // If `predicate` panics we need to make sure our length only covers the characters we've
+        // already processed, which are always valid UTF-8. Modeled after `String::retain(...)`
+        struct SetLenOnDrop<'a, const N: usize, A: Allocator, S: Storage<A>>
+        where
+            InlineCapacity<N>: ValidInlineCapacity,
+        {
+            repr: &'a mut Repr<N, A, S>,
+            idx: usize,
+            del_bytes: usize,
+        }
+
+        impl<'a, const N: usize, A: Allocator, S: Storage<A>> Drop for SetLenOnDrop<'a, N, A, S>
+        where
+            InlineCapacity<N>: ValidInlineCapacity,
+        {
+            fn drop(&mut self) {
+                let new_len = self.idx - self.del_bytes;
+                debug_assert!(new_len <= self.repr.len());
+
+                // SAFETY: Every byte in `[0, new_len)` is a char we decided to retain
+                unsafe { self.repr.set_len(new_len) };
+            }
+        }
+
+        let len = self.len();
+        let mut guard = SetLenOnDrop {
+            repr: self,
+            idx: 0,
+            del_bytes: 0,
+        };
+
+        while guard.idx < len {
+            // SAFETY: We haven't modified any bytes at or after `idx` yet, and `idx` always falls
+            // on a char boundary, so these bytes are valid UTF-8
+            let remaining =
+                unsafe { core::str::from_utf8_unchecked(&guard.repr.as_slice()[guard.idx..len]) };
+            let ch = match remaining.chars().next() {
+                Some(ch) => ch,
+                None => break,
+            };
+            let ch_len = ch.len_utf8();
+
+            if !predicate(ch) {
+                guard.del_bytes += ch_len;
+            } else if guard.del_bytes > 0 {
+                // SAFETY: We're moving an entire char towards the front of the buffer, directly
+                // after the last char we retained
+                let slice = unsafe { guard.repr.as_mut_slice() };
+                slice.copy_within(guard.idx..guard.idx + ch_len, guard.idx - guard.del_bytes);
+            }
+
+            // Point `idx` to the next char
+            guard.idx += ch_len;
+        }
+
+        // `guard` gets dropped here, which sets our new length
+    }
+
+    #[inline]
+    pub fn replace_range<R: RangeBounds<usize>>(&mut self, range: R, replace_with: &str) {
+        let (start, end) = self.char_range(range);
+
+        // SAFETY: `char_range(...)` asserts that both `start` and `end` fall on char boundaries,
+        // and we're replacing them with a `&str` which is valid UTF-8
+        unsafe { self.splice(start, end, replace_with.as_bytes()) };
+    }
+
+    #[inline]
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(self.as_str().is_char_boundary(at));
+
+        let other = Self::new_in(&self.as_str()[at..], self.allocator().clone());
+        // SAFETY: We just asserted `at` falls on a char boundary, and `at` is at most our length
+        unsafe { self.set_len(at) };
+
+        other
+    }
+
+    /// Returns a new [`Repr`] of the bytes `start..end`.
+    ///
+    /// Short substrings get inlined, a substring of a `&'static str` borrows the same static
+    /// string, and with [`Shared`] storage, a substring of a heap allocated string shares its
+    /// buffer. Otherwise the substring gets copied.
+    ///
+    /// Note: The caller must make sure `start..end` is in bounds and falls on char boundaries, e.g.
+    /// by calling `Repr::char_range(...)`
+    #[inline]
+    pub fn substr(&self, start: usize, end: usize) -> Self {
+        let text = &self.as_str()[start..end];
+        let alloc = self.allocator().clone();
+
+        if text.len() <= N {
+            return Self::new_in(text, alloc);
+        }
+
+        match self.cast() {
+            StrongRepr::Static(static_str) => {
+                let text = &static_str.as_str()[start..end];
+                return Self::from_static(StaticStr::new(text), alloc);
+            }
+            StrongRepr::Heap(heap) => {
+                if let Some(heap) = heap.slice(start, end) {
+                    return Self::from_heap(heap, alloc);
+                }
+            }
+            _ => {}
+        }
+
+        Self::new_in(text, alloc)
+    }
+
+    /// Converts `range` into a `(start, end)` pair of byte indices for this [`Repr`], panicking if
+    /// the range is out of bounds, or either end does not fall on a char boundary
+    #[inline]
+    pub fn char_range<R: RangeBounds<usize>>(&self, range: R) -> (usize, usize) {
+        char_range(self.as_str(), range)
+    }
+
+    /// Replaces the bytes in `[start, end)` with `bytes`, shifting any bytes after `end` to make
+    /// room, and possibly growing or heap allocating the underlying buffer.
+    ///
+    /// # Safety
+    /// * `start <= end <= self.len()`
+    /// * Both `start` and `end` must fall on char boundaries
+    /// * `bytes` must be valid UTF-8
+    #[inline]
+    unsafe fn splice(&mut self, start: usize, end: usize, bytes: &[u8]) {
+        debug_assert!(start <= end && end <= self.len());
+
+        let len = self.len();
+        let removed = end - start;
+        let inserted = bytes.len();
+
+        // Reserve enough space for the new bytes, possibly causing a heap allocation
+        if inserted > removed {
+            self.reserve(inserted - removed);
+        }
+        let new_len = len - removed + inserted;
+
+        let slice = self.as_mut_slice();
+        // Shift everything after the range over, to either make room or to close the gap
+        slice.copy_within(end..len, start + inserted);
+        // Write in the new bytes
+        slice[start..start + inserted].copy_from_slice(bytes);
+
+        // Update our length
+        //
+        // Note: If we're inline and just shrank from `N` bytes, this will also restore our length
+        // byte, which may have previously been occupied by a UTF-8 char
+        self.set_len(new_len);
     }
 
     #[inline]
     pub unsafe fn set_len(&mut self, length: usize) {
-        self.cast_mut().set_len(length)
+        match self.discriminant() {
+            // Shortening a `StaticStr` doesn't require us to copy it, and if we've written into
+            // the buffer, `cast_mut()` would have already copied it
+            Discriminant::Static => self.raw.as_static_mut().set_len(length),
+            _ => self.cast_mut().set_len(length),
+        }
     }
 
     #[inline]
@@ -216,62 +822,308 @@ impl Repr {
 
     #[inline(always)]
     fn discriminant(&self) -> Discriminant {
-        // SAFETY: `heap` and `inline` all store a discriminant in their last byte
-        unsafe { self.mask.discriminant() }
+        // Note: `heap`, `inline`, and `static` all store a discriminant in their last byte
+        self.raw.last_byte.discriminant()
+    }
+
+    #[inline(always)]
+    fn from_inline(inline: InlineString<N>, alloc: A) -> Self {
+        Repr {
+            raw: RawRepr::from_inline(inline),
+            alloc,
+            storage: PhantomData,
+        }
     }
 
     #[inline(always)]
-    fn cast(&self) -> StrongRepr<'_> {
+    fn from_heap(heap: HeapString<A, S>, alloc: A) -> Self {
+        Repr {
+            raw: RawRepr::from_heap(heap),
+            alloc,
+            storage: PhantomData,
+        }
+    }
+
+    #[inline(always)]
+    fn from_static(static_str: StaticStr, alloc: A) -> Self {
+        Repr {
+            raw: RawRepr::from_static(static_str),
+            alloc,
+            storage: PhantomData,
+        }
+    }
+
+    /// # Safety
+    /// * The discriminant must be `Discriminant::Heap`
+    #[inline(always)]
+    unsafe fn as_heap(&self) -> &HeapString<A, S> {
+        self.raw.as_heap()
+    }
+
+    #[inline(always)]
+    fn cast(&self) -> StrongRepr<'_, N, A, S> {
         match self.discriminant() {
             Discriminant::Heap => {
-                // SAFETY: We checked the discriminant to make sure the union is `heap`
-                StrongRepr::Heap(unsafe { &self.heap })
+                // SAFETY: We checked the discriminant to make sure the `Repr` is a `HeapString`
+                StrongRepr::Heap(unsafe { self.raw.as_heap() })
             }
             Discriminant::Inline => {
-                // SAFETY: We checked the discriminant to make sure the union is `inline`
-                StrongRepr::Inline(unsafe { &self.inline })
+                // SAFETY: We checked the discriminant to make sure the `Repr` is an
+                // `InlineString`
+                StrongRepr::Inline(unsafe { self.raw.as_inline() })
+            }
+            Discriminant::Static => {
+                // SAFETY: We checked the discriminant to make sure the `Repr` is a `StaticStr`
+                StrongRepr::Static(unsafe { self.raw.as_static() })
             }
         }
     }
 
+    /// Returns a mutable view of this [`Repr`], first copying a [`StaticStr`] into an
+    /// [`InlineString`] or [`HeapString`] since we can't mutate a `&'static str`
     #[inline(always)]
-    fn cast_mut(&mut self) -> MutStrongRepr<'_> {
+    fn cast_mut(&mut self) -> MutStrongRepr<'_, N, A, S> {
+        if let Discriminant::Static = self.discriminant() {
+            *self = Self::new_in(self.as_str(), self.allocator().clone());
+        }
+
         match self.discriminant() {
             Discriminant::Heap => {
-                // SAFETY: We checked the discriminant to make sure the union is `heap`
-                MutStrongRepr::Heap(unsafe { &mut self.heap })
+                // SAFETY: We checked the discriminant to make sure the `Repr` is a `HeapString`
+                MutStrongRepr::Heap(unsafe { self.raw.as_heap_mut() })
             }
             Discriminant::Inline => {
-                // SAFETY: We checked the discriminant to make sure the union is `inline`
-                MutStrongRepr::Inline(unsafe { &mut self.inline })
+                // SAFETY: We checked the discriminant to make sure the `Repr` is an
+                // `InlineString`
+                MutStrongRepr::Inline(unsafe { self.raw.as_inline_mut() })
             }
+            Discriminant::Static => unreachable!("static strings are copied above"),
         }
     }
 }
 
-impl Clone for Repr {
+/// Converts `range` into a `(start, end)` pair of byte indices into `text`, panicking if the range
+/// is out of bounds, or either end does not fall on a char boundary
+#[inline]
+pub fn char_range<R: RangeBounds<usize>>(text: &str, range: R) -> (usize, usize) {
+    let len = text.len();
+
+    let start = match range.start_bound() {
+        Bound::Included(&n) => n,
+        Bound::Excluded(&n) => n
+            .checked_add(1)
+            .expect("attempted to index from usize::MAX"),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&n) => n.checked_add(1).expect("attempted to index to usize::MAX"),
+        Bound::Excluded(&n) => n,
+        Bound::Unbounded => len,
+    };
+
+    assert!(
+        start <= end,
+        "range start {} is greater than range end {}",
+        start,
+        end
+    );
+    assert!(
+        end <= len,
+        "range end {} is out of bounds for length {}",
+        end,
+        len
+    );
+    assert!(text.is_char_boundary(start));
+    assert!(text.is_char_boundary(end));
+
+    (start, end)
+}
+
+impl<const N: usize> RawRepr<N>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
+    #[inline(always)]
+    fn from_inline(inline: InlineString<N>) -> Self {
+        let mut raw = MaybeUninit::<Self>::uninit();
+        // SAFETY: An `InlineString<N>` and a `RawRepr<N>` are both `N` bytes, and the last byte of
+        // an `InlineString` is always a valid `LastByte`
+        unsafe {
+            ptr::write(raw.as_mut_ptr() as *mut InlineString<N>, inline);
+            raw.assume_init()
+        }
+    }
+
+    #[inline(always)]
+    fn from_heap<A: Allocator, S: Storage<A>>(heap: HeapString<A, S>) -> Self {
+        let mut raw = MaybeUninit::<Self>::uninit();
+        // SAFETY: A `HeapString` is the first `MAX_SIZE` bytes of a `RawRepr<N>`, and its last
+        // byte is always `HEAP_MASK`. When `N` is larger than `MAX_SIZE` we also write the
+        // discriminant into our own last byte, the bytes in between are never read
+        unsafe {
+            ptr::write(raw.as_mut_ptr() as *mut HeapString<A, S>, heap);
+            ptr::addr_of_mut!((*raw.as_mut_ptr()).last_byte).write(LastByte::Heap);
+            raw.assume_init()
+        }
+    }
+
+    #[inline(always)]
+    fn from_static(static_str: StaticStr) -> Self {
+        let mut raw = MaybeUninit::<Self>::uninit();
+        // SAFETY: A `StaticStr` is the first `MAX_SIZE` bytes of a `RawRepr<N>`, and its last
+        // byte is always `STATIC_MASK`, see `RawRepr::from_heap()`
+        unsafe {
+            ptr::write(raw.as_mut_ptr() as *mut StaticStr, static_str);
+            ptr::addr_of_mut!((*raw.as_mut_ptr()).last_byte).write(LastByte::Static);
+            raw.assume_init()
+        }
+    }
+
+    /// # Safety
+    /// * The discriminant must be `Discriminant::Inline`
+    #[inline(always)]
+    unsafe fn as_inline(&self) -> &InlineString<N> {
+        &*(self as *const Self as *const InlineString<N>)
+    }
+
+    /// # Safety
+    /// * The discriminant must be `Discriminant::Inline`
+    #[inline(always)]
+    unsafe fn as_inline_mut(&mut self) -> &mut InlineString<N> {
+        &mut *(self as *mut Self as *mut InlineString<N>)
+    }
+
+    /// # Safety
+    /// * The discriminant must be `Discriminant::Heap`
+    /// * `A` must be the allocator of the `Repr` we belong to
+    #[inline(always)]
+    unsafe fn as_heap<A: Allocator, S: Storage<A>>(&self) -> &HeapString<A, S> {
+        &*(self as *const Self as *const HeapString<A, S>)
+    }
+
+    /// # Safety
+    /// * The discriminant must be `Discriminant::Heap`
+    /// * `A` must be the allocator of the `Repr` we belong to
+    #[inline(always)]
+    unsafe fn as_heap_mut<A: Allocator, S: Storage<A>>(&mut self) -> &mut HeapString<A, S> {
+        &mut *(self as *mut Self as *mut HeapString<A, S>)
+    }
+
+    /// # Safety
+    /// * The discriminant must be `Discriminant::Static`
+    #[inline(always)]
+    unsafe fn as_static(&self) -> &StaticStr {
+        &*(self as *const Self as *const StaticStr)
+    }
+
+    /// # Safety
+    /// * The discriminant must be `Discriminant::Static`
+    #[inline(always)]
+    unsafe fn as_static_mut(&mut self) -> &mut StaticStr {
+        &mut *(self as *mut Self as *mut StaticStr)
+    }
+}
+
+impl RawRepr<MAX_SIZE> {
+    #[inline]
+    const fn new_const(text: &str) -> Self {
+        let len = text.len();
+
+        if len <= MAX_SIZE {
+            let inline = InlineString::new_const(text);
+            RawRepr::from_inline_const(inline)
+        } else {
+            // HACK: This allows us to make assertions within a `const fn` without requiring
+            // nightly, see unstable `const_panic` feature. This results in a build
+            // failure, not a runtime panic
+            #[allow(clippy::no_effect)]
+            #[allow(unconditional_panic)]
+            ["Trying to create a non-inline-able string at compile time!"][42];
+            EMPTY
+        }
+    }
+
+    #[inline]
+    const fn from_static_str(text: &'static str) -> Self {
+        if text.len() <= MAX_SIZE {
+            // Short strings are cheaper to store inline, than to store a pointer to
+            let inline = InlineString::new_const(text);
+            RawRepr::from_inline_const(inline)
+        } else {
+            let static_str = StaticStr::new(text);
+            RawRepr {
+                ptr: static_str.as_ptr() as *const (),
+                data: MaybeUninit::new(static_str.data()),
+                last_byte: LastByte::Static,
+            }
+        }
+    }
+
+    /// Creates a [`RawRepr`] from an [`InlineString`] in a `const` context.
+    ///
+    /// Note: `core::mem::transmute` isn't a `const fn` on our MSRV, so we copy the bytes instead
+    #[inline]
+    const fn from_inline_const(inline: InlineString<MAX_SIZE>) -> Self {
+        let buffer = inline.into_buffer();
+
+        // Note: for loops aren't allowed in `const fn`, hence the while
+        let mut ptr = [0u8; USIZE_SIZE];
+        let mut i = 0;
+        while i < USIZE_SIZE {
+            ptr[i] = buffer[i];
+            i += 1;
+        }
+
+        let mut data = [0u8; MAX_SIZE - USIZE_SIZE - 1];
+        let mut i = 0;
+        while i < MAX_SIZE - USIZE_SIZE - 1 {
+            data[i] = buffer[USIZE_SIZE + i];
+            i += 1;
+        }
+
+        RawRepr {
+            ptr: usize::from_ne_bytes(ptr) as *const (),
+            data: MaybeUninit::new(data),
+            last_byte: LastByte::from_u8(buffer[MAX_SIZE - 1]),
+        }
+    }
+}
+
+impl<const N: usize, A: Allocator, S: Storage<A>> Clone for Repr<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn clone(&self) -> Self {
+        let alloc = self.allocator().clone();
         match self.cast() {
-            StrongRepr::Heap(heap) => Repr { heap: heap.clone() },
-            StrongRepr::Inline(inline) => Repr { inline: *inline },
+            StrongRepr::Heap(heap) => Self::from_heap(heap.clone_in(&alloc), alloc),
+            StrongRepr::Inline(inline) => Self::from_inline(*inline, alloc),
+            StrongRepr::Static(static_str) => Self::from_static(*static_str, alloc),
         }
     }
 }
 
-impl Drop for Repr {
+impl<const N: usize, A: Allocator, S: Storage<A>> Drop for Repr<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn drop(&mut self) {
-        match self.discriminant() {
-            Discriminant::Heap => {
-                // SAFETY: We checked the discriminant to make sure the union is `heap`
-                unsafe { ManuallyDrop::drop(&mut self.heap) };
-            }
-            // No-op, the value is on the stack and doesn't need to be explicitly dropped
-            Discriminant::Inline => {}
+        // The value is on the stack and doesn't need to be explicitly dropped, and for a
+        // `StaticStr` we don't own the underlying `&'static str`
+        //
+        // Note: Our allocator gets dropped after this, as a regular field
+        if let Discriminant::Heap = self.discriminant() {
+            // SAFETY: We checked the discriminant to make sure the `Repr` is a `HeapString`, which
+            // we allocated from our allocator, and we never touch it again
+            unsafe { self.raw.as_heap_mut::<A, S>().dealloc(&self.alloc) };
         }
     }
 }
 
-impl Extend<char> for Repr {
+impl<const N: usize, A: Allocator, S: Storage<A>> Extend<char> for Repr<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     #[inline]
     fn extend<T: IntoIterator<Item = char>>(&mut self, iter: T) {
         let mut iterator = iter.into_iter().peekable();
@@ -281,18 +1133,24 @@ impl Extend<char> for Repr {
             return;
         }
         let (lower_bound, _) = iterator.size_hint();
+        // Note: We might need to heap allocate, and can't borrow our allocator while we're
+        // mutably borrowing `self` below
+        let alloc = self.allocator().clone();
 
         match self.cast_mut() {
-            MutStrongRepr::Heap(heap) => heap.string.extend(iterator),
+            // SAFETY: The `HeapString` was allocated from our allocator, which `alloc` is a clone
+            // of
+            MutStrongRepr::Heap(heap) => unsafe { heap.extend(iterator, &alloc) },
             MutStrongRepr::Inline(inline) => {
                 // Check if the lower_bound of the iterator indicates we'll need to heap allocate
-                if lower_bound + inline.len() > MAX_SIZE {
-                    let mut heap = HeapString::with_additional(inline.as_str(), lower_bound);
-                    heap.string.extend(iterator);
+                if lower_bound + inline.len() > N {
+                    let mut heap =
+                        HeapString::with_additional(inline.as_bytes(), lower_bound, &alloc);
+                    // SAFETY: We just allocated `heap` from `alloc`
+                    unsafe { heap.extend(iterator, &alloc) };
 
                     // Replace `self` with the new Repr
-                    let heap = ManuallyDrop::new(heap);
-                    *self = Repr { heap };
+                    *self = Self::from_heap(heap, alloc);
                     return;
                 }
 
@@ -302,7 +1160,7 @@ impl Extend<char> for Repr {
                     let inline_len = inline.len();
                     let char_len = ch.len_utf8();
 
-                    if inline_len + char_len <= MAX_SIZE {
+                    if inline_len + char_len <= N {
                         // SAFTEY: We're writing a `char` into the buffer, which we know is valid
                         // UTF-8
                         let buffer = unsafe { inline.as_mut_slice() };
@@ -314,16 +1172,19 @@ impl Extend<char> for Repr {
                     } else {
                         // We can't fit the remainder of the iterator in an InlineString, so we
                         // either need to make a HeapString
-                        let mut heap = HeapString::with_additional(inline.as_str(), lower_bound);
+                        let mut heap =
+                            HeapString::with_additional(inline.as_bytes(), lower_bound, &alloc);
 
-                        // push the char we just popped off, but couldn't fit inline
-                        heap.string.push(ch);
-                        // write in the rest of the iterator!
-                        heap.string.extend(iterator);
+                        // SAFETY: We just allocated `heap` from `alloc`
+                        unsafe {
+                            // push the char we just popped off, but couldn't fit inline
+                            heap.push(ch, &alloc);
+                            // write in the rest of the iterator!
+                            heap.extend(iterator, &alloc);
+                        }
 
                         // Replace `self` with the new Repr
-                        let heap = ManuallyDrop::new(heap);
-                        *self = Repr { heap };
+                        *self = Self::from_heap(heap, alloc);
 
                         // All done!
                         return;
@@ -334,48 +1195,65 @@ impl Extend<char> for Repr {
     }
 }
 
-impl<'a> Extend<&'a char> for Repr {
+impl<'a, const N: usize, A: Allocator, S: Storage<A>> Extend<&'a char> for Repr<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn extend<T: IntoIterator<Item = &'a char>>(&mut self, iter: T) {
         self.extend(iter.into_iter().copied());
     }
 }
 
-impl<'a> Extend<&'a str> for Repr {
+impl<'a, const N: usize, A: Allocator, S: Storage<A>> Extend<&'a str> for Repr<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn extend<T: IntoIterator<Item = &'a str>>(&mut self, iter: T) {
         iter.into_iter().for_each(|s| self.push_str(s));
     }
 }
 
-impl Extend<Box<str>> for Repr {
+impl<const N: usize, A: Allocator, S: Storage<A>> Extend<Box<str>> for Repr<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn extend<T: IntoIterator<Item = Box<str>>>(&mut self, iter: T) {
         iter.into_iter().for_each(move |s| self.push_str(&s));
     }
 }
 
-impl<'a> Extend<Cow<'a, str>> for Repr {
+impl<'a, const N: usize, A: Allocator, S: Storage<A>> Extend<Cow<'a, str>> for Repr<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn extend<T: IntoIterator<Item = Cow<'a, str>>>(&mut self, iter: T) {
         iter.into_iter().for_each(move |s| self.push_str(&s));
     }
 }
 
-impl Extend<String> for Repr {
+impl<const N: usize, A: Allocator, S: Storage<A>> Extend<String> for Repr<N, A, S>
+where
+    InlineCapacity<N>: ValidInlineCapacity,
+{
     fn extend<T: IntoIterator<Item = String>>(&mut self, iter: T) {
         iter.into_iter().for_each(move |s| self.push_str(&s));
     }
 }
 
 #[derive(Debug)]
-enum StrongRepr<'a> {
-    Inline(&'a InlineString),
-    Heap(&'a ManuallyDrop<HeapString>),
+enum StrongRepr<'a, const N: usize, A: Allocator, S: Storage<A>> {
+    Inline(&'a InlineString<N>),
+    Heap(&'a HeapString<A, S>),
+    Static(&'a StaticStr),
 }
 
-impl<'a> StrongRepr<'a> {
+impl<'a, const N: usize, A: Allocator, S: Storage<A>> StrongRepr<'a, N, A, S> {
     #[inline]
     pub fn len(self) -> usize {
         match self {
             Self::Inline(inline) => inline.len(),
-            Self::Heap(heap) => heap.string.len(),
+            Self::Heap(heap) => heap.len(),
+            Self::Static(static_str) => static_str.len(),
         }
     }
 
@@ -383,7 +1261,8 @@ impl<'a> StrongRepr<'a> {
     pub fn capacity(self) -> usize {
         match self {
             Self::Inline(inline) => inline.capacity(),
-            Self::Heap(heap) => heap.string.capacity(),
+            Self::Heap(heap) => heap.capacity(),
+            Self::Static(static_str) => static_str.len(),
         }
     }
 
@@ -391,7 +1270,8 @@ impl<'a> StrongRepr<'a> {
     pub fn into_str(self) -> &'a str {
         match self {
             Self::Inline(inline) => inline.as_str(),
-            Self::Heap(heap) => heap.string.as_str(),
+            Self::Heap(heap) => heap.as_str(),
+            Self::Static(static_str) => static_str.as_str(),
         }
     }
 
@@ -399,18 +1279,19 @@ impl<'a> StrongRepr<'a> {
     pub fn into_slice(self) -> &'a [u8] {
         match self {
             Self::Inline(inline) => inline.as_slice(),
-            Self::Heap(heap) => heap.string.as_slice(),
+            Self::Heap(heap) => heap.as_slice(),
+            Self::Static(static_str) => static_str.as_slice(),
         }
     }
 }
 
 #[derive(Debug)]
-enum MutStrongRepr<'a> {
-    Inline(&'a mut InlineString),
-    Heap(&'a mut ManuallyDrop<HeapString>),
+enum MutStrongRepr<'a, const N: usize, A: Allocator, S: Storage<A>> {
+    Inline(&'a mut InlineString<N>),
+    Heap(&'a mut HeapString<A, S>),
 }
 
-impl<'a> MutStrongRepr<'a> {
+impl<'a, const N: usize, A: Allocator, S: Storage<A>> MutStrongRepr<'a, N, A, S> {
     #[inline]
     pub unsafe fn into_mut_slice(self) -> &'a mut [u8] {
         match self {
@@ -419,6 +1300,14 @@ impl<'a> MutStrongRepr<'a> {
         }
     }
 
+    #[inline]
+    pub unsafe fn try_into_mut_slice(self) -> Result<&'a mut [u8], TryReserveError> {
+        match self {
+            Self::Inline(inline) => Ok(inline.as_mut_slice()),
+            Self::Heap(heap) => heap.try_make_mut_slice(),
+        }
+    }
+
     #[inline]
     pub unsafe fn set_len(self, length: usize) {
         match self {
@@ -437,10 +1326,9 @@ crate::asserts::assert_size!(Repr, 12);
 
 #[cfg(test)]
 mod tests {
-    use super::{
-        Repr,
-        MAX_SIZE,
-    };
+    use super::MAX_SIZE;
+
+    type Repr = super::Repr;
 
     #[test]
     fn test_inline_str() {
@@ -662,14 +1550,281 @@ mod tests {
     }
 
     #[test]
-    fn test_from_small_string_then_mutate() {
+    fn test_shrink_to_fit_reinlines() {
+        let mut repr = Repr::new("i am a long string that will be heap allocated");
+
+        repr.truncate(10);
+        assert!(repr.is_heap_allocated());
+
+        // shrinking should move us back onto the stack
+        repr.shrink_to_fit();
+        assert!(!repr.is_heap_allocated());
+        assert_eq!(repr.as_str(), "i am a lon");
+        assert_eq!(repr.capacity(), MAX_SIZE);
+    }
+
+    #[test]
+    fn test_shrink_to_fit_heap() {
+        let long = "i am a long string that will be heap allocated";
+        let mut repr = Repr::new(long);
+
+        repr.reserve(100);
+        assert!(repr.capacity() >= long.len() + 100);
+
+        repr.shrink_to_fit();
+        assert!(repr.is_heap_allocated());
+        assert_eq!(repr.capacity(), long.len());
+        assert_eq!(repr.as_str(), long);
+    }
+
+    #[test]
+    fn test_shrink_to() {
+        let long = "i am a long string that will be heap allocated";
+        let mut repr = Repr::with_capacity(256);
+        repr.push_str(long);
+
+        // we should never shrink below `min_capacity`...
+        repr.shrink_to(100);
+        assert_eq!(repr.capacity(), 100);
+
+        // ...or grow
+        repr.shrink_to(200);
+        assert_eq!(repr.capacity(), 100);
+
+        // ...or shrink below our length
+        repr.shrink_to(0);
+        assert_eq!(repr.capacity(), long.len());
+        assert_eq!(repr.as_str(), long);
+
+        // we can't fit inline while keeping at least `min_capacity`
+        repr.truncate(5);
+        repr.shrink_to(MAX_SIZE + 1);
+        assert!(repr.is_heap_allocated());
+        assert_eq!(repr.capacity(), MAX_SIZE + 1);
+
+        repr.shrink_to(MAX_SIZE);
+        assert!(!repr.is_heap_allocated());
+        assert_eq!(repr.as_str(), "i am ");
+    }
+
+    #[test]
+    fn test_shrink_to_fit_from_small_string() {
+        let mut s = String::with_capacity(4);
+        s.push_str("abc");
+
+        // a `String` with a small capacity gets heap allocated when preserving its capacity...
+        let mut repr = Repr::from_string_preserve_capacity(s);
+        // ...but shrinking should still re-inline it
+        repr.shrink_to_fit();
+        assert!(!repr.is_heap_allocated());
+        assert_eq!(repr.as_str(), "abc");
+    }
+
+    #[test]
+    fn test_static_str_shrink_to_fit_does_not_copy() {
+        let mut repr = Repr::from_static_str(LONG_STATIC);
+        repr.shrink_to_fit();
+
+        assert!(!repr.is_heap_allocated());
+        assert_eq!(repr.as_str().as_ptr(), LONG_STATIC.as_ptr());
+    }
+
+    #[test]
+    fn test_into_string_inline() {
+        let repr = Repr::new("hello world");
+        let s = repr.into_string();
+
+        assert_eq!(s, "hello world");
+    }
+
+    #[test]
+    fn test_into_string_reuses_heap_buffer() {
+        let repr = Repr::new("i am a long string that will be heap allocated");
+        let ptr = repr.as_str().as_ptr();
+        let capacity = repr.capacity();
+
+        let s = repr.into_string();
+
+        // converting into a `String` shouldn't copy the buffer
+        assert_eq!(s, "i am a long string that will be heap allocated");
+        assert_eq!(s.as_ptr(), ptr);
+        assert_eq!(s.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_truncate_packed() {
+        let packed = "i am 24 characters long!";
+        let mut repr = Repr::new(&packed[..MAX_SIZE]);
+        assert_eq!(repr.len(), MAX_SIZE);
+
+        repr.truncate(5);
+
+        // truncating a packed string needs to restore the length in our last byte
+        assert_eq!(repr.len(), 5);
+        assert_eq!(repr.as_str(), &packed[..5]);
+        assert!(!repr.is_heap_allocated());
+    }
+
+    #[test]
+    #[should_panic(expected = "is_char_boundary")]
+    fn test_truncate_non_char_boundary() {
+        let mut repr = Repr::new("🦀");
+        repr.truncate(1);
+    }
+
+    #[test]
+    fn test_insert_str_can_heap_allocate() {
+        let mut repr = Repr::new("hello world");
+        repr.insert_str(5, ", this is a long string that will be heap allocated,");
+
+        assert_eq!(
+            repr.as_str(),
+            "hello, this is a long string that will be heap allocated, world"
+        );
+        assert!(repr.is_heap_allocated());
+    }
+
+    #[test]
+    fn test_insert_makes_packed() {
+        let mut repr = Repr::new("a".repeat(MAX_SIZE - 1));
+        repr.insert(0, 'b');
+
+        assert_eq!(repr.len(), MAX_SIZE);
+        assert!(repr.as_str().starts_with('b'));
+        assert!(!repr.is_heap_allocated());
+    }
+
+    #[test]
+    fn test_remove_from_packed() {
+        let mut packed = "a".repeat(MAX_SIZE - 1);
+        packed.push('b');
+        let mut repr = Repr::new(&packed);
+
+        assert_eq!(repr.remove(MAX_SIZE - 1), 'b');
+        assert_eq!(repr.len(), MAX_SIZE - 1);
+        assert_eq!(repr.as_str(), &packed[..MAX_SIZE - 1]);
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut repr = Repr::new("i am a long string that will be heap allocated");
+        repr.retain(|c| c != ' ');
+
+        assert_eq!(repr.as_str(), "iamalongstringthatwillbeheapallocated");
+        assert!(repr.is_heap_allocated());
+
+        let mut repr = Repr::new("🦀a🦀b🦀c");
+        repr.retain(|c| c == '🦀');
+
+        assert_eq!(repr.as_str(), "🦀🦀🦀");
+    }
+
+    #[test]
+    fn test_retain_panic() {
+        let mut repr = Repr::new("a_b_c_d");
+        let mut count = 0;
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            repr.retain(|c| {
+                count += 1;
+                if count == 4 {
+                    panic!("oops");
+                }
+                c != '_'
+            })
+        }));
+        assert!(result.is_err());
+
+        // we should only keep the characters we've already processed
+        assert_eq!(repr.as_str(), "ab");
+    }
+
+    #[test]
+    fn test_replace_range() {
+        let mut repr = Repr::new("hello world");
+
+        repr.replace_range(0..5, "goodbye");
+        assert_eq!(repr.as_str(), "goodbye world");
+
+        repr.replace_range(7.., "");
+        assert_eq!(repr.as_str(), "goodbye");
+
+        repr.replace_range(..=3, "🦀");
+        assert_eq!(repr.as_str(), "🦀bye");
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_replace_range_out_of_bounds() {
+        let mut repr = Repr::new("hello");
+        repr.replace_range(3..10, "");
+    }
+
+    #[test]
+    fn test_split_off() {
+        let mut repr = Repr::new("i am a long string that will be heap allocated");
+        let other = repr.split_off(36);
+
+        assert_eq!(repr.as_str(), "i am a long string that will be heap");
+        assert_eq!(other.as_str(), " allocated");
+        // the split off half is short, so it should be inlined
+        assert!(!other.is_heap_allocated());
+    }
+
+    #[test]
+    fn test_from_small_string_inlines() {
+        let s = String::from("hello world");
+        let repr = Repr::from_string(s);
+
+        assert_eq!(repr.capacity(), MAX_SIZE);
+        assert!(!repr.is_heap_allocated());
+        assert_eq!(repr.as_str(), "hello world");
+    }
+
+    #[test]
+    fn test_from_small_string_with_capacity_inlines() {
+        let mut s = String::with_capacity(100);
+        s.push_str("hello world");
+        let repr = Repr::from_string(s);
+
+        assert_eq!(repr.capacity(), MAX_SIZE);
+        assert!(!repr.is_heap_allocated());
+        assert_eq!(repr.as_str(), "hello world");
+    }
+
+    #[test]
+    fn test_from_small_box_str_inlines() {
+        let b = String::from("hello world").into_boxed_str();
+        let repr = Repr::from_box_str(b);
+
+        assert_eq!(repr.capacity(), MAX_SIZE);
+        assert!(!repr.is_heap_allocated());
+        assert_eq!(repr.as_str(), "hello world");
+    }
+
+    #[test]
+    fn test_from_long_string_reuses_buffer() {
+        let s = String::from("i am a long string that will be stored on the heap");
+        let ptr = s.as_ptr();
+        let repr = Repr::from_string(s);
+
+        assert!(repr.is_heap_allocated());
+        assert_eq!(repr.as_str().as_ptr(), ptr);
+        assert_eq!(
+            repr.as_str(),
+            "i am a long string that will be stored on the heap"
+        );
+    }
+
+    #[test]
+    fn test_from_small_string_preserve_capacity_then_mutate() {
         let s = String::from("hello world");
         assert_eq!(s.capacity(), 11);
 
-        let mut repr = Repr::from_string(s);
+        let mut repr = Repr::from_string_preserve_capacity(s);
 
-        // When converting from a String, we defer inlining the string until necessary to prevent
-        // dropping the heap allocated buffer
+        // When preserving the capacity of a String, we defer inlining the string until necessary
+        // to prevent dropping the heap allocated buffer
         assert_eq!(repr.capacity(), 11);
         assert!(repr.is_heap_allocated());
 
@@ -682,24 +1837,102 @@ mod tests {
         assert_eq!(repr.as_str(), "hello world!");
     }
 
+    const LONG_STATIC: &str = "i am a long static string that is stored as a pointer";
+
     #[test]
-    fn test_from_small_box_str_then_mutate() {
-        let b = String::from("hello world").into_boxed_str();
-        assert_eq!(b.len(), 11);
+    fn test_from_static_str() {
+        const REPR: Repr = Repr::from_static_str(LONG_STATIC);
+        let repr = REPR;
 
-        let mut repr = Repr::from_box_str(b);
+        assert_eq!(repr.as_str(), LONG_STATIC);
+        assert_eq!(repr.as_str().as_ptr(), LONG_STATIC.as_ptr());
+        assert_eq!(repr.len(), LONG_STATIC.len());
+        assert!(!repr.is_heap_allocated());
 
-        // When converting from a Box<str>, we defer inlining the string until necessary to prevent
-        // dropping the heap allocated buffer
-        assert_eq!(repr.capacity(), 11);
+        let clone = repr.clone();
+        assert_eq!(clone.as_str().as_ptr(), LONG_STATIC.as_ptr());
+    }
+
+    #[test]
+    fn test_from_static_str_short_is_inline() {
+        let repr = Repr::from_static_str("hello");
+
+        assert_eq!(repr.as_str(), "hello");
+        assert_ne!(repr.as_str().as_ptr(), "hello".as_ptr());
+    }
+
+    #[test]
+    fn test_static_str_copy_on_write() {
+        let mut repr = Repr::from_static_str(LONG_STATIC);
+        repr.push_str("!!");
+
+        assert_eq!(repr.as_str(), format!("{}!!", LONG_STATIC));
         assert!(repr.is_heap_allocated());
+    }
+
+    #[test]
+    fn test_static_str_truncate_does_not_copy() {
+        let mut repr = Repr::from_static_str(LONG_STATIC);
+        repr.truncate(11);
+
+        assert_eq!(repr.as_str(), "i am a long");
+        assert_eq!(repr.as_str().as_ptr(), LONG_STATIC.as_ptr());
+        assert!(!repr.is_heap_allocated());
 
+        // pushing onto the truncated string should inline it
         repr.push('!');
+        assert_eq!(repr.as_str(), "i am a long!");
+        assert!(!repr.is_heap_allocated());
+        assert_ne!(repr.as_str().as_ptr(), LONG_STATIC.as_ptr());
+    }
 
-        // Once we push a character we'll need to resize, it's at this point we'll inline the string
-        // since we need to drop the original buffer anyways
-        assert_eq!(repr.capacity(), MAX_SIZE);
+    #[test]
+    fn test_static_str_mutate_in_place() {
+        let mut repr = Repr::from_static_str(LONG_STATIC);
+        repr.replace_range(0..4, "you are");
+
+        assert_eq!(
+            repr.as_str(),
+            "you are a long static string that is stored as a pointer"
+        );
+    }
+
+    #[test]
+    fn test_static_str_into_string() {
+        let repr = Repr::from_static_str(LONG_STATIC);
+        assert_eq!(repr.into_string(), LONG_STATIC);
+    }
+
+    #[test]
+    fn test_try_reserve() {
+        let mut repr = Repr::new("hello");
+
+        repr.try_reserve(100).unwrap();
+        assert!(repr.capacity() >= 105);
+        assert!(repr.is_heap_allocated());
+        assert_eq!(repr.as_str(), "hello");
+    }
+
+    #[test]
+    fn test_try_reserve_overflow() {
+        let mut repr = Repr::new("hello");
+
+        let err = repr.try_reserve(usize::MAX).unwrap_err();
+        assert!(err.is_capacity_overflow());
+        assert_eq!(repr.as_str(), "hello");
         assert!(!repr.is_heap_allocated());
-        assert_eq!(repr.as_str(), "hello world!");
+    }
+
+    #[test]
+    fn test_try_with_capacity_overflow() {
+        let err = Repr::try_with_capacity(usize::MAX).err().unwrap();
+        assert!(err.is_capacity_overflow());
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn test_reserve_overflow_panics() {
+        let mut repr = Repr::new("hello");
+        repr.reserve(usize::MAX);
     }
 }
@@ -0,0 +1,116 @@
+use core::{
+    slice,
+    str,
+};
+
+use super::{
+    MAX_SIZE,
+    STATIC_MASK,
+};
+
+const USIZE_SIZE: usize = core::mem::size_of::<usize>();
+// A `StaticStr` is only two words long, so we need to pad it out to the size of a `String`, and
+// explicitly store our discriminant in the last byte
+const PADDING_SIZE: usize = MAX_SIZE - 2 * USIZE_SIZE - 1;
+
+/// A borrowed `&'static str`, stored as a pointer and length so the layout matches the first two
+/// words of a `Repr`.
+///
+/// Note: we never mutate the underlying string, any mutation of a `Repr` first copies a
+/// `StaticStr` into an `InlineString` or `HeapString`
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct StaticStr {
+    ptr: *const u8,
+    len: usize,
+    _padding: [u8; PADDING_SIZE],
+    discriminant: u8,
+}
+
+impl StaticStr {
+    #[inline]
+    pub const fn new(text: &'static str) -> Self {
+        StaticStr {
+            ptr: text.as_ptr(),
+            len: text.len(),
+            _padding: [0; PADDING_SIZE],
+            discriminant: STATIC_MASK,
+        }
+    }
+
+    /// Returns the leading bytes of a [`StaticStr`], i.e. everything but the pointer and the
+    /// discriminant, so we can construct a `Repr` in a `const` context
+    #[inline]
+    pub const fn data(&self) -> [u8; MAX_SIZE - USIZE_SIZE - 1] {
+        let len = self.len.to_ne_bytes();
+
+        // Note: for loops aren't allowed in `const fn`, hence the while
+        let mut data = [0u8; MAX_SIZE - USIZE_SIZE - 1];
+        let mut i = 0;
+        while i < USIZE_SIZE {
+            data[i] = len[i];
+            i += 1;
+        }
+
+        data
+    }
+
+    #[inline]
+    pub const fn as_ptr(&self) -> *const u8 {
+        self.ptr
+    }
+
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> &'static str {
+        // SAFETY: A `StaticStr` can only be created from a `&'static str`, and we only ever
+        // shorten it on a char boundary
+        unsafe { str::from_utf8_unchecked(self.as_slice()) }
+    }
+
+    #[inline]
+    pub fn as_slice(&self) -> &'static [u8] {
+        // SAFETY: `ptr` and `len` came from a `&'static str`
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+
+    /// Shortens the [`StaticStr`], which doesn't require copying the underlying string
+    ///
+    /// # Safety
+    /// * `length` must be less than or equal to the current length
+    /// * `length` must fall on a char boundary
+    #[inline]
+    pub unsafe fn set_len(&mut self, length: usize) {
+        debug_assert!(length <= self.len);
+        self.len = length;
+    }
+}
+
+crate::asserts::assert_size_eq!(StaticStr, alloc::string::String);
+
+#[cfg(test)]
+mod tests {
+    use super::StaticStr;
+
+    #[test]
+    fn test_static_str() {
+        let text = "this is a static string that is pretty long";
+        let s = StaticStr::new(text);
+
+        assert_eq!(s.as_str(), text);
+        assert_eq!(s.as_ptr(), text.as_ptr());
+        assert_eq!(s.len(), text.len());
+    }
+
+    #[test]
+    fn test_set_len() {
+        let mut s = StaticStr::new("hello world");
+        unsafe { s.set_len(5) };
+
+        assert_eq!(s.as_str(), "hello");
+    }
+}
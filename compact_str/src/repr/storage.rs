@@ -0,0 +1,52 @@
+//! The ways a [`Repr`](super::Repr) can store a heap allocated string
+
+use super::arc::ArcString;
+use super::boxed::BoxString;
+use super::heap::HeapBuffer;
+use crate::Allocator;
+
+/// [`Storage`] that uniquely owns its heap allocated buffer, like a [`String`].
+///
+/// This is the storage of a [`CompactStr`](crate::CompactStr). Converting from and into a
+/// [`String`], [`Box<str>`](alloc::boxed::Box), or [`Vec<u8>`](alloc::vec::Vec) re-uses the
+/// underlying buffer, and cloning a heap allocated string copies it.
+///
+/// [`String`]: alloc::string::String
+#[derive(Debug, Clone, Copy)]
+pub struct Boxed;
+
+/// [`Storage`] that shares its heap allocated buffer between clones, like an `Arc<str>`.
+///
+/// This is the storage of a [`SharedCompactStr`](crate::SharedCompactStr). Cloning a heap
+/// allocated string, or taking a substring of one, increments a reference count instead of
+/// copying, and mutating a shared buffer copies it first. The reference count is stored next to
+/// the string data, so converting from or into a [`String`], [`Box<str>`](alloc::boxed::Box), or
+/// [`Vec<u8>`](alloc::vec::Vec) always copies.
+///
+/// [`String`]: alloc::string::String
+#[derive(Debug, Clone, Copy)]
+pub struct Shared;
+
+/// How a [`CompactStrN`](crate::CompactStrN) stores a string that doesn't fit inline, either
+/// [`Boxed`] or [`Shared`].
+///
+/// This trait is sealed, so it can't be implemented outside of `compact_str`.
+pub trait Storage<A: Allocator>: HeapStorage<A> + 'static {}
+
+/// The heap allocated buffer of a [`Storage`].
+///
+/// Note: Without generic associated types, which require Rust 1.65, the buffer can't be generic
+/// over the allocator, so instead the storage is generic over the allocator
+pub trait HeapStorage<A: Allocator> {
+    type Buffer: HeapBuffer<A>;
+}
+
+impl<A: Allocator> HeapStorage<A> for Boxed {
+    type Buffer = BoxString;
+}
+impl<A: Allocator> Storage<A> for Boxed {}
+
+impl<A: Allocator> HeapStorage<A> for Shared {
+    type Buffer = ArcString<A>;
+}
+impl<A: Allocator> Storage<A> for Shared {}
@@ -0,0 +1,534 @@
+//! A two word representation of a string, used by [`CompactStr16`](crate::CompactStr16).
+//!
+//! A [`ThinRepr`] is either inline, storing up to [`MAX_INLINE`] bytes followed by their length
+//! tagged with `LENGTH_MASK`, or it's heap allocated. When heap allocated, the first word is a
+//! pointer to a [`ThinHeader`] which stores the capacity, followed by the string itself, and the
+//! length is packed into the remaining bytes of the second word, followed by `HEAP_MASK`.
+//!
+//! ```text
+//! inline: [ up to 15 bytes of the string ................. | len | LENGTH_MASK ]
+//! heap:   [ *const ThinHeader        | len (7 bytes, little endian) | HEAP_MASK ]
+//! ```
+
+use alloc::alloc::Layout;
+use alloc::string::String;
+use core::mem::MaybeUninit;
+use core::ops::RangeBounds;
+use core::ptr;
+
+use super::discriminant::LastByte;
+use super::inline::LENGTH_MASK;
+use super::{
+    char_range,
+    USIZE_SIZE,
+};
+use crate::error::handle_reserve;
+use crate::{
+    Allocator,
+    FromUtf8Error,
+    Global,
+    TryReserveError,
+};
+
+/// The size of a [`ThinRepr`], two words
+pub const THIN_SIZE: usize = 2 * USIZE_SIZE;
+/// The maximum number of bytes we can store inline, the last byte is always our discriminant
+pub const MAX_INLINE: usize = THIN_SIZE - 1;
+/// The number of bytes of the second word we pack the length of a heap string into
+const LEN_SIZE: usize = USIZE_SIZE - 1;
+/// The largest length, and capacity, we can pack into [`LEN_SIZE`] bytes, i.e. `2^56 - 1` on
+/// 64-bit architectures and `2^24 - 1` on 32-bit architectures
+pub const MAX_LEN: usize = usize::MAX >> 8;
+
+const UNKNOWN: usize = 0;
+type StrBuffer = [u8; UNKNOWN];
+
+/// The start of every heap allocation, the string itself directly follows the header
+#[repr(C)]
+struct ThinHeader {
+    capacity: usize,
+    buffer: StrBuffer,
+}
+
+#[repr(C)]
+pub struct ThinRepr {
+    // Either the first word of an inline string, or a pointer to a `ThinHeader`
+    ptr: *mut ThinHeader,
+    // Either the next bytes of an inline string, or the length of a heap string
+    data: MaybeUninit<[u8; LEN_SIZE]>,
+    // Either the length of an inline string tagged with `LENGTH_MASK`, or `HEAP_MASK`
+    last_byte: LastByte,
+}
+// SAFETY: A `ThinRepr` uniquely owns its heap allocation, like a `String`
+unsafe impl Send for ThinRepr {}
+unsafe impl Sync for ThinRepr {}
+
+crate::asserts::assert_size!(ThinRepr, THIN_SIZE);
+
+impl ThinRepr {
+    #[inline]
+    pub fn new(text: &str) -> Self {
+        let len = text.len();
+
+        if len <= MAX_INLINE {
+            ThinRepr::new_inline(text)
+        } else {
+            let mut repr = ThinRepr::with_capacity(len);
+            repr.push_str(text);
+            repr
+        }
+    }
+
+    /// Creates an inline [`ThinRepr`], usable in a `const` context.
+    ///
+    /// Note: `core::mem::transmute` isn't a `const fn` on our MSRV, so we build each field from
+    /// the bytes of `text` instead
+    #[inline]
+    pub const fn new_inline(text: &str) -> Self {
+        let len = text.len();
+
+        if len > MAX_INLINE {
+            // HACK: This allows us to make assertions within a `const fn` without requiring
+            // nightly, see unstable `const_panic` feature. This results in a build
+            // failure, not a runtime panic
+            #[allow(clippy::no_effect)]
+            #[allow(unconditional_panic)]
+            ["Provided string has a length greater than MAX_INLINE!"][42];
+        }
+
+        // Note: for loops aren't allowed in `const fn`, hence the while
+        let mut buffer = [0u8; THIN_SIZE];
+        let mut i = 0;
+        while i < len {
+            buffer[i] = text.as_bytes()[i];
+            i += 1;
+        }
+
+        let mut ptr = [0u8; USIZE_SIZE];
+        let mut i = 0;
+        while i < USIZE_SIZE {
+            ptr[i] = buffer[i];
+            i += 1;
+        }
+
+        let mut data = [0u8; LEN_SIZE];
+        let mut i = 0;
+        while i < LEN_SIZE {
+            data[i] = buffer[USIZE_SIZE + i];
+            i += 1;
+        }
+
+        ThinRepr {
+            ptr: usize::from_ne_bytes(ptr) as *mut ThinHeader,
+            data: MaybeUninit::new(data),
+            last_byte: LastByte::from_u8(len as u8 | LENGTH_MASK),
+        }
+    }
+
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        handle_reserve(ThinRepr::try_with_capacity(capacity))
+    }
+
+    #[inline]
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+        if capacity <= MAX_INLINE {
+            return Ok(ThinRepr::new_inline(""));
+        }
+        if capacity > MAX_LEN {
+            return Err(TryReserveError::capacity_overflow());
+        }
+
+        let layout = layout(capacity)?;
+        // Note: The layout always includes the header, so it's never zero-sized
+        let ptr = Global
+            .allocate(layout)
+            .map_err(|_| TryReserveError::alloc_error(layout))?
+            .cast::<ThinHeader>();
+
+        // SAFETY: We just allocated space for a `ThinHeader`, and checked the pointer isn't null
+        unsafe { (*ptr.as_ptr()).capacity = capacity };
+
+        let mut repr = ThinRepr {
+            ptr: ptr.as_ptr(),
+            data: MaybeUninit::uninit(),
+            last_byte: LastByte::Heap,
+        };
+        // SAFETY: A length of 0 is always valid
+        unsafe { repr.set_len(0) };
+
+        Ok(repr)
+    }
+
+    #[inline]
+    pub fn from_utf8(buf: &[u8]) -> Result<Self, FromUtf8Error> {
+        match core::str::from_utf8(buf) {
+            Ok(s) => Ok(ThinRepr::new(s)),
+            // Note: We only borrow the buffer, so we need to copy it to give the bytes back
+            Err(e) => Err(FromUtf8Error::new(buf.to_vec(), e)),
+        }
+    }
+
+    #[inline]
+    pub fn into_string(self) -> String {
+        String::from(self.as_str())
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        if self.is_heap_allocated() {
+            let mut bytes = [0u8; USIZE_SIZE];
+            bytes[..LEN_SIZE].copy_from_slice(&self.as_raw_bytes()[USIZE_SIZE..MAX_INLINE]);
+            usize::from_le_bytes(bytes)
+        } else {
+            (self.last_byte as u8 & !LENGTH_MASK) as usize
+        }
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        if self.is_heap_allocated() {
+            // SAFETY: We're heap allocated, so `ptr` points to a valid `ThinHeader`
+            unsafe { (*self.ptr).capacity }
+        } else {
+            MAX_INLINE
+        }
+    }
+
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        handle_reserve(self.try_reserve(additional))
+    }
+
+    #[inline]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let len = self.len();
+        let required = len
+            .checked_add(additional)
+            .ok_or_else(TryReserveError::capacity_overflow)?;
+
+        // We already have at least `additional` capacity, so we don't need to do anything
+        if self.capacity() >= required {
+            return Ok(());
+        }
+
+        let amortized = core::cmp::min(3 * self.capacity() / 2, MAX_LEN);
+        let new_capacity = core::cmp::max(amortized, required);
+        self.try_realloc(new_capacity)
+    }
+
+    #[inline]
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink_to(0)
+    }
+
+    #[inline]
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        let new_capacity = core::cmp::max(self.len(), min_capacity);
+
+        // Only heap allocated strings can have excess capacity
+        if self.is_heap_allocated() && new_capacity < self.capacity() {
+            // Note: Shrinking should never fail, but if the allocator does, keep our old buffer
+            let _ = self.try_realloc(new_capacity);
+        }
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        // SAFETY: The first `len` bytes of our buffer are always valid UTF-8
+        unsafe { core::str::from_utf8_unchecked(&self.as_slice()[..self.len()]) }
+    }
+
+    /// Returns the entire buffer of this [`ThinRepr`], including any spare capacity
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        if self.is_heap_allocated() {
+            // SAFETY: We're heap allocated, so `ptr` points to a `ThinHeader` followed by
+            // `capacity` bytes
+            unsafe { core::slice::from_raw_parts(buffer_ptr(self.ptr), (*self.ptr).capacity) }
+        } else {
+            &self.as_raw_bytes()[..MAX_INLINE]
+        }
+    }
+
+    /// Returns the entire buffer of this [`ThinRepr`], including any spare capacity
+    ///
+    /// # Safety
+    /// * The caller must make sure the first `len` bytes remain valid UTF-8
+    #[inline]
+    pub unsafe fn as_mut_slice(&mut self) -> &mut [u8] {
+        if self.is_heap_allocated() {
+            core::slice::from_raw_parts_mut(buffer_ptr(self.ptr), (*self.ptr).capacity)
+        } else {
+            // Note: We never expose our last byte, it's the length of the string
+            &mut self.as_raw_bytes_mut()[..MAX_INLINE]
+        }
+    }
+
+    #[inline]
+    pub fn as_mut_str(&mut self) -> &mut str {
+        let len = self.len();
+        // SAFETY: We only expose the first `len` bytes, which are valid UTF-8, and safe code can
+        // only modify a `&mut str` in ways that keep it valid UTF-8
+        unsafe { core::str::from_utf8_unchecked_mut(&mut self.as_mut_slice()[..len]) }
+    }
+
+    #[inline]
+    pub fn push(&mut self, ch: char) {
+        let mut buf = [0u8; 4];
+        self.push_str(ch.encode_utf8(&mut buf));
+    }
+
+    #[inline]
+    pub fn pop(&mut self) -> Option<char> {
+        let ch = self.as_str().chars().next_back()?;
+
+        // SAFETY: We know this is is a valid length which falls on a char boundary
+        unsafe { self.set_len(self.len() - ch.len_utf8()) };
+
+        Some(ch)
+    }
+
+    #[inline]
+    pub fn push_str(&mut self, s: &str) {
+        handle_reserve(self.try_push_str(s))
+    }
+
+    #[inline]
+    pub fn try_push_str(&mut self, s: &str) -> Result<(), TryReserveError> {
+        let len = self.len();
+        self.try_reserve(s.len())?;
+
+        // SAFETY: We're copying in a `&str`, which is valid UTF-8, and we just reserved space
+        unsafe {
+            self.as_mut_slice()[len..len + s.len()].copy_from_slice(s.as_bytes());
+            self.set_len(len + s.len());
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn truncate(&mut self, new_len: usize) {
+        if new_len <= self.len() {
+            assert!(self.as_str().is_char_boundary(new_len));
+
+            // SAFETY: `new_len` is less than or equal to our current length, and it falls on a
+            // char boundary, so the remaining string is still valid UTF-8
+            unsafe { self.set_len(new_len) };
+        }
+    }
+
+    #[inline]
+    pub fn insert_str(&mut self, idx: usize, s: &str) {
+        assert!(self.as_str().is_char_boundary(idx));
+
+        // SAFETY: We just asserted `idx` falls on a char boundary, and we're inserting a `&str`
+        // which is valid UTF-8
+        unsafe { self.splice(idx, idx, s.as_bytes()) };
+    }
+
+    #[inline]
+    pub fn remove(&mut self, idx: usize) -> char {
+        let ch = match self.as_str()[idx..].chars().next() {
+            Some(ch) => ch,
+            None => panic!("cannot remove a char from the end of a string"),
+        };
+
+        // SAFETY: `idx` is the start of `ch`, and `idx + ch.len_utf8()` is the start of the next
+        // char, so removing the bytes between them leaves us with valid UTF-8
+        unsafe { self.splice(idx, idx + ch.len_utf8(), &[]) };
+
+        ch
+    }
+
+    #[inline]
+    pub fn retain<F: FnMut(char) -> bool>(&mut self, mut predicate: F) {
+        // If `predicate` panics we need to make sure our length only covers the characters we've
+        // already processed, which are always valid UTF-8. Modeled after `String::retain(...)`
+        struct SetLenOnDrop<'a> {
+            repr: &'a mut ThinRepr,
+            idx: usize,
+            del_bytes: usize,
+        }
+
+        impl Drop for SetLenOnDrop<'_> {
+            fn drop(&mut self) {
+                // SAFETY: Every byte in `[0, idx - del_bytes)` is a char we decided to retain
+                unsafe { self.repr.set_len(self.idx - self.del_bytes) };
+            }
+        }
+
+        let len = self.len();
+        let mut guard = SetLenOnDrop {
+            repr: self,
+            idx: 0,
+            del_bytes: 0,
+        };
+
+        while guard.idx < len {
+            // SAFETY: We haven't modified any bytes at or after `idx` yet, and `idx` always falls
+            // on a char boundary, so these bytes are valid UTF-8
+            let remaining =
+                unsafe { core::str::from_utf8_unchecked(&guard.repr.as_slice()[guard.idx..len]) };
+            let ch = match remaining.chars().next() {
+                Some(ch) => ch,
+                None => break,
+            };
+            let ch_len = ch.len_utf8();
+
+            if !predicate(ch) {
+                guard.del_bytes += ch_len;
+            } else if guard.del_bytes > 0 {
+                // SAFETY: We're moving an entire char towards the front of the buffer, directly
+                // after the last char we retained
+                let slice = unsafe { guard.repr.as_mut_slice() };
+                slice.copy_within(guard.idx..guard.idx + ch_len, guard.idx - guard.del_bytes);
+            }
+
+            // Point `idx` to the next char
+            guard.idx += ch_len;
+        }
+
+        // `guard` gets dropped here, which sets our new length
+    }
+
+    #[inline]
+    pub fn replace_range<R: RangeBounds<usize>>(&mut self, range: R, replace_with: &str) {
+        let (start, end) = char_range(self.as_str(), range);
+
+        // SAFETY: `char_range(...)` asserts that both `start` and `end` fall on char boundaries,
+        // and we're replacing them with a `&str` which is valid UTF-8
+        unsafe { self.splice(start, end, replace_with.as_bytes()) };
+    }
+
+    /// Replaces the bytes in `[start, end)` with `bytes`, see `Repr::splice(...)`
+    ///
+    /// # Safety
+    /// * `start <= end <= self.len()`
+    /// * Both `start` and `end` must fall on char boundaries
+    /// * `bytes` must be valid UTF-8
+    #[inline]
+    unsafe fn splice(&mut self, start: usize, end: usize, bytes: &[u8]) {
+        debug_assert!(start <= end && end <= self.len());
+
+        let len = self.len();
+        let removed = end - start;
+        let inserted = bytes.len();
+
+        // Reserve enough space for the new bytes, possibly causing a heap allocation
+        if inserted > removed {
+            self.reserve(inserted - removed);
+        }
+
+        let slice = self.as_mut_slice();
+        // Shift everything after the range over, to either make room or to close the gap
+        slice.copy_within(end..len, start + inserted);
+        // Write in the new bytes
+        slice[start..start + inserted].copy_from_slice(bytes);
+
+        self.set_len(len - removed + inserted);
+    }
+
+    /// # Safety
+    /// * `length` must be less than or equal to our capacity
+    /// * The first `length` bytes of our buffer must be valid UTF-8
+    #[inline]
+    pub unsafe fn set_len(&mut self, length: usize) {
+        debug_assert!(length <= self.capacity());
+
+        if self.is_heap_allocated() {
+            let bytes = length.to_le_bytes();
+            self.as_raw_bytes_mut()[USIZE_SIZE..MAX_INLINE].copy_from_slice(&bytes[..LEN_SIZE]);
+        } else {
+            self.last_byte = LastByte::from_u8(length as u8 | LENGTH_MASK);
+        }
+    }
+
+    #[inline(always)]
+    pub fn is_heap_allocated(&self) -> bool {
+        self.last_byte == LastByte::Heap
+    }
+
+    /// Moves this string into a buffer with `new_capacity` bytes, inlining it if it fits
+    #[inline]
+    fn try_realloc(&mut self, new_capacity: usize) -> Result<(), TryReserveError> {
+        let len = self.len();
+        debug_assert!(new_capacity >= len);
+
+        let mut new = ThinRepr::try_with_capacity(new_capacity)?;
+        // SAFETY: We're copying our string, which is valid UTF-8, into a buffer we know is large
+        // enough
+        unsafe {
+            new.as_mut_slice()[..len].copy_from_slice(self.as_str().as_bytes());
+            new.set_len(len);
+        }
+
+        // Note: This drops our old representation, deallocating our old buffer
+        *self = new;
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn as_raw_bytes(&self) -> &[u8; THIN_SIZE] {
+        // SAFETY: A `ThinRepr` is `THIN_SIZE` bytes, all of which are initialized, with an
+        // alignment greater than 1
+        unsafe { &*(self as *const ThinRepr as *const [u8; THIN_SIZE]) }
+    }
+
+    /// # Safety
+    /// * The caller must keep the last byte a valid [`LastByte`]
+    #[inline(always)]
+    unsafe fn as_raw_bytes_mut(&mut self) -> &mut [u8; THIN_SIZE] {
+        &mut *(self as *mut ThinRepr as *mut [u8; THIN_SIZE])
+    }
+}
+
+impl Clone for ThinRepr {
+    fn clone(&self) -> Self {
+        if self.is_heap_allocated() {
+            ThinRepr::new(self.as_str())
+        } else {
+            // SAFETY: An inline `ThinRepr` is only bytes, which we can copy
+            unsafe { ptr::read(self) }
+        }
+    }
+}
+
+impl Drop for ThinRepr {
+    fn drop(&mut self) {
+        if self.is_heap_allocated() {
+            // SAFETY: We're heap allocated, so `ptr` points to a `ThinHeader`
+            let capacity = unsafe { (*self.ptr).capacity };
+            // Note: We successfully allocated with this capacity, so we know the layout is valid
+            let layout = layout(capacity).expect("valid layout");
+
+            // SAFETY: We allocated `ptr` from the global allocator, with this layout
+            unsafe { Global.deallocate(ptr::NonNull::new_unchecked(self.ptr as *mut u8), layout) };
+        }
+    }
+}
+
+fn layout(capacity: usize) -> Result<Layout, TryReserveError> {
+    let buffer_layout =
+        Layout::array::<u8>(capacity).map_err(|_| TryReserveError::capacity_overflow())?;
+    let layout = Layout::new::<ThinHeader>()
+        .extend(buffer_layout)
+        .map_err(|_| TryReserveError::capacity_overflow())?
+        .0
+        .pad_to_align();
+
+    Ok(layout)
+}
+
+/// Returns a pointer to the buffer that directly follows a [`ThinHeader`].
+///
+/// Note: We offset from `header` instead of borrowing `ThinHeader::buffer`, since a reference to
+/// a zero-sized array doesn't cover the bytes that follow it
+///
+/// # Safety
+/// * `header` must point to a heap allocated `ThinHeader`
+#[inline(always)]
+unsafe fn buffer_ptr(header: *mut ThinHeader) -> *mut u8 {
+    (header as *mut u8).add(core::mem::size_of::<ThinHeader>())
+}
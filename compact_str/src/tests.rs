@@ -1,10 +1,22 @@
+use core::alloc::Layout;
+use core::cell::Cell;
+use core::convert::TryFrom;
+use core::ptr::NonNull;
 use std::str::FromStr;
 
 use proptest::prelude::*;
 use proptest::strategy::Strategy;
 use test_strategy::proptest;
 
-use crate::CompactStr;
+use crate::{
+    AllocError,
+    Allocator,
+    CompactStr,
+    CompactStrN,
+    Global,
+    Shared,
+    SharedCompactStr,
+};
 
 #[cfg(target_pointer_width = "64")]
 const MAX_SIZE: usize = 24;
@@ -35,8 +47,59 @@ pub fn rand_unicode_with_max_len(len: usize) -> impl Strategy<Value = String> {
     })
 }
 
+/// Counts the allocations made through a [`CountingAllocator`]
+#[derive(Default)]
+struct Counter {
+    allocations: Cell<usize>,
+    deallocations: Cell<usize>,
+    live_bytes: Cell<usize>,
+}
+
+/// An [`Allocator`] that counts its allocations, and forwards them to the [`Global`] allocator
+#[derive(Clone, Copy)]
+struct CountingAllocator<'a> {
+    counter: &'a Counter,
+}
+
+unsafe impl<'a> Allocator for CountingAllocator<'a> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        let ptr = Global.allocate(layout)?;
+        self.counter
+            .allocations
+            .set(self.counter.allocations.get() + 1);
+        self.counter
+            .live_bytes
+            .set(self.counter.live_bytes.get() + layout.size());
+        Ok(ptr)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.counter
+            .deallocations
+            .set(self.counter.deallocations.get() + 1);
+        self.counter
+            .live_bytes
+            .set(self.counter.live_bytes.get() - layout.size());
+        Global.deallocate(ptr, layout)
+    }
+}
+
+/// An [`Allocator`] that always fails
+#[derive(Clone, Copy)]
+struct FailingAllocator;
+
+unsafe impl Allocator for FailingAllocator {
+    fn allocate(&self, _layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        Err(AllocError)
+    }
+
+    unsafe fn deallocate(&self, _ptr: NonNull<u8>, _layout: Layout) {
+        unreachable!("we never allocate")
+    }
+}
+
 /// generates groups upto 40 strings long of random unicode strings, upto 80 chars long
-fn rand_unicode_collection() -> impl Strategy<Value = Vec<String>> {
+pub fn rand_unicode_collection() -> impl Strategy<Value = Vec<String>> {
     proptest::collection::vec(rand_unicode(), 0..40)
 }
 
@@ -96,11 +159,54 @@ fn test_from_bytes_only_valid_utf8(#[strategy(rand_bytes())] bytes: Vec<u8>) {
 
     match (compact_result, word_result) {
         (Ok(c), Ok(s)) => prop_assert_eq!(c, s),
-        (Err(c_err), Err(s_err)) => prop_assert_eq!(c_err, s_err.utf8_error()),
+        (Err(c_err), Err(s_err)) => {
+            prop_assert_eq!(c_err.utf8_error(), s_err.utf8_error());
+            prop_assert_eq!(c_err.into_bytes(), s_err.into_bytes());
+        }
         _ => panic!("CompactStr and core::str read UTF-8 differently?"),
     }
 }
 
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_try_from_vec_matches_std(#[strategy(rand_bytes())] bytes: Vec<u8>) {
+    let compact_result = CompactStr::try_from(bytes.clone());
+    let word_result = String::from_utf8(bytes);
+
+    match (compact_result, word_result) {
+        (Ok(c), Ok(s)) => {
+            prop_assert_eq!(&c, &s);
+            assert_allocated_properly(&c);
+        }
+        (Err(c_err), Err(s_err)) => {
+            prop_assert_eq!(c_err.utf8_error(), s_err.utf8_error());
+            prop_assert_eq!(c_err.into_bytes(), s_err.into_bytes());
+        }
+        _ => panic!("CompactStr and String read UTF-8 differently?"),
+    }
+}
+
+#[test]
+fn test_try_from_vec_reuses_buffer() {
+    let bytes = b"this string is too long to be stored inline".to_vec();
+    let ptr = bytes.as_ptr();
+
+    let compact = CompactStr::try_from(bytes).unwrap();
+    assert_eq!(compact.as_ptr(), ptr);
+}
+
+#[test]
+fn test_try_from_vec_gives_back_buffer() {
+    let mut bytes = b"this string is too long to be stored inline".to_vec();
+    bytes.push(255);
+    let ptr = bytes.as_ptr();
+
+    let err = CompactStr::try_from(bytes).unwrap_err();
+    assert_eq!(err.utf8_error().valid_up_to(), 43);
+    let bytes = err.into_bytes();
+    assert_eq!(bytes.as_ptr(), ptr);
+}
+
 #[proptest]
 #[cfg_attr(miri, ignore)]
 fn test_from_lossy_cow_roundtrips(#[strategy(rand_bytes())] bytes: Vec<u8>) {
@@ -117,6 +223,88 @@ fn test_from_lossy_cow_allocated_properly(#[strategy(rand_bytes())] bytes: Vec<u
     assert_allocated_properly(&compact);
 }
 
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_from_utf8_lossy_matches_std(#[strategy(rand_bytes())] bytes: Vec<u8>) {
+    let compact = CompactStr::from_utf8_lossy(&bytes);
+    let cow = String::from_utf8_lossy(&bytes);
+
+    prop_assert_eq!(&compact, &cow);
+    assert_allocated_properly(&compact);
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_from_utf8_unchecked_roundtrips(#[strategy(rand_unicode())] word: String) {
+    // SAFETY: The bytes come from a String, so they're valid UTF-8
+    let compact = unsafe { CompactStr::from_utf8_unchecked(word.as_bytes()) };
+
+    prop_assert_eq!(&compact, &word);
+    assert_allocated_properly(&compact);
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_from_utf16_roundtrips(#[strategy(rand_unicode())] word: String) {
+    let units: Vec<u16> = word.encode_utf16().collect();
+    let le: Vec<u8> = units
+        .iter()
+        .flat_map(|u| u.to_le_bytes().to_vec())
+        .collect();
+    let be: Vec<u8> = units
+        .iter()
+        .flat_map(|u| u.to_be_bytes().to_vec())
+        .collect();
+
+    let compact = CompactStr::from_utf16(&units).unwrap();
+    prop_assert_eq!(&compact, &word);
+    assert_allocated_properly(&compact);
+
+    prop_assert_eq!(CompactStr::from_utf16_lossy(&units), &word);
+    prop_assert_eq!(CompactStr::from_utf16le(&le).unwrap(), &word);
+    prop_assert_eq!(CompactStr::from_utf16le_lossy(&le), &word);
+    prop_assert_eq!(CompactStr::from_utf16be(&be).unwrap(), &word);
+    prop_assert_eq!(CompactStr::from_utf16be_lossy(&be), &word);
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_from_utf16_matches_std(
+    #[strategy(proptest::collection::vec(any::<u16>(), 0..40))] units: Vec<u16>,
+) {
+    let compact_result = CompactStr::from_utf16(&units);
+    let std_result = String::from_utf16(&units);
+
+    match (compact_result, std_result) {
+        (Ok(c), Ok(s)) => prop_assert_eq!(c, s),
+        (Err(_), Err(_)) => (),
+        _ => panic!("CompactStr and String decode UTF-16 differently?"),
+    }
+
+    let compact = CompactStr::from_utf16_lossy(&units);
+    prop_assert_eq!(&compact, &String::from_utf16_lossy(&units));
+    assert_allocated_properly(&compact);
+}
+
+#[test]
+fn test_from_utf16_bytes_odd_length() {
+    let bytes = [b'a', 0, b'b', 0, b'c'];
+
+    assert!(CompactStr::from_utf16le(bytes).is_err());
+    assert_eq!(CompactStr::from_utf16le_lossy(bytes), "ab\u{FFFD}");
+
+    let bytes = [0, b'a', 0, b'b', 0];
+
+    assert!(CompactStr::from_utf16be(bytes).is_err());
+    assert_eq!(CompactStr::from_utf16be_lossy(bytes), "ab\u{FFFD}");
+}
+
+#[test]
+fn test_from_utf16_error_message() {
+    let err = CompactStr::from_utf16([0xD800]).unwrap_err();
+    assert_eq!(err.to_string(), "invalid utf-16: lone surrogate found");
+}
+
 #[proptest]
 #[cfg_attr(miri, ignore)]
 fn test_reserve_and_write_bytes(#[strategy(rand_unicode())] word: String) {
@@ -178,6 +366,339 @@ fn test_extend_chars_allocated_properly(
     assert_allocated_properly(&compact);
 }
 
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_truncate_roundtrips(#[strategy(rand_unicode())] word: String, new_len: usize) {
+    let mut compact = CompactStr::new(&word);
+    let mut control = word.clone();
+
+    // find the closest char boundary to truncate at
+    let mut new_len = new_len % (word.len() + 1);
+    while !word.is_char_boundary(new_len) {
+        new_len -= 1;
+    }
+
+    compact.truncate(new_len);
+    control.truncate(new_len);
+
+    prop_assert_eq!(&compact, &control);
+    prop_assert_eq!(compact.len(), control.len());
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_insert_str_roundtrips(
+    #[strategy(rand_unicode())] word: String,
+    #[strategy(rand_unicode())] insert: String,
+    idx: usize,
+) {
+    let mut compact = CompactStr::new(&word);
+    let mut control = word.clone();
+
+    // find the closest char boundary to insert at
+    let mut idx = idx % (word.len() + 1);
+    while !word.is_char_boundary(idx) {
+        idx -= 1;
+    }
+
+    compact.insert_str(idx, &insert);
+    control.insert_str(idx, &insert);
+
+    prop_assert_eq!(&compact, &control);
+    prop_assert_eq!(compact.len(), control.len());
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_retain_roundtrips(#[strategy(rand_unicode())] word: String) {
+    let mut compact = CompactStr::new(&word);
+    let mut control = word.clone();
+
+    compact.retain(|c| c.is_ascii());
+    control.retain(|c| c.is_ascii());
+
+    prop_assert_eq!(&compact, &control);
+    prop_assert_eq!(compact.len(), control.len());
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_drain_roundtrips(#[strategy(rand_unicode())] word: String, a: usize, b: usize) {
+    let mut compact = CompactStr::new(&word);
+    let mut control = word.clone();
+
+    // find the closest char boundaries to drain between
+    let mut start = core::cmp::min(a, b) % (word.len() + 1);
+    let mut end = core::cmp::max(a, b) % (word.len() + 1);
+    if start > end {
+        core::mem::swap(&mut start, &mut end);
+    }
+    while !word.is_char_boundary(start) {
+        start -= 1;
+    }
+    while !word.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    let drained: CompactStr = compact.drain(start..end).collect();
+    let control_drained: String = control.drain(start..end).collect();
+
+    prop_assert_eq!(&drained, &control_drained);
+    prop_assert_eq!(&compact, &control);
+    prop_assert_eq!(compact.len(), control.len());
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_substr_roundtrips(
+    #[strategy(rand_unicode())] word: String,
+    #[strategy(rand_unicode())] push: String,
+    a: usize,
+    b: usize,
+) {
+    let compact = CompactStr::new(&word);
+
+    // find the closest char boundaries to take a substring between
+    let mut start = core::cmp::min(a, b) % (word.len() + 1);
+    let mut end = core::cmp::max(a, b) % (word.len() + 1);
+    if start > end {
+        core::mem::swap(&mut start, &mut end);
+    }
+    while !word.is_char_boundary(start) {
+        start -= 1;
+    }
+    while !word.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    let mut substr = compact.substr(start..end);
+    let mut control = String::from(&word[start..end]);
+
+    prop_assert_eq!(&substr, &control);
+    assert_allocated_properly(&substr);
+    prop_assert_eq!(&substr, &compact.slice_ref(&compact[start..end]));
+
+    // mutating the substring should never modify the original
+    substr.push_str(&push);
+    control.push_str(&push);
+
+    prop_assert_eq!(&substr, &control);
+    prop_assert_eq!(&compact, &word);
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_split_off_roundtrips(#[strategy(rand_unicode())] word: String, at: usize) {
+    let mut compact = CompactStr::new(&word);
+    let mut control = word.clone();
+
+    // find the closest char boundary to split at
+    let mut at = at % (word.len() + 1);
+    while !word.is_char_boundary(at) {
+        at -= 1;
+    }
+
+    let compact_other = compact.split_off(at);
+    let control_other = control.split_off(at);
+
+    prop_assert_eq!(&compact, &control);
+    prop_assert_eq!(&compact_other, &control_other);
+    assert_allocated_properly(&compact_other);
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_clone_then_mutate_roundtrips(
+    #[strategy(rand_unicode())] word: String,
+    #[strategy(rand_unicode())] push: String,
+) {
+    let compact = CompactStr::new(&word);
+    let mut clone = compact.clone();
+
+    clone.push_str(&push);
+    let mut control = word.clone();
+    control.push_str(&push);
+
+    // mutating the clone should never modify the original
+    prop_assert_eq!(&compact, &word);
+    prop_assert_eq!(&clone, &control);
+    assert_allocated_properly(&clone);
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_into_string_roundtrips(#[strategy(rand_unicode())] word: String) {
+    let compact = CompactStr::new(&word);
+    let s = String::from(compact);
+
+    prop_assert_eq!(&word, &s);
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_from_string_allocated_properly(#[strategy(rand_unicode())] word: String) {
+    let compact = CompactStr::from(word.clone());
+    prop_assert_eq!(&compact, &word);
+    assert_allocated_properly(&compact);
+
+    let compact = CompactStr::from(word.clone().into_boxed_str());
+    prop_assert_eq!(&compact, &word);
+    assert_allocated_properly(&compact);
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_from_string_preserve_capacity(
+    #[strategy(rand_unicode())] word: String,
+    #[strategy(0..256usize)] additional: usize,
+) {
+    let mut s = word.clone();
+    s.reserve_exact(additional);
+    let capacity = s.capacity();
+
+    let compact = CompactStr::from_string_preserve_capacity(s);
+    prop_assert_eq!(&compact, &word);
+    prop_assert_eq!(compact.is_heap_allocated(), capacity > 0);
+    prop_assert_eq!(compact.capacity(), core::cmp::max(capacity, word.len()));
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_from_string_into_string_roundtrips(#[strategy(rand_unicode())] word: String) {
+    let compact = CompactStr::from(word.clone());
+    let s = compact.into_string();
+
+    prop_assert_eq!(&word, &s);
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_fmt_write(#[strategy(rand_unicode())] word: String, num: u32) {
+    use core::fmt::Write;
+
+    let mut compact = CompactStr::default();
+    write!(compact, "{}{}", word, num).unwrap();
+
+    let control = format!("{}{}", word, num);
+    prop_assert_eq!(&compact, &control);
+    prop_assert_eq!(compact.is_heap_allocated(), control.len() > MAX_SIZE);
+}
+
+#[test]
+fn test_niche_optimization() {
+    assert_eq!(
+        std::mem::size_of::<Option<CompactStr>>(),
+        std::mem::size_of::<CompactStr>()
+    );
+    assert_eq!(std::mem::size_of::<Option<CompactStr>>(), MAX_SIZE);
+
+    let inline = Some(CompactStr::new("hello"));
+    let heap = Some(CompactStr::new(
+        "I am a long string that will be heap allocated",
+    ));
+    let empty = Some(CompactStr::new(""));
+    let full = Some(CompactStr::new("a".repeat(MAX_SIZE)));
+    let none: Option<CompactStr> = None;
+
+    assert_eq!(inline.as_deref(), Some("hello"));
+    assert_eq!(
+        heap.as_deref(),
+        Some("I am a long string that will be heap allocated")
+    );
+    assert_eq!(empty.as_deref(), Some(""));
+    assert_eq!(full.as_deref(), Some("a".repeat(MAX_SIZE).as_str()));
+    assert!(none.is_none());
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_from_static_str_then_mutate(
+    #[strategy(rand_unicode())] word: String,
+    #[strategy(rand_unicode())] other: String,
+) {
+    let text: &'static str = Box::leak(word.clone().into_boxed_str());
+    let mut compact = CompactStr::from_static_str(text);
+    let mut control = word;
+
+    prop_assert_eq!(&compact, &control);
+    prop_assert!(!compact.is_heap_allocated());
+
+    compact.push_str(&other);
+    control.push_str(&other);
+
+    prop_assert_eq!(&compact, &control);
+    prop_assert_eq!(compact.is_heap_allocated(), control.len() > MAX_SIZE);
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_try_push_str(
+    #[strategy(rand_unicode())] word: String,
+    #[strategy(rand_unicode())] other: String,
+) {
+    let mut compact = CompactStr::new(&word);
+    let mut control = word;
+
+    compact.try_push_str(&other).unwrap();
+    control.push_str(&other);
+
+    prop_assert_eq!(&compact, &control);
+    prop_assert_eq!(compact.is_heap_allocated(), control.len() > MAX_SIZE);
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_shrink_to_fit(
+    #[strategy(rand_unicode())] word: String,
+    #[strategy(0..256usize)] additional: usize,
+) {
+    let mut compact = CompactStr::new(&word);
+    compact.reserve(additional);
+    compact.shrink_to_fit();
+
+    prop_assert_eq!(&compact, &word);
+    prop_assert_eq!(compact.capacity(), core::cmp::max(word.len(), MAX_SIZE));
+    prop_assert_eq!(compact.is_heap_allocated(), word.len() > MAX_SIZE);
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_as_mut_str_make_ascii_uppercase(#[strategy(rand_unicode())] word: String) {
+    let mut compact = CompactStr::new(&word);
+    let mut control = word;
+
+    compact.as_mut_str().make_ascii_uppercase();
+    control.make_ascii_uppercase();
+
+    prop_assert_eq!(&compact, &control);
+    prop_assert_eq!(compact.len(), control.len());
+    assert_allocated_properly(&compact);
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_clone_then_as_mut_str(#[strategy(rand_unicode())] word: String) {
+    let compact = CompactStr::new(&word);
+    let mut clone = compact.clone();
+
+    clone.make_ascii_lowercase();
+
+    // mutating the clone should never modify the original
+    prop_assert_eq!(&compact, &word);
+    prop_assert_eq!(&clone, &word.to_ascii_lowercase());
+}
+
+#[test]
+fn test_try_reserve_error_display() {
+    let mut compact = CompactStr::new("hello");
+    let err = compact.try_reserve(usize::MAX).unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "memory allocation failed because the computed capacity exceeded the collection's maximum"
+    );
+}
+
 #[test]
 fn test_const_creation() {
     const EMPTY: CompactStr = CompactStr::new_inline("");
@@ -334,8 +855,613 @@ fn test_extend_from_empty_strs() {
     assert!(!compact.is_heap_allocated());
 }
 
+#[test]
+fn test_drain_leaked_leaves_string_unmodified() {
+    let mut compact = CompactStr::new("hello world");
+
+    let mut drain = compact.drain(..5);
+    assert_eq!(drain.next(), Some('h'));
+    core::mem::forget(drain);
+
+    assert_eq!(compact, "hello world");
+}
+
+#[test]
+fn test_drain_dropped_removes_range() {
+    let mut compact = CompactStr::new("this is a long string that will be heap allocated");
+
+    let mut drain = compact.drain(4..);
+    assert_eq!(drain.next_back(), Some('d'));
+    drop(drain);
+
+    assert_eq!(compact, "this");
+}
+
+#[test]
+fn test_shared_clone_heap_is_shared() {
+    let compact = SharedCompactStr::new("this is a long string that will be heap allocated");
+    let mut clone = compact.clone();
+
+    // with `Shared` storage, cloning a heap allocated string is `O(1)` and shares the buffer
+    assert_eq!(compact.as_ptr(), clone.as_ptr());
+
+    // mutating the clone makes a copy
+    clone.push('!');
+    assert_ne!(compact.as_ptr(), clone.as_ptr());
+    assert_eq!(compact, "this is a long string that will be heap allocated");
+    assert_eq!(clone, "this is a long string that will be heap allocated!");
+}
+
+#[test]
+fn test_index_ranges() {
+    let compact = CompactStr::new("hello world");
+
+    assert_eq!(&compact[..], "hello world");
+    assert_eq!(&compact[6..], "world");
+    assert_eq!(&compact[..5], "hello");
+    assert_eq!(&compact[..=4], "hello");
+    assert_eq!(&compact[2..4], "ll");
+    assert_eq!(&compact[2..=4], "llo");
+}
+
+#[test]
+#[should_panic]
+fn test_index_not_char_boundary() {
+    let compact = CompactStr::new("🦀");
+    let _ = &compact[1..];
+}
+
+#[test]
+fn test_index_mut_ranges() {
+    let mut compact = CompactStr::new("hello world, this string is long enough to be on the heap");
+    assert!(compact.is_heap_allocated());
+
+    compact[..5].make_ascii_uppercase();
+    compact[6..=10].make_ascii_uppercase();
+    assert_eq!(
+        compact,
+        "HELLO WORLD, this string is long enough to be on the heap"
+    );
+
+    compact[..].make_ascii_lowercase();
+    assert_eq!(
+        compact,
+        "hello world, this string is long enough to be on the heap"
+    );
+
+    if let Some(s) = compact.get_mut(..5) {
+        s.make_ascii_uppercase();
+    }
+    assert_eq!(
+        compact,
+        "HELLO world, this string is long enough to be on the heap"
+    );
+}
+
+#[test]
+fn test_as_mut_str_max_inline() {
+    let mut compact = CompactStr::new("a".repeat(MAX_SIZE));
+    assert_eq!(compact.len(), MAX_SIZE);
+    assert!(!compact.is_heap_allocated());
+
+    compact.make_ascii_uppercase();
+    assert_eq!(compact, "A".repeat(MAX_SIZE));
+    assert_eq!(compact.len(), MAX_SIZE);
+}
+
+#[test]
+fn test_as_mut_str_static_copies() {
+    const TEXT: &str = "this static string is long enough to be on the heap";
+    let mut compact = CompactStr::from_static_str(TEXT);
+    assert_eq!(compact.as_ptr(), TEXT.as_ptr());
+
+    compact.make_ascii_uppercase();
+    assert_ne!(compact.as_ptr(), TEXT.as_ptr());
+    assert_eq!(
+        compact,
+        "THIS STATIC STRING IS LONG ENOUGH TO BE ON THE HEAP"
+    );
+    assert_eq!(TEXT, "this static string is long enough to be on the heap");
+}
+
+#[test]
+fn test_as_mut_str_shared_copies() {
+    let compact = SharedCompactStr::new("this is a long string that will be heap allocated");
+    let mut clone = compact.clone();
+    assert_eq!(compact.as_ptr(), clone.as_ptr());
+
+    clone.as_mut_str().make_ascii_uppercase();
+    assert_ne!(compact.as_ptr(), clone.as_ptr());
+    assert_eq!(compact, "this is a long string that will be heap allocated");
+    assert_eq!(clone, "THIS IS A LONG STRING THAT WILL BE HEAP ALLOCATED");
+}
+
+#[test]
+fn test_substr_outlives_parent() {
+    let compact = CompactStr::new("this is a long string that will be heap allocated");
+    let substr = compact.substr(10..);
+    drop(compact);
+
+    assert_eq!(substr, "long string that will be heap allocated");
+    assert!(substr.is_heap_allocated());
+}
+
+#[test]
+fn test_substr_heap_is_copied() {
+    let compact = CompactStr::new("this is a long string that will be heap allocated");
+    let substr = compact.substr(10..);
+
+    assert_eq!(substr, "long string that will be heap allocated");
+    assert_ne!(substr.as_ptr(), compact[10..].as_ptr());
+}
+
+#[test]
+fn test_shared_substr_heap_is_shared() {
+    let compact = SharedCompactStr::new("this is a long string that will be heap allocated");
+    let mut substr = compact.substr(10..);
+
+    // with `Shared` storage, a long substring shares the buffer of its parent...
+    assert_eq!(substr.as_ptr(), compact[10..].as_ptr());
+    assert_eq!(substr.capacity(), compact.capacity() - 10);
+
+    // ...until it's mutated
+    substr.push('!');
+    assert_ne!(substr.as_ptr(), compact[10..].as_ptr());
+    assert_eq!(compact, "this is a long string that will be heap allocated");
+    assert_eq!(substr, "long string that will be heap allocated!");
+}
+
+#[test]
+fn test_substr_static_is_borrowed() {
+    const TEXT: &str = "this is a long static string that will be borrowed";
+    let compact = CompactStr::from_static_str(TEXT);
+    let substr = compact.substr(5..);
+
+    assert_eq!(substr, &TEXT[5..]);
+    assert_eq!(substr.as_ptr(), TEXT[5..].as_ptr());
+    assert!(!substr.is_heap_allocated());
+}
+
+#[test]
+fn test_substr_short_is_inlined() {
+    let compact = CompactStr::new("this is a long string that will be heap allocated");
+    let substr = compact.substr(10..21);
+
+    assert_eq!(substr, "long string");
+    assert!(!substr.is_heap_allocated());
+}
+
+#[test]
+#[should_panic]
+fn test_substr_not_char_boundary() {
+    let compact = CompactStr::new("🦀🦀🦀");
+    let _ = compact.substr(1..);
+}
+
+#[test]
+#[should_panic]
+fn test_substr_out_of_bounds() {
+    let compact = CompactStr::new("hello");
+    let _ = compact.substr(..6);
+}
+
+#[test]
+fn test_slice_ref() {
+    let compact = CompactStr::new("the quick brown fox jumps over the lazy dog, and keeps running");
+    let (head, tail) = compact.split_at(20);
+
+    assert_eq!(compact.slice_ref(head), "the quick brown fox ");
+    assert_eq!(
+        compact.slice_ref(tail),
+        "jumps over the lazy dog, and keeps running"
+    );
+    assert_eq!(compact.slice_ref(""), "");
+}
+
+#[test]
+#[should_panic]
+fn test_slice_ref_not_contained() {
+    let compact = CompactStr::new("hello world");
+    let other = String::from("hello");
+    let _ = compact.slice_ref(&other);
+}
+
 #[test]
 fn test_compact_str_is_send_and_sync() {
     fn is_send_and_sync<T: Send + Sync>() {}
     is_send_and_sync::<CompactStr>();
 }
+
+#[test]
+fn test_custom_allocator_inline_does_not_allocate() {
+    let counter = Counter::default();
+    let alloc = CountingAllocator { counter: &counter };
+
+    let mut compact = CompactStr::new_in("hello", alloc);
+    compact.push_str(" world");
+    let clone = compact.clone();
+
+    assert_eq!(compact, "hello world");
+    assert_eq!(clone, "hello world");
+    assert_eq!(counter.allocations.get(), 0);
+}
+
+#[test]
+fn test_custom_allocator_heap() {
+    let counter = Counter::default();
+    let alloc = CountingAllocator { counter: &counter };
+
+    let long = "this is a long string that will be heap allocated";
+    let compact = CompactStr::new_in(long, alloc);
+
+    assert_eq!(compact, long);
+    assert!(compact.is_heap_allocated());
+    assert_eq!(counter.allocations.get(), 1);
+    assert!(counter.live_bytes.get() >= long.len());
+
+    drop(compact);
+    assert_eq!(counter.deallocations.get(), 1);
+    assert_eq!(counter.live_bytes.get(), 0);
+}
+
+#[test]
+fn test_custom_allocator_with_capacity() {
+    let counter = Counter::default();
+    let alloc = CountingAllocator { counter: &counter };
+
+    let empty = CompactStr::with_capacity_in(MAX_SIZE, alloc);
+    assert!(!empty.is_heap_allocated());
+    assert_eq!(counter.allocations.get(), 0);
+
+    let mut compact = CompactStr::with_capacity_in(128, alloc);
+    assert_eq!(compact.capacity(), 128);
+    assert_eq!(counter.allocations.get(), 1);
+
+    // we have enough capacity, so pushing shouldn't allocate again
+    compact.push_str("hello world, this string will be heap allocated");
+    assert_eq!(counter.allocations.get(), 1);
+
+    drop(empty);
+    drop(compact);
+    assert_eq!(counter.live_bytes.get(), 0);
+}
+
+#[test]
+fn test_custom_allocator_grow_and_clone() {
+    let counter = Counter::default();
+    let alloc = CountingAllocator { counter: &counter };
+
+    let mut compact = CompactStr::new_in("hello", alloc);
+    for _ in 0..16 {
+        compact.push_str(" world");
+    }
+    let clone = compact.clone();
+    let other = compact.split_off(5);
+
+    assert_eq!(compact, "hello");
+    assert_eq!(other, " world".repeat(16));
+    assert_eq!(clone.len(), 5 + 6 * 16);
+    assert!(counter.allocations.get() > 0);
+
+    drop(compact);
+    drop(clone);
+    drop(other);
+    assert_eq!(counter.allocations.get(), counter.deallocations.get());
+    assert_eq!(counter.live_bytes.get(), 0);
+}
+
+#[test]
+fn test_custom_allocator_failure() {
+    let mut compact = CompactStr::new_in("hello", FailingAllocator);
+
+    let err = compact.try_reserve(128).unwrap_err();
+    assert!(!err.is_capacity_overflow());
+    assert_eq!(compact, "hello");
+
+    assert!(CompactStr::try_with_capacity_in(128, FailingAllocator).is_err());
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_custom_allocator_frees_everything(
+    #[strategy(rand_unicode())] word: String,
+    #[strategy(rand_unicode_collection())] collection: Vec<String>,
+) {
+    let counter = Counter::default();
+    let alloc = CountingAllocator { counter: &counter };
+
+    {
+        let mut compact = CompactStr::new_in(&word, alloc);
+        let mut control = word.clone();
+
+        compact.extend(collection.iter().map(|s| s.as_str()));
+        control.extend(collection.iter().map(|s| s.as_str()));
+        prop_assert_eq!(&compact, &control);
+
+        // truncating shouldn't leak the buffer of a heap allocated string
+        let clone = compact.clone();
+        let half = (0..=control.len() / 2)
+            .rev()
+            .find(|idx| control.is_char_boundary(*idx))
+            .unwrap();
+        compact.truncate(half);
+        compact.push('!');
+
+        prop_assert_eq!(clone, control);
+    }
+
+    prop_assert_eq!(counter.allocations.get(), counter.deallocations.get());
+    prop_assert_eq!(counter.live_bytes.get(), 0);
+}
+
+#[test]
+fn test_custom_allocator_size() {
+    let counter = Counter::default();
+    let alloc = CountingAllocator { counter: &counter };
+    let compact = CompactStr::new_in("hello", alloc);
+
+    // zero-sized allocators don't take up any space...
+    assert_eq!(
+        core::mem::size_of::<CompactStr<Global>>(),
+        core::mem::size_of::<String>()
+    );
+    // ...while other allocators are stored inline
+    assert_eq!(
+        core::mem::size_of_val(&compact),
+        core::mem::size_of::<String>() + core::mem::size_of::<CountingAllocator>()
+    );
+}
+
+type CompactStr40<A = Global> = CompactStrN<40, A>;
+
+#[test]
+fn test_compact_str_n_size() {
+    assert_eq!(core::mem::size_of::<CompactStr40>(), 40);
+    assert_eq!(core::mem::size_of::<Option<CompactStr40>>(), 40);
+    assert_eq!(core::mem::size_of::<CompactStrN<56>>(), 56);
+    // rounded up to the alignment of a pointer
+    #[cfg(target_pointer_width = "64")]
+    assert_eq!(core::mem::size_of::<CompactStrN<41>>(), 48);
+    #[cfg(target_pointer_width = "32")]
+    assert_eq!(core::mem::size_of::<CompactStrN<41>>(), 44);
+    assert_eq!(core::mem::size_of::<CompactStrN<MAX_SIZE>>(), MAX_SIZE);
+}
+
+#[test]
+fn test_compact_str_n_max_inline() {
+    let word = "a".repeat(40);
+    let compact = CompactStr40::new(&word);
+    assert_eq!(compact, word);
+    assert!(!compact.is_heap_allocated());
+
+    let word = "a".repeat(41);
+    let compact = CompactStr40::new(&word);
+    assert_eq!(compact, word);
+    assert!(compact.is_heap_allocated());
+
+    // the largest `N` still fits the length of a shorter string in the last byte
+    let mut compact = CompactStrN::<56>::new("a".repeat(56));
+    assert!(!compact.is_heap_allocated());
+    compact.truncate(55);
+    assert_eq!(compact.len(), 55);
+    assert!(!compact.is_heap_allocated());
+}
+
+#[test]
+fn test_compact_str_n_option_niche() {
+    let none: Option<CompactStr40> = None;
+    assert!(none.is_none());
+
+    let full = Some(CompactStr40::new("a".repeat(40)));
+    let heap = Some(CompactStr40::new("a".repeat(41)));
+
+    assert_eq!(full.as_deref(), Some("a".repeat(40).as_str()));
+    assert_eq!(heap.as_deref(), Some("a".repeat(41).as_str()));
+}
+
+#[test]
+fn test_compact_str_n_push_grows_onto_heap() {
+    let mut compact = CompactStr40::default();
+    let mut control = String::new();
+
+    for ch in "the quick brown 🦊 jumps over the lazy 🐶, again and again".chars() {
+        compact.push(ch);
+        control.push(ch);
+        assert_eq!(compact, control);
+        assert_eq!(compact.is_heap_allocated(), control.len() > 40);
+    }
+
+    while let Some(ch) = control.pop() {
+        assert_eq!(compact.pop(), Some(ch));
+        assert_eq!(compact, control);
+    }
+    assert_eq!(compact.pop(), None);
+}
+
+#[test]
+fn test_compact_str_n_reserve_and_shrink() {
+    let mut compact = CompactStr40::new("hello");
+    compact.reserve(35);
+    assert!(!compact.is_heap_allocated());
+
+    compact.reserve(36);
+    assert!(compact.is_heap_allocated());
+    assert_eq!(compact, "hello");
+
+    compact.shrink_to_fit();
+    assert!(!compact.is_heap_allocated());
+    assert_eq!(compact, "hello");
+}
+
+#[test]
+fn test_compact_str_n_clone_heap() {
+    let original = CompactStr40::new("this string is long enough to be heap allocated");
+    let mut clone = original.clone();
+    assert_eq!(original, clone);
+    assert_ne!(original.as_ptr(), clone.as_ptr());
+
+    clone.truncate(4);
+    let clone = clone.clone();
+    assert_eq!(clone, "this");
+    assert_eq!(original, "this string is long enough to be heap allocated");
+}
+
+#[test]
+fn test_compact_str_n_conversions() {
+    let string = "hello world, this is a long string with a long key".to_string();
+    let ptr = string.as_ptr();
+
+    let compact = CompactStr40::from(string);
+    assert_eq!(compact.as_ptr(), ptr);
+
+    let string: String = compact.into();
+    assert_eq!(string, "hello world, this is a long string with a long key");
+    assert_eq!(string.as_ptr(), ptr);
+
+    let compact = CompactStr40::from(String::from("short"));
+    assert!(!compact.is_heap_allocated());
+}
+
+#[test]
+fn test_compact_str_n_write() {
+    use core::fmt::Write;
+
+    let mut s = CompactStr40::default();
+    write!(s, "{} + {} = {}", 40, 2, 42).unwrap();
+    assert_eq!(s, "40 + 2 = 42");
+}
+
+#[test]
+fn test_compact_str_n_custom_allocator() {
+    let counter = Counter::default();
+    let alloc = CountingAllocator { counter: &counter };
+
+    let mut compact = CompactStr40::new_in("a".repeat(40), alloc);
+    assert!(!compact.is_heap_allocated());
+    assert_eq!(counter.allocations.get(), 0);
+
+    compact.push_str(" and a little more");
+    assert!(compact.is_heap_allocated());
+    assert_eq!(counter.allocations.get(), 1);
+
+    let clone = compact.clone();
+    assert_eq!(clone, compact);
+    assert_eq!(
+        core::mem::size_of_val(&compact),
+        40 + core::mem::size_of::<CountingAllocator>()
+    );
+
+    drop(compact);
+    drop(clone);
+    assert_eq!(counter.allocations.get(), counter.deallocations.get());
+    assert_eq!(counter.live_bytes.get(), 0);
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_compact_str_n_roundtrip(#[strategy(rand_unicode())] word: String) {
+    let compact = CompactStr40::new(&word);
+
+    prop_assert_eq!(&compact, &word);
+    prop_assert_eq!(compact.len(), word.len());
+    prop_assert_eq!(compact.is_heap_allocated(), word.len() > 40);
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_compact_str_n_extend_matches_string(
+    #[strategy(rand_unicode())] word: String,
+    #[strategy(rand_unicode_collection())] collection: Vec<String>,
+) {
+    let mut compact = CompactStr40::new(&word);
+    let mut control = word.clone();
+
+    compact.extend(collection.iter().map(|s| s.as_str()));
+    control.extend(collection.iter().map(|s| s.as_str()));
+    prop_assert_eq!(&compact, &control);
+
+    let collected: CompactStr40 = collection.iter().map(String::as_str).collect();
+    let control: String = collection.iter().map(String::as_str).collect();
+    prop_assert_eq!(&collected, &control);
+    prop_assert_eq!(collected.is_heap_allocated(), control.len() > 40);
+}
+
+#[test]
+fn test_shared_size_of() {
+    assert_eq!(
+        core::mem::size_of::<SharedCompactStr>(),
+        core::mem::size_of::<String>()
+    );
+    assert_eq!(
+        core::mem::size_of::<Option<SharedCompactStr>>(),
+        core::mem::size_of::<String>()
+    );
+}
+
+#[test]
+fn test_shared_conversions_copy() {
+    let string = "this is a long string that will be heap allocated".to_string();
+    let ptr = string.as_ptr();
+
+    // a shared buffer needs room for its reference count, so we can't re-use the `String`
+    let compact = SharedCompactStr::from(string);
+    assert!(compact.is_heap_allocated());
+    assert_ne!(compact.as_ptr(), ptr);
+
+    let clone = compact.clone();
+    let string = compact.into_string();
+    assert_eq!(string, "this is a long string that will be heap allocated");
+    assert_ne!(string.as_ptr(), clone.as_ptr());
+    assert_eq!(clone, string);
+}
+
+#[test]
+fn test_shared_compact_str_n_clone_heap() {
+    let original =
+        CompactStrN::<40, Global, Shared>::new("this string is long enough to be heap allocated");
+    let mut clone = original.clone();
+    assert_eq!(original.as_ptr(), clone.as_ptr());
+
+    clone.push('!');
+    assert_ne!(original.as_ptr(), clone.as_ptr());
+    assert_eq!(original, "this string is long enough to be heap allocated");
+    assert_eq!(clone, "this string is long enough to be heap allocated!");
+}
+
+#[test]
+fn test_shared_custom_allocator() {
+    let counter = Counter::default();
+    let alloc = CountingAllocator { counter: &counter };
+
+    let compact =
+        SharedCompactStr::new_in("this is a long string that will be heap allocated", alloc);
+    assert_eq!(counter.allocations.get(), 1);
+
+    // cloning shares the buffer, so it doesn't allocate
+    let clone = compact.clone();
+    assert_eq!(counter.allocations.get(), 1);
+    assert_eq!(compact.as_ptr(), clone.as_ptr());
+
+    // the buffer is deallocated once the last reference to it is dropped
+    drop(compact);
+    assert_eq!(counter.deallocations.get(), 0);
+    drop(clone);
+    assert_eq!(counter.deallocations.get(), 1);
+    assert_eq!(counter.live_bytes.get(), 0);
+}
+
+#[proptest]
+#[cfg_attr(miri, ignore)]
+fn test_shared_extend_matches_string(
+    #[strategy(rand_unicode())] word: String,
+    #[strategy(rand_unicode_collection())] collection: Vec<String>,
+) {
+    let compact = SharedCompactStr::new(&word);
+    let mut clone = compact.clone();
+    let mut control = word.clone();
+
+    clone.extend(collection.iter().map(|s| s.as_str()));
+    control.extend(collection.iter().map(|s| s.as_str()));
+    prop_assert_eq!(&clone, &control);
+    prop_assert_eq!(&compact, &word);
+}
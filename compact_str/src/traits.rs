@@ -0,0 +1,89 @@
+use core::fmt::{
+    self,
+    Write,
+};
+
+use crate::CompactStr;
+
+/// A trait for converting a value to a [`CompactStr`].
+///
+/// This trait is automatically implemented for any type which implements the [`fmt::Display`]
+/// trait. As such, [`ToCompactStr`] shouldn't be implemented directly: [`fmt::Display`] should
+/// be implemented instead, and you get the [`ToCompactStr`] implementation for free.
+pub trait ToCompactStr {
+    /// Converts the given value to a [`CompactStr`].
+    ///
+    /// The value is formatted directly into the [`CompactStr`], so short values are stored
+    /// inline without ever allocating on the heap.
+    ///
+    /// # Panics
+    /// Panics if the [`fmt::Display`] implementation of the value returns an error. This
+    /// indicates an incorrect implementation since writing to a [`CompactStr`] never fails.
+    ///
+    /// # Examples
+    /// ```
+    /// use compact_str::ToCompactStr;
+    ///
+    /// let i = 5;
+    /// let five = compact_str::CompactStr::new("5");
+    ///
+    /// assert_eq!(i.to_compact_str(), five);
+    /// ```
+    fn to_compact_str(&self) -> CompactStr;
+}
+
+impl<T: fmt::Display + ?Sized> ToCompactStr for T {
+    #[inline]
+    fn to_compact_str(&self) -> CompactStr {
+        let mut compact = CompactStr::default();
+        write!(compact, "{}", self)
+            .expect("a Display implementation returned an error unexpectedly");
+        compact
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::{
+        String,
+        ToString,
+    };
+    use core::mem::size_of;
+
+    use proptest::prelude::*;
+    use test_strategy::proptest;
+
+    use super::ToCompactStr;
+    use crate::CompactStr;
+
+    #[test]
+    fn test_to_compact_str() {
+        assert_eq!(42.to_compact_str(), "42");
+        assert_eq!('a'.to_compact_str(), "a");
+        assert_eq!("hello".to_compact_str(), "hello");
+        assert_eq!(CompactStr::new("world").to_compact_str(), "world");
+
+        let short = 1234567890u64.to_compact_str();
+        assert!(!short.is_heap_allocated());
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn test_to_compact_str_int(val: i64) {
+        let compact = val.to_compact_str();
+        prop_assert_eq!(compact.as_str(), val.to_string());
+        // note: an `i64` can be up to 20 bytes long, which is too long to inline on 32-bit
+        // architectures
+        prop_assert_eq!(
+            compact.is_heap_allocated(),
+            compact.len() > size_of::<String>()
+        );
+    }
+
+    #[proptest]
+    #[cfg_attr(miri, ignore)]
+    fn test_to_compact_str_str(word: String) {
+        let compact = word.to_compact_str();
+        prop_assert_eq!(compact.as_str(), word.as_str());
+    }
+}
@@ -1,11 +1,58 @@
+use std::alloc::{
+    GlobalAlloc,
+    Layout,
+    System,
+};
 use std::collections::VecDeque;
+use std::convert::TryFrom;
 use std::io::Cursor;
+use std::sync::atomic::{
+    AtomicUsize,
+    Ordering,
+};
 
 use arbitrary::Arbitrary;
+#[cfg(not(feature = "shared"))]
 use compact_str::CompactStr;
+// Fuzz the reference counted heap variant instead, see `compact_str::Shared`
+#[cfg(feature = "shared")]
+use compact_str::SharedCompactStr as CompactStr;
 
 const MAX_INLINE_LENGTH: usize = std::mem::size_of::<String>();
 
+/// The largest allocation, in bytes, that [`FailingAllocator`] will currently allow
+static ALLOCATION_LIMIT: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// A global allocator which fails any allocation larger than [`ALLOCATION_LIMIT`], used to test
+/// our fallible allocation APIs, e.g. [`CompactStr::try_reserve`]
+pub struct FailingAllocator;
+
+unsafe impl GlobalAlloc for FailingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.size() > ALLOCATION_LIMIT.load(Ordering::Relaxed) {
+            std::ptr::null_mut()
+        } else {
+            System.alloc(layout)
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: FailingAllocator = FailingAllocator;
+
+/// Runs `f` while failing any allocation larger than `limit` bytes
+fn with_allocation_limit<T, F: FnOnce() -> T>(limit: usize, f: F) -> T {
+    ALLOCATION_LIMIT.store(limit, Ordering::Relaxed);
+    let result = f();
+    ALLOCATION_LIMIT.store(usize::MAX, Ordering::Relaxed);
+
+    result
+}
+
 /// A framework to generate a `CompactStr` and control `String`, and then run a series of actions
 /// and assert equality
 ///
@@ -30,10 +77,23 @@ pub enum Creation<'a> {
     Word(String),
     /// Create using [`CompactStr::from_utf8_buf`] when the buffer is non-contiguous
     NonContiguousBuf(&'a [u8]),
-    /// Create using `From<String>`, which consumes the `String` for `O(1)` runtime
+    /// Create using `From<String>`, which inlines short strings, and consumes long ones for
+    /// `O(1)` runtime
     FromString(String),
-    /// Create using `From<Box<str>>`, which consumes the `Box<str>` for `O(1)` runtime
+    /// Create using `From<Box<str>>`, which inlines short strings, and consumes long ones for
+    /// `O(1)` runtime
     FromBoxStr(Box<str>),
+    /// Create using [`CompactStr::from_string_preserve_capacity`], which always consumes the
+    /// `String` for `O(1)` runtime
+    FromStringPreserveCapacity(String),
+    /// Create using `TryFrom<Bytes>`, which takes ownership of the buffer for long strings
+    FromBytes(&'a [u8]),
+    /// Create by writing chunks of bytes into a [`compact_str::CompactStrWriter`]
+    Writer(&'a [u8], u8),
+    /// Create using [`CompactStr::from_utf8_lossy`]
+    BytesLossy(&'a [u8]),
+    /// Create using [`CompactStr::from_utf16`] and [`CompactStr::from_utf16_lossy`]
+    Utf16(Vec<u16>),
 }
 
 impl Creation<'_> {
@@ -54,8 +114,33 @@ impl Creation<'_> {
 
                 assert_eq!(compact, s);
 
-                // Note: converting From<String> will always be heap allocated because we use the
-                // underlying buffer from the source String
+                // Note: converting From<String> inlines short strings, and re-uses the
+                // underlying buffer of long strings, or with the `shared` feature copies it into a
+                // new reference counted buffer
+                assert_properly_allocated(&compact, &s);
+
+                Some((compact, s))
+            }
+            FromBoxStr(b) => {
+                let compact = CompactStr::from(b.clone());
+
+                assert_eq!(compact, b);
+
+                // Note: converting From<Box<str>> inlines short strings, and re-uses the
+                // underlying buffer of long strings
+                assert_properly_allocated(&compact, &b);
+
+                let string = String::from(b);
+                Some((compact, string))
+            }
+            FromStringPreserveCapacity(s) => {
+                let compact = CompactStr::from_string_preserve_capacity(s.clone());
+
+                assert_eq!(compact, s);
+
+                // Note: this will always be heap allocated because we use the underlying buffer
+                // from the source String, or with the `shared` feature because we copy it into a
+                // new reference counted buffer
                 if s.capacity() == 0 {
                     assert!(!compact.is_heap_allocated());
                 } else {
@@ -64,21 +149,87 @@ impl Creation<'_> {
 
                 Some((compact, s))
             }
-            FromBoxStr(b) => {
-                let compact = CompactStr::from(b.clone());
+            FromBytes(data) => {
+                let compact = CompactStr::try_from(bytes::Bytes::copy_from_slice(data));
+                let std_str = std::str::from_utf8(data);
 
-                assert_eq!(compact, b);
+                match (compact, std_str) {
+                    // valid UTF-8
+                    (Ok(c), Ok(s)) => {
+                        assert_eq!(c, s);
+                        assert_properly_allocated(&c, s);
 
-                // Note: converting From<Box<str>> will always be heap allocated because we use the
-                // underlying buffer from the source String
-                if b.len() == 0 {
-                    assert!(!compact.is_heap_allocated())
-                } else {
-                    assert!(compact.is_heap_allocated())
+                        // converting back into `Bytes` shouldn't lose anything
+                        assert_eq!(bytes::Bytes::from(c.clone()), s.as_bytes());
+
+                        Some((c, s.to_string()))
+                    }
+                    // non-valid UTF-8
+                    (Err(c_err), Err(s_err)) => {
+                        assert_eq!(c_err.utf8_error(), s_err);
+                        assert_eq!(c_err.as_bytes(), data);
+                        None
+                    }
+                    _ => panic!("CompactStr and core::str read UTF-8 differently?"),
                 }
+            }
+            Writer(data, chunk_size) => {
+                use bytes::BufMut;
 
-                let string = String::from(b);
-                Some((compact, string))
+                let mut writer = compact_str::CompactStrWriter::new();
+                for chunk in data.chunks(chunk_size as usize + 1) {
+                    writer.put_slice(chunk);
+                }
+                assert_eq!(writer.as_bytes(), data);
+
+                match (writer.finish(), std::str::from_utf8(data)) {
+                    // valid UTF-8
+                    (Ok(c), Ok(s)) => {
+                        // Note: A `CompactStrWriter` always writes into a
+                        // `compact_str::CompactStr`, which re-uses its
+                        // buffer when converted into our `CompactStr`, unless
+                        // we're fuzzing the `shared` variant
+                        let c = CompactStr::from(c.into_string());
+                        assert_eq!(c, s);
+                        assert_properly_allocated(&c, s);
+
+                        Some((c, s.to_string()))
+                    }
+                    // non-valid UTF-8
+                    (Err(c_err), Err(s_err)) => {
+                        assert_eq!(c_err.utf8_error(), s_err);
+                        assert_eq!(c_err.as_bytes(), data);
+                        None
+                    }
+                    _ => panic!("CompactStr and core::str read UTF-8 differently?"),
+                }
+            }
+            BytesLossy(data) => {
+                let compact = CompactStr::from_utf8_lossy(data);
+                let std_str = String::from_utf8_lossy(data).into_owned();
+
+                assert_eq!(compact, std_str);
+                assert_properly_allocated(&compact, &std_str);
+
+                Some((compact, std_str))
+            }
+            Utf16(units) => {
+                let compact_result = CompactStr::from_utf16(&units);
+                let std_result = String::from_utf16(&units);
+
+                match (&compact_result, &std_result) {
+                    (Ok(c), Ok(s)) => assert_eq!(c, s),
+                    (Err(_), Err(_)) => (),
+                    _ => panic!("CompactStr and String decode UTF-16 differently?"),
+                }
+
+                let compact = CompactStr::from_utf16_lossy(&units);
+                let std_str = String::from_utf16_lossy(&units);
+
+                assert_eq!(compact, std_str);
+                assert_properly_allocated(&compact, &std_str);
+
+                Some((compact, std_str))
             }
             IterChar(chars) => {
                 let compact: CompactStr = chars.iter().collect();
@@ -112,7 +263,9 @@ impl Creation<'_> {
                     }
                     // non-valid UTF-8
                     (Err(c_err), Err(s_err)) => {
-                        assert_eq!(c_err, s_err);
+                        assert_eq!(c_err.utf8_error(), s_err);
+                        // the error gives back all of the bytes
+                        assert_eq!(c_err.as_bytes(), data);
                         None
                     }
                     _ => panic!("CompactStr and core::str read UTF-8 differently?"),
@@ -134,7 +287,9 @@ impl Creation<'_> {
                     }
                     // non-valid UTF-8
                     (Err(c_err), Err(s_err)) => {
-                        assert_eq!(c_err, s_err);
+                        assert_eq!(c_err.utf8_error(), s_err);
+                        // the error gives back all of the bytes
+                        assert_eq!(c_err.as_bytes(), data);
                         None
                     }
                     _ => panic!("CompactStr and core::str read UTF-8 differently?"),
@@ -174,7 +329,7 @@ impl Creation<'_> {
                     }
                     // non-valid UTF-8
                     (Err(c_err), Err(s_err)) => {
-                        assert_eq!(c_err, s_err);
+                        assert_eq!(c_err.utf8_error(), s_err);
                         None
                     }
                     _ => panic!("CompactStr and core::str read UTF-8 differently?"),
@@ -193,6 +348,27 @@ pub enum Action<'a> {
     ExtendChars(Vec<char>),
     ExtendStr(Vec<&'a str>),
     CheckSubslice(u8, u8),
+    Truncate(u8),
+    Clear,
+    Insert(u8, char),
+    InsertStr(u8, &'a str),
+    Remove(u8),
+    // Note: We retain all chars whose length in bytes is in the provided set
+    Retain(Vec<u8>),
+    Drain(u8, u8),
+    ReplaceRange(u8, u8, &'a str),
+    SplitOff(u8),
+    CloneThenPushStr(&'a str),
+    RoundtripString,
+    // Note: We fail any allocations larger than the provided `u16` number of bytes
+    TryReserve(usize, u16),
+    TryPushStr(&'a str, u16),
+    CloneThenTryPushStr(&'a str, u16),
+    ShrinkToFit,
+    // Note: We use a `u8` to limit the minimum capacity we shrink to
+    ShrinkTo(u8),
+    MakeAsciiUppercase(u8, u8),
+    Substr(u8, u8),
 }
 
 impl Action<'_> {
@@ -263,10 +439,247 @@ impl Action<'_> {
 
                 assert_eq!(control_slice, compact_slice);
             }
+            // shorten the string
+            Truncate(a) => {
+                let new_len = to_index(control, a);
+
+                control.truncate(new_len);
+                compact.truncate(new_len);
+
+                assert_eq!(control, compact);
+                assert_eq!(control.len(), compact.len());
+            }
+            // remove all contents
+            Clear => {
+                control.clear();
+                compact.clear();
+
+                assert_eq!(control, compact);
+                assert!(compact.is_empty());
+            }
+            // insert a character at a byte position
+            Insert(a, c) => {
+                let idx = to_index(control, a);
+
+                control.insert(idx, c);
+                compact.insert(idx, c);
+
+                assert_eq!(control, compact);
+                assert_eq!(control.len(), compact.len());
+            }
+            // insert a `&str` at a byte position
+            InsertStr(a, s) => {
+                let idx = to_index(control, a);
+
+                control.insert_str(idx, s);
+                compact.insert_str(idx, s);
+
+                assert_eq!(control, compact);
+                assert_eq!(control.len(), compact.len());
+            }
+            // remove a character at a byte position
+            Remove(a) => {
+                let idx = to_index(control, a);
+
+                // removing from the end of the string panics
+                if idx < control.len() {
+                    assert_eq!(control.remove(idx), compact.remove(idx));
+                }
+
+                assert_eq!(control, compact);
+                assert_eq!(control.len(), compact.len());
+            }
+            // retain only the characters whose UTF-8 length is in `lens`
+            Retain(lens) => {
+                control.retain(|c| lens.contains(&(c.len_utf8() as u8)));
+                compact.retain(|c| lens.contains(&(c.len_utf8() as u8)));
+
+                assert_eq!(control, compact);
+                assert_eq!(control.len(), compact.len());
+            }
+            // drain a range of characters
+            Drain(a, b) => {
+                let (start, end) = to_range(control, a, b);
+
+                let control_drain: String = control.drain(start..end).collect();
+                let compact_drain: CompactStr = compact.drain(start..end).collect();
+
+                assert_eq!(control_drain, compact_drain);
+                assert_eq!(control, compact);
+                assert_eq!(control.len(), compact.len());
+            }
+            // replace a range of characters with a `&str`
+            ReplaceRange(a, b, s) => {
+                let (start, end) = to_range(control, a, b);
+
+                control.replace_range(start..end, s);
+                compact.replace_range(start..end, s);
+
+                assert_eq!(control, compact);
+                assert_eq!(control.len(), compact.len());
+            }
+            // split the string in two at a byte position
+            SplitOff(a) => {
+                let at = to_index(control, a);
+
+                let control_other = control.split_off(at);
+                let compact_other = compact.split_off(at);
+
+                assert_eq!(control_other, compact_other);
+                assert_properly_allocated(&compact_other, &control_other);
+
+                assert_eq!(control, compact);
+                assert_eq!(control.len(), compact.len());
+            }
+            // clone the string, and make sure mutating the clone doesn't modify the original
+            CloneThenPushStr(s) => {
+                let mut control_clone = control.clone();
+                let mut compact_clone = compact.clone();
+
+                control_clone.push_str(s);
+                compact_clone.push_str(s);
+
+                assert_eq!(control_clone, compact_clone);
+                assert_eq!(control, compact);
+                assert_eq!(control.len(), compact.len());
+            }
+            // convert into a `String` and back again
+            RoundtripString => {
+                let s = core::mem::take(compact).into_string();
+                assert_eq!(control, &s);
+
+                *compact = CompactStr::from(s);
+                assert_eq!(control, compact);
+                assert_eq!(control.len(), compact.len());
+            }
+            // try to reserve additional space, while possibly failing to allocate
+            TryReserve(additional, limit) => {
+                let required = control.len().checked_add(additional);
+                let result =
+                    with_allocation_limit(limit as usize, || compact.try_reserve(additional));
+
+                match (result, required) {
+                    (Ok(()), Some(required)) => assert!(compact.capacity() >= required),
+                    (Ok(()), None) => panic!("reserved more than usize::MAX bytes?"),
+                    // we only fail to allocate if we need to heap allocate
+                    (Err(_), Some(required)) => assert!(required > MAX_INLINE_LENGTH),
+                    (Err(err), None) => assert!(err.is_capacity_overflow()),
+                }
+
+                // regardless of whether or not we failed, the contents should remain the same
+                assert_eq!(control, compact);
+                assert_eq!(control.len(), compact.len());
+            }
+            // try to push a `&str`, while possibly failing to allocate
+            TryPushStr(s, limit) => {
+                let result = with_allocation_limit(limit as usize, || compact.try_push_str(s));
+
+                // if we succeeded then the `&str` should have been pushed, otherwise we should be
+                // left unchanged
+                if result.is_ok() {
+                    control.push_str(s);
+                }
+
+                assert_eq!(control, compact);
+                assert_eq!(control.len(), compact.len());
+            }
+            // try to push a `&str` onto a clone, which might share its buffer with the original
+            // and need to copy it, while possibly failing to allocate
+            CloneThenTryPushStr(s, limit) => {
+                let mut control_clone = control.clone();
+                let mut compact_clone = compact.clone();
+
+                let result =
+                    with_allocation_limit(limit as usize, || compact_clone.try_push_str(s));
+                if result.is_ok() {
+                    control_clone.push_str(s);
+                }
+
+                assert_eq!(control_clone, compact_clone);
+                assert_eq!(control, compact);
+                assert_eq!(control.len(), compact.len());
+            }
+            // shrink the capacity to match the length, possibly moving back onto the stack
+            ShrinkToFit => {
+                control.shrink_to_fit();
+                compact.shrink_to_fit();
+
+                assert_eq!(control, compact);
+                assert_properly_allocated(compact, control);
+                assert_eq!(
+                    compact.capacity(),
+                    core::cmp::max(control.len(), MAX_INLINE_LENGTH)
+                );
+            }
+            // shrink the capacity with a lower bound
+            ShrinkTo(min_capacity) => {
+                let min_capacity = min_capacity as usize;
+                let prev_capacity = compact.capacity();
+
+                compact.shrink_to(min_capacity);
+
+                assert_eq!(control, compact);
+                assert_eq!(control.len(), compact.len());
+                assert!(compact.capacity() <= core::cmp::max(prev_capacity, MAX_INLINE_LENGTH));
+                assert!(compact.capacity() >= core::cmp::min(prev_capacity, min_capacity));
+                assert!(compact.capacity() >= compact.len());
+            }
+            // uppercase a range of the string in place
+            MakeAsciiUppercase(a, b) => {
+                let a = to_index(control, a);
+                let b = to_index(control, b);
+
+                let start = core::cmp::min(a, b);
+                let end = core::cmp::max(a, b);
+
+                control[start..end].make_ascii_uppercase();
+                compact[start..end].make_ascii_uppercase();
+
+                assert_eq!(control, compact);
+                assert_eq!(control.len(), compact.len());
+            }
+            // replace the string with a substring of itself
+            Substr(a, b) => {
+                let a = to_index(control, a);
+                let b = to_index(control, b);
+
+                let start = core::cmp::min(a, b);
+                let end = core::cmp::max(a, b);
+
+                let substr = compact.substr(start..end);
+                assert_eq!(&control[start..end], substr);
+                assert_properly_allocated(&substr, &control[start..end]);
+
+                *control = String::from(&control[start..end]);
+                *compact = substr;
+
+                assert_eq!(control, compact);
+                assert_eq!(control.len(), compact.len());
+            }
         }
     }
 }
 
+/// Scales `a` to be an index in `[0, control.len()]`, and then moves it backwards until it lies on
+/// a char boundary
+fn to_index(control: &str, a: u8) -> usize {
+    let c = a as f32 / u8::MAX as f32;
+    let mut idx = (c * control.len() as f32) as usize;
+
+    while !control.is_char_boundary(idx) {
+        idx -= 1;
+    }
+    idx
+}
+
+/// Scales `a` and `b` to be a valid range of indices in `control`, that lie on char boundaries
+fn to_range(control: &str, a: u8, b: u8) -> (usize, usize) {
+    let e = to_index(control, a);
+    let f = to_index(control, b);
+
+    (core::cmp::min(e, f), core::cmp::max(e, f))
+}
+
 /// Asserts the provided CompactStr is allocated properly either on the stack or on the heap, using
 /// a "control" `&str` for a reference length.
 fn assert_properly_allocated(compact: &CompactStr, control: &str) {